@@ -39,7 +39,8 @@ impl Add {
         let task = &added_tasks[0];
         Ok(String::from(format!(
             "Added [id: {}, body: {}]",
-            task.id, task.body
+            task.display_id.unwrap_or(task.id),
+            task.body
         )))
     }
 
@@ -26,7 +26,8 @@ impl Agenda {
         let mut operation = AgendaOperation::new(start_day.to_string(), end_day.to_owned(), None);
         execute_agenda(&mut operation)?;
 
-        DisplayAgenda::display(operation.get_result(), &self.config)?;
+        let efforts = operation.get_effort();
+        DisplayAgenda::display(operation.get_result(), &efforts, &self.config)?;
         Ok(String::new())
     }
 }
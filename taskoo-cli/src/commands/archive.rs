@@ -0,0 +1,34 @@
+use anyhow::Result;
+use log::info;
+
+use taskoo_core::core::Operation;
+use taskoo_core::operation::{execute, Archive as ArchiveOp, Task, Unarchive};
+
+pub struct Archive;
+
+// taskoo archive
+// taskoo archive --days 7
+// taskoo unarchive 1 2 3
+impl Archive {
+    pub fn archive(days: &Option<i64>) -> Result<String> {
+        info!("Archiving completed tasks, older than {:?} days", days);
+        let mut operation = ArchiveOp::new(*days);
+        execute(&mut operation)?;
+        Ok(Archive::format_tasks("Archived", operation.get_result()))
+    }
+
+    pub fn unarchive(task_ids: &Vec<String>) -> Result<String> {
+        info!("Restoring tasks {:?} from the archive", task_ids);
+        let mut operation = Unarchive::new(task_ids)?;
+        execute(&mut operation)?;
+        Ok(Archive::format_tasks("Restored", operation.get_result()))
+    }
+
+    fn format_tasks(action: &str, tasks: &Vec<Task>) -> String {
+        let mut output = format!("{} {} task(s)", action, tasks.len());
+        for task in tasks.iter() {
+            output.push_str(&format!("\n  [id: {}, body: {}]", task.id, task.body));
+        }
+        output
+    }
+}
@@ -0,0 +1,137 @@
+use log::info;
+use std::env;
+use std::fs;
+use std::process::Command;
+
+use taskoo_core::core::Operation;
+use taskoo_core::operation::{
+    execute, execute_open_attachment, AddAttachment, Attachment as AttachmentData,
+    DeleteAttachment, Get as GetOp, OpenAttachment, Task,
+};
+
+use crate::display::Display;
+use crate::error::ClientError;
+
+pub struct Attachment;
+
+// taskoo attach 12 https://example.com/spec
+// taskoo attach 12 ./contract.pdf --copy
+// taskoo attachments 12
+// taskoo open 12
+// taskoo open 12 3
+// taskoo detach 12 3
+impl Attachment {
+    pub fn attach(task: &str, location: &str, copy: bool) -> Result<String, ClientError> {
+        let task = Attachment::get_task(task)?;
+        info!("Attaching {} to task {}", location, task.id);
+        Attachment::print(&mut AddAttachment::new(task.id, location.to_string(), copy))
+    }
+
+    pub fn list(task: &str) -> Result<String, ClientError> {
+        Display::print(&Attachment::format_attachments(&Attachment::get_task(task)?));
+        Ok(String::new())
+    }
+
+    pub fn detach(task: &str, attachment_id: i64) -> Result<String, ClientError> {
+        let task = Attachment::get_task(task)?;
+        Attachment::check_attachment(&task, attachment_id)?;
+        info!("Detaching attachment {} from task {}", attachment_id, task.id);
+        Attachment::print(&mut DeleteAttachment::new(attachment_id))
+    }
+
+    // URLs are opened with $BROWSER, files and the copies with xdg-open. The
+    // attachment can be left out when the task has only one.
+    pub fn open(task: &str, attachment_id: &Option<i64>) -> Result<String, ClientError> {
+        let task = Attachment::get_task(task)?;
+        let attachment_id = match attachment_id {
+            Some(attachment_id) => {
+                Attachment::check_attachment(&task, *attachment_id)?;
+                *attachment_id
+            }
+            None if task.attachments.len() == 1 => task.attachments[0].id,
+            None => {
+                return Err(ClientError::ArgumentError(format!(
+                    "Task {} has {} attachments, which one should be opened?",
+                    task.id,
+                    task.attachments.len()
+                )))
+            }
+        };
+
+        let mut operation = OpenAttachment::new(attachment_id);
+        execute_open_attachment(&mut operation)?;
+        let (attachment, data) = operation.get_result().clone().unwrap();
+        let target = match data {
+            Some(data) => {
+                let mut path = env::temp_dir();
+                path.push(attachment.file_name());
+                fs::write(&path, data)
+                    .map_err(|source| ClientError::TerminalError { source })?;
+                path.to_string_lossy().to_string()
+            }
+            None => attachment.location.clone(),
+        };
+        Attachment::launch(&attachment, &target)?;
+        Ok(String::new())
+    }
+
+    fn launch(attachment: &AttachmentData, target: &str) -> Result<(), ClientError> {
+        let opener = match env::var("BROWSER") {
+            Ok(browser) if attachment.is_url() && !browser.trim().is_empty() => browser,
+            _ => String::from("xdg-open"),
+        };
+        info!("Opening {} with {}", target, opener);
+        Command::new(&opener).arg(target).spawn().map_err(|error| {
+            ClientError::UnexpectedFailure(format!(
+                "Unable to open {} with {}: {}",
+                target, opener, error
+            ))
+        })?;
+        Ok(())
+    }
+
+    fn get_task(task: &str) -> Result<Task, ClientError> {
+        let mut operation = GetOp::new();
+        operation.select_task(task)?;
+        execute(&mut operation)?;
+        match operation.get_result().first() {
+            Some(task) => Ok(task.clone()),
+            None => Err(ClientError::UnexpectedFailure(format!(
+                "Unable to find task with id : {}",
+                task
+            ))),
+        }
+    }
+
+    // Attachment ids are global like note ids, make sure the attachment is
+    // one of this task
+    fn check_attachment(task: &Task, attachment_id: i64) -> Result<(), ClientError> {
+        if !task.attachments.iter().any(|attachment| attachment.id == attachment_id) {
+            return Err(ClientError::ArgumentError(format!(
+                "Task {} doesn't have attachment {}",
+                task.id, attachment_id
+            )));
+        }
+        Ok(())
+    }
+
+    fn print(operation: &mut impl Operation) -> Result<String, ClientError> {
+        execute(operation)?;
+        for task in operation.get_result().iter() {
+            Display::print(&Attachment::format_attachments(task));
+        }
+        Ok(String::new())
+    }
+
+    fn format_attachments(task: &Task) -> String {
+        let mut output = String::from("Id\tKind\tSize\tCreated At\tLocation\n");
+        for attachment in task.attachments.iter() {
+            let size = attachment.size.map(|size| size.to_string()).unwrap_or_default();
+            output.push_str(&format!(
+                "{}\t{}\t{}\t{}\t{}\n",
+                attachment.id, attachment.kind, size, attachment.created_at, attachment.location
+            ));
+        }
+        output
+    }
+}
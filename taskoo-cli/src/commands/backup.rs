@@ -0,0 +1,72 @@
+use anyhow::Result;
+use log::info;
+
+use taskoo_core::operation::{
+    execute_backup, Backup as BackupSnapshot, BackupAction, Backups as BackupsOp,
+};
+
+use crate::display::Display;
+
+pub struct Backup;
+
+// taskoo backup
+// taskoo backup list
+// taskoo restore 20261018-153000-delete
+impl Backup {
+    pub fn create() -> Result<String> {
+        info!("Taking a snapshot of the database");
+        let snapshots = Backup::run(BackupAction::Create)?;
+        Ok(format!("Saved snapshot {}", snapshots[0].name))
+    }
+
+    pub fn list() -> Result<String> {
+        info!("Listing the snapshots");
+        let snapshots = Backup::run(BackupAction::List)?;
+        if snapshots.is_empty() {
+            return Ok(String::from("No snapshots"));
+        }
+        let mut output = String::from("Snapshot\tCreated\tReason\tSize\n");
+        for snapshot in snapshots.iter() {
+            output.push_str(&format!(
+                "{}\t{}\t{}\t{} KiB\n",
+                snapshot.name,
+                snapshot.created_at,
+                snapshot.reason,
+                (snapshot.size + 1023) / 1024
+            ));
+        }
+        Display::print(&output);
+        Ok(String::new())
+    }
+
+    pub fn restore(name: &str) -> Result<String> {
+        info!("Restoring the database from {}", name);
+        let snapshots = Backup::run(BackupAction::Restore(name.to_owned()))?;
+        match snapshots.first() {
+            Some(previous) => Ok(format!(
+                "Restored snapshot {}, the database before it was saved as {}",
+                name, previous.name
+            )),
+            None => Ok(format!("Restored snapshot {}", name)),
+        }
+    }
+
+    // Whether `restore` was given the name of a snapshot rather than tasks
+    pub fn is_snapshot(arguments: &Vec<String>) -> bool {
+        if arguments.len() != 1 {
+            return false;
+        }
+        match Backup::run(BackupAction::List) {
+            Ok(snapshots) => snapshots
+                .iter()
+                .any(|snapshot| snapshot.name == arguments[0]),
+            Err(_) => false,
+        }
+    }
+
+    fn run(action: BackupAction) -> Result<Vec<BackupSnapshot>> {
+        let mut operation = BackupsOp::new(action);
+        execute_backup(&mut operation)?;
+        Ok(operation.get_result().clone())
+    }
+}
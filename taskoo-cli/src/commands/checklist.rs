@@ -0,0 +1,95 @@
+use log::info;
+
+use taskoo_core::core::Operation;
+use taskoo_core::operation::{
+    execute, AddChecklistItem, CheckChecklistItem, DeleteChecklistItem, Get as GetOp,
+    MoveChecklistItem, Task,
+};
+
+use crate::display::Display;
+use crate::error::ClientError;
+
+pub struct Checklist;
+
+// taskoo checklist 12
+// taskoo checklist 12 pack the charger
+// taskoo checklist 12 --move 3 1
+// taskoo checklist 12 --delete 2
+// taskoo check 12 2
+// taskoo uncheck 12 2
+impl Checklist {
+    pub fn run(
+        task: &str,
+        move_item: &Option<Vec<i64>>,
+        delete: &Option<i64>,
+        text: &Vec<String>,
+    ) -> Result<String, ClientError> {
+        let body = text.join(" ");
+        let task = Checklist::get_task(task)?;
+
+        if let Some(position) = delete {
+            info!("Deleting checklist item {} of task {}", position, task.id);
+            Checklist::print(&mut DeleteChecklistItem::new(task.id, *position))
+        } else if let Some(positions) = move_item {
+            info!(
+                "Moving checklist item {} of task {} to {}",
+                positions[0], task.id, positions[1]
+            );
+            Checklist::print(&mut MoveChecklistItem::new(
+                task.id,
+                positions[0],
+                positions[1],
+            ))
+        } else if !body.is_empty() {
+            info!("Adding a checklist item to task {}", task.id);
+            Checklist::print(&mut AddChecklistItem::new(task.id, body))
+        } else {
+            Display::print(&Checklist::format_checklist(&task));
+            Ok(String::new())
+        }
+    }
+
+    // Check the item at `position`, or uncheck it when `done` is false
+    pub fn check(task: &str, position: i64, done: bool) -> Result<String, ClientError> {
+        let task = Checklist::get_task(task)?;
+        info!(
+            "Setting checklist item {} of task {} to {}",
+            position, task.id, done
+        );
+        Checklist::print(&mut CheckChecklistItem::new(task.id, position, done))
+    }
+
+    fn get_task(task: &str) -> Result<Task, ClientError> {
+        let mut operation = GetOp::new();
+        operation.select_task(task)?;
+        execute(&mut operation)?;
+        match operation.get_result().first() {
+            Some(task) => Ok(task.clone()),
+            None => Err(ClientError::UnexpectedFailure(format!(
+                "Unable to find task with id : {}",
+                task
+            ))),
+        }
+    }
+
+    fn print(operation: &mut impl Operation) -> Result<String, ClientError> {
+        execute(operation)?;
+        for task in operation.get_result().iter() {
+            Display::print(&Checklist::format_checklist(task));
+        }
+        Ok(String::new())
+    }
+
+    pub fn format_checklist(task: &Task) -> String {
+        let mut output = String::from("Item\tDone\tStep\n");
+        for item in task.checklist.iter() {
+            output.push_str(&format!(
+                "{}\t[{}]\t{}\n",
+                item.position,
+                if item.done { "x" } else { " " },
+                item.body
+            ));
+        }
+        output
+    }
+}
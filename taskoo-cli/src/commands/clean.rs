@@ -4,7 +4,9 @@ use anyhow::{Result};
 use clap::ArgMatches;
 use log::{info};
 
-use taskoo_core::command::{TagCommand, ContextCommand, StateCommand, SimpleCommand};
+use taskoo_core::command::{
+    TagCommand, ContextCommand, StateCommand, ProjectCommand, SimpleCommand,
+};
 
 use dialoguer::{theme::ColorfulTheme, Select};
 
@@ -27,6 +29,10 @@ impl Clean {
                 let command = StateCommand::new()?;
                 return Clean::process_remove_state(command);
             }
+            "project" => {
+                let command = ProjectCommand::new()?;
+                return Clean::process_remove(command, "project");
+            }
             &_ => {
                 return Err(ClientError::UnexpectedFailure(
                 String::from("The provided type is neither 'context' nor 'tag', so we can't process it, but how come?")));
@@ -8,10 +8,13 @@ pub struct Delete;
 // taskoo delete 1 2 3 4
 // taskoo delete 1
 // taskoo delete 1..4
+// taskoo delete id:12
+// taskoo delete --cascade 5
 impl Delete {
-    pub fn delete(delete_config: &Vec<String>) -> Result<String, CoreError> {
+    pub fn delete(delete_config: &Vec<String>, cascade: bool) -> Result<String, CoreError> {
         info!("Process delete command!");
-        let mut operation = DeleteOperation::new(delete_config)?;
+        let mut operation = DeleteOperation::new_with_display_ids(delete_config)?;
+        operation.cascade = cascade;
 
         execute(&mut operation)?;
         Ok(String::new())
@@ -0,0 +1,58 @@
+use anyhow::Result;
+use log::info;
+
+use taskoo_core::operation::{execute_doctor, Doctor as DoctorOp, ProblemKind};
+
+use crate::display::Display;
+
+pub struct Doctor;
+
+// taskoo doctor
+// taskoo doctor --fix
+impl Doctor {
+    pub fn run(fix: bool) -> Result<String> {
+        info!("Checking the database, fix: {}", fix);
+        let mut operation = DoctorOp::new(fix);
+        execute_doctor(&mut operation)?;
+
+        let problems = operation.get_result();
+        if problems.is_empty() {
+            return Ok(String::from("No problems found"));
+        }
+
+        let mut output = String::from("Problem\tTasks\tDescription\n");
+        for problem in problems.iter() {
+            let task_ids: Vec<String> = problem.task_ids.iter().map(|id| id.to_string()).collect();
+            output.push_str(&format!(
+                "{}\t{}\t{}\n",
+                Doctor::format_kind(&problem.kind),
+                task_ids.join(","),
+                problem.description
+            ));
+        }
+        Display::print(&output);
+
+        if fix {
+            Ok(format!(
+                "Fixed {} problem(s), run `taskoo undo` to revert the repairs",
+                problems.len()
+            ))
+        } else {
+            Ok(format!(
+                "Found {} problem(s), run `taskoo doctor --fix` to repair them",
+                problems.len()
+            ))
+        }
+    }
+
+    fn format_kind(kind: &ProblemKind) -> &'static str {
+        match kind {
+            ProblemKind::OrphanedRow => "orphaned row",
+            ProblemKind::MissingState => "missing state",
+            ProblemKind::MissingContext => "missing context",
+            ProblemKind::DanglingDependency => "dangling dependency",
+            ProblemKind::DependencyCycle => "dependency cycle",
+            ProblemKind::InvalidDate => "invalid date",
+        }
+    }
+}
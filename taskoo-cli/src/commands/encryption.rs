@@ -0,0 +1,97 @@
+use anyhow::Result;
+use dialoguer::{theme::ColorfulTheme, Password};
+use log::info;
+
+use taskoo_core::core::ConfigManager;
+use taskoo_core::error::CoreError;
+use taskoo_core::operation::{execute_encryption, Encryption as EncryptionOp, EncryptionChange};
+
+use crate::error::ClientError;
+
+pub struct Encryption;
+
+// taskoo encryption enable
+// taskoo encryption rotate
+// taskoo encryption disable
+//
+// The current passphrase is taken from TASKOO_PASSPHRASE, key_file or
+// passphrase_command, and asked for when none of them unlocks the database.
+impl Encryption {
+    pub fn enable() -> Result<String> {
+        info!("Encrypting the database");
+        let passphrase = Encryption::prompt_new_passphrase()?;
+        let replaced = Encryption::run(EncryptionChange::Enable(passphrase))?;
+        Ok(Encryption::with_snapshots(
+            String::from("Encrypted the database, the undo history was cleared"),
+            replaced,
+        ))
+    }
+
+    pub fn rotate() -> Result<String> {
+        info!("Rotating the key of the database");
+        let passphrase = Encryption::prompt_new_passphrase()?;
+        let replaced =
+            Encryption::unlock(|| Encryption::run(EncryptionChange::Rotate(passphrase.clone())))?;
+        Ok(Encryption::with_snapshots(
+            String::from(
+                "Encrypted the database with the new passphrase, the undo history was cleared",
+            ),
+            replaced,
+        ))
+    }
+
+    pub fn disable() -> Result<String> {
+        info!("Decrypting the database");
+        Encryption::unlock(|| Encryption::run(EncryptionChange::Disable))?;
+        Ok(String::from(
+            "Decrypted the database, the undo history was cleared",
+        ))
+    }
+
+    // The snapshots taken before held the plain text or the old key
+    fn with_snapshots(mut message: String, replaced: usize) -> String {
+        if replaced > 0 {
+            message.push_str(&format!(
+                "\nWarning: the {} snapshots taken before were deleted, \
+                a new one was taken with the new key",
+                replaced
+            ));
+        }
+        message
+    }
+
+    // How many snapshots were replaced, see `Encryption::replaced_backups`
+    fn run(change: EncryptionChange) -> Result<usize, ClientError> {
+        let mut operation = EncryptionOp::new(change);
+        execute_encryption(&mut operation)?;
+        Ok(operation.replaced_backups)
+    }
+
+    // Run `change`, and once more with the passphrase of the user when the
+    // database is locked
+    fn unlock<T, F>(change: F) -> Result<T, ClientError>
+    where
+        F: Fn() -> Result<T, ClientError>,
+    {
+        match change() {
+            Err(ClientError::CoreError(CoreError::EncryptionError(reason))) => {
+                info!("The database is locked: {}", reason);
+                let passphrase = Password::with_theme(&ColorfulTheme::default())
+                    .with_prompt("Current passphrase")
+                    .interact()
+                    .map_err(|source| ClientError::TerminalError { source })?;
+                ConfigManager::set_passphrase(&passphrase);
+                change()
+            }
+            result => result,
+        }
+    }
+
+    fn prompt_new_passphrase() -> Result<String, ClientError> {
+        Password::with_theme(&ColorfulTheme::default())
+            .with_prompt("New passphrase")
+            .with_confirmation("Repeat the passphrase", "The passphrases don't match")
+            .interact()
+            .map_err(|source| ClientError::TerminalError { source })
+    }
+}
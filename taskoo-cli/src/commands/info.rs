@@ -3,9 +3,10 @@ use log::info;
 use anyhow::Result;
 
 use taskoo_core::core::Operation;
-use taskoo_core::operation::{execute, Get as GetOp};
+use taskoo_core::operation::{execute, execute_history, Get as GetOp, History as HistoryOp};
 use taskoo_core::command::{SimpleCommand, TagCommand};
 
+use crate::display::Display;
 use crate::error::ClientError;
 
 pub struct Info;
@@ -17,51 +18,36 @@ impl Info {
 
     pub fn run(
         &self,
-        task_id: &Option<u64>,
+        task: &Option<String>,
         attribute: &Option<String>,
+        history: bool,
     ) -> Result<String, ClientError> {
         info!("Running info command");
-        if task_id.is_none() && attribute.is_none() {
+        if task.is_none() && attribute.is_none() {
             return Err(ClientError::MissingAttrError {
                 attr: String::from("None of task_id or attribute is provided"),
             });
         }
-        // let task_id = match matches.value_of("task_id") {
-        //     Some(raw_task_id) => raw_task_id.parse::<i64>().map_err(|_error| {
-        //         ClientError::ParseError(String::from(raw_task_id), String::from("i64"))
-        //     })?,
-        //     None => {
-        //         return Err(ClientError::MissingAttrError {
-        //             attr: String::from("task_id"),
-        //             backtrace: Backtrace::capture(),
-        //         });
-        //     }
-        // };
 
-        if let Some(id) = task_id {
-            if *id > std::i64::MAX as u64 {
-                return Err(ClientError::MissingAttrError {
-                    attr: String::from("Provided task_id is greater than i64::MAX"),
-                });
-            }
-
-            let real_id = *id as i64;
-            info!("Task id: {:?}", real_id);
+        if let Some(reference) = task {
+            info!("Task: {:?}", reference);
             let mut operation = GetOp::new();
-            operation.task_id = Some(real_id);
+            operation.select_task(reference)?;
             execute(&mut operation)?;
 
             let tasks = &operation.get_result();
             if tasks.is_empty() {
                 return Err(ClientError::UnexpectedFailure(String::from(format!(
                     "Unable to find task with id : {}",
-                    real_id
+                    reference
                 ))));
             }
 
             assert_eq!(tasks.len(), 1);
 
-            if let Some(attr) = attribute {
+            if history {
+                Info::print_history(tasks[0].id)?;
+            } else if let Some(attr) = attribute {
                 println!("{}", tasks[0].get_property_value(attr)?);
             } else {
                 println!("{:?}", tasks[0]);
@@ -83,4 +69,19 @@ impl Info {
         }
         Ok(String::new())
     }
+
+    fn print_history(task_id: i64) -> Result<(), ClientError> {
+        let mut operation = HistoryOp::new(task_id);
+        execute_history(&mut operation)?;
+
+        let mut output = String::from("Changed At\tField\tOld\tNew\n");
+        for change in operation.get_result().iter() {
+            output.push_str(&format!(
+                "{}\t{}\t{}\t{}\n",
+                change.changed_at, change.field, change.old_value, change.new_value
+            ));
+        }
+        Display::print(&output);
+        Ok(())
+    }
 }
@@ -0,0 +1,33 @@
+use anyhow::Result;
+use log::info;
+
+use taskoo_core::core::Operation;
+use taskoo_core::operation::{execute, Redo, Task, Undo};
+
+pub struct Journal;
+
+// taskoo undo
+// taskoo redo
+impl Journal {
+    pub fn undo() -> Result<String> {
+        info!("Undoing the last change");
+        let mut operation = Undo::new();
+        execute(&mut operation)?;
+        Ok(Journal::format_tasks("Undone", operation.get_result()))
+    }
+
+    pub fn redo() -> Result<String> {
+        info!("Redoing the last undone change");
+        let mut operation = Redo::new();
+        execute(&mut operation)?;
+        Ok(Journal::format_tasks("Redone", operation.get_result()))
+    }
+
+    fn format_tasks(action: &str, tasks: &Vec<Task>) -> String {
+        let mut output = format!("{} change of {} task(s)", action, tasks.len());
+        for task in tasks.iter() {
+            output.push_str(&format!("\n  [id: {}, body: {}]", task.id, task.body));
+        }
+        output
+    }
+}
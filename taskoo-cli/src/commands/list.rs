@@ -7,7 +7,7 @@ use taskoo_core::error::CoreError;
 use taskoo_core::operation::{Get as GetOp};
 use taskoo_core::option_parser::{CommandOption, parse_command_option};
 
-use crate::display::Display;
+use crate::display::{format_effort, Display};
 
 pub struct List {
     config: Ini,
@@ -18,14 +18,30 @@ impl List {
         List { config: config }
     }
 
-    pub fn list(&self, all: bool, matches: &Vec<String>) -> Result<String, CoreError> {
+    pub fn list(
+        &self,
+        all: bool,
+        archived: bool,
+        with_archived: bool,
+        sort: &Option<String>,
+        matches: &Vec<String>,
+    ) -> Result<String, CoreError> {
         let mut operations = GetOp::new2(&matches)?;
         for operation_tuple in operations.iter_mut() {
-            let final_tabbed_string = String::from(&self.process_operation(
+            operation_tuple.1.archived = archived;
+            operation_tuple.1.with_archived = with_archived;
+            operation_tuple.1.sort_by = sort.clone();
+            // Archived tasks are always completed
+            let mut final_tabbed_string = String::from(&self.process_operation(
                 &operation_tuple.0,
                 &mut operation_tuple.1,
-                all,
+                all || archived || with_archived,
             )?);
+            if let Some(effort) = format_effort(&operation_tuple.1.get_effort()) {
+                if !final_tabbed_string.is_empty() {
+                    final_tabbed_string.push_str(&format!("Estimate: {}\n", effort));
+                }
+            }
             // Skip the contexts that doesn't have tasks
             if !final_tabbed_string.is_empty() {
                 Display::print(&final_tabbed_string);
@@ -40,31 +56,34 @@ impl List {
         operation: &mut GetOp,
         display_completed: bool,
     ) -> Result<String, CoreError> {
-        return Display::display(&context_name, operation, &self.config, display_completed);
+        let keep_order = operation.sort_by.is_some();
+        return Display::display(
+            &context_name,
+            operation,
+            &self.config,
+            display_completed,
+            keep_order,
+        );
     }
 
     pub fn get_operations(
         command_option: CommandOption,
         some_context_names: Option<Vec<String>>,
     ) -> Result<Vec<(String, GetOp)>, CoreError> {
-        let context_names = match some_context_names {
-            Some(context_names) => context_names,
-            None => {
-                // If no context names are passed, use all context
-                let mut command = ContextCommand::new()?;
-                let context_names = command.get_all()?;
-                context_names
-            }
-        };
+        // The contexts and their subcontexts as a tree, each with its own
+        // tasks. If no context names are passed, use all context
+        let context_names = ContextCommand::new()?.get_tree(&some_context_names)?;
 
         let mut result = vec![];
         for context in context_names.iter() {
             let mut operation = GetOp::new();
             operation.context = Some(context.to_string());
+            operation.exact_context = true;
             operation.tags = command_option.tags.clone();
             operation.date_due = command_option.date_due;
             operation.date_scheduled = command_option.date_scheduled;
             operation.not_tags = command_option.not_tags.clone();
+            operation.udas = command_option.udas.clone();
             result.push((context.to_string(), operation));
         }
         Ok(result)
@@ -1,9 +1,22 @@
 pub mod add;
 pub mod agenda;
+pub mod archive;
+pub mod attachment;
+pub mod backup;
+pub mod checklist;
 pub mod clean;
 pub mod delete;
+pub mod doctor;
+pub mod encryption;
 pub mod info;
+pub mod journal;
 pub mod list;
 pub mod modify;
+pub mod note;
+pub mod profile;
+pub mod project;
 pub mod review;
+pub mod search;
 pub mod state_changer;
+pub mod timesheet;
+pub mod trash;
@@ -8,7 +8,7 @@ pub struct Modify;
 impl Modify {
     pub fn modify(matches: &Vec<String>) -> Result<String> {
         info!("Modifying Task");
-        let mut operation = ModifyOperation::new(matches)
+        let mut operation = ModifyOperation::new_with_display_ids(matches)
             .context("Unable to parse the provided option for modify")?;
 
         debug!("Executing ModifyOperation {:?}", operation);
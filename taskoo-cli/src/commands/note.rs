@@ -0,0 +1,80 @@
+use log::info;
+
+use taskoo_core::core::Operation;
+use taskoo_core::operation::{execute, AddNote, DeleteNote, EditNote, Get as GetOp, Task};
+
+use crate::display::Display;
+use crate::error::ClientError;
+
+pub struct Note;
+
+// taskoo note 12
+// taskoo note id:40
+// taskoo note 12 called vendor
+// taskoo note 12 --edit 3 called vendor twice
+// taskoo note 12 --delete 3
+impl Note {
+    pub fn run(
+        task: &str,
+        edit: &Option<i64>,
+        delete: &Option<i64>,
+        text: &Vec<String>,
+    ) -> Result<String, ClientError> {
+        let body = text.join(" ");
+
+        let mut operation = GetOp::new();
+        operation.select_task(task)?;
+        execute(&mut operation)?;
+        let tasks = operation.get_result();
+        if tasks.is_empty() {
+            return Err(ClientError::UnexpectedFailure(format!(
+                "Unable to find task with id : {}",
+                task
+            )));
+        }
+        let task_id = tasks[0].id;
+
+        // Note ids are global, make sure the note is one of this task
+        if let Some(note_id) = delete.or(*edit) {
+            if !tasks[0].notes.iter().any(|note| note.id == note_id) {
+                return Err(ClientError::ArgumentError(format!(
+                    "Task {} doesn't have note {}",
+                    task_id, note_id
+                )));
+            }
+        }
+
+        if let Some(note_id) = delete {
+            info!("Deleting note {} of task {}", note_id, task_id);
+            Note::print(&mut DeleteNote::new(*note_id))
+        } else if let Some(note_id) = edit {
+            info!("Editing note {} of task {}", note_id, task_id);
+            Note::print(&mut EditNote::new(*note_id, body))
+        } else if !body.is_empty() {
+            info!("Adding a note to task {}", task_id);
+            Note::print(&mut AddNote::new(task_id, body))
+        } else {
+            Display::print(&Note::format_notes(&tasks[0]));
+            Ok(String::new())
+        }
+    }
+
+    fn print(operation: &mut impl Operation) -> Result<String, ClientError> {
+        execute(operation)?;
+        for task in operation.get_result().iter() {
+            Display::print(&Note::format_notes(task));
+        }
+        Ok(String::new())
+    }
+
+    fn format_notes(task: &Task) -> String {
+        let mut output = String::from("Id\tCreated At\tUpdated At\tNote\n");
+        for note in task.notes.iter() {
+            output.push_str(&format!(
+                "{}\t{}\t{}\t{}\n",
+                note.id, note.created_at, note.updated_at, note.body
+            ));
+        }
+        output
+    }
+}
@@ -0,0 +1,33 @@
+use anyhow::Result;
+use log::info;
+
+use taskoo_core::core::ConfigManager;
+
+use crate::display::Display;
+
+pub struct Profile;
+
+// taskoo profile list
+// taskoo profile use work
+impl Profile {
+    pub fn list() -> Result<String> {
+        info!("Listing the profiles");
+        let mut output = String::from("Active\tProfile\tDatabase\n");
+        for profile in ConfigManager::get_profiles()?.iter() {
+            output.push_str(&format!(
+                "{}\t{}\t{}\n",
+                if profile.active { "*" } else { "" },
+                profile.name,
+                profile.db_path
+            ));
+        }
+        Display::print(&output);
+        Ok(String::new())
+    }
+
+    pub fn use_profile(name: &str) -> Result<String> {
+        info!("Switching to profile {}", name);
+        ConfigManager::use_profile(name)?;
+        Ok(format!("Using profile {}", name))
+    }
+}
@@ -0,0 +1,50 @@
+use anyhow::Result;
+use log::info;
+
+use taskoo_core::command::{ProjectCommand, SimpleCommand};
+
+use crate::display::Display;
+
+pub struct Project;
+
+// taskoo projects
+// taskoo project wedding --due 2026-09-12 --status on-hold --description "..."
+impl Project {
+    pub fn list() -> Result<String> {
+        info!("Listing the projects");
+        let projects = ProjectCommand::new()?.projects()?;
+        if projects.is_empty() {
+            return Ok(String::from("No projects, join one with proj:<name>"));
+        }
+
+        let mut output = String::from("Project\tStatus\tDue\tProgress\tNext action\n");
+        for project in projects.iter() {
+            let next_action = match project.next_action_id {
+                Some(task_id) => format!("{} (id:{})", project.next_action, task_id),
+                None if project.is_stalled() => String::from("stalled"),
+                None => String::new(),
+            };
+            output.push_str(&format!(
+                "{}\t{}\t{}\t{}\t{}\n",
+                project.name,
+                project.status,
+                project.date_due,
+                project.format_progress(),
+                next_action
+            ));
+        }
+        Display::print(&output);
+        Ok(String::new())
+    }
+
+    pub fn update(
+        name: &str,
+        description: &Option<String>,
+        date_due: &Option<String>,
+        status: &Option<String>,
+    ) -> Result<String> {
+        info!("Updating project {}", name);
+        ProjectCommand::new()?.update(name, description, date_due, status)?;
+        Ok(format!("Project {} updated", name.to_lowercase()))
+    }
+}
@@ -1,9 +1,14 @@
-use taskoo_core::core::Operation;
+use taskoo_core::core::{ConfigManager, Operation};
+use taskoo_core::error::CoreError;
 use taskoo_core::command::{ContextCommand, TagCommand, SimpleCommand};
 use taskoo_core::operation::{Task, execute, Get as GetOperation, ModifyOperation, DeleteOperation};
+use taskoo_core::operation::{
+    AddChecklistItem, CheckChecklistItem, DeleteChecklistItem, MoveChecklistItem,
+};
 use taskoo_core::option_parser::{CommandOption, parse_command_option};
 
 use crate::List;
+use crate::commands::checklist::Checklist;
 use crate::error::ClientError;
 use crate::display::{Display, get_output_columns};
 
@@ -37,7 +42,8 @@ impl Review {
 
         let (option, context_name) = if !matches.is_empty() {
             let v2: Vec<&str> = matches.iter().map(|s| &**s).collect();
-            let option = parse_command_option(&v2, false, false, false)?;
+            let uda_names = ConfigManager::get_uda_names().map_err(CoreError::from)?;
+            let option = parse_command_option(&v2, false, false, false, &uda_names)?;
             match option.context {
                 Some(ref context) => {
                     let cloned_context = context.clone();
@@ -50,8 +56,9 @@ impl Review {
         };
 
         let mut operations_tuple = List::get_operations(option, Some(vec![context_name]))?;
-        assert_eq!(operations_tuple.len(), 1);
-        let mut op_tuple = &mut operations_tuple[0];
+        // The context comes before its subcontexts, which are reviewed with it
+        let op_tuple = &mut operations_tuple[0];
+        op_tuple.1.exact_context = false;
         match self.process_operation(&op_tuple.0, &mut op_tuple.1) {
             Ok(()) => {
                 return Ok(String::new());
@@ -116,9 +123,12 @@ impl Review {
                 .interact()
                 .map_err(|error| ClientError::TerminalError { source: error })?
             {
-                let mut operation = DeleteOperation {
+                let mut operation: DeleteOperation = DeleteOperation {
                     database_manager: None,
                     task_ids: vec![task.id],
+                    task_uuids: vec![],
+                    display_ids: vec![],
+                    cascade: false,
                     result: None,
                 };
                 execute(&mut operation)?;
@@ -129,6 +139,8 @@ impl Review {
         }
 
         println!("");
+        Review::review_checklist(task)?;
+
         let mut context_command = ContextCommand::new()?;
         let context_names = context_command.get_all()?;
 
@@ -229,6 +241,86 @@ impl Review {
         Ok(())
     }
 
+    // Edit the checklist one change at a time until `Done` is picked
+    fn review_checklist(task: &Task) -> Result<(), ClientError> {
+        let actions = [
+            "Done",
+            "Add an item",
+            "Check or uncheck an item",
+            "Move an item",
+            "Delete an item",
+        ];
+        let mut task = task.clone();
+        loop {
+            if !task.checklist.is_empty() {
+                Display::print(&Checklist::format_checklist(&task));
+            }
+            // Only adding makes sense without items
+            let available = if task.checklist.is_empty() { &actions[..2] } else { &actions[..] };
+            let action = Select::with_theme(&ColorfulTheme::default())
+                .with_prompt("Edit the checklist?")
+                .default(0)
+                .items(available)
+                .interact()
+                .map_err(|error| ClientError::TerminalError { source: error })?;
+
+            let changed = match action {
+                0 => return Ok(()),
+                1 => {
+                    let body: String = Input::with_theme(&ColorfulTheme::default())
+                        .with_prompt("New item?")
+                        .interact()
+                        .map_err(|error| ClientError::TerminalError { source: error })?;
+                    Review::change_checklist(&mut AddChecklistItem::new(task.id, body))
+                }
+                2 => {
+                    let position = Review::select_item(&task, "Which item?")?;
+                    let done = !task.checklist[position as usize - 1].done;
+                    Review::change_checklist(&mut CheckChecklistItem::new(task.id, position, done))
+                }
+                3 => {
+                    let from = Review::select_item(&task, "Which item?")?;
+                    let to: i64 = Input::with_theme(&ColorfulTheme::default())
+                        .with_prompt(format!("New position? (1 to {})", task.checklist.len()))
+                        .interact()
+                        .map_err(|error| ClientError::TerminalError { source: error })?;
+                    Review::change_checklist(&mut MoveChecklistItem::new(task.id, from, to))
+                }
+                _ => {
+                    let position = Review::select_item(&task, "Which item?")?;
+                    Review::change_checklist(&mut DeleteChecklistItem::new(task.id, position))
+                }
+            };
+            // A mistyped position shouldn't end the review
+            match changed {
+                Ok(changed) => task = changed,
+                Err(error) => eprintln!("{}", error),
+            }
+        }
+    }
+
+    // The position of the picked item
+    fn select_item(task: &Task, prompt: &str) -> Result<i64, ClientError> {
+        let items: Vec<String> = task
+            .checklist
+            .iter()
+            .map(|item| format!("[{}] {}", if item.done { "x" } else { " " }, item.body))
+            .collect();
+        let selection = Select::with_theme(&ColorfulTheme::default())
+            .with_prompt(prompt)
+            .default(0)
+            .items(&items)
+            .interact()
+            .map_err(|error| ClientError::TerminalError { source: error })?;
+        Ok(selection as i64 + 1)
+    }
+
+    // The task with its changed checklist
+    fn change_checklist(operation: &mut impl Operation) -> Result<Task, ClientError> {
+        execute(operation)?;
+        Ok(operation.get_result()[0].clone())
+    }
+
     fn ask_attribute(message: &str) -> Option<String> {
         print!("{}", message);
         io::stdout().flush().unwrap();
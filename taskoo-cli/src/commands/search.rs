@@ -0,0 +1,56 @@
+use anyhow::Result;
+use log::info;
+use yansi::{Color, Paint};
+
+use taskoo_core::operation::{execute_search, Search as SearchOp};
+
+use crate::display::Display;
+
+const HIGHLIGHT_START: &str = "\u{2}";
+const HIGHLIGHT_END: &str = "\u{3}";
+
+pub struct Search;
+
+// taskoo search vendor
+// taskoo search call vendor
+impl Search {
+    pub fn search(query: &Vec<String>) -> Result<String> {
+        info!("Searching tasks with {:?}", query);
+        let mut operation = SearchOp::new(query.join(" "));
+        operation.highlight_start = String::from(HIGHLIGHT_START);
+        operation.highlight_end = String::from(HIGHLIGHT_END);
+        execute_search(&mut operation)?;
+
+        let results = operation.get_result();
+        if results.is_empty() {
+            return Ok(String::from("No matching tasks"));
+        }
+
+        let mut output = String::from("Id\tContext\tMatch\n");
+        for result in results.iter() {
+            output.push_str(&format!(
+                "{}\t{}\t{}\n",
+                result.task.id,
+                result.task.context,
+                Search::highlight(&result.snippet)
+            ));
+        }
+        Display::print(&output);
+        Ok(String::new())
+    }
+
+    fn highlight(snippet: &str) -> String {
+        let mut output = String::new();
+        for (index, part) in snippet.split(HIGHLIGHT_START).enumerate() {
+            if index == 0 {
+                output.push_str(part);
+                continue;
+            }
+            let mut split = part.splitn(2, HIGHLIGHT_END);
+            let matched = split.next().unwrap_or("");
+            output.push_str(&Paint::new(matched).bold().fg(Color::Yellow).to_string());
+            output.push_str(split.next().unwrap_or(""));
+        }
+        output
+    }
+}
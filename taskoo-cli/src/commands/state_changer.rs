@@ -63,8 +63,9 @@ impl<'a> StateChanger<'a> {
         }
     }
 
-    pub fn run(&self, task_ids: &Vec<u64>) -> Result<String> {
-        let mut tokens: Vec<String> = task_ids.iter().map(|s| s.to_string()).collect();
+    // The tasks are display ids, id:<id> or UUID prefixes
+    pub fn run(&self, task_ids: &Vec<String>) -> Result<String> {
+        let mut tokens: Vec<String> = task_ids.clone();
         if self.to_started {
             tokens.push(String::from("@started"));
         } else if self.to_completed {
@@ -80,7 +81,7 @@ impl<'a> StateChanger<'a> {
 
         debug!("Running state_changer with {:?}", tokens);
 
-        let mut operation = ModifyOperation::new(&tokens)
+        let mut operation = ModifyOperation::new_with_display_ids(&tokens)
             .context("Unable to parse the provided option for modify")?;
 
         execute(&mut operation)?;
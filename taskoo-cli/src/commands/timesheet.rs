@@ -0,0 +1,163 @@
+use anyhow::Result;
+use chrono::{Local, NaiveDateTime, TimeZone, Utc};
+use log::info;
+use std::collections::BTreeMap;
+
+use taskoo_core::core::Operation;
+use taskoo_core::operation::{
+    execute, execute_interval, execute_timesheet, export_intervals, EditInterval, Get as GetOp,
+    TimeInterval, Timesheet as TimesheetOp,
+};
+
+use crate::display::Display;
+use crate::error::ClientError;
+
+pub struct Timesheet;
+
+// taskoo timesheet
+// taskoo timesheet 2026-07-01 2026-07-31 c:work +client
+// taskoo timesheet --export csv -7days today
+// taskoo interval add 12 "2026-07-01 09:00:00" "2026-07-01 10:30:00"
+// taskoo interval edit 3 --stop "2026-07-01 11:00:00"
+// taskoo interval delete 3
+impl Timesheet {
+    pub fn report(
+        arguments: &Vec<String>,
+        export: &Option<String>,
+        intervals: bool,
+    ) -> Result<String> {
+        info!("Running timesheet with {:?}", arguments);
+        let mut operation = TimesheetOp::new2(arguments)?;
+        execute_timesheet(&mut operation)?;
+
+        if let Some(format) = export {
+            return Ok(export_intervals(operation.get_intervals(), format)?);
+        }
+        if intervals {
+            Display::print(&Timesheet::format_intervals(operation.get_intervals()));
+            return Ok(String::new());
+        }
+
+        let entries = operation.get_result();
+        if entries.is_empty() {
+            return Ok(String::from("No time tracked"));
+        }
+
+        let mut output = String::from("Day\tContext\tTask\tTime\n");
+        let mut per_context: BTreeMap<&str, i64> = BTreeMap::new();
+        let mut per_task: BTreeMap<(i64, &str), i64> = BTreeMap::new();
+        let mut per_day: BTreeMap<String, i64> = BTreeMap::new();
+        for entry in entries.iter() {
+            let day = entry.day.to_string();
+            output.push_str(&format!(
+                "{}\t{}\tid:{} {}\t{}\n",
+                day,
+                entry.context,
+                entry.task_id,
+                entry.body,
+                Timesheet::format_seconds(entry.seconds)
+            ));
+            *per_context.entry(&entry.context).or_insert(0) += entry.seconds;
+            *per_task.entry((entry.task_id, &entry.body)).or_insert(0) += entry.seconds;
+            *per_day.entry(day).or_insert(0) += entry.seconds;
+        }
+
+        output.push_str("\nDay\tTime\n");
+        for (day, seconds) in per_day.iter() {
+            output.push_str(&format!("{}\t{}\n", day, Timesheet::format_seconds(*seconds)));
+        }
+        output.push_str("\nContext\tTime\n");
+        for (context, seconds) in per_context.iter() {
+            output.push_str(&format!("{}\t{}\n", context, Timesheet::format_seconds(*seconds)));
+        }
+        output.push_str("\nTask\tTime\n");
+        for ((task_id, body), seconds) in per_task.iter() {
+            output.push_str(&format!(
+                "id:{} {}\t{}\n",
+                task_id,
+                body,
+                Timesheet::format_seconds(*seconds)
+            ));
+        }
+        Display::print(&output);
+
+        let total = entries.iter().map(|entry| entry.seconds).sum();
+        Ok(format!("Total: {}", Timesheet::format_seconds(total)))
+    }
+
+    pub fn add_interval(task: &str, start: &str, stop: &str) -> Result<String> {
+        let task_id = Timesheet::get_task_id(task)?;
+        info!("Adding an interval to task {}", task_id);
+        Timesheet::print(&mut EditInterval::add(task_id, start.to_string(), stop.to_string()))
+    }
+
+    pub fn edit_interval(
+        interval_id: i64,
+        start: &Option<String>,
+        stop: &Option<String>,
+    ) -> Result<String> {
+        if start.is_none() && stop.is_none() {
+            return Err(ClientError::ArgumentError(String::from(
+                "Provide the new time with --start or --stop",
+            ))
+            .into());
+        }
+        info!("Editing interval {}", interval_id);
+        Timesheet::print(&mut EditInterval::edit(interval_id, start.clone(), stop.clone()))
+    }
+
+    pub fn delete_interval(interval_id: i64) -> Result<String> {
+        info!("Deleting interval {}", interval_id);
+        Timesheet::print(&mut EditInterval::delete(interval_id))
+    }
+
+    fn get_task_id(task: &str) -> Result<i64, ClientError> {
+        let mut operation = GetOp::new();
+        operation.select_task(task)?;
+        execute(&mut operation)?;
+        match operation.get_result().first() {
+            Some(task) => Ok(task.id),
+            None => Err(ClientError::UnexpectedFailure(format!(
+                "Unable to find task with id : {}",
+                task
+            ))),
+        }
+    }
+
+    fn print(operation: &mut EditInterval) -> Result<String> {
+        execute_interval(operation)?;
+        Display::print(&Timesheet::format_intervals(operation.get_result()));
+        Ok(String::new())
+    }
+
+    fn format_intervals(intervals: &Vec<TimeInterval>) -> String {
+        let mut output = String::from("Id\tTask\tStarted At\tStopped At\n");
+        for interval in intervals.iter() {
+            output.push_str(&format!(
+                "{}\tid:{} {}\t{}\t{}\n",
+                interval.id,
+                interval.task_id,
+                interval.body,
+                Timesheet::format_time(&interval.started_at),
+                Timesheet::format_time(&interval.stopped_at)
+            ));
+        }
+        output
+    }
+
+    // The intervals are kept in UTC, they're shown in local time
+    fn format_time(time: &str) -> String {
+        match NaiveDateTime::parse_from_str(time, "%Y-%m-%d %H:%M:%S") {
+            Ok(time) => Utc
+                .from_utc_datetime(&time)
+                .with_timezone(&Local)
+                .format("%Y-%m-%d %H:%M:%S")
+                .to_string(),
+            Err(_) => String::from("running"),
+        }
+    }
+
+    fn format_seconds(seconds: i64) -> String {
+        format!("{}h {:02}m", seconds / 3600, seconds % 3600 / 60)
+    }
+}
@@ -0,0 +1,54 @@
+use anyhow::Result;
+use ini::Ini;
+use log::info;
+
+use taskoo_core::core::Operation;
+use taskoo_core::operation::{execute, Purge, Restore, Task, Trash as TrashOp};
+
+use crate::display::Display;
+
+pub struct Trash {
+    config: Ini,
+}
+
+// taskoo trash
+// taskoo restore 1 2 3
+// taskoo purge --older-than 30days
+impl Trash {
+    pub fn new(config: Ini) -> Trash {
+        Trash { config: config }
+    }
+
+    pub fn list(&self) -> Result<String> {
+        info!("Listing the tasks in the trash");
+        let mut operation = TrashOp::new();
+        let output = Display::display("trash", &mut operation, &self.config, true, false)?;
+        if output.is_empty() {
+            return Ok(String::from("The trash is empty"));
+        }
+        Display::print(&output);
+        Ok(String::new())
+    }
+
+    pub fn restore(task_ids: &Vec<String>) -> Result<String> {
+        info!("Restoring tasks {:?}", task_ids);
+        let mut operation = Restore::new(task_ids)?;
+        execute(&mut operation)?;
+        Ok(Trash::format_tasks("Restored", operation.get_result()))
+    }
+
+    pub fn purge(older_than: &Option<String>) -> Result<String> {
+        info!("Purging the trash, older than {:?}", older_than);
+        let mut operation = Purge::new(older_than.clone());
+        execute(&mut operation)?;
+        Ok(Trash::format_tasks("Purged", operation.get_result()))
+    }
+
+    fn format_tasks(action: &str, tasks: &Vec<Task>) -> String {
+        let mut output = format!("{} {} task(s)", action, tasks.len());
+        for task in tasks.iter() {
+            output.push_str(&format!("\n  [id: {}, body: {}]", task.id, task.body));
+        }
+        output
+    }
+}
@@ -3,11 +3,13 @@ use ini::Ini;
 use log::info;
 use std::io::Write;
 use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
 use std::convert::TryInto;
 use tabwriter::TabWriter;
 use taskoo_core::core::Operation;
 use taskoo_core::error::CoreError;
-use taskoo_core::operation::{execute, Task};
+use taskoo_core::operation::{execute, format_estimate, Effort, Task};
+use taskoo_core::option_parser::TASK_ID_PREFIX;
 use yansi::Color;
 use yansi::Paint;
 use terminal_size::{Width, Height, terminal_size};
@@ -150,7 +152,7 @@ impl DisplayColumn {
     fn get_data(&self, task: &Task, config: &Ini) -> String {
         match *self {
             DisplayColumn::Id => {
-                let mut task_id = task.id.to_string();
+                let mut task_id = format_task_id(task);
                 if !task.repetition_due.is_empty() || !task.repetition_scheduled.is_empty() {
                     task_id.push_str("(R)");
                 }
@@ -166,6 +168,15 @@ impl DisplayColumn {
             DisplayColumn::Body => {
                 let mut task_body = String::clone(&task.body);
 
+                // Parents show how many of their subtasks are done, and
+                // tasks with a checklist how many of its items
+                if task.subtasks_total > 0 {
+                    task_body.push_str(&format!(" [{}]", task.format_subtask_progress()));
+                }
+                if !task.checklist.is_empty() {
+                    task_body.push_str(&format!(" [{}]", task.format_checklist_progress()));
+                }
+
                 if !task.annotation.is_empty() {
                     task_body.push_str(&Paint::new("*").fg(Color::White).bold().to_string());
                 }
@@ -191,6 +202,20 @@ impl DisplayColumn {
                     );
                 }
 
+                // Then the project of the task
+                if !task.project.is_empty() {
+                    task_body.push_str(" ");
+                    task_body.push_str(
+                        &Paint::new(format!("proj:{}", task.project)).dimmed().to_string(),
+                    );
+                }
+
+                // And the UDAs after the tags
+                if !task.udas.is_empty() {
+                    task_body.push_str(" ");
+                    task_body.push_str(&Paint::new(task.format_udas()).dimmed().to_string());
+                }
+
                 let color_code_name = if task.is_started() {
                     "started_task_color"
                 } else if task.is_completed() {
@@ -305,6 +330,28 @@ pub fn get_output_columns() -> Vec<DisplayColumn> {
     };
 }
 
+// The display id of pending tasks, other tasks are referred to by id:<id>
+fn format_task_id(task: &Task) -> String {
+    match task.display_id {
+        Some(display_id) => display_id.to_string(),
+        None => format!("{}{}", TASK_ID_PREFIX, task.id),
+    }
+}
+
+// The estimates and the time tracked on the estimated tasks, nothing
+// when none of the tasks has an estimate
+pub fn format_effort(effort: &Effort) -> Option<String> {
+    if effort.estimated == 0 {
+        return None;
+    }
+    Some(format!(
+        "{} remaining, {} estimated vs {} tracked",
+        format_estimate(effort.remaining),
+        format_estimate(effort.estimated),
+        format_estimate(effort.tracked / 60)
+    ))
+}
+
 fn to_first_letter_capitalized(s: &str) -> String {
     let mut c = s.chars();
     match c.next() {
@@ -387,7 +434,7 @@ impl AgendaDisplayColumn {
                         .unwrap_or(DisplayColors::IdHeader.get_color_code()),
                     None => DisplayColors::IdHeader.get_color_code(),
                 };
-                return Paint::new(task.id.to_string())
+                return Paint::new(format_task_id(task))
                     .fg(Color::Fixed(code))
                     .to_string();
             }
@@ -508,9 +555,13 @@ impl AgendaDisplayColumn {
     }
 }
 impl DisplayAgenda {
-    pub fn display(tasks: &Vec<(NaiveDate, Vec<Task>)>, config: &Ini) -> Result<(), CoreError> {
+    pub fn display(
+        tasks: &Vec<(NaiveDate, Vec<Task>)>,
+        efforts: &Vec<(NaiveDate, Vec<(String, Effort)>)>,
+        config: &Ini,
+    ) -> Result<(), CoreError> {
         let mut output = String::new();
-        for day_tasks in tasks.iter() {
+        for (day_tasks, day_efforts) in tasks.iter().zip(efforts.iter()) {
             let day = day_tasks.0;
             let tasks_on_day = &day_tasks.1;
             // Print the day as `Sunday 27 March 2016`
@@ -537,6 +588,15 @@ impl DisplayAgenda {
                 output.push_str(&task_row);
                 output.push_str("\n");
             }
+            for (context, effort) in day_efforts.1.iter() {
+                if let Some(effort) = format_effort(effort) {
+                    output.push_str(&format!(
+                        "{} estimate: {}\n",
+                        to_first_letter_capitalized(context),
+                        effort
+                    ));
+                }
+            }
             Display::print(&output);
             output.clear();
         }
@@ -581,16 +641,19 @@ impl Display {
         operation: &mut impl Operation,
         config: &Ini,
         display_completed: bool,
+        keep_order: bool, // The tasks are already sorted, see `Get::sort_by`
     ) -> Result<String, CoreError> {
         let processed_operation =
-            Display::process_operation(operation, &config, display_completed)?;
+            Display::process_operation(operation, &config, display_completed, keep_order)?;
 
         if processed_operation.1 == 0 {
             return Ok(String::from(""));
         }
 
+        // Subcontexts are indented under their parent
         println!(
-            "{}",
+            "{}{}",
+            "  ".repeat(context_name.matches('.').count()),
             Paint::new(format!(
                 "{}({})",
                 to_first_letter_capitalized(context_name),
@@ -647,6 +710,7 @@ impl Display {
         operation: &mut impl Operation,
         config: &Ini,
         display_completed: bool,
+        keep_order: bool,
     ) -> Result<(String, usize), CoreError> {
         // TODO Why &mut operation doesn't work?
         execute(operation)?;
@@ -688,20 +752,61 @@ impl Display {
         };
 
         // Sort tasks based on priority -> created_at
-        result.sort_by(
-            |task2, task1| match priority_cmp(&task1.priority, &task2.priority) {
-                Ordering::Equal => {
-                    return task1.date_created.cmp(&task2.date_created);
+        if !keep_order {
+            result.sort_by(
+                |task2, task1| match priority_cmp(&task1.priority, &task2.priority) {
+                    Ordering::Equal => {
+                        return task1.date_created.cmp(&task2.date_created);
+                    }
+                    Ordering::Less => Ordering::Less,
+                    Ordering::Greater => Ordering::Greater,
+                },
+            );
+        }
+
+        // Subtasks follow their parent, indented under it
+        let tree: Vec<Task> = Display::order_as_tree(&result)
+            .into_iter()
+            .map(|(task, depth)| {
+                let mut task = task.clone();
+                if depth > 0 {
+                    task.body = format!("{}└ {}", "  ".repeat(depth - 1), task.body);
                 }
-                Ordering::Less => Ordering::Less,
-                Ordering::Greater => Ordering::Greater,
-            },
-        );
+                task
+            })
+            .collect();
 
-        tabbed_output = Display::get_tabbed_output_for_tasks(&result, &config);
+        tabbed_output = Display::get_tabbed_output_for_tasks(&tree.iter().collect(), &config);
         Ok((tabbed_output, result.len()))
     }
 
+    // The tasks with their depth in the tree of subtasks, in their order. A
+    // subtask whose parent isn't listed is shown at the top level.
+    fn order_as_tree<'a>(tasks: &Vec<&'a Task>) -> Vec<(&'a Task, usize)> {
+        let listed: HashSet<i64> = tasks.iter().map(|task| task.id).collect();
+        let mut children: HashMap<i64, Vec<&'a Task>> = HashMap::new();
+        let mut roots = vec![];
+        for task in tasks.iter() {
+            match task.parent_id {
+                Some(parent_id) if listed.contains(&parent_id) => {
+                    children.entry(parent_id).or_default().push(task)
+                }
+                _ => roots.push(*task),
+            }
+        }
+
+        let mut ordered = vec![];
+        let mut stack: Vec<(&'a Task, usize)> =
+            roots.into_iter().rev().map(|task| (task, 0)).collect();
+        while let Some((task, depth)) = stack.pop() {
+            ordered.push((task, depth));
+            if let Some(subtasks) = children.get(&task.id) {
+                stack.extend(subtasks.iter().rev().map(|subtask| (*subtask, depth + 1)));
+            }
+        }
+        ordered
+    }
+
     pub fn get_tabbed_output_for_tasks(tasks: &Vec<&Task>, config: &Ini) -> String {
         let mut tabbed_output = String::new();
         for task in tasks {
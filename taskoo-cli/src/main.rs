@@ -3,16 +3,31 @@ use clap::{Subcommand};
 use ini::Ini;
 use log::{info, debug};
 use directories::ProjectDirs;
+use taskoo_core::core::ConfigManager;
+use taskoo_core::option_parser::{is_uuid_prefix, TASK_ID_PREFIX};
 
 use commands::add::Add;
 use commands::delete::Delete;
+use commands::doctor::Doctor;
+use commands::encryption::Encryption;
 use commands::state_changer::StateChanger;
 use commands::info::Info;
+use commands::journal::Journal;
+use commands::trash::Trash;
 use commands::list::List;
 use commands::modify::Modify;
+use commands::note::Note;
+use commands::profile::Profile;
+use commands::project::Project;
 use commands::review::Review;
+use commands::search::Search;
 use commands::agenda::Agenda;
+use commands::archive::Archive;
+use commands::attachment::Attachment;
+use commands::backup::Backup;
+use commands::checklist::Checklist;
 use commands::clean::Clean;
+use commands::timesheet::Timesheet;
 
 mod commands;
 mod display;
@@ -53,7 +68,7 @@ fn handle_result(result: Result<String, anyhow::Error>) {
 
 #[derive(Debug)]
 struct InfoCommand {
-    task_id: Option<u64>,
+    task: Option<String>,
     attribute: Option<String>,
 }
 
@@ -69,6 +84,12 @@ use clap::Parser;
     long_about = ""
 )]
 struct Cli {
+    /// Use the database of this profile instead of the default one
+    #[clap(long, global = true)]
+    profile: Option<String>,
+    /// Use this database file, regardless of the profiles
+    #[clap(long, global = true, conflicts_with = "profile")]
+    db: Option<String>,
     #[clap(subcommand)]
     command: Commands,
 }
@@ -86,6 +107,15 @@ enum Commands {
     List {
         #[clap(short, long)]
         all: bool,
+        /// Show the archived tasks instead
+        #[clap(long)]
+        archived: bool,
+        /// Show the archived tasks after the others
+        #[clap(long, conflicts_with = "archived")]
+        with_archived: bool,
+        /// Sort the tasks by a UDA or a task property, like cost or date_due
+        #[clap(long)]
+        sort: Option<String>,
         /// Apply filters to the search query
         arguments: Vec<String>,
     },
@@ -98,6 +128,9 @@ enum Commands {
         arguments: Vec<String>,
     },
     Delete {
+        /// Delete the subtasks of the tasks too, instead of moving them up
+        #[clap(long)]
+        cascade: bool,
         /// Apply filters to the search query
         arguments: Vec<String>,
     },
@@ -106,24 +139,229 @@ enum Commands {
         start_day: String,
         end_day: Option<String>,
     },
+    /// Show the notes of the given task, or append a note to it
+    Note {
+        /// Display id, id:<id> or UUID prefix of the task
+        task: String,
+        /// Replace the text of this note instead
+        #[clap(long)]
+        edit: Option<i64>,
+        /// Delete this note
+        #[clap(long, conflicts_with = "edit")]
+        delete: Option<i64>,
+        text: Vec<String>,
+    },
+    /// Attach a URL or a file to the given task
+    Attach {
+        /// Display id, id:<id> or UUID prefix of the task
+        task: String,
+        /// A URL, or the path of a file
+        location: String,
+        /// Copy the file into the database instead of keeping its path
+        #[clap(long)]
+        copy: bool,
+    },
+    /// Show the attachments of the given task
+    Attachments {
+        /// Display id, id:<id> or UUID prefix of the task
+        task: String,
+    },
+    /// Open an attachment of the given task with $BROWSER or xdg-open
+    Open {
+        /// Display id, id:<id> or UUID prefix of the task
+        task: String,
+        /// Can be left out when the task has a single attachment
+        attachment: Option<i64>,
+    },
+    /// Remove an attachment from the given task
+    Detach {
+        /// Display id, id:<id> or UUID prefix of the task
+        task: String,
+        attachment: i64,
+    },
+    /// Show the checklist of the given task, or append an item to it
+    Checklist {
+        /// Display id, id:<id> or UUID prefix of the task
+        task: String,
+        /// Move the item at the first position to the second
+        #[clap(
+            long = "move",
+            number_of_values = 2,
+            value_names = &["FROM", "TO"],
+            conflicts_with = "delete"
+        )]
+        move_item: Option<Vec<i64>>,
+        /// Delete the item at this position
+        #[clap(long)]
+        delete: Option<i64>,
+        text: Vec<String>,
+    },
+    /// Check an item of the checklist of the given task
+    Check {
+        /// Display id, id:<id> or UUID prefix of the task
+        task: String,
+        /// The position of the item, from 1
+        item: i64,
+    },
+    /// Uncheck an item of the checklist of the given task
+    Uncheck {
+        /// Display id, id:<id> or UUID prefix of the task
+        task: String,
+        /// The position of the item, from 1
+        item: i64,
+    },
+    /// Find tasks by the words in their body or annotation
+    Search { query: Vec<String> },
     /// Show information about the given task
-    Info { input: String },
-    /// Clean context, tag or state
+    Info {
+        /// Show every recorded change of the task
+        #[clap(long)]
+        history: bool,
+        input: String,
+    },
+    /// Clean context, tag, state or project
     Clean { provided_type: String },
     /// Change the state of the given tasks to 'start'
-    Start { task_ids: Vec<u64> },
+    Start { task_ids: Vec<String> },
+    /// Stop the time tracking of the given tasks and change their state to 'ready'
+    Stop { task_ids: Vec<String> },
     /// Change the state of the given tasks to 'complete'
-    Complete { task_ids: Vec<u64> },
+    Complete { task_ids: Vec<String> },
     /// Change the state of the given tasks to 'ready'
-    Ready { task_ids: Vec<u64> },
+    Ready { task_ids: Vec<String> },
     /// Change the state of the given tasks to 'block'
-    Block { task_ids: Vec<u64> },
+    Block { task_ids: Vec<String> },
+    /// Revert the last add, modify, delete or state change
+    Undo,
+    /// Apply the change that was undone most recently
+    Redo,
+    /// Show the deleted tasks
+    Trash,
+    /// Move the given tasks out of the trash, or replace the database with a snapshot
+    Restore { task_ids: Vec<String> },
+    /// Take a snapshot of the database, or show the snapshots
+    Backup {
+        #[clap(subcommand)]
+        command: Option<BackupCommands>,
+    },
+    /// Move the tasks completed more than `archive_after_days` ago into the archive
+    Archive {
+        /// Archive the tasks completed more than this many days ago instead
+        #[clap(long)]
+        days: Option<i64>,
+    },
+    /// Move the given tasks out of the archive
+    Unarchive { task_ids: Vec<String> },
+    /// Permanently remove the deleted tasks
+    Purge {
+        /// Only purge the tasks deleted before this period or date, e.g. 30days
+        #[clap(long)]
+        older_than: Option<String>,
+    },
+    /// Check the database for inconsistencies
+    Doctor {
+        /// Repair the problems that are found
+        #[clap(long)]
+        fix: bool,
+    },
+    /// Encrypt the bodies, annotations and notes with a passphrase
+    Encryption {
+        #[clap(subcommand)]
+        command: EncryptionCommands,
+    },
+    /// Show the tracked time per day, context and task
+    Timesheet {
+        /// Print the intervals as csv or json instead
+        #[clap(long)]
+        export: Option<String>,
+        /// List the intervals with their ids instead
+        #[clap(long, conflicts_with = "export")]
+        intervals: bool,
+        /// [start day] [end day] [c:<context>] [+<tag>]..., after the options
+        #[clap(allow_hyphen_values = true)]
+        arguments: Vec<String>,
+    },
+    /// Add, change or delete the tracked time of a task
+    Interval {
+        #[clap(subcommand)]
+        command: IntervalCommands,
+    },
+    /// Show the projects with their progress and next action
+    Projects,
+    /// Change the due date, status or description of a project
+    Project {
+        name: String,
+        /// Due date of the project, an empty one removes it
+        #[clap(long)]
+        due: Option<String>,
+        /// active, on-hold or done
+        #[clap(long)]
+        status: Option<String>,
+        #[clap(long)]
+        description: Option<String>,
+    },
+    /// Show the profiles or change the default one
+    Profile {
+        #[clap(subcommand)]
+        command: ProfileCommands,
+    },
+}
+
+#[derive(Subcommand)]
+enum IntervalCommands {
+    /// Record time spent on a task
+    Add {
+        /// Display id, id:<id> or UUID prefix of the task
+        task: String,
+        start: String,
+        stop: String,
+    },
+    /// Change when an interval started or stopped
+    Edit {
+        id: i64,
+        #[clap(long)]
+        start: Option<String>,
+        #[clap(long)]
+        stop: Option<String>,
+    },
+    /// Delete an interval
+    Delete { id: i64 },
+}
+
+#[derive(Subcommand)]
+enum BackupCommands {
+    /// Show the snapshots, the latest first
+    List,
+}
+
+#[derive(Subcommand)]
+enum EncryptionCommands {
+    /// Encrypt the database with a new passphrase
+    Enable,
+    /// Encrypt the database again with another passphrase
+    Rotate,
+    /// Store everything decrypted again
+    Disable,
+}
+
+#[derive(Subcommand)]
+enum ProfileCommands {
+    /// Show the profiles and their databases
+    List,
+    /// Use this profile when no other is selected
+    Use { name: String },
 }
 
 fn main() -> Result<(), ClientError> {
     env_logger::init();
     let cli = Cli::parse();
 
+    if let Some(db_path) = &cli.db {
+        ConfigManager::select_database(db_path);
+    } else if let Some(profile) = &cli.profile {
+        ConfigManager::select_profile(profile);
+    }
+
     match &cli.command {
         Commands::Add {
             annotation,
@@ -131,10 +369,22 @@ fn main() -> Result<(), ClientError> {
         } => {
             handle_result(Add::add(annotation, arguments).context("add command failed to operate"));
         }
-        Commands::List { all, arguments } => {
+        Commands::List {
+            all,
+            archived,
+            with_archived,
+            sort,
+            arguments,
+        } => {
             handle_result(
                 List::new(get_config())
-                    .list(all.to_owned(), arguments)
+                    .list(
+                        all.to_owned(),
+                        archived.to_owned(),
+                        with_archived.to_owned(),
+                        sort,
+                        arguments,
+                    )
                     .context("list command failed to operate"),
             );
         }
@@ -149,29 +399,77 @@ fn main() -> Result<(), ClientError> {
         Commands::Modify { arguments } => {
             handle_result(Modify::modify(arguments).context("modify command failed to operate"))
         }
-        Commands::Delete { arguments } => {
-            handle_result(Delete::delete(arguments).context("delete command failed to operate"));
+        Commands::Delete { cascade, arguments } => {
+            handle_result(
+                Delete::delete(arguments, *cascade).context("delete command failed to operate"),
+            );
         }
         Commands::Agenda { start_day, end_day } => handle_result(
             Agenda::new(get_config())
                 .agenda(&start_day, &end_day)
                 .context("agenda command failed to operate"),
         ),
-        Commands::Info { input } => {
+        Commands::Note {
+            task,
+            edit,
+            delete,
+            text,
+        } => handle_result(
+            Note::run(task, edit, delete, text).context("note command failed to operate"),
+        ),
+        Commands::Attach {
+            task,
+            location,
+            copy,
+        } => handle_result(
+            Attachment::attach(task, location, *copy).context("attach command failed to operate"),
+        ),
+        Commands::Attachments { task } => handle_result(
+            Attachment::list(task).context("attachments command failed to operate"),
+        ),
+        Commands::Open { task, attachment } => handle_result(
+            Attachment::open(task, attachment).context("open command failed to operate"),
+        ),
+        Commands::Detach { task, attachment } => handle_result(
+            Attachment::detach(task, *attachment).context("detach command failed to operate"),
+        ),
+        Commands::Checklist {
+            task,
+            move_item,
+            delete,
+            text,
+        } => handle_result(
+            Checklist::run(task, move_item, delete, text)
+                .context("checklist command failed to operate"),
+        ),
+        Commands::Check { task, item } => handle_result(
+            Checklist::check(task, *item, true).context("check command failed to operate"),
+        ),
+        Commands::Uncheck { task, item } => handle_result(
+            Checklist::check(task, *item, false).context("uncheck command failed to operate"),
+        ),
+        Commands::Search { query } => {
+            handle_result(Search::search(query).context("search command failed to operate"))
+        }
+        Commands::Info { input, history } => {
             let mut info_command = InfoCommand {
-                task_id: None,
+                task: None,
                 attribute: None,
             };
 
-            // Try to parse the input as a task_id (u64), otherwise treat it as an attribute
-            if let Ok(task_id) = input.parse::<u64>() {
-                info_command.task_id = Some(task_id);
+            // Try to parse the input as a display id, id:<id> or a UUID
+            // prefix, otherwise treat it as an attribute
+            if input.parse::<u64>().is_ok()
+                || input.starts_with(TASK_ID_PREFIX)
+                || is_uuid_prefix(input)
+            {
+                info_command.task = Some(input.clone());
             } else {
                 info_command.attribute = Some(input.clone());
             }
             let info = Info::new();
             handle_result(
-                info.run(&info_command.task_id, &info_command.attribute)
+                info.run(&info_command.task, &info_command.attribute, *history)
                     .context("info command failed to operate"),
             );
         }
@@ -183,6 +481,11 @@ fn main() -> Result<(), ClientError> {
                 .run(task_ids)
                 .context("start command failed to operate"),
         ),
+        Commands::Stop { task_ids } => handle_result(
+            StateChanger::to_ready()
+                .run(task_ids)
+                .context("stop command failed to operate"),
+        ),
         Commands::Complete { task_ids } => handle_result(
             StateChanger::to_completed()
                 .run(task_ids)
@@ -198,6 +501,92 @@ fn main() -> Result<(), ClientError> {
                 .run(task_ids)
                 .context("block command failed to operate"),
         ),
+        Commands::Undo => {
+            handle_result(Journal::undo().context("undo command failed to operate"))
+        }
+        Commands::Redo => {
+            handle_result(Journal::redo().context("redo command failed to operate"))
+        }
+        Commands::Trash => handle_result(
+            Trash::new(get_config())
+                .list()
+                .context("trash command failed to operate"),
+        ),
+        Commands::Restore { task_ids } => handle_result(
+            if Backup::is_snapshot(task_ids) {
+                Backup::restore(&task_ids[0])
+            } else {
+                Trash::restore(task_ids)
+            }
+            .context("restore command failed to operate"),
+        ),
+        Commands::Backup { command } => handle_result(
+            match command {
+                Some(BackupCommands::List) => Backup::list(),
+                None => Backup::create(),
+            }
+            .context("backup command failed to operate"),
+        ),
+        Commands::Archive { days } => handle_result(
+            Archive::archive(days).context("archive command failed to operate"),
+        ),
+        Commands::Unarchive { task_ids } => handle_result(
+            Archive::unarchive(task_ids).context("unarchive command failed to operate"),
+        ),
+        Commands::Purge { older_than } => handle_result(
+            Trash::purge(older_than).context("purge command failed to operate"),
+        ),
+        Commands::Doctor { fix } => {
+            handle_result(Doctor::run(*fix).context("doctor command failed to operate"))
+        }
+        Commands::Encryption { command } => handle_result(
+            match command {
+                EncryptionCommands::Enable => Encryption::enable(),
+                EncryptionCommands::Rotate => Encryption::rotate(),
+                EncryptionCommands::Disable => Encryption::disable(),
+            }
+            .context("encryption command failed to operate"),
+        ),
+        Commands::Timesheet {
+            export,
+            intervals,
+            arguments,
+        } => handle_result(
+            Timesheet::report(arguments, export, *intervals)
+                .context("timesheet command failed to operate"),
+        ),
+        Commands::Interval { command } => handle_result(
+            match command {
+                IntervalCommands::Add { task, start, stop } => {
+                    Timesheet::add_interval(task, start, stop)
+                }
+                IntervalCommands::Edit { id, start, stop } => {
+                    Timesheet::edit_interval(*id, start, stop)
+                }
+                IntervalCommands::Delete { id } => Timesheet::delete_interval(*id),
+            }
+            .context("interval command failed to operate"),
+        ),
+        Commands::Projects => {
+            handle_result(Project::list().context("projects command failed to operate"))
+        }
+        Commands::Project {
+            name,
+            due,
+            status,
+            description,
+        } => handle_result(
+            Project::update(name, description, due, status)
+                .context("project command failed to operate"),
+        ),
+        Commands::Profile { command } => match command {
+            ProfileCommands::List => {
+                handle_result(Profile::list().context("profile command failed to operate"))
+            }
+            ProfileCommands::Use { name } => handle_result(
+                Profile::use_profile(name).context("profile command failed to operate"),
+            ),
+        },
     }
     Ok(())
 }
@@ -1,7 +1,8 @@
 use crate::core::ConfigManager;
 use crate::db::task_manager::TaskManager;
 use crate::error::CoreError;
-use crate::db::task_helper::TASK_STATES;
+use crate::db::task_helper::{is_within_label, sort_label_tree, TASK_STATES};
+use crate::db::project::{get_projects, update_project, Project};
 use rusqlite::{Result, named_params};
 
 pub trait SimpleCommand<'a> {
@@ -19,7 +20,28 @@ pub struct ContextCommand<'a> {
     db_manager_for_test: Option<&'a mut TaskManager>,
 }
 
+// A label and its descendants, see `is_within_label`
+const WITHIN_NAME: &str = "name = :name OR substr(name, 1, length(:name) + 1) = :name || '.'";
+
 impl ContextCommand<'_> {
+    // The contexts within `names` as a tree, the parents first. All the
+    // contexts without `names`.
+    pub fn get_tree(&mut self, names: &Option<Vec<String>>) -> Result<Vec<String>, CoreError> {
+        let mut context_names = self.context()?;
+        if let Some(names) = names {
+            let names: Vec<String> = names.iter().map(|name| name.to_lowercase()).collect();
+            context_names.retain(|context| names.iter().any(|name| is_within_label(context, name)));
+            // The contexts that don't exist fail when their tasks are read
+            for name in names {
+                if !context_names.contains(&name) {
+                    context_names.push(name);
+                }
+            }
+        }
+        sort_label_tree(&mut context_names);
+        Ok(context_names)
+    }
+
     fn context(&self) -> Result<Vec<String>, CoreError> {
         let mut statement;
         match self.db_manager.as_ref() {
@@ -46,20 +68,18 @@ impl ContextCommand<'_> {
     }
 
     fn delete_context_base(&mut self, names: Vec<String>) -> Result<(), CoreError> {
-        let tx = match self.db_manager.as_mut() {
-            Some(manager) => manager.conn.transaction()?,
-            None => self
-                .db_manager_for_test
-                .as_mut()
-                .unwrap()
-                .conn
-                .transaction()?,
+        let manager = match self.db_manager.as_mut() {
+            Some(manager) => manager,
+            None => self.db_manager_for_test.as_deref_mut().unwrap(),
         };
+        manager.auto_backup("clean")?;
+        let tx = manager.conn.transaction()?;
 
+        // The subcontexts are deleted with their parent
         for context_name in names.iter() {
             let lower_context_name = context_name.to_lowercase();
             tx.execute(
-                "DELETE FROM context where name = :name",
+                &format!("DELETE FROM context where {}", WITHIN_NAME),
                 named_params! {":name": lower_context_name},
             )?;
         }
@@ -76,7 +96,7 @@ impl<'a> SimpleCommand<'a> for ContextCommand<'a> {
         Ok(ContextCommand {
             db_manager: Some(TaskManager::new(
                 &ConfigManager::init_and_get_database_path()?,
-            )),
+            )?),
             db_manager_for_test: None,
         })
     }
@@ -92,16 +112,20 @@ impl<'a> SimpleCommand<'a> for ContextCommand<'a> {
         return self.context();
     }
 
-    // Get the number of tasks that belong to this context
+    // Get the number of tasks that belong to this context or its subcontexts
     fn get_count(&mut self, name: &str) -> Result<i64, CoreError> {
         let mut statement;
-        let statement_query = "
+        let statement_query = &format!(
+            "
         SELECT COUNT(*) FROM task_context INNER JOIN
             (
-            SELECT id FROM context WHERE name = :name
+            SELECT id FROM context WHERE {}
             )
             context
-        ON task_context.context_id = context.id group by context.id";
+        ON task_context.context_id = context.id
+        INNER JOIN task ON task_context.task_id = task.id AND task.deleted_at IS NULL",
+            WITHIN_NAME
+        );
 
         match self.db_manager.as_ref() {
             Some(manager) => {
@@ -163,7 +187,7 @@ impl<'a> SimpleCommand<'a> for TagCommand<'a> {
         Ok(TagCommand {
             db_manager: Some(TaskManager::new(
                 &ConfigManager::init_and_get_database_path()?,
-            )),
+            )?),
             db_manager_for_test: None,
         })
     }
@@ -175,28 +199,23 @@ impl<'a> SimpleCommand<'a> for TagCommand<'a> {
         }
     }
 
-    // Get the number of tasks that have this tag
+    // Get the number of tasks that have this tag or one of its subtags
     fn get_count(&mut self, name: &str) -> Result<i64, CoreError> {
-        let mut statement = match self.db_manager.as_mut() {
-            Some(manager) => manager.conn.prepare(
-                "
-        SELECT COUNT(*) FROM task_tag INNER JOIN
+        let statement_query = &format!(
+            "
+        SELECT COUNT(DISTINCT task.id) FROM task_tag INNER JOIN
             (
-            SELECT id FROM tag WHERE name = :name
+            SELECT id FROM tag WHERE {}
             )
             tag
-        ON task_tag.tag_id = tag.id group by tag.id",
-            )?,
+        ON task_tag.tag_id = tag.id
+        INNER JOIN task ON task_tag.task_id = task.id AND task.deleted_at IS NULL",
+            WITHIN_NAME
+        );
+        let mut statement = match self.db_manager.as_mut() {
+            Some(manager) => manager.conn.prepare(statement_query)?,
             None => match self.db_manager_for_test.as_mut() {
-                Some(manager) => manager.conn.prepare(
-                    "
-        SELECT COUNT(*) FROM task_tag INNER JOIN
-            (
-            SELECT id FROM tag WHERE name = :name
-            )
-            tag
-        ON task_tag.tag_id = tag.id group by tag.id",
-                )?,
+                Some(manager) => manager.conn.prepare(statement_query)?,
                 None => {
                     return Err(CoreError::UnexpetedError(String::from("How come?")));
                 }
@@ -216,21 +235,24 @@ impl<'a> SimpleCommand<'a> for TagCommand<'a> {
 
     fn delete(&mut self, names: Vec<String>) -> Result<(), CoreError> {
         println!("got here with names {:?}", names);
-        let tx = match self.db_manager.as_mut() {
-            Some(manager) => manager.conn.transaction()?,
-            None => match self.db_manager_for_test.as_mut() {
-                Some(manager) => manager.conn.transaction()?,
+        let manager = match self.db_manager.as_mut() {
+            Some(manager) => manager,
+            None => match self.db_manager_for_test.as_deref_mut() {
+                Some(manager) => manager,
                 None => {
                     return Err(CoreError::UnexpetedError(String::from("How come")));
                 }
             },
         };
+        manager.auto_backup("clean")?;
+        let tx = manager.conn.transaction()?;
 
         {
+            // The subtags are deleted with their parent
             for name in names.iter() {
                 let lower_context_name = name.to_lowercase();
                 tx.execute(
-                    "DELETE FROM tag where tag.name = :name",
+                    &format!("DELETE FROM tag where {}", WITHIN_NAME),
                     named_params! {":name": lower_context_name},
                 )?;
             }
@@ -279,7 +301,7 @@ impl<'a> SimpleCommand<'a> for StateCommand<'a> {
         Ok(StateCommand {
             db_manager: Some(TaskManager::new(
                 &ConfigManager::init_and_get_database_path()?,
-            )),
+            )?),
             db_manager_for_test: None,
         })
     }
@@ -299,7 +321,9 @@ impl<'a> SimpleCommand<'a> for StateCommand<'a> {
             SELECT id FROM state WHERE name = :name
             )
             state
-        ON task_state.state_id = state.id group by state.id";
+        ON task_state.state_id = state.id
+        INNER JOIN task ON task_state.task_id = task.id AND task.deleted_at IS NULL
+        group by state.id";
 
         let mut statement = match self.db_manager.as_mut() {
             Some(manager) => manager.conn.prepare(statement_query)?,
@@ -324,15 +348,17 @@ impl<'a> SimpleCommand<'a> for StateCommand<'a> {
     }
 
     fn delete(&mut self, names: Vec<String>) -> Result<(), CoreError> {
-        let tx = match self.db_manager.as_mut() {
-            Some(manager) => manager.conn.transaction()?,
-            None => match self.db_manager_for_test.as_mut() {
-                Some(manager) => manager.conn.transaction()?,
+        let manager = match self.db_manager.as_mut() {
+            Some(manager) => manager,
+            None => match self.db_manager_for_test.as_deref_mut() {
+                Some(manager) => manager,
                 None => {
                     return Err(CoreError::UnexpetedError(String::from("How come")));
                 }
             },
         };
+        manager.auto_backup("clean")?;
+        let tx = manager.conn.transaction()?;
 
         {
             for name in names.iter() {
@@ -348,6 +374,105 @@ impl<'a> SimpleCommand<'a> for StateCommand<'a> {
     }
 }
 
+pub struct ProjectCommand<'a> {
+    db_manager: Option<TaskManager>,
+    db_manager_for_test: Option<&'a mut TaskManager>,
+}
+
+impl ProjectCommand<'_> {
+    fn manager(&mut self) -> Result<&mut TaskManager, CoreError> {
+        match self.db_manager.as_mut() {
+            Some(manager) => Ok(manager),
+            None => match self.db_manager_for_test.as_deref_mut() {
+                Some(manager) => Ok(manager),
+                None => Err(CoreError::UnexpetedError(String::from(
+                    "How come we don't have a task manager here?",
+                ))),
+            },
+        }
+    }
+
+    // The projects with their progress and next action
+    pub fn projects(&mut self) -> Result<Vec<Project>, CoreError> {
+        let tx = self.manager()?.conn.transaction()?;
+        let projects = get_projects(&tx)?;
+        tx.commit()?;
+        Ok(projects)
+    }
+
+    // Change the description, due date or status of the project, it's
+    // created when it doesn't exist yet
+    pub fn update(
+        &mut self,
+        name: &str,
+        description: &Option<String>,
+        date_due: &Option<String>,
+        status: &Option<String>,
+    ) -> Result<(), CoreError> {
+        let tx = self.manager()?.conn.transaction()?;
+        update_project(&tx, name, description, date_due, status)?;
+        tx.commit()?;
+        Ok(())
+    }
+}
+
+impl<'a> SimpleCommand<'a> for ProjectCommand<'a> {
+    fn new() -> Result<ProjectCommand<'a>, CoreError> {
+        Ok(ProjectCommand {
+            db_manager: Some(TaskManager::new(
+                &ConfigManager::init_and_get_database_path()?,
+            )?),
+            db_manager_for_test: None,
+        })
+    }
+
+    fn new_with_manager(db_manager: &'a mut TaskManager) -> ProjectCommand<'a> {
+        ProjectCommand {
+            db_manager: None,
+            db_manager_for_test: Some(db_manager),
+        }
+    }
+
+    // Get the number of tasks that belong to this project
+    fn get_count(&mut self, name: &str) -> Result<i64, CoreError> {
+        let statement_query = "
+        SELECT COUNT(*) as count FROM task_project INNER JOIN
+            (
+            SELECT id FROM project WHERE name = :name
+            )
+            project
+        ON task_project.project_id = project.id
+        INNER JOIN task ON task_project.task_id = task.id AND task.deleted_at IS NULL
+        group by project.id";
+
+        let mut statement = self.manager()?.conn.prepare(statement_query)?;
+        let mut rows = statement.query(named_params! {":name": name.to_lowercase()})?;
+
+        if let Some(row) = rows.next()? {
+            return Ok(row.get("count")?);
+        }
+        Ok(0)
+    }
+
+    fn get_all(&mut self) -> Result<Vec<String>, CoreError> {
+        Ok(self.projects()?.into_iter().map(|project| project.name).collect())
+    }
+
+    fn delete(&mut self, names: Vec<String>) -> Result<(), CoreError> {
+        let manager = self.manager()?;
+        manager.auto_backup("clean")?;
+        let tx = manager.conn.transaction()?;
+        for name in names.iter() {
+            tx.execute(
+                "DELETE FROM project where project.name = :name",
+                named_params! {":name": name.to_lowercase()},
+            )?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -363,7 +488,7 @@ mod tests {
 
     #[test]
     fn test_get_context() -> Result<(), CoreError> {
-        let mut manager = TaskManager::new(&get_setting());
+        let mut manager = TaskManager::new(&get_setting())?;
 
         let mut context_names = ContextCommand::new_with_manager(&mut manager);
         assert_eq!(context_names.get_all()?, vec!["inbox"]);
@@ -372,7 +497,7 @@ mod tests {
 
     #[test]
     fn test_get_tags() -> Result<(), CoreError> {
-        let mut manager = TaskManager::new(&get_setting());
+        let mut manager = TaskManager::new(&get_setting())?;
         {
             let mut command = TagCommand::new_with_manager(&mut manager);
             assert!(command.get_all()?.is_empty());
@@ -390,7 +515,7 @@ mod tests {
 
     #[test]
     fn test_get_states() -> Result<(), CoreError> {
-        let mut manager = TaskManager::new(&get_setting());
+        let mut manager = TaskManager::new(&get_setting())?;
         {
             let mut command = StateCommand::new_with_manager(&mut manager);
             assert!(command.get_all()?.is_empty());
@@ -418,7 +543,7 @@ mod tests {
 
     #[test]
     fn test_get_context_task_count() -> Result<(), CoreError> {
-        let mut manager = TaskManager::new(&get_setting());
+        let mut manager = TaskManager::new(&get_setting())?;
         {
             let mut command = ContextCommand::new_with_manager(&mut manager);
             assert_eq!(command.get_count("inbox")?, 0);
@@ -435,7 +560,7 @@ mod tests {
 
     #[test]
     fn test_get_tag_task_count() -> Result<(), CoreError> {
-        let mut manager = TaskManager::new(&get_setting());
+        let mut manager = TaskManager::new(&get_setting())?;
         {
             let mut command = TagCommand::new_with_manager(&mut manager);
             assert!(command.get_all()?.is_empty());
@@ -448,9 +573,25 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_get_tag_task_count_without_trash() -> Result<(), CoreError> {
+        let mut manager = TaskManager::new(&get_setting())?;
+        let mut operation = Add::new_with_task_manager("Test Body", &mut manager);
+        operation.tags = vec!["tag1".to_owned()];
+        execute(&mut operation)?;
+        let mut operation = Add::new_with_task_manager("Test Body 2", &mut manager);
+        operation.tags = vec!["tag1".to_owned()];
+        execute(&mut operation)?;
+        manager.delete(&vec![1])?;
+
+        let mut command = TagCommand::new_with_manager(&mut manager);
+        assert_eq!(command.get_count("tag1")?, 1);
+        Ok(())
+    }
+
     #[test]
     fn test_get_state_task_count() -> Result<(), CoreError> {
-        let mut manager = TaskManager::new(&get_setting());
+        let mut manager = TaskManager::new(&get_setting())?;
 
         let mut operation = Add::new_with_task_manager("Task Body 2", &mut manager);
         operation.set_custom_state(String::from("new_state"));
@@ -463,7 +604,7 @@ mod tests {
 
     #[test]
     fn test_delete_context() -> Result<(), CoreError> {
-        let mut manager = TaskManager::new(&get_setting());
+        let mut manager = TaskManager::new(&get_setting())?;
         let mut command = ContextCommand::new_with_manager(&mut manager);
 
         assert_eq!(command.get_all()?, vec!["inbox"]);
@@ -475,7 +616,7 @@ mod tests {
 
     #[test]
     fn test_delete_context_with_task_associated() -> Result<(), CoreError> {
-        let mut manager = TaskManager::new(&get_setting());
+        let mut manager = TaskManager::new(&get_setting())?;
         let mut operation = Add::new_with_task_manager("Test Body", &mut manager);
         operation.tags = vec!["tag1".to_owned()];
         execute(&mut operation)?;
@@ -490,7 +631,7 @@ mod tests {
 
     #[test]
     fn test_delete_tag_with_tag_associated() -> Result<(), CoreError> {
-        let mut manager = TaskManager::new(&get_setting());
+        let mut manager = TaskManager::new(&get_setting())?;
         let mut operation = Add::new_with_task_manager("Test Body", &mut manager);
         operation.tags = vec!["tag1".to_owned()];
         execute(&mut operation)?;
@@ -505,7 +646,7 @@ mod tests {
 
     #[test]
     fn test_delete_tag() -> Result<(), CoreError> {
-        let mut manager = TaskManager::new(&get_setting());
+        let mut manager = TaskManager::new(&get_setting())?;
 
         let mut operation = Add::new_with_task_manager("Test Body", &mut manager);
         operation.tags = vec!["tag1".to_owned()];
@@ -517,6 +658,11 @@ mod tests {
             .as_mut()
             .unwrap()
             .delete(&vec![1])?;
+        command
+            .db_manager_for_test
+            .as_mut()
+            .unwrap()
+            .purge(&None)?;
 
         assert_eq!(command.get_all()?, vec!["tag1"]);
 
@@ -528,7 +674,7 @@ mod tests {
 
     #[test]
     fn test_delete_state_with_task_associated() -> Result<(), CoreError> {
-        let mut manager = TaskManager::new(&get_setting());
+        let mut manager = TaskManager::new(&get_setting())?;
         let mut operation = Add::new_with_task_manager("Test Body", &mut manager);
         operation.set_custom_state(String::from("new_state"));
         execute(&mut operation)?;
@@ -542,7 +688,7 @@ mod tests {
 
     #[test]
     fn test_delete_state() -> Result<(), CoreError> {
-        let mut manager = TaskManager::new(&get_setting());
+        let mut manager = TaskManager::new(&get_setting())?;
         let mut operation = Add::new_with_task_manager("Test Body", &mut manager);
         operation.set_custom_state(String::from("new_state"));
         execute(&mut operation)?;
@@ -553,9 +699,40 @@ mod tests {
             .as_mut()
             .unwrap()
             .delete(&vec![1])?;
+        command
+            .db_manager_for_test
+            .as_mut()
+            .unwrap()
+            .purge(&None)?;
 
         command.delete(vec![String::from("new_state")])?;
         assert!(command.get_all()?.is_empty());
         Ok(())
     }
+
+    #[test]
+    fn test_projects() -> Result<(), CoreError> {
+        let mut manager = TaskManager::new(&get_setting())?;
+        let mut operation = Add::new_with_task_manager("Book the venue", &mut manager);
+        operation.project = Some(String::from("Wedding"));
+        execute(&mut operation)?;
+
+        let mut command = ProjectCommand::new_with_manager(&mut manager);
+        assert_eq!(command.get_all()?, vec!["wedding"]);
+        assert_eq!(command.get_count("Wedding")?, 1);
+
+        let status = Some(String::from("on-hold"));
+        command.update("wedding", &Some(String::from("June")), &None, &status)?;
+        let projects = command.projects()?;
+        assert_eq!(projects[0].description, "June");
+        assert_eq!(projects[0].status, "on-hold");
+        assert!(command.update("wedding", &None, &None, &Some(String::from("later"))).is_err());
+
+        // A project with tasks can't be deleted
+        assert!(command.delete(vec![String::from("wedding")]).is_err());
+        command.update("garden", &None, &None, &None)?;
+        command.delete(vec![String::from("garden")])?;
+        assert_eq!(command.get_all()?, vec!["wedding"]);
+        Ok(())
+    }
 }
@@ -1,4 +1,10 @@
+use crate::db::backup::{
+    AUTO_BACKUP_SETTING, BACKUP_DIR_SETTING, BACKUP_KEEP_DAILY_SETTING, BACKUP_KEEP_LAST_SETTING,
+};
+use crate::db::encryption::{KEY_FILE_SETTING, PASSPHRASE_COMMAND_SETTING, PASSPHRASE_SETTING};
+use crate::db::subtask::AUTO_COMPLETE_PARENT_SETTING;
 use crate::db::task_helper::Task;
+use crate::db::uda::UDA_SETTING_PREFIX;
 use crate::error::{InitialError, CoreError};
 
 use ini::Ini;
@@ -7,31 +13,226 @@ use std::collections::HashMap;
 use std::fs::create_dir_all;
 use std::fs::OpenOptions;
 use std::io;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
 
 use rusqlite::Result;
 
 use crate::util::create_default_init;
 
+// Environment variable that selects the profile, `select_profile` wins over it
+pub const PROFILE_ENV_VAR: &str = "TASKOO_PROFILE";
+// The profile made of the settings outside of any [profile.<name>] section
+pub const DEFAULT_PROFILE: &str = "default";
+const PROFILE_SECTION_PREFIX: &str = "profile.";
+// Key of the general section that stores the profile set by `use_profile`
+const PROFILE_KEY: &str = "profile";
+
+// Profile or database chosen for this process, ahead of the config
+static SELECTED_PROFILE: RwLock<Option<String>> = RwLock::new(None);
+static SELECTED_DATABASE: RwLock<Option<String>> = RwLock::new(None);
+// Passphrase of the encrypted database, ahead of the key file and command
+static SELECTED_PASSPHRASE: RwLock<Option<String>> = RwLock::new(None);
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Profile {
+    pub name: String,
+    pub db_path: String,
+    // Whether the operations use this profile
+    pub active: bool,
+}
+
 pub struct ConfigManager;
 impl ConfigManager {
     pub fn init_and_get_database_path() -> Result<HashMap<String, String>, InitialError> {
         let config = &ConfigManager::get_config()?;
-        let general_section = config.general_section();
-        let database_path = general_section
-            .get("db_path")
-            .expect("Failed to get the location of the database file");
-        let expanded_db_path: &str = &shellexpand::tilde(database_path);
+        let mut setting = ConfigManager::get_setting(
+            config,
+            &ConfigManager::get_selected_profile(),
+            &SELECTED_DATABASE.read().unwrap(),
+        )?;
+        if let Some(passphrase) = &*SELECTED_PASSPHRASE.read().unwrap() {
+            setting.insert(PASSPHRASE_SETTING.to_owned(), passphrase.clone());
+        }
+        let expanded_db_path = &setting["db_path"];
         debug!("Expanded Database Path: {} \n", &expanded_db_path);
         ConfigManager::ensure_db_file_exists(&expanded_db_path)
             .expect("Unable to create the database file");
+        return Ok(setting);
+    }
+
+    // Use the database of this profile for every following operation
+    pub fn select_profile(name: &str) {
+        *SELECTED_PROFILE.write().unwrap() = Some(name.to_owned());
+    }
+
+    // Use this database file for every following operation, regardless
+    // of the profiles
+    pub fn select_database(db_path: &str) {
+        *SELECTED_DATABASE.write().unwrap() = Some(db_path.to_owned());
+    }
+
+    // Unlock the encrypted database with this passphrase for every
+    // following operation
+    pub fn set_passphrase(passphrase: &str) {
+        *SELECTED_PASSPHRASE.write().unwrap() = Some(passphrase.to_owned());
+    }
+
+    // The names of the UDAs declared in the [uda] section, they're the same
+    // for every profile
+    pub fn get_uda_names() -> Result<Vec<String>, InitialError> {
+        let config = ConfigManager::get_config()?;
+        Ok(match config.section(Some("uda")) {
+            Some(section) => section.iter().map(|(name, _)| name.to_owned()).collect(),
+            None => vec![],
+        })
+    }
+
+    // Every profile of the config, the default one first
+    pub fn get_profiles() -> Result<Vec<Profile>, InitialError> {
+        let config = &ConfigManager::get_config()?;
+        let active = ConfigManager::get_active_profile_name(
+            config,
+            &ConfigManager::get_selected_profile(),
+        );
+        let mut profiles = vec![];
+        for name in ConfigManager::get_profile_names(config).iter() {
+            let setting = ConfigManager::get_setting(config, &Some(name.clone()), &None)?;
+            profiles.push(Profile {
+                name: name.clone(),
+                db_path: setting["db_path"].clone(),
+                active: SELECTED_DATABASE.read().unwrap().is_none() && *name == active,
+            });
+        }
+        Ok(profiles)
+    }
+
+    // Make the profile the one that's used when no other is selected
+    pub fn use_profile(name: &str) -> Result<(), InitialError> {
+        let config_file_path = ConfigManager::get_config_file_path()?;
+        let mut config = Ini::load_from_file(&config_file_path)?;
+        if !ConfigManager::get_profile_names(&config).iter().any(|profile| profile == name) {
+            return Err(InitialError::UnknownProfile(name.to_owned()));
+        }
+
+        if name == DEFAULT_PROFILE {
+            config.general_section_mut().remove(PROFILE_KEY);
+        } else {
+            config.with_general_section().set(PROFILE_KEY, name);
+        }
+        config
+            .write_to_file(&config_file_path)
+            .map_err(|error| InitialError::IoError {
+                path: config_file_path.to_str().unwrap().to_string(),
+                source: error,
+            })
+    }
+
+    // `select_profile` wins over the environment variable
+    fn get_selected_profile() -> Option<String> {
+        match &*SELECTED_PROFILE.read().unwrap() {
+            Some(name) => Some(name.clone()),
+            None => std::env::var(PROFILE_ENV_VAR)
+                .ok()
+                .filter(|name| !name.is_empty()),
+        }
+    }
+
+    fn get_profile_names(config: &Ini) -> Vec<String> {
+        let mut names = vec![DEFAULT_PROFILE.to_owned()];
+        for section in config.sections().flatten() {
+            if let Some(name) = section.strip_prefix(PROFILE_SECTION_PREFIX) {
+                names.push(name.to_owned());
+            }
+        }
+        names
+    }
+
+    fn get_active_profile_name(config: &Ini, profile: &Option<String>) -> String {
+        match profile {
+            Some(name) => name.clone(),
+            None => config
+                .general_section()
+                .get(PROFILE_KEY)
+                .unwrap_or(DEFAULT_PROFILE)
+                .to_owned(),
+        }
+    }
+
+    // The setting of the operations. `db_path` wins over the profile,
+    // then the profile, then the one set by `use_profile`. The settings
+    // of a profile section override the ones outside of it.
+    fn get_setting(
+        config: &Ini,
+        profile: &Option<String>,
+        db_path: &Option<String>,
+    ) -> Result<HashMap<String, String>, InitialError> {
+        let name = ConfigManager::get_active_profile_name(config, profile);
+        let general_section = config.general_section();
+        let profile_section = if name == DEFAULT_PROFILE {
+            None
+        } else {
+            match config.section(Some(format!("{}{}", PROFILE_SECTION_PREFIX, name))) {
+                Some(section) => Some(section),
+                None => return Err(InitialError::UnknownProfile(name)),
+            }
+        };
+        let get = |key: &str| {
+            profile_section
+                .and_then(|section| section.get(key))
+                .or_else(|| general_section.get(key))
+        };
+
+        let database_path = match db_path {
+            Some(path) => path.as_str(),
+            None => match profile_section {
+                Some(section) => section
+                    .get("db_path")
+                    .ok_or_else(|| InitialError::MissingDatabasePath(name.clone()))?,
+                None => general_section
+                    .get("db_path")
+                    .ok_or_else(|| InitialError::MissingDatabasePath(name.clone()))?,
+            },
+        };
 
         let mut setting = HashMap::new();
-        setting.insert("db_path".to_owned(), expanded_db_path.to_owned());
+        setting.insert(
+            "db_path".to_owned(),
+            shellexpand::tilde(database_path).into_owned(),
+        );
+        for key in [
+            "archive_after_days",
+            AUTO_COMPLETE_PARENT_SETTING,
+            PASSPHRASE_COMMAND_SETTING,
+            AUTO_BACKUP_SETTING,
+            BACKUP_KEEP_LAST_SETTING,
+            BACKUP_KEEP_DAILY_SETTING,
+        ] {
+            if let Some(value) = get(key) {
+                setting.insert(key.to_owned(), value.to_owned());
+            }
+        }
+        for key in [KEY_FILE_SETTING, BACKUP_DIR_SETTING] {
+            if let Some(path) = get(key) {
+                setting.insert(key.to_owned(), shellexpand::tilde(path).into_owned());
+            }
+        }
+        // The UDAs are the same for every profile
+        if let Some(section) = config.section(Some("uda")) {
+            for (name, uda_type) in section.iter() {
+                setting.insert(format!("{}{}", UDA_SETTING_PREFIX, name), uda_type.to_owned());
+            }
+        }
         return Ok(setting);
     }
 
     fn get_config() -> Result<Ini, InitialError> {
+        let config_file_path = ConfigManager::get_config_file_path()?;
+        debug!("Load config from file {:?}", &config_file_path);
+        return Ok(Ini::load_from_file(config_file_path)?);
+    }
+
+    fn get_config_file_path() -> Result<PathBuf, InitialError> {
         let home = match std::env::var("HOME") {
             Ok(dir) => {
                 debug!("Successfully read $HOME");
@@ -69,8 +270,7 @@ impl ConfigManager {
         } else {
             debug!("Found existing config directory {:?}", &config_file_path);
         }
-        debug!("Load config from file {:?}", &config_file_path);
-        return Ok(Ini::load_from_file(config_file_path)?);
+        return Ok(config_file_path);
     }
 
     fn create_config_file(config: &mut std::path::PathBuf) -> Result<(), io::Error> {
@@ -95,7 +295,7 @@ impl ConfigManager {
 }
 
 pub trait Operation {
-    fn init(&mut self) -> Result<(), InitialError>;
+    fn init(&mut self) -> Result<(), CoreError>;
     fn do_work(&mut self) -> Result<Vec<Task>, CoreError>;
     fn set_result(&mut self, result: Vec<Task>);
     fn get_result(&mut self) -> &Vec<Task>;
@@ -104,3 +304,103 @@ pub trait Operation {
 pub trait Command {
     fn run(&mut self);
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get_config() -> Ini {
+        Ini::load_from_str(
+            "db_path = ~/.config/taskoo/tasks.db
+archive_after_days = 30
+
+[profile.work]
+db_path = /tmp/work.db
+archive_after_days = 7
+auto_complete_parent = true
+
+[profile.personal]
+db_path = /tmp/personal.db
+
+[profile.broken]
+archive_after_days = 7
+
+[uda]
+client = string
+cost = number
+",
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_default_profile() {
+        let setting = ConfigManager::get_setting(&get_config(), &None, &None).unwrap();
+        assert!(setting["db_path"].ends_with("/.config/taskoo/tasks.db"));
+        assert!(!setting["db_path"].starts_with("~"));
+        assert_eq!(setting["archive_after_days"], "30");
+        assert_eq!(setting["uda.client"], "string");
+        assert_eq!(setting["uda.cost"], "number");
+    }
+
+    #[test]
+    fn test_named_profiles() {
+        let config = get_config();
+        let setting =
+            ConfigManager::get_setting(&config, &Some("work".to_owned()), &None).unwrap();
+        assert_eq!(setting["db_path"], "/tmp/work.db");
+        assert_eq!(setting["archive_after_days"], "7");
+        assert_eq!(setting["auto_complete_parent"], "true");
+
+        // Settings that the profile doesn't have come from outside of it
+        let setting =
+            ConfigManager::get_setting(&config, &Some("personal".to_owned()), &None).unwrap();
+        assert_eq!(setting["db_path"], "/tmp/personal.db");
+        assert_eq!(setting["archive_after_days"], "30");
+
+        assert!(ConfigManager::get_setting(&config, &Some("school".to_owned()), &None).is_err());
+        assert!(ConfigManager::get_setting(&config, &Some("broken".to_owned()), &None).is_err());
+    }
+
+    #[test]
+    fn test_default_profile_without_database() {
+        let config = Ini::load_from_str("archive_after_days = 30").unwrap();
+        match ConfigManager::get_setting(&config, &None, &None) {
+            Err(InitialError::MissingDatabasePath(name)) => assert_eq!(name, DEFAULT_PROFILE),
+            result => panic!("Expected a missing db_path, got {:?}", result),
+        }
+    }
+
+    #[test]
+    fn test_profile_set_by_use() {
+        let mut config = get_config();
+        config.with_general_section().set(PROFILE_KEY, "personal");
+        let setting = ConfigManager::get_setting(&config, &None, &None).unwrap();
+        assert_eq!(setting["db_path"], "/tmp/personal.db");
+
+        // The selected profile wins over it
+        let setting =
+            ConfigManager::get_setting(&config, &Some("default".to_owned()), &None).unwrap();
+        assert!(setting["db_path"].ends_with("/.config/taskoo/tasks.db"));
+    }
+
+    #[test]
+    fn test_database_wins_over_profile() {
+        let setting = ConfigManager::get_setting(
+            &get_config(),
+            &Some("work".to_owned()),
+            &Some("/tmp/other.db".to_owned()),
+        )
+        .unwrap();
+        assert_eq!(setting["db_path"], "/tmp/other.db");
+        assert_eq!(setting["archive_after_days"], "7");
+    }
+
+    #[test]
+    fn test_profile_names() {
+        assert_eq!(
+            ConfigManager::get_profile_names(&get_config()),
+            vec!["default", "work", "personal", "broken"]
+        );
+    }
+}
@@ -1,6 +1,15 @@
 use crate::db::task_helper::{Task};
 use crate::error::CoreError;
 use super::get_base::get_base;
+use super::query_builder::Filter;
+use super::query_helper::NEW_UUID;
+use super::history::record_changes;
+use super::modify::update_state_timestamps;
+use super::selection::Selection;
+use super::time_tracking::track_started_task;
+use super::project::set_project;
+use super::subtask::set_parent;
+use super::uda::set_udas;
 use log::debug;
 use rusqlite::{named_params, Result, Transaction};
 
@@ -98,15 +107,26 @@ pub fn add(
     annotation: &Option<&str>,
     state_id: &Option<i64>,
     parent_task_ids: &Option<Vec<i64>>,
+    estimate: &Option<i64>,
+    udas: &Vec<(String, Option<String>)>,
+    parent_id: &Option<i64>,
+    project_id: &Option<i64>,
 ) -> Result<Vec<Task>, CoreError> {
     debug!("  parent_task_ids: {:?}", parent_task_ids);
     debug!("  state_id: {:?}", state_id);
-    let mut statement = tx.prepare(
+    // Archived tasks keep their ids, so they can't be reused by new tasks
+    let mut statement = tx.prepare(&format!(
         "
     INSERT INTO task
-    (body, due_date, scheduled_at, due_repeat, scheduled_repeat, annotation) VALUES
-    (:body, :due_date, :scheduled_at, :due_repeat, :scheduled_repeat, :annotation)",
-    )?;
+    (id, uuid, body, due_date, scheduled_at, due_repeat, scheduled_repeat, annotation, modified_at,
+    estimate)
+    VALUES
+    ((SELECT IFNULL(MAX(id), 0) + 1 FROM (SELECT id FROM task UNION ALL SELECT task_id FROM archive)),
+    {}, taskoo_encrypt(:body), :due_date, :scheduled_at, :due_repeat, :scheduled_repeat,
+    taskoo_encrypt(:annotation),
+    CURRENT_TIMESTAMP, :estimate)",
+        NEW_UUID
+    ))?;
 
     statement.execute(named_params! {
         ":body": body,
@@ -114,7 +134,8 @@ pub fn add(
         ":scheduled_at": scheduled_at.unwrap_or(""),
         ":due_repeat": due_repeat.unwrap_or(""),
         ":scheduled_repeat": scheduled_repeat.unwrap_or(""),
-        ":annotation": annotation.unwrap_or("")
+        ":annotation": annotation.unwrap_or(""),
+        ":estimate": estimate,
     })?;
 
     let inserted_task_id = tx.last_insert_rowid();
@@ -122,10 +143,22 @@ pub fn add(
     add_tag(&tx, &inserted_task_id, tag_ids)?;
     add_context(&tx, &inserted_task_id, *context_id)?;
     add_state(&tx, &inserted_task_id, state_id)?;
+    Selection::Task.fill(tx, &vec![inserted_task_id])?;
+    update_state_timestamps(tx, Selection::Task)?;
+    if state_id.is_some() {
+        track_started_task(tx, &vec![inserted_task_id])?;
+    }
 
     if let Some(priority_id) = priority {
         add_priority(&tx, &inserted_task_id, &priority_id)?;
     }
+    set_udas(tx, Selection::Task, udas)?;
+    if parent_id.is_some() {
+        set_parent(tx, Selection::Task, &vec![inserted_task_id], parent_id)?;
+    }
+    if project_id.is_some() {
+        set_project(tx, Selection::Task, project_id)?;
+    }
 
     if let Some(parent_task_ids) = parent_task_ids {
         for parent_task_id in parent_task_ids.into_iter() {
@@ -133,7 +166,10 @@ pub fn add(
         }
     }
 
-    let tasks = get_base(&tx, &format!("task.id = {}", inserted_task_id))?;
+    let tasks = get_base(&tx, &Filter::task_id(&inserted_task_id))?;
+    for task in tasks.iter() {
+        record_changes(&tx, None, &task)?;
+    }
     Ok(tasks)
 }
 
@@ -144,7 +180,7 @@ pub fn add_annotation(
 ) -> Result<Vec<Task>, CoreError> {
     let mut statement = tx.prepare(
         "
-        Update task set annotation = :annotation where id = :task_id
+        Update task set annotation = taskoo_encrypt(:annotation) where id = :task_id
         ",
     )?;
 
@@ -153,5 +189,5 @@ pub fn add_annotation(
         ":task_id": task_id
     })?;
 
-    get_base(tx, &format!("task.id = {}", task_id))
+    get_base(tx, &Filter::task_id(&task_id))
 }
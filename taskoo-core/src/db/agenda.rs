@@ -1,11 +1,13 @@
 use chrono::{NaiveDate, Duration};
 use super::query_helper::generate_agenda_condition;
+use super::query_builder::{Column, Condition, Filter};
 use log::info;
 use crate::db::task_helper::{Task};
 use crate::error::CoreError;
 use super::get_base::get_base;
 
-use rusqlite::{Transaction, Result};
+use rusqlite::types::Value;
+use rusqlite::{named_params, OptionalExtension, Result, Transaction};
 
 pub fn agenda(
     conn: &Transaction,
@@ -28,15 +30,31 @@ pub fn agenda(
         }
     }
 
+    // The tasks of the subcontexts are in the agenda of their parent
+    let context_condition = match context_name {
+        Some(context_name) => {
+            let context_id: Option<i64> = conn
+                .query_row(
+                    "SELECT id FROM context WHERE name = :name",
+                    named_params! {":name": context_name},
+                    |row| row.get(0),
+                )
+                .optional()?;
+            Some(match context_id {
+                Some(context_id) => Condition::InContext(context_id),
+                None => Condition::Eq(Column::ContextName, Value::from(context_name.clone())),
+            })
+        }
+        None => None,
+    };
+
     let mut result = vec![];
     for day in days.iter() {
-        let day_conditions = generate_agenda_condition(day);
-        assert!(!day_conditions.is_empty());
-        let mut conditions = vec![format!("({})", day_conditions.join(" or "))];
-        if let Some(context_name) = context_name {
-            conditions.push(format!("context.name = '{}'", context_name));
+        let mut filter = Filter::new().and(generate_agenda_condition(day));
+        if let Some(condition) = &context_condition {
+            filter.push(condition.clone());
         }
-        let tasks = get_base(&conn, &conditions.join(" and "))?;
+        let tasks = get_base(&conn, &filter)?;
         result.push((day.clone(), tasks));
     }
 
@@ -0,0 +1,238 @@
+use super::get_base::get_base;
+use super::query_builder::Filter;
+use super::query_helper::NEW_UUID;
+use super::journal;
+use super::journal::TaskSnapshot;
+use super::selection::Selection;
+use super::subtask::reparent_children;
+use crate::db::task_helper::Task;
+use crate::error::CoreError;
+use log::{debug, info};
+use rusqlite::{named_params, OptionalExtension, Result, Transaction};
+use serde::{Deserialize, Serialize};
+
+// The tables a task refers to by id, together with the table and the
+// column that hold the reference.
+const LABEL_TABLES: &[(&str, &str, &str)] = &[
+    ("context", "task_context", "context_id"),
+    ("state", "task_state", "state_id"),
+    ("tag", "task_tag", "tag_id"),
+    ("priority", "priority_task", "priority_id"),
+    ("project", "task_project", "project_id"),
+];
+
+#[derive(Serialize, Deserialize, Debug)]
+struct Label {
+    table: String,
+    id: i64,
+    name: String,
+}
+
+fn to_json<T: Serialize>(value: &T) -> Result<String, CoreError> {
+    serde_json::to_string(value).map_err(|error| {
+        CoreError::UnexpetedError(format!("Failed to serialize the archived task: {}", error))
+    })
+}
+
+fn from_json<'a, T: Deserialize<'a>>(data: &'a str) -> Result<T, CoreError> {
+    serde_json::from_str(data).map_err(|error| {
+        CoreError::UnexpetedError(format!("Failed to read the archived task: {}", error))
+    })
+}
+
+fn get_labels(tx: &Transaction, task_id: i64) -> Result<Vec<Label>, CoreError> {
+    let mut labels = vec![];
+    for (table, join_table, column) in LABEL_TABLES.iter() {
+        let mut statement = tx.prepare(&format!(
+            "SELECT {table}.id, {table}.name FROM {join_table}
+            INNER JOIN {table} ON {join_table}.{column} = {table}.id
+            WHERE {join_table}.task_id = :task_id",
+            table = table,
+            join_table = join_table,
+            column = column
+        ))?;
+        let mut rows = statement.query(named_params! {":task_id": task_id})?;
+        while let Some(row) = rows.next()? {
+            labels.push(Label {
+                table: table.to_string(),
+                id: row.get(0)?,
+                name: row.get(1)?,
+            });
+        }
+    }
+    Ok(labels)
+}
+
+// When the task was completed. Tasks completed before `completed_at`
+// existed use the history, tasks without one use their creation time.
+const COMPLETED_AT: &str = "COALESCE(
+    task.completed_at,
+    (
+    SELECT MAX(changed_at) FROM task_history
+    WHERE task_history.task_id = task.id
+    AND field = 'state' AND new_value = 'completed'
+    ),
+    task.created_at)";
+
+// The ids of the completed tasks that were completed before `completed_before`
+pub fn get_archivable(tx: &Transaction, completed_before: &str) -> Result<Vec<i64>, CoreError> {
+    let mut statement = tx.prepare(&format!(
+        "SELECT id FROM (
+            SELECT task.id as id, {} as completed_at FROM task
+            INNER JOIN task_state ON task.id = task_state.task_id
+            INNER JOIN state ON task_state.state_id = state.id
+            WHERE state.name = 'completed' AND task.deleted_at IS NULL
+        ) WHERE completed_at <= :completed_before ORDER BY id",
+        COMPLETED_AT
+    ))?;
+    let mut rows = statement.query(named_params! {":completed_before": completed_before})?;
+    let mut task_ids = vec![];
+    while let Some(row) = rows.next()? {
+        task_ids.push(row.get(0)?);
+    }
+    Ok(task_ids)
+}
+
+// Move the tasks out of the task tables into the archive
+pub fn archive(tx: &Transaction, task_ids: &Vec<i64>) -> Result<Vec<Task>, CoreError> {
+    let mut insert_statement = tx.prepare(
+        "INSERT INTO archive (task_id, task, snapshot, labels, completed_at)
+        VALUES (:task_id, taskoo_encrypt(:task), :snapshot, :labels, :completed_at)",
+    )?;
+
+    // The subtasks that stay move up to the parent of the archived task
+    Selection::Subtask.fill(tx, task_ids)?;
+    reparent_children(tx, Selection::Subtask)?;
+
+    let mut archived_tasks = vec![];
+    for task_id in task_ids.iter() {
+        let mut tasks = get_base(tx, &Filter::task_id(task_id))?;
+        if tasks.is_empty() {
+            continue;
+        }
+        let task = tasks.remove(0);
+        debug!("Archiving task {}", task_id);
+
+        let completed_at: String = tx.query_row(
+            &format!("SELECT {} FROM task WHERE task.id = :task_id", COMPLETED_AT),
+            named_params! {":task_id": task_id},
+            |row| row.get(0),
+        )?;
+        let snapshot = journal::snapshot_with_history(tx, &vec![*task_id])?;
+        let labels = get_labels(tx, *task_id)?;
+
+        // Remove every row of the task from the task tables
+        journal::restore(tx, &vec![TaskSnapshot::missing(*task_id)])?;
+
+        insert_statement.execute(named_params! {
+            ":task_id": task_id,
+            ":task": to_json(&task)?,
+            ":snapshot": journal::to_json(&snapshot)?,
+            ":labels": to_json(&labels)?,
+            ":completed_at": completed_at,
+        })?;
+        archived_tasks.push(task);
+    }
+    Ok(archived_tasks)
+}
+
+pub fn get_archived(tx: &Transaction) -> Result<Vec<Task>, CoreError> {
+    let mut statement = tx.prepare("SELECT taskoo_decrypt(task) FROM archive ORDER BY task_id")?;
+    let mut rows = statement.query([])?;
+    let mut tasks = vec![];
+    while let Some(row) = rows.next()? {
+        let data: String = row.get(0)?;
+        tasks.push(from_json(&data)?);
+    }
+    Ok(tasks)
+}
+
+// Move the tasks back from the archive. The contexts, tags, states and
+// priorities that were cleaned in the meantime are created again.
+pub fn unarchive(tx: &Transaction, task_ids: &Vec<i64>) -> Result<Vec<Task>, CoreError> {
+    let mut restored_tasks = vec![];
+    for task_id in task_ids.iter() {
+        let archived: Option<(String, String, Option<String>)> = tx
+            .query_row(
+                "SELECT snapshot, labels, json_extract(taskoo_decrypt(task), '$.uuid') FROM archive
+                WHERE task_id = :task_id",
+                named_params! {":task_id": task_id},
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+            )
+            .optional()?;
+        let (snapshot_data, labels_data, uuid) = match archived {
+            Some(archived) => archived,
+            None => continue,
+        };
+        if task_exists(tx, *task_id) {
+            return Err(CoreError::ArgumentError(format!(
+                "Unable to restore task {}, the id is used by another task",
+                task_id
+            )));
+        }
+        info!("Restoring task {} from the archive", task_id);
+
+        let mut snapshots = journal::from_json(&snapshot_data)?;
+        let labels: Vec<Label> = from_json(&labels_data)?;
+
+        for label in labels.iter() {
+            let current_id = get_or_create_label(tx, &label.table, &label.name)?;
+            if current_id != label.id {
+                let (_, join_table, column) = LABEL_TABLES
+                    .iter()
+                    .find(|(table, _, _)| *table == label.table)
+                    .expect("Labels are only read from LABEL_TABLES");
+                for snapshot in snapshots.iter_mut() {
+                    snapshot.replace_value(join_table, column, label.id, current_id);
+                }
+            }
+        }
+
+        for snapshot in snapshots.iter_mut() {
+            snapshot.retain_dependencies(|id| task_exists(tx, id));
+            // The parent can be restored along with the task
+            snapshot.retain_parent(|id| task_exists(tx, id) || task_ids.contains(&id));
+        }
+        // This also removes the row from the archive
+        journal::restore(tx, &snapshots)?;
+        // Tasks archived before UUIDs existed only have it in the archived task
+        tx.execute(
+            &format!(
+                "UPDATE task SET uuid = IFNULL(:uuid, {}) WHERE id = :task_id AND uuid IS NULL",
+                NEW_UUID
+            ),
+            named_params! {":uuid": uuid, ":task_id": task_id},
+        )?;
+        restored_tasks.append(&mut get_base(tx, &Filter::task_id(task_id))?);
+    }
+    Ok(restored_tasks)
+}
+
+fn task_exists(tx: &Transaction, task_id: i64) -> bool {
+    tx.query_row(
+        "SELECT id FROM task WHERE id = :task_id",
+        named_params! {":task_id": task_id},
+        |row| row.get::<_, i64>(0),
+    )
+    .optional()
+    .unwrap_or(None)
+    .is_some()
+}
+
+fn get_or_create_label(tx: &Transaction, table: &str, name: &str) -> Result<i64, CoreError> {
+    let existing: Option<i64> = tx
+        .query_row(
+            &format!("SELECT id FROM {} WHERE name = :name", table),
+            named_params! {":name": name},
+            |row| row.get(0),
+        )
+        .optional()?;
+    if let Some(id) = existing {
+        return Ok(id);
+    }
+    tx.execute(
+        &format!("INSERT INTO {} (name) VALUES (:name)", table),
+        named_params! {":name": name},
+    )?;
+    Ok(tx.last_insert_rowid())
+}
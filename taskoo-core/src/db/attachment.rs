@@ -0,0 +1,198 @@
+use super::query_builder::placeholders;
+use crate::db::task_helper::Task;
+use crate::error::CoreError;
+use rusqlite::{named_params, params_from_iter, OptionalExtension, Result, Transaction};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+// An attachment is a URL, a reference to a file by its path, or a copy of
+// a file kept in the database. The copies live in attachment_blob, a
+// detached copy stays until its task is purged so the detach can be undone.
+// The copies aren't in the journal, a purge deletes them for good.
+
+pub const ATTACHMENT_KINDS: [&str; 3] = ["url", "path", "blob"];
+
+// Attachments are looked up for this many tasks at a time, like the notes
+const TASK_IDS_PER_QUERY: usize = 500;
+
+// The metadata of an attachment, the copied data is only read to open it
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Attachment {
+    pub id: i64,
+    pub task_id: i64,
+    // One of ATTACHMENT_KINDS
+    pub kind: String,
+    // The URL, the absolute path, or the name of the copied file
+    pub location: String,
+    // The size of the copy in bytes
+    pub size: Option<i64>,
+    pub created_at: String,
+}
+
+impl Attachment {
+    pub fn is_url(&self) -> bool {
+        self.kind == ATTACHMENT_KINDS[0]
+    }
+
+    pub fn is_copy(&self) -> bool {
+        self.kind == ATTACHMENT_KINDS[2]
+    }
+
+    // The name a copy is saved under when it's opened, the id keeps copies
+    // with the same name apart
+    pub fn file_name(&self) -> String {
+        let name = Path::new(&self.location)
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+        format!("taskoo-{}-{}", self.id, name)
+    }
+}
+
+pub fn is_url(location: &str) -> bool {
+    location.contains("://") || location.starts_with("mailto:")
+}
+
+const SELECT_ATTACHMENT_QUERY: &str = "
+    SELECT attachment.id, attachment.task_id, attachment.kind,
+        taskoo_decrypt(attachment.location), LENGTH(taskoo_decrypt_blob(attachment_blob.data)),
+        attachment.created_at
+    FROM attachment
+    LEFT JOIN attachment_blob ON attachment.blob_id = attachment_blob.id";
+
+fn convert_row(row: &rusqlite::Row) -> Result<Attachment> {
+    Ok(Attachment {
+        id: row.get(0)?,
+        task_id: row.get(1)?,
+        kind: row.get(2)?,
+        location: row.get(3)?,
+        size: row.get(4)?,
+        created_at: row.get(5)?,
+    })
+}
+
+// Fill in the attachments of the tasks, oldest first
+pub fn attach_attachments(tx: &Transaction, tasks: &mut Vec<Task>) -> Result<(), CoreError> {
+    let task_ids: Vec<i64> = tasks.iter().map(|task| task.id).collect();
+    for chunk in task_ids.chunks(TASK_IDS_PER_QUERY) {
+        let mut statement = tx.prepare(&format!(
+            "{} WHERE attachment.task_id IN ({}) ORDER BY attachment.created_at, attachment.id",
+            SELECT_ATTACHMENT_QUERY,
+            placeholders(chunk.len())
+        ))?;
+        let mut rows = statement.query(params_from_iter(chunk.iter()))?;
+        while let Some(row) = rows.next()? {
+            let attachment = convert_row(row)?;
+            if let Some(task) = tasks.iter_mut().find(|task| task.id == attachment.task_id) {
+                task.attachments.push(attachment);
+            }
+        }
+    }
+    Ok(())
+}
+
+// Attach a URL or a file to the task. A file is referenced by its absolute
+// path, or copied into the database with `copy`.
+pub fn add_attachment(
+    tx: &Transaction,
+    task_id: i64,
+    location: &str,
+    copy: bool,
+) -> Result<(), CoreError> {
+    let location = location.trim();
+    if location.is_empty() {
+        return Err(CoreError::ArgumentError(String::from(
+            "The attachment needs a URL or a path",
+        )));
+    }
+
+    let (kind, location, blob_id) = if is_url(location) {
+        if copy {
+            return Err(CoreError::ArgumentError(format!(
+                "{} is a URL, only files can be copied",
+                location
+            )));
+        }
+        (ATTACHMENT_KINDS[0], location.to_string(), None)
+    } else {
+        let cant_attach =
+            |error| CoreError::ArgumentError(format!("Can't attach {}: {}", location, error));
+        let path = fs::canonicalize(location).map_err(cant_attach)?;
+        if !path.is_file() {
+            return Err(CoreError::ArgumentError(format!(
+                "{} isn't a file",
+                path.display()
+            )));
+        }
+        if copy {
+            let data = fs::read(&path).map_err(cant_attach)?;
+            tx.execute(
+                "INSERT INTO attachment_blob (task_id, data)
+                VALUES (:task_id, taskoo_encrypt_blob(:data))",
+                named_params! {":task_id": task_id, ":data": data},
+            )?;
+            let name = path
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_default();
+            (ATTACHMENT_KINDS[2], name, Some(tx.last_insert_rowid()))
+        } else {
+            (ATTACHMENT_KINDS[1], path.to_string_lossy().to_string(), None)
+        }
+    };
+
+    tx.execute(
+        "INSERT INTO attachment (task_id, kind, location, blob_id)
+        VALUES (:task_id, :kind, taskoo_encrypt(:location), :blob_id)",
+        named_params! {
+            ":task_id": task_id,
+            ":kind": kind,
+            ":location": location,
+            ":blob_id": blob_id,
+        },
+    )?;
+    Ok(())
+}
+
+pub fn get_attachment(tx: &Transaction, attachment_id: i64) -> Result<Attachment, CoreError> {
+    let attachment = tx
+        .query_row(
+            &format!("{} WHERE attachment.id = :attachment_id", SELECT_ATTACHMENT_QUERY),
+            named_params! {":attachment_id": attachment_id},
+            convert_row,
+        )
+        .optional()?;
+    match attachment {
+        Some(attachment) => Ok(attachment),
+        None => Err(CoreError::ArgumentError(format!(
+            "Attachment {} doesn't exist",
+            attachment_id
+        ))),
+    }
+}
+
+// The copied data of the attachment, None for URLs and paths
+pub fn get_attachment_data(
+    tx: &Transaction,
+    attachment_id: i64,
+) -> Result<Option<Vec<u8>>, CoreError> {
+    Ok(tx
+        .query_row(
+            "SELECT taskoo_decrypt_blob(attachment_blob.data) FROM attachment
+            INNER JOIN attachment_blob ON attachment.blob_id = attachment_blob.id
+            WHERE attachment.id = :attachment_id",
+            named_params! {":attachment_id": attachment_id},
+            |row| row.get(0),
+        )
+        .optional()?)
+}
+
+// The copy isn't deleted with the attachment, see above
+pub fn delete_attachment(tx: &Transaction, attachment_id: i64) -> Result<(), CoreError> {
+    tx.execute(
+        "DELETE FROM attachment WHERE id = :attachment_id",
+        named_params! {":attachment_id": attachment_id},
+    )?;
+    Ok(())
+}
@@ -0,0 +1,232 @@
+use crate::error::CoreError;
+use chrono::{Duration, Local, NaiveDate, NaiveDateTime};
+use log::{debug, info};
+use rusqlite::backup::Progress;
+use rusqlite::{Connection, DatabaseName, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// A snapshot is a copy of the database taken with the online backup API of
+// SQLite, so it's consistent even while the database is in use. Snapshots
+// are taken on demand and before the operations that remove data, they are
+// files named `<time>-<reason>.db` in the backup directory.
+
+// Where the snapshots are kept, `backups/<database name>` next to the
+// database by default
+pub const BACKUP_DIR_SETTING: &str = "backup_dir";
+// `false` disables the snapshots before the destructive operations
+pub const AUTO_BACKUP_SETTING: &str = "auto_backup";
+// How many of the latest snapshots are kept
+pub const BACKUP_KEEP_LAST_SETTING: &str = "backup_keep_last";
+// The latest snapshot of each of this many days is kept as well
+pub const BACKUP_KEEP_DAILY_SETTING: &str = "backup_keep_daily";
+
+const DEFAULT_KEEP_LAST: usize = 10;
+const DEFAULT_KEEP_DAILY: i64 = 7;
+const TIME_FORMAT: &str = "%Y%m%d-%H%M%S";
+const EXTENSION: &str = "db";
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Backup {
+    // What `restore` takes, like 20261018-153000-delete
+    pub name: String,
+    // The operation that caused it, `manual` for the ones taken on demand
+    pub reason: String,
+    pub created_at: String,
+    // In bytes
+    pub size: u64,
+}
+
+// The time, the sequence that tells apart the snapshots taken within the
+// same second, and the reason of the snapshot. None for the files that
+// aren't snapshots.
+fn parse_name(name: &str) -> Option<(NaiveDateTime, u32, String)> {
+    let time = NaiveDateTime::parse_from_str(name.get(..15)?, TIME_FORMAT).ok()?;
+    let rest = name.get(15..)?.strip_prefix('-')?;
+    match rest.split_once('-') {
+        Some((sequence, reason)) if sequence.chars().all(|c| c.is_ascii_digit()) => {
+            Some((time, sequence.parse().ok()?, reason.to_owned()))
+        }
+        _ => Some((time, 0, rest.to_owned())),
+    }
+}
+
+fn to_backup(name: &str, size: u64) -> Option<Backup> {
+    let (time, _, reason) = parse_name(name)?;
+    Some(Backup {
+        name: name.to_owned(),
+        reason,
+        created_at: time.format("%Y-%m-%d %H:%M:%S").to_string(),
+        size,
+    })
+}
+
+pub fn get_backup_dir(setting: &HashMap<String, String>) -> Option<PathBuf> {
+    if let Some(dir) = setting.get(BACKUP_DIR_SETTING) {
+        return Some(PathBuf::from(dir));
+    }
+    let db_path = Path::new(setting.get("db_path")?);
+    if db_path.to_str() == Some(":memory:") {
+        return None;
+    }
+    let mut dir = db_path.parent()?.join("backups");
+    dir.push(db_path.file_stem()?);
+    Some(dir)
+}
+
+pub fn is_auto_backup(setting: &HashMap<String, String>) -> bool {
+    setting
+        .get(AUTO_BACKUP_SETTING)
+        .map_or(true, |value| value.trim().to_lowercase() != "false")
+}
+
+fn get_limit<T: std::str::FromStr>(
+    setting: &HashMap<String, String>,
+    key: &str,
+    default: T,
+) -> Result<T, CoreError> {
+    match setting.get(key) {
+        Some(value) => value.trim().parse().map_err(|_| {
+            CoreError::BackupError(format!("{} should be a number, got {}", key, value))
+        }),
+        None => Ok(default),
+    }
+}
+
+fn path_of(dir: &Path, name: &str) -> PathBuf {
+    dir.join(format!("{}.{}", name, EXTENSION))
+}
+
+// The snapshots in the directory, the latest first
+pub fn get_backups(dir: &Path) -> Result<Vec<Backup>, CoreError> {
+    if !dir.exists() {
+        return Ok(vec![]);
+    }
+    let mut backups = vec![];
+    for entry in fs::read_dir(dir).map_err(|error| io_error(dir, error))? {
+        let entry = entry.map_err(|error| io_error(dir, error))?;
+        let path = entry.path();
+        if path.extension().and_then(|extension| extension.to_str()) != Some(EXTENSION) {
+            continue;
+        }
+        let size = entry.metadata().map(|metadata| metadata.len()).unwrap_or(0);
+        if let Some(backup) = path
+            .file_stem()
+            .and_then(|name| name.to_str())
+            .and_then(|name| to_backup(name, size))
+        {
+            backups.push(backup);
+        }
+    }
+    backups.sort_by_key(|backup| {
+        let (time, sequence, _) = parse_name(&backup.name).unwrap();
+        std::cmp::Reverse((time, sequence))
+    });
+    Ok(backups)
+}
+
+fn io_error(path: &Path, error: std::io::Error) -> CoreError {
+    CoreError::BackupError(format!("{}: {}", path.display(), error))
+}
+
+// Copy the database into a new snapshot of the directory
+pub fn backup(conn: &Connection, dir: &Path, reason: &str) -> Result<Backup, CoreError> {
+    fs::create_dir_all(dir).map_err(|error| io_error(dir, error))?;
+    let time = Local::now().naive_local().format(TIME_FORMAT).to_string();
+    let sequence = get_backups(dir)?
+        .iter()
+        .filter(|backup| backup.name.starts_with(&time))
+        .filter_map(|backup| parse_name(&backup.name))
+        .map(|(_, sequence, _)| sequence + 1)
+        .max()
+        .unwrap_or(0);
+    let name = if sequence == 0 {
+        format!("{}-{}", time, reason)
+    } else {
+        format!("{}-{}-{}", time, sequence, reason)
+    };
+
+    let path = path_of(dir, &name);
+    info!("Taking a snapshot of the database into {}", path.display());
+    conn.backup(DatabaseName::Main, &path, None)?;
+    let size = fs::metadata(&path)
+        .map(|metadata| metadata.len())
+        .unwrap_or(0);
+    to_backup(&name, size)
+        .ok_or_else(|| CoreError::BackupError(format!("Invalid snapshot name {}", name)))
+}
+
+// Replace the content of the database with the snapshot of the directory
+pub fn restore(conn: &mut Connection, dir: &Path, name: &str) -> Result<(), CoreError> {
+    info!("Restoring the database from {}", name);
+    conn.restore(DatabaseName::Main, path_of(dir, name), None::<fn(Progress)>)?;
+    Ok(())
+}
+
+// The snapshots that are neither among the `keep_last` latest ones nor the
+// latest of one of the last `keep_daily` days, counting `today`
+pub fn get_expired(
+    backups: &Vec<Backup>,
+    keep_last: usize,
+    keep_daily: i64,
+    today: NaiveDate,
+) -> Vec<Backup> {
+    let first_day = today - Duration::days(keep_daily - 1);
+    let mut kept_days: Vec<NaiveDate> = vec![];
+    let mut expired = vec![];
+    // The backups are the latest first
+    for (index, backup) in backups.iter().enumerate() {
+        let day = match parse_name(&backup.name) {
+            Some((time, _, _)) => time.date(),
+            None => continue,
+        };
+        let latest_of_day = keep_daily > 0 && day >= first_day && !kept_days.contains(&day);
+        if latest_of_day {
+            kept_days.push(day);
+        }
+        if index >= keep_last && !latest_of_day {
+            expired.push(backup.clone());
+        }
+    }
+    expired
+}
+
+// Delete the snapshots that the setting doesn't keep
+pub fn rotate(dir: &Path, setting: &HashMap<String, String>) -> Result<(), CoreError> {
+    let keep_last = get_limit(setting, BACKUP_KEEP_LAST_SETTING, DEFAULT_KEEP_LAST)?;
+    let keep_daily = get_limit(setting, BACKUP_KEEP_DAILY_SETTING, DEFAULT_KEEP_DAILY)?;
+    let today = Local::now().naive_local().date();
+    for backup in get_expired(&get_backups(dir)?, keep_last, keep_daily, today).iter() {
+        debug!("Deleting the expired snapshot {}", backup.name);
+        remove(dir, &backup.name)?;
+    }
+    Ok(())
+}
+
+// Take a new snapshot and delete every older one, for the snapshots that
+// hold the database as it was before its key changed. Returns how many were
+// deleted, no snapshot is taken when there were none.
+pub fn replace(conn: &Connection, dir: &Path, reason: &str) -> Result<usize, CoreError> {
+    let older = get_backups(dir)?;
+    if older.is_empty() {
+        return Ok(0);
+    }
+    backup(conn, dir, reason)?;
+    for backup in older.iter() {
+        debug!("Deleting the snapshot {} taken with the old key", backup.name);
+        remove(dir, &backup.name)?;
+    }
+    Ok(older.len())
+}
+
+fn remove(dir: &Path, name: &str) -> Result<(), CoreError> {
+    let path = path_of(dir, name);
+    if let Err(error) = fs::remove_file(&path) {
+        if error.kind() != std::io::ErrorKind::NotFound {
+            return Err(io_error(&path, error));
+        }
+    }
+    Ok(())
+}
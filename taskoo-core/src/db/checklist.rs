@@ -0,0 +1,137 @@
+use super::query_builder::placeholders;
+use crate::db::task_helper::Task;
+use crate::error::CoreError;
+use rusqlite::{named_params, params_from_iter, Result, Transaction};
+use serde::{Deserialize, Serialize};
+
+// The items of a checklist are numbered from 1 within their task, and the
+// positions stay contiguous when items are moved or deleted. Commands refer
+// to the items by their position rather than their id.
+
+// Checklists are looked up for this many tasks at a time, like the notes
+const TASK_IDS_PER_QUERY: usize = 500;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ChecklistItem {
+    pub id: i64,
+    pub task_id: i64,
+    pub position: i64,
+    pub body: String,
+    pub done: bool,
+}
+
+// Fill in the checklists of the tasks, in the order of their items
+pub fn attach_checklists(tx: &Transaction, tasks: &mut Vec<Task>) -> Result<(), CoreError> {
+    let task_ids: Vec<i64> = tasks.iter().map(|task| task.id).collect();
+    for chunk in task_ids.chunks(TASK_IDS_PER_QUERY) {
+        let mut statement = tx.prepare(&format!(
+            "SELECT id, task_id, position, taskoo_decrypt(body), done FROM checklist_item
+            WHERE task_id IN ({}) ORDER BY position",
+            placeholders(chunk.len())
+        ))?;
+        let mut rows = statement.query(params_from_iter(chunk.iter()))?;
+        while let Some(row) = rows.next()? {
+            let item = ChecklistItem {
+                id: row.get(0)?,
+                task_id: row.get(1)?,
+                position: row.get(2)?,
+                body: row.get(3)?,
+                done: row.get(4)?,
+            };
+            if let Some(task) = tasks.iter_mut().find(|task| task.id == item.task_id) {
+                task.checklist.push(item);
+            }
+        }
+    }
+    Ok(())
+}
+
+fn count_items(tx: &Transaction, task_id: i64) -> Result<i64, CoreError> {
+    Ok(tx.query_row(
+        "SELECT COUNT(*) FROM checklist_item WHERE task_id = :task_id",
+        named_params! {":task_id": task_id},
+        |row| row.get(0),
+    )?)
+}
+
+fn check_position(tx: &Transaction, task_id: i64, position: i64) -> Result<(), CoreError> {
+    if position < 1 || position > count_items(tx, task_id)? {
+        return Err(CoreError::ArgumentError(format!(
+            "Task {} doesn't have checklist item {}",
+            task_id, position
+        )));
+    }
+    Ok(())
+}
+
+// Append an item to the end of the checklist
+pub fn add_item(tx: &Transaction, task_id: i64, body: &str) -> Result<(), CoreError> {
+    tx.execute(
+        "INSERT INTO checklist_item (task_id, position, body)
+        VALUES (:task_id, :position, taskoo_encrypt(:body))",
+        named_params! {
+            ":task_id": task_id,
+            ":position": count_items(tx, task_id)? + 1,
+            ":body": body,
+        },
+    )?;
+    Ok(())
+}
+
+pub fn check_item(
+    tx: &Transaction,
+    task_id: i64,
+    position: i64,
+    done: bool,
+) -> Result<(), CoreError> {
+    check_position(tx, task_id, position)?;
+    tx.execute(
+        "UPDATE checklist_item SET done = :done WHERE task_id = :task_id AND position = :position",
+        named_params! {":task_id": task_id, ":position": position, ":done": done},
+    )?;
+    Ok(())
+}
+
+// Move the item at `from` to `to`, the items in between shift by one
+pub fn move_item(tx: &Transaction, task_id: i64, from: i64, to: i64) -> Result<(), CoreError> {
+    check_position(tx, task_id, from)?;
+    check_position(tx, task_id, to)?;
+    if from == to {
+        return Ok(());
+    }
+    // The moved item is parked at position 0 while the others shift
+    tx.execute(
+        "UPDATE checklist_item SET position = 0 WHERE task_id = :task_id AND position = :from",
+        named_params! {":task_id": task_id, ":from": from},
+    )?;
+    let shift = if from < to {
+        "UPDATE checklist_item SET position = position - 1
+        WHERE task_id = :task_id AND position > :from AND position <= :to"
+    } else {
+        "UPDATE checklist_item SET position = position + 1
+        WHERE task_id = :task_id AND position >= :to AND position < :from"
+    };
+    tx.execute(
+        shift,
+        named_params! {":task_id": task_id, ":from": from, ":to": to},
+    )?;
+    tx.execute(
+        "UPDATE checklist_item SET position = :to WHERE task_id = :task_id AND position = 0",
+        named_params! {":task_id": task_id, ":to": to},
+    )?;
+    Ok(())
+}
+
+pub fn delete_item(tx: &Transaction, task_id: i64, position: i64) -> Result<(), CoreError> {
+    check_position(tx, task_id, position)?;
+    tx.execute(
+        "DELETE FROM checklist_item WHERE task_id = :task_id AND position = :position",
+        named_params! {":task_id": task_id, ":position": position},
+    )?;
+    tx.execute(
+        "UPDATE checklist_item SET position = position - 1
+        WHERE task_id = :task_id AND position > :position",
+        named_params! {":task_id": task_id, ":position": position},
+    )?;
+    Ok(())
+}
@@ -1,55 +1,122 @@
 use crate::db::task_helper::Task;
-use super::get_base::get_base;
+use super::get_base::{get_base, get_trash};
+use super::modify::{block_dependents, update_dependency};
+use super::query_builder::{Column, Condition, Filter};
+use super::selection::Selection;
+use super::subtask::reparent_children;
+use super::time_tracking::stop_tracking;
 use crate::error::CoreError;
+use chrono::Local;
+use rusqlite::types::Value;
 use rusqlite::{named_params, Result, Transaction};
-use rusqlite::Statement;
 
+// Move the tasks to the trash, they are hidden from `get_base` until they
+// are restored or purged. Their subtasks move up to the parent of the task,
+// and the tasks blocked by them only by them become ready.
 pub fn delete(conn: &Transaction, task_ids: &Vec<i64>) -> Result<Vec<Task>, CoreError> {
     if task_ids.is_empty() {
         return Ok(vec![]);
     }
 
-    let delete_tag_state = conn.prepare("DELETE FROM task_tag where task_id = :task_id")?;
-    let delete_priority = conn.prepare("DELETE FROM priority_task where task_id = :task_id")?;
-    let delete_from_task_context =
-        conn.prepare("DELETE FROM task_context where task_id = :task_id")?;
-    let delete_from_task_state = conn.prepare("DELETE FROM task_state where task_id = :task_id")?;
-    let delete_dependency = conn
-        .prepare("DELETE FROM dependency where task_id = :task_id or parent_task_id =:task_id;")?;
-    let delete_task = conn.prepare("DELETE FROM task where id = :task_id;")?;
-
-    let delete_stmt_to_run: &mut [Statement; 6] = &mut [
-        delete_tag_state,
-        delete_priority,
-        delete_from_task_state,
-        delete_from_task_context,
-        delete_dependency,
-        delete_task,
-    ];
+    Selection::Task.fill(conn, task_ids)?;
+    let deleted_tasks = get_base(conn, &Filter::selected(Selection::Task))?;
 
-    let task_ids_str: Vec<String> = task_ids.into_iter().map(|i| i.to_string()).collect();
-    let mut deleted_tasks: Vec<Task> = vec![];
+    let deleted_at = Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+    conn.execute(
+        &format!(
+            "UPDATE task SET deleted_at = :deleted_at WHERE id IN ({}) AND deleted_at IS NULL",
+            Selection::Task.ids()
+        ),
+        named_params! {":deleted_at": deleted_at},
+    )?;
+    update_dependency(conn)?;
+    reparent_children(conn, Selection::Task)?;
+    stop_tracking(conn)?;
 
-    for task_id in task_ids_str.iter() {
-        let mut tasks = get_base(conn, &format!("task.id = {}", task_id))?;
-        deleted_tasks.append(&mut tasks);
+    Ok(deleted_tasks)
+}
+
+// The tasks out of `task_ids` that `delete` moves to the trash
+pub fn get_deletable_ids(conn: &Transaction, task_ids: &Vec<i64>) -> Result<Vec<i64>, CoreError> {
+    if task_ids.is_empty() {
+        return Ok(vec![]);
     }
+    Selection::Task.fill(conn, task_ids)?;
+    let mut statement = conn.prepare(&format!(
+        "SELECT id FROM task WHERE id IN ({}) AND deleted_at IS NULL",
+        Selection::Task.ids()
+    ))?;
+    let rows = statement.query_map([], |row| row.get(0))?;
+    Ok(rows.collect::<Result<Vec<i64>>>()?)
+}
 
-    // Okay, it's super stupid that we need to execute the delete
-    // query for each task_id, however this seems to be the limitation
-    // of rusqlite. I've tried to use IN operator, however it doesn't
-    // work.
-    //
-    // Another approach would be creating a temporary table and insert
-    // the task ids to that table and then do a delete query based on
-    // that temporary table.
-    for task_id in task_ids_str.iter() {
-        for stmt in delete_stmt_to_run.into_iter() {
-            stmt.execute(named_params! {
-                ":task_id": task_id,
-            })?;
-        }
+// Take the tasks out of the trash, the tasks that depend on them are
+// blocked again until they are completed
+pub fn restore(conn: &Transaction, task_ids: &Vec<i64>) -> Result<Vec<Task>, CoreError> {
+    Selection::Task.fill(conn, task_ids)?;
+    let restored_ids: Vec<i64> = get_trash(conn, &Filter::selected(Selection::Task))?
+        .iter()
+        .map(|task| task.id)
+        .collect();
+    if restored_ids.is_empty() {
+        return Ok(vec![]);
     }
 
-    Ok(deleted_tasks)
+    Selection::Task.fill(conn, &restored_ids)?;
+    conn.execute(
+        &format!(
+            "UPDATE task SET deleted_at = NULL WHERE id IN ({})",
+            Selection::Task.ids()
+        ),
+        [],
+    )?;
+    block_dependents(conn)?;
+    get_base(conn, &Filter::selected(Selection::Task))
+}
+
+// The tasks in the trash that `purge` removes
+pub fn get_purgeable(
+    conn: &Transaction,
+    deleted_before: &Option<String>,
+) -> Result<Vec<Task>, CoreError> {
+    let mut filter = Filter::new();
+    if let Some(date) = deleted_before {
+        filter.push(Condition::Le(Column::DeletedAt, Value::from(date.clone())));
+    }
+    get_trash(conn, &filter)
+}
+
+// Permanently remove the tasks in the trash, only the ones that were
+// deleted before `deleted_before` if it's provided.
+pub fn purge(conn: &Transaction, deleted_before: &Option<String>) -> Result<Vec<Task>, CoreError> {
+    let purged_tasks = get_purgeable(conn, deleted_before)?;
+    if purged_tasks.is_empty() {
+        return Ok(purged_tasks);
+    }
+
+    let purged_ids: Vec<i64> = purged_tasks.iter().map(|task| task.id).collect();
+    Selection::Task.fill(conn, &purged_ids)?;
+    let ids = Selection::Task.ids();
+    let delete_queries = [
+        format!("DELETE FROM task_tag where task_id IN ({})", ids),
+        format!("DELETE FROM priority_task where task_id IN ({})", ids),
+        format!("DELETE FROM task_state where task_id IN ({})", ids),
+        format!("DELETE FROM task_context where task_id IN ({})", ids),
+        format!("DELETE FROM dependency where task_id IN ({ids}) or parent_task_id IN ({ids})"),
+        format!("DELETE FROM task_history where task_id IN ({})", ids),
+        format!("DELETE FROM note where task_id IN ({})", ids),
+        format!("DELETE FROM time_interval where task_id IN ({})", ids),
+        format!("DELETE FROM task_uda where task_id IN ({})", ids),
+        format!("DELETE FROM task_project where task_id IN ({})", ids),
+        format!("DELETE FROM attachment where task_id IN ({})", ids),
+        format!("DELETE FROM attachment_blob where task_id IN ({})", ids),
+        format!("DELETE FROM checklist_item where task_id IN ({})", ids),
+        format!("DELETE FROM task where id IN ({})", ids),
+    ];
+
+    for query in delete_queries.iter() {
+        conn.execute(query, [])?;
+    }
+
+    Ok(purged_tasks)
 }
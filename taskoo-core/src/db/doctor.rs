@@ -0,0 +1,352 @@
+use crate::db::task_manager::TaskManager;
+use crate::error::CoreError;
+use chrono::{NaiveDate, NaiveDateTime};
+use rusqlite::{named_params, Result, Transaction};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum ProblemKind {
+    OrphanedRow,
+    MissingState,
+    MissingContext,
+    DanglingDependency,
+    DependencyCycle,
+    InvalidDate,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Problem {
+    pub kind: ProblemKind,
+    // The tasks involved, the cycle in order for dependency cycles
+    pub task_ids: Vec<i64>,
+    pub description: String,
+}
+
+impl Problem {
+    fn new(kind: ProblemKind, task_ids: Vec<i64>, description: String) -> Problem {
+        Problem {
+            kind,
+            task_ids,
+            description,
+        }
+    }
+}
+
+// Rows of the tables that refer to tasks by task_id, and the label each
+// row refers to, if any
+const TASK_ROW_TABLES: &[(&str, Option<(&str, &str)>)] = &[
+    ("task_context", None),
+    ("task_state", None),
+    ("task_tag", Some(("tag_id", "tag"))),
+    ("priority_task", Some(("priority_id", "priority"))),
+    ("note", None),
+    ("time_interval", None),
+    ("task_uda", None),
+    ("task_project", Some(("project_id", "project"))),
+    ("attachment", None),
+    ("checklist_item", None),
+    ("working_set", None),
+];
+
+// The date columns of the task table, the ones that can't be empty get
+// the current time when they're repaired
+const DATE_COLUMNS: &[(&str, bool)] = &[
+    ("created_at", true),
+    ("due_date", false),
+    ("scheduled_at", false),
+    ("deleted_at", true),
+    ("modified_at", true),
+    ("started_at", true),
+    ("completed_at", true),
+];
+const REPEAT_COLUMNS: &[&str] = &["due_repeat", "scheduled_repeat"];
+
+// States and contexts of the tasks that lost theirs
+const FIX_STATE: &str = "ready";
+const FIX_CONTEXT: &str = "inbox";
+
+// Look for the inconsistencies that the schema doesn't prevent and repair
+// them when `fix` is set. The problems are returned either way, each
+// check sees the repairs of the checks before it.
+pub fn diagnose(tx: &Transaction, fix: bool) -> Result<Vec<Problem>, CoreError> {
+    let mut problems = vec![];
+    problems.append(&mut find_orphaned_rows(tx, fix)?);
+    problems.append(&mut find_dangling_dependencies(tx, fix)?);
+    problems.append(&mut find_dependency_cycles(tx, fix)?);
+    problems.append(&mut find_missing_labels(tx, fix, "state", FIX_STATE)?);
+    problems.append(&mut find_missing_labels(tx, fix, "context", FIX_CONTEXT)?);
+    problems.append(&mut find_invalid_dates(tx, fix)?);
+    Ok(problems)
+}
+
+fn query_pairs(tx: &Transaction, query: &str) -> Result<Vec<(i64, i64)>, CoreError> {
+    let mut statement = tx.prepare(query)?;
+    let mut rows = statement.query([])?;
+    let mut pairs = vec![];
+    while let Some(row) = rows.next()? {
+        pairs.push((row.get(0)?, row.get(1)?));
+    }
+    Ok(pairs)
+}
+
+fn query_ids(tx: &Transaction, query: &str) -> Result<Vec<i64>, CoreError> {
+    let mut statement = tx.prepare(query)?;
+    let mut rows = statement.query([])?;
+    let mut ids = vec![];
+    while let Some(row) = rows.next()? {
+        ids.push(row.get(0)?);
+    }
+    Ok(ids)
+}
+
+// Rows of tasks that don't exist, and tags or priorities that don't exist
+fn find_orphaned_rows(tx: &Transaction, fix: bool) -> Result<Vec<Problem>, CoreError> {
+    let mut problems = vec![];
+    for (table, label) in TASK_ROW_TABLES.iter() {
+        let condition = "task_id NOT IN (SELECT id FROM task)";
+        let query = format!(
+            "SELECT task_id, COUNT(*) FROM {} WHERE {} GROUP BY task_id ORDER BY task_id",
+            table, condition
+        );
+        for (task_id, count) in query_pairs(tx, &query)? {
+            problems.push(Problem::new(
+                ProblemKind::OrphanedRow,
+                vec![task_id],
+                format!(
+                    "{} row(s) in {} belong to task {} which doesn't exist",
+                    count, table, task_id
+                ),
+            ));
+        }
+        if fix {
+            tx.execute(&format!("DELETE FROM {} WHERE {}", table, condition), [])?;
+        }
+
+        if let Some((column, label_table)) = label {
+            let condition = format!("{} NOT IN (SELECT id FROM {})", column, label_table);
+            let query = format!(
+                "SELECT task_id, {} FROM {} WHERE {} ORDER BY task_id, {}",
+                column, table, condition, column
+            );
+            for (task_id, label_id) in query_pairs(tx, &query)? {
+                problems.push(Problem::new(
+                    ProblemKind::OrphanedRow,
+                    vec![task_id],
+                    format!(
+                        "Task {} has the {} {} which doesn't exist",
+                        task_id, label_table, label_id
+                    ),
+                ));
+            }
+            if fix {
+                tx.execute(&format!("DELETE FROM {} WHERE {}", table, condition), [])?;
+            }
+        }
+    }
+    Ok(problems)
+}
+
+fn find_dangling_dependencies(tx: &Transaction, fix: bool) -> Result<Vec<Problem>, CoreError> {
+    let condition = "task_id NOT IN (SELECT id FROM task)
+        OR parent_task_id NOT IN (SELECT id FROM task)";
+    let query = format!(
+        "SELECT task_id, parent_task_id FROM dependency WHERE {} ORDER BY task_id, parent_task_id",
+        condition
+    );
+    let mut problems = vec![];
+    for (task_id, parent_task_id) in query_pairs(tx, &query)? {
+        problems.push(Problem::new(
+            ProblemKind::DanglingDependency,
+            vec![task_id, parent_task_id],
+            format!(
+                "Task {} depends on task {}, one of them doesn't exist",
+                task_id, parent_task_id
+            ),
+        ));
+    }
+    if fix {
+        tx.execute(&format!("DELETE FROM dependency WHERE {}", condition), [])?;
+    }
+    Ok(problems)
+}
+
+// Every cycle is broken by removing the dependency that closes it
+fn find_dependency_cycles(tx: &Transaction, fix: bool) -> Result<Vec<Problem>, CoreError> {
+    let mut parents: BTreeMap<i64, Vec<i64>> = BTreeMap::new();
+    for (task_id, parent_task_id) in query_pairs(
+        tx,
+        "SELECT task_id, parent_task_id FROM dependency ORDER BY task_id, parent_task_id",
+    )? {
+        parents.entry(task_id).or_default().push(parent_task_id);
+    }
+
+    let mut problems = vec![];
+    for (task_id, parent_task_id, cycle) in find_cycles(&parents) {
+        let path: Vec<String> = cycle.iter().map(|id| id.to_string()).collect();
+        problems.push(Problem::new(
+            ProblemKind::DependencyCycle,
+            cycle,
+            format!(
+                "Tasks depend on each other in a cycle: {} -> {}",
+                path.join(" -> "),
+                path[0]
+            ),
+        ));
+        if fix {
+            tx.execute(
+                "DELETE FROM dependency WHERE task_id = :task_id AND parent_task_id = :parent_task_id",
+                named_params! {":task_id": task_id, ":parent_task_id": parent_task_id},
+            )?;
+        }
+    }
+    Ok(problems)
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Visit {
+    InProgress,
+    Done,
+}
+
+// Depth-first search over the dependencies. Returns the dependency that
+// closes each cycle, as (task_id, parent_task_id), and the tasks of the
+// cycle. The search goes on as if that dependency didn't exist.
+fn find_cycles(parents: &BTreeMap<i64, Vec<i64>>) -> Vec<(i64, i64, Vec<i64>)> {
+    let mut visits: HashMap<i64, Visit> = HashMap::new();
+    let mut cycles = vec![];
+    for start in parents.keys() {
+        if visits.contains_key(start) {
+            continue;
+        }
+        // The tasks being visited and the index of their next parent
+        let mut stack: Vec<(i64, usize)> = vec![(*start, 0)];
+        visits.insert(*start, Visit::InProgress);
+        while let Some((task_id, index)) = stack.last_mut() {
+            let task_id = *task_id;
+            let next = parents.get(&task_id).and_then(|ids| ids.get(*index)).copied();
+            *index += 1;
+            match next {
+                None => {
+                    visits.insert(task_id, Visit::Done);
+                    stack.pop();
+                }
+                Some(parent_id) => match visits.get(&parent_id) {
+                    Some(Visit::Done) => {}
+                    Some(Visit::InProgress) => {
+                        let position = stack.iter().position(|(id, _)| *id == parent_id).unwrap();
+                        let cycle = stack[position..].iter().map(|(id, _)| *id).collect();
+                        cycles.push((task_id, parent_id, cycle));
+                    }
+                    None => {
+                        visits.insert(parent_id, Visit::InProgress);
+                        stack.push((parent_id, 0));
+                    }
+                },
+            }
+        }
+    }
+    cycles
+}
+
+// Tasks without a state or context, or with one that doesn't exist. They
+// get `default_name` when they're repaired.
+fn find_missing_labels(
+    tx: &Transaction,
+    fix: bool,
+    label_table: &str,
+    default_name: &str,
+) -> Result<Vec<Problem>, CoreError> {
+    let query = format!(
+        "SELECT task.id FROM task
+        LEFT JOIN task_{label} ON task.id = task_{label}.task_id
+        LEFT JOIN {label} ON task_{label}.{label}_id = {label}.id
+        WHERE {label}.id IS NULL ORDER BY task.id",
+        label = label_table
+    );
+    let kind = if label_table == "state" {
+        ProblemKind::MissingState
+    } else {
+        ProblemKind::MissingContext
+    };
+
+    let mut problems = vec![];
+    for task_id in query_ids(tx, &query)? {
+        problems.push(Problem::new(
+            kind,
+            vec![task_id],
+            format!("Task {} doesn't have a {}", task_id, label_table),
+        ));
+        if fix {
+            tx.execute(
+                &format!("INSERT OR IGNORE INTO {} (name) VALUES (:name)", label_table),
+                named_params! {":name": default_name},
+            )?;
+            tx.execute(
+                &format!(
+                    "INSERT OR REPLACE INTO task_{label} (task_id, {label}_id)
+                    SELECT :task_id, id FROM {label} WHERE name = :name",
+                    label = label_table
+                ),
+                named_params! {":task_id": task_id, ":name": default_name},
+            )?;
+        }
+    }
+    Ok(problems)
+}
+
+fn is_valid_date(date: &str) -> bool {
+    NaiveDateTime::parse_from_str(date, "%Y-%m-%d %H:%M:%S").is_ok()
+        || NaiveDate::parse_from_str(date, "%Y-%m-%d").is_ok()
+}
+
+// Dates and repetitions that can't be parsed. Repaired dates are cleared,
+// or set to the current time when they can't be empty.
+fn find_invalid_dates(tx: &Transaction, fix: bool) -> Result<Vec<Problem>, CoreError> {
+    let mut invalid: Vec<(i64, &str, String, bool)> = vec![];
+    let mut statement = tx.prepare(
+        "SELECT id, created_at, due_date, scheduled_at, deleted_at, modified_at, started_at,
+        completed_at, due_repeat, scheduled_repeat FROM task ORDER BY id",
+    )?;
+    let mut rows = statement.query([])?;
+    while let Some(row) = rows.next()? {
+        let task_id: i64 = row.get(0)?;
+        for (index, (column, required)) in DATE_COLUMNS.iter().enumerate() {
+            let value: Option<String> = row.get(index + 1)?;
+            match value {
+                Some(date) if !date.is_empty() && !is_valid_date(&date) => {
+                    invalid.push((task_id, column, date, *required))
+                }
+                _ => {}
+            }
+        }
+        for (index, column) in REPEAT_COLUMNS.iter().enumerate() {
+            let value: Option<String> = row.get(index + 1 + DATE_COLUMNS.len())?;
+            match value {
+                Some(repeat) if !repeat.is_empty() => {
+                    if TaskManager::parse_date_string(&repeat).is_err() {
+                        invalid.push((task_id, column, repeat, false));
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    let mut problems = vec![];
+    for (task_id, column, value, required) in invalid {
+        problems.push(Problem::new(
+            ProblemKind::InvalidDate,
+            vec![task_id],
+            format!("Task {} has {} '{}' which can't be parsed", task_id, column, value),
+        ));
+        if fix {
+            let replacement = if required { "CURRENT_TIMESTAMP" } else { "''" };
+            tx.execute(
+                &format!("UPDATE task SET {} = {} WHERE id = :task_id", column, replacement),
+                named_params! {":task_id": task_id},
+            )?;
+        }
+    }
+    Ok(problems)
+}
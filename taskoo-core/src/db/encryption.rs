@@ -0,0 +1,509 @@
+use super::journal;
+use crate::error::CoreError;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use log::{debug, info};
+use rusqlite::functions::FunctionFlags;
+use rusqlite::{named_params, Connection, OptionalExtension, Result, Transaction};
+use sha2::Sha256;
+use std::collections::HashMap;
+use std::fs;
+use std::process::Command;
+
+// In the encrypted mode the bodies and the annotations of the tasks, the
+// notes, the checklist items, the bodies recorded in the history, the
+// locations and copies of the attachments and the descriptions of the
+// projects are stored encrypted with ChaCha20-Poly1305. The key is derived
+// from the passphrase with PBKDF2 and the salt in the `encryption` table.
+// The other columns stay readable, so filtering and the agenda work the same
+// in both modes: the names of the contexts, tags and projects and the values
+// of the user-defined attributes are matched and sorted in the queries, they
+// aren't encrypted. The full-text index only gets the encrypted text,
+// `db::search` matches the decrypted tasks instead.
+//
+// The queries encrypt and decrypt with the SQL functions `taskoo_encrypt`
+// and `taskoo_decrypt`, and `taskoo_encrypt_blob` and `taskoo_decrypt_blob`
+// for the copied files, which `register_functions` binds to the key of the
+// connection. Text and data without ENCRYPTED_PREFIX are stored plain.
+
+// The passphrase given by the program, e.g. after prompting for it
+pub const PASSPHRASE_SETTING: &str = "passphrase";
+pub const PASSPHRASE_ENV_VAR: &str = "TASKOO_PASSPHRASE";
+// A file whose content is the passphrase
+pub const KEY_FILE_SETTING: &str = "key_file";
+// A command that prints the passphrase, like `pass show taskoo`
+pub const PASSPHRASE_COMMAND_SETTING: &str = "passphrase_command";
+
+const ENCRYPTED_PREFIX: &str = "enc1:";
+const NONCE_SIZE: usize = 12;
+const SALT_SIZE: usize = 16;
+// Encrypted with the key to tell a wrong passphrase apart
+const CHECK_TEXT: &str = "taskoo";
+
+// Deriving the key is slow on purpose, the tests don't need that
+#[cfg(not(test))]
+const KDF_ROUNDS: u32 = 600_000;
+#[cfg(test)]
+const KDF_ROUNDS: u32 = 1_000;
+
+// The encrypted columns by table, with the condition a row has to match
+const ENCRYPTED_COLUMNS: &[(&str, &str, Option<(&str, &str)>)] = &[
+    ("task", "body", None),
+    ("task", "annotation", None),
+    ("note", "body", None),
+    ("task_history", "old_value", Some(("field", "body"))),
+    ("task_history", "new_value", Some(("field", "body"))),
+    ("checklist_item", "body", None),
+    ("attachment", "location", None),
+    ("project", "description", None),
+];
+
+// The encrypted columns that hold data rather than text
+const ENCRYPTED_BLOB_COLUMNS: &[(&str, &str)] = &[("attachment_blob", "data")];
+
+#[derive(Clone)]
+pub struct Cipher {
+    cipher: ChaCha20Poly1305,
+}
+
+impl Cipher {
+    fn derive(passphrase: &str, salt: &[u8], rounds: u32) -> Cipher {
+        let mut key = [0u8; 32];
+        pbkdf2::pbkdf2_hmac::<Sha256>(passphrase.as_bytes(), salt, rounds, &mut key);
+        Cipher {
+            cipher: ChaCha20Poly1305::new(Key::from_slice(&key)),
+        }
+    }
+
+    // The nonce followed by the encrypted data
+    fn seal(&self, data: &[u8]) -> Result<Vec<u8>, CoreError> {
+        let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let encrypted = self
+            .cipher
+            .encrypt(&nonce, data)
+            .map_err(|_| CoreError::EncryptionError(String::from("Failed to encrypt the text")))?;
+        let mut sealed = nonce.to_vec();
+        sealed.extend(encrypted);
+        Ok(sealed)
+    }
+
+    fn open(&self, sealed: &[u8]) -> Result<Vec<u8>, CoreError> {
+        if sealed.len() < NONCE_SIZE {
+            return Err(invalid_key());
+        }
+        self.cipher
+            .decrypt(
+                Nonce::from_slice(&sealed[..NONCE_SIZE]),
+                &sealed[NONCE_SIZE..],
+            )
+            .map_err(|_| invalid_key())
+    }
+
+    fn encrypt(&self, text: &str) -> Result<String, CoreError> {
+        Ok(format!(
+            "{}{}",
+            ENCRYPTED_PREFIX,
+            BASE64.encode(self.seal(text.as_bytes())?)
+        ))
+    }
+
+    fn decrypt(&self, text: &str) -> Result<String, CoreError> {
+        let sealed = match text.strip_prefix(ENCRYPTED_PREFIX) {
+            Some(data) => BASE64.decode(data).map_err(|_| invalid_key())?,
+            None => return Ok(text.to_string()),
+        };
+        String::from_utf8(self.open(&sealed)?).map_err(|_| invalid_key())
+    }
+
+    fn encrypt_blob(&self, data: &[u8]) -> Result<Vec<u8>, CoreError> {
+        let mut encrypted = ENCRYPTED_PREFIX.as_bytes().to_vec();
+        encrypted.extend(self.seal(data)?);
+        Ok(encrypted)
+    }
+
+    fn decrypt_blob(&self, data: &[u8]) -> Result<Vec<u8>, CoreError> {
+        match data.strip_prefix(ENCRYPTED_PREFIX.as_bytes()) {
+            Some(sealed) => self.open(sealed),
+            None => Ok(data.to_vec()),
+        }
+    }
+}
+
+fn invalid_key() -> CoreError {
+    CoreError::EncryptionError(String::from(
+        "Unable to decrypt the text, it was encrypted with another key",
+    ))
+}
+
+// The key of a connection. An encrypted database without the right
+// passphrase is locked: the database opens, but the queries that read or
+// write the encrypted text fail.
+#[derive(Clone)]
+pub enum KeyState {
+    Plain,
+    Unlocked(Cipher),
+    // Why the database can't be unlocked
+    Locked(String),
+}
+
+impl KeyState {
+    pub fn is_encrypted(&self) -> bool {
+        !matches!(self, KeyState::Plain)
+    }
+
+    fn encrypt(&self, text: &str) -> Result<String, CoreError> {
+        match self {
+            KeyState::Plain => Ok(text.to_string()),
+            KeyState::Unlocked(cipher) => cipher.encrypt(text),
+            KeyState::Locked(reason) => Err(CoreError::EncryptionError(reason.clone())),
+        }
+    }
+
+    fn decrypt(&self, text: &str) -> Result<String, CoreError> {
+        match self {
+            KeyState::Unlocked(cipher) => cipher.decrypt(text),
+            KeyState::Locked(reason) if is_encrypted_text(text) => {
+                Err(CoreError::EncryptionError(reason.clone()))
+            }
+            _ => Ok(text.to_string()),
+        }
+    }
+
+    fn encrypt_blob(&self, data: &[u8]) -> Result<Vec<u8>, CoreError> {
+        match self {
+            KeyState::Plain => Ok(data.to_vec()),
+            KeyState::Unlocked(cipher) => cipher.encrypt_blob(data),
+            KeyState::Locked(reason) => Err(CoreError::EncryptionError(reason.clone())),
+        }
+    }
+
+    fn decrypt_blob(&self, data: &[u8]) -> Result<Vec<u8>, CoreError> {
+        match self {
+            KeyState::Unlocked(cipher) => cipher.decrypt_blob(data),
+            KeyState::Locked(reason) if is_encrypted_blob(data) => {
+                Err(CoreError::EncryptionError(reason.clone()))
+            }
+            _ => Ok(data.to_vec()),
+        }
+    }
+}
+
+// Whether the database has a key, whether or not it's unlocked
+pub fn is_enabled(conn: &Connection) -> Result<bool, CoreError> {
+    Ok(
+        conn.query_row("SELECT COUNT(*) FROM encryption", [], |row| {
+            row.get::<_, i64>(0)
+        })? > 0,
+    )
+}
+
+pub fn is_encrypted_text(text: &str) -> bool {
+    text.starts_with(ENCRYPTED_PREFIX)
+}
+
+pub fn is_encrypted_blob(data: &[u8]) -> bool {
+    data.starts_with(ENCRYPTED_PREFIX.as_bytes())
+}
+
+// The passphrase given by the program, then the one in the environment,
+// in the key file, or printed by the command
+fn get_passphrase(setting: &HashMap<String, String>) -> Result<Option<String>, CoreError> {
+    if let Some(passphrase) = setting.get(PASSPHRASE_SETTING) {
+        return Ok(Some(passphrase.clone()));
+    }
+    if let Ok(passphrase) = std::env::var(PASSPHRASE_ENV_VAR) {
+        if !passphrase.is_empty() {
+            return Ok(Some(passphrase));
+        }
+    }
+    if let Some(path) = setting.get(KEY_FILE_SETTING) {
+        let content = fs::read_to_string(path).map_err(|error| {
+            CoreError::EncryptionError(format!("Unable to read the key file {}: {}", path, error))
+        })?;
+        return Ok(Some(
+            content.trim_end_matches(&['\r', '\n'][..]).to_string(),
+        ));
+    }
+    if let Some(command) = setting.get(PASSPHRASE_COMMAND_SETTING) {
+        debug!("Getting the passphrase from {}", command);
+        let output = Command::new("sh")
+            .arg("-c")
+            .arg(command)
+            .output()
+            .map_err(|error| {
+                CoreError::EncryptionError(format!("Unable to run {}: {}", command, error))
+            })?;
+        if !output.status.success() {
+            return Err(CoreError::EncryptionError(format!(
+                "{} failed with {}",
+                command, output.status
+            )));
+        }
+        let passphrase = String::from_utf8_lossy(&output.stdout);
+        return Ok(Some(
+            passphrase.trim_end_matches(&['\r', '\n'][..]).to_string(),
+        ));
+    }
+    Ok(None)
+}
+
+// The key of the database, from the passphrase in the setting
+pub fn load_key(
+    conn: &Connection,
+    setting: &HashMap<String, String>,
+) -> Result<KeyState, CoreError> {
+    let stored: Option<(String, String, u32)> = conn
+        .query_row(
+            "SELECT salt, key_check, rounds FROM encryption WHERE id = 1",
+            [],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+        )
+        .optional()?;
+    let (salt, key_check, rounds) = match stored {
+        Some(stored) => stored,
+        None => return Ok(KeyState::Plain),
+    };
+
+    let passphrase = match get_passphrase(setting) {
+        Ok(Some(passphrase)) => passphrase,
+        Ok(None) => {
+            return Ok(KeyState::Locked(format!(
+                "The database is encrypted, provide the passphrase with {}, {} or {}",
+                PASSPHRASE_ENV_VAR, KEY_FILE_SETTING, PASSPHRASE_COMMAND_SETTING
+            )))
+        }
+        Err(error) => return Ok(KeyState::Locked(error.to_string())),
+    };
+    let salt = BASE64
+        .decode(salt)
+        .map_err(|error| CoreError::EncryptionError(format!("Invalid salt: {}", error)))?;
+    let cipher = Cipher::derive(&passphrase, &salt, rounds);
+    match cipher.decrypt(&key_check) {
+        Ok(text) if text == CHECK_TEXT => Ok(KeyState::Unlocked(cipher)),
+        _ => Ok(KeyState::Locked(String::from(
+            "The passphrase doesn't match the one of the database",
+        ))),
+    }
+}
+
+fn to_sql_error(error: CoreError) -> rusqlite::Error {
+    rusqlite::Error::UserFunctionError(error.to_string().into())
+}
+
+// Bind the encryption functions to the key, they keep NULL
+pub fn register_functions(conn: &Connection, key: &KeyState) -> Result<(), CoreError> {
+    let encrypt_key = key.clone();
+    conn.create_scalar_function(
+        "taskoo_encrypt",
+        1,
+        FunctionFlags::SQLITE_UTF8,
+        move |ctx| {
+            let text: Option<String> = ctx.get(0)?;
+            text.map(|text| encrypt_key.encrypt(&text))
+                .transpose()
+                .map_err(to_sql_error)
+        },
+    )?;
+
+    let decrypt_key = key.clone();
+    conn.create_scalar_function(
+        "taskoo_decrypt",
+        1,
+        FunctionFlags::SQLITE_UTF8 | FunctionFlags::SQLITE_DETERMINISTIC,
+        move |ctx| {
+            let text: Option<String> = ctx.get(0)?;
+            text.map(|text| decrypt_key.decrypt(&text))
+                .transpose()
+                .map_err(to_sql_error)
+        },
+    )?;
+
+    let encrypt_key = key.clone();
+    conn.create_scalar_function(
+        "taskoo_encrypt_blob",
+        1,
+        FunctionFlags::SQLITE_UTF8,
+        move |ctx| {
+            let data: Option<Vec<u8>> = ctx.get(0)?;
+            data.map(|data| encrypt_key.encrypt_blob(&data))
+                .transpose()
+                .map_err(to_sql_error)
+        },
+    )?;
+
+    let decrypt_key = key.clone();
+    conn.create_scalar_function(
+        "taskoo_decrypt_blob",
+        1,
+        FunctionFlags::SQLITE_UTF8 | FunctionFlags::SQLITE_DETERMINISTIC,
+        move |ctx| {
+            let data: Option<Vec<u8>> = ctx.get(0)?;
+            data.map(|data| decrypt_key.decrypt_blob(&data))
+                .transpose()
+                .map_err(to_sql_error)
+        },
+    )?;
+    Ok(())
+}
+
+fn store_key(tx: &Transaction, passphrase: &str) -> Result<KeyState, CoreError> {
+    if passphrase.is_empty() {
+        return Err(CoreError::ArgumentError(String::from(
+            "The passphrase can't be empty",
+        )));
+    }
+    let mut salt = [0u8; SALT_SIZE];
+    OsRng.fill_bytes(&mut salt);
+    let cipher = Cipher::derive(passphrase, &salt, KDF_ROUNDS);
+    tx.execute("DELETE FROM encryption", [])?;
+    tx.execute(
+        "INSERT INTO encryption (id, salt, key_check, rounds)
+        VALUES (1, :salt, :key_check, :rounds)",
+        named_params! {
+            ":salt": BASE64.encode(salt),
+            ":key_check": cipher.encrypt(CHECK_TEXT)?,
+            ":rounds": KDF_ROUNDS,
+        },
+    )?;
+    Ok(KeyState::Unlocked(cipher))
+}
+
+// Decrypt the text with `from` and encrypt it again with `to`
+fn convert(text: &str, from: &KeyState, to: &KeyState) -> Result<String, CoreError> {
+    to.encrypt(&from.decrypt(text)?)
+}
+
+fn convert_blob(data: &[u8], from: &KeyState, to: &KeyState) -> Result<Vec<u8>, CoreError> {
+    to.encrypt_blob(&from.decrypt_blob(data)?)
+}
+
+// Encrypt everything that's encrypted by `from` with `to` instead. The
+// archived tasks are converted with the rows that restore them, the
+// journal is cleared as its rows would need the old key to be restored.
+fn reencrypt(tx: &Transaction, from: &KeyState, to: &KeyState) -> Result<(), CoreError> {
+    for (table, column, condition) in ENCRYPTED_COLUMNS.iter() {
+        // Only the names of the tables and columns go into the query
+        let rows: Vec<(i64, Option<String>)> = match condition {
+            Some((condition_column, value)) => {
+                let mut statement = tx.prepare(&format!(
+                    "SELECT rowid, {} FROM {} WHERE {} = :value",
+                    column, table, condition_column
+                ))?;
+                let rows = statement.query_map(named_params! {":value": value}, |row| {
+                    Ok((row.get(0)?, row.get(1)?))
+                })?;
+                rows.collect::<Result<Vec<_>>>()?
+            }
+            None => {
+                let mut statement =
+                    tx.prepare(&format!("SELECT rowid, {} FROM {}", column, table))?;
+                let rows = statement.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+                rows.collect::<Result<Vec<_>>>()?
+            }
+        };
+        debug!("Converting {} of {} rows of {}", column, rows.len(), table);
+        let mut update = tx.prepare(&format!(
+            "UPDATE {} SET {} = :value WHERE rowid = :rowid",
+            table, column
+        ))?;
+        for (rowid, value) in rows.iter() {
+            if let Some(value) = value {
+                update.execute(named_params! {
+                    ":value": convert(value, from, to)?,
+                    ":rowid": rowid,
+                })?;
+            }
+        }
+    }
+
+    for (table, column) in ENCRYPTED_BLOB_COLUMNS.iter() {
+        let rows: Vec<(i64, Vec<u8>)> = {
+            let mut statement = tx.prepare(&format!(
+                "SELECT rowid, {} FROM {} WHERE {} IS NOT NULL",
+                column, table, column
+            ))?;
+            let rows = statement.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+            rows.collect::<Result<Vec<_>>>()?
+        };
+        debug!("Converting {} of {} rows of {}", column, rows.len(), table);
+        let mut update = tx.prepare(&format!(
+            "UPDATE {} SET {} = :value WHERE rowid = :rowid",
+            table, column
+        ))?;
+        for (rowid, value) in rows.iter() {
+            update.execute(named_params! {
+                ":value": convert_blob(value, from, to)?,
+                ":rowid": rowid,
+            })?;
+        }
+    }
+
+    let archived: Vec<(i64, String, String)> = {
+        let mut statement = tx.prepare("SELECT task_id, task, snapshot FROM archive")?;
+        let rows = statement.query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?;
+        rows.collect::<Result<Vec<_>>>()?
+    };
+    for (task_id, task, snapshot) in archived.iter() {
+        let mut snapshots = journal::from_json(snapshot)?;
+        for snapshot in snapshots.iter_mut() {
+            for (table, column, condition) in ENCRYPTED_COLUMNS.iter() {
+                snapshot.map_text(table, column, *condition, |text| convert(text, from, to))?;
+            }
+        }
+        tx.execute(
+            "UPDATE archive SET task = :task, snapshot = :snapshot WHERE task_id = :task_id",
+            named_params! {
+                ":task": convert(task, from, to)?,
+                ":snapshot": journal::to_json(&snapshots)?,
+                ":task_id": task_id,
+            },
+        )?;
+    }
+
+    journal::clear(tx)?;
+    Ok(())
+}
+
+fn check_unlocked(key: &KeyState) -> Result<(), CoreError> {
+    match key {
+        KeyState::Plain => Err(CoreError::ArgumentError(String::from(
+            "The database isn't encrypted",
+        ))),
+        KeyState::Locked(reason) => Err(CoreError::EncryptionError(reason.clone())),
+        KeyState::Unlocked(_) => Ok(()),
+    }
+}
+
+// Encrypt the database with the passphrase, the new key is returned
+pub fn enable(tx: &Transaction, key: &KeyState, passphrase: &str) -> Result<KeyState, CoreError> {
+    if key.is_encrypted() {
+        return Err(CoreError::ArgumentError(String::from(
+            "The database is encrypted already, rotate the key instead",
+        )));
+    }
+    let new_key = store_key(tx, passphrase)?;
+    reencrypt(tx, key, &new_key)?;
+    info!("Encrypted the database");
+    Ok(new_key)
+}
+
+// Encrypt the database with a key derived from the new passphrase
+pub fn rotate(tx: &Transaction, key: &KeyState, passphrase: &str) -> Result<KeyState, CoreError> {
+    check_unlocked(key)?;
+    let new_key = store_key(tx, passphrase)?;
+    reencrypt(tx, key, &new_key)?;
+    info!("Rotated the key of the database");
+    Ok(new_key)
+}
+
+// Store the text decrypted again
+pub fn disable(tx: &Transaction, key: &KeyState) -> Result<KeyState, CoreError> {
+    check_unlocked(key)?;
+    tx.execute("DELETE FROM encryption", [])?;
+    reencrypt(tx, key, &KeyState::Plain)?;
+    info!("Decrypted the database");
+    Ok(KeyState::Plain)
+}
@@ -1,28 +1,12 @@
 use super::query_helper::generate_get_condition;
+use super::query_builder::{Column, Condition, Filter};
 use super::get_base::get_base;
 
 use crate::db::task_helper::{Task};
 use crate::error::CoreError;
 
-use rusqlite::{Result, Transaction};
-
-fn task_matches_tag_ids(task: &Task, tag_ids: &Vec<i64>) -> bool {
-    for tag_id in tag_ids.iter() {
-        if !task.tag_ids.contains(tag_id) {
-            return false;
-        }
-    }
-    return true;
-}
-
-fn task_not_matches_tag_ids(task: &Task, not_tag_ids: &Vec<i64>) -> bool {
-    for tag_id in not_tag_ids.iter() {
-        if task.tag_ids.contains(tag_id) {
-            return false;
-        }
-    }
-    return true;
-}
+use rusqlite::types::Value;
+use rusqlite::{named_params, Result, Transaction};
 
 pub fn get(
     conn: &Transaction,
@@ -34,29 +18,51 @@ pub fn get(
     task_id: &Option<i64>,
     not_tag_ids: &Option<Vec<i64>>,
 ) -> Result<Vec<Task>, CoreError> {
-    let mut conditions = match task_id {
-        Some(id) => vec![format!("task.id = {}", id)],
-        None => generate_get_condition(&None, context_id, date_due, date_scheduled),
+    let mut filter = match task_id {
+        Some(id) => Filter::task_id(id),
+        None => generate_get_condition(context_id, date_due, date_scheduled),
     };
     if let Some(priority_id) = priority_id {
-        conditions.push(format!("priority_task.priority_id = {}", priority_id));
+        filter.push(Condition::Eq(Column::PriorityId, Value::from(*priority_id)));
     }
 
-    let mut tasks = get_base(&conn, &conditions.join(" and "))?;
-
     // Filter the tags that we'd like to get
     if !tag_ids.is_empty() {
-        tasks = tasks
-            .into_iter()
-            .filter(|task| task_matches_tag_ids(task, &tag_ids))
-            .collect();
+        filter.push(Condition::HasAllTags(tag_ids.clone()));
     }
-
     if let Some(not_tag_ids) = not_tag_ids {
-        tasks = tasks
-            .into_iter()
-            .filter(|task| task_not_matches_tag_ids(task, &not_tag_ids))
-            .collect();
+        filter.push(Condition::HasNoTags(not_tag_ids.clone()));
     }
+
+    let tasks = get_base(&conn, &filter)?;
     Ok(tasks)
 }
+
+// The id of the task whose UUID starts with `prefix`, archived tasks
+// included. The prefix has to match exactly one task.
+pub fn get_task_id_by_uuid(conn: &Transaction, prefix: &str) -> Result<i64, CoreError> {
+    let mut statement = conn.prepare(
+        "SELECT id FROM task WHERE substr(uuid, 1, length(:prefix)) = :prefix
+        UNION ALL
+        SELECT task_id FROM archive
+        WHERE substr(json_extract(taskoo_decrypt(task), '$.uuid'), 1, length(:prefix)) = :prefix
+        LIMIT 2",
+    )?;
+    let mut rows = statement.query(named_params! {":prefix": prefix.to_lowercase()})?;
+    let mut task_ids: Vec<i64> = vec![];
+    while let Some(row) = rows.next()? {
+        task_ids.push(row.get(0)?);
+    }
+
+    match task_ids.len() {
+        0 => Err(CoreError::ArgumentError(format!(
+            "No task has the UUID {}",
+            prefix
+        ))),
+        1 => Ok(task_ids[0]),
+        _ => Err(CoreError::ArgumentError(format!(
+            "The UUID prefix {} matches more than one task",
+            prefix
+        ))),
+    }
+}
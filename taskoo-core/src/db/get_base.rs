@@ -1,21 +1,39 @@
 use crate::error::CoreError;
+use crate::db::attachment::attach_attachments;
+use crate::db::checklist::attach_checklists;
+use crate::db::note::attach_notes;
+use crate::db::uda::attach_udas;
+use crate::db::query_builder::Filter;
 use crate::db::task_helper::{convert_rows_into_task, Task};
 use log::debug;
-use rusqlite::{Result, Transaction};
+use rusqlite::{params_from_iter, Result, Transaction};
 
-pub fn get_base(tx: &Transaction, conditions: &str) -> Result<Vec<Task>, CoreError> {
+// Tasks in the trash are excluded, use `get_trash` to get them.
+pub fn get_base(tx: &Transaction, filter: &Filter) -> Result<Vec<Task>, CoreError> {
+    query_tasks(tx, filter, false)
+}
+
+pub fn get_trash(tx: &Transaction, filter: &Filter) -> Result<Vec<Task>, CoreError> {
+    query_tasks(tx, filter, true)
+}
+
+fn query_tasks(tx: &Transaction, filter: &Filter, trashed: bool) -> Result<Vec<Task>, CoreError> {
     let mut query = String::from("
-    SELECT task.id as id, body, priority_task.name as priority, created_at, due_date, scheduled_at, due_repeat, scheduled_repeat, context.name as context, state.name as state, task.annotation, GROUP_CONCAT(DISTINCT task_tag.tag_id) as concat_tag_ids, GROUP_CONCAT(DISTINCT task_tag.name) as concat_tag_names, GROUP_CONCAT(dependency.parent_task_id) as parent_task_ids FROM task
+    SELECT task.id as id, task.uuid as uuid, working_set.display_id as display_id, taskoo_decrypt(body) as body, priority_task.name as priority, created_at, due_date, scheduled_at, due_repeat, scheduled_repeat, IFNULL(task.modified_at, '') as modified_at, IFNULL(task.started_at, '') as started_at, IFNULL(task.completed_at, '') as completed_at, task.estimate as estimate, IFNULL(time_tracked.seconds, 0) as time_tracked, task.parent_id as parent_id, IFNULL(subtasks.total, 0) as subtasks_total, IFNULL(subtasks.completed, 0) as subtasks_completed, context.name as context, IFNULL(task_project.name, '') as project, state.name as state, taskoo_decrypt(task.annotation) as annotation, GROUP_CONCAT(DISTINCT tag.id) as concat_tag_ids, GROUP_CONCAT(DISTINCT tag.name) as concat_tag_names, GROUP_CONCAT(dependency.parent_task_id) as parent_task_ids FROM task
     INNER JOIN task_context
     on task.id = task_context.task_id
     INNER JOIN context
     on task_context.context_id = context.id
     LEFT JOIN
         (
-        SELECT task_tag.task_id, task_tag.tag_id, tag.name FROM task_tag
-        INNER JOIN tag ON task_tag.tag_id = tag.id
-        ) task_tag
+        SELECT task_project.task_id, project.name FROM task_project
+        INNER JOIN project ON task_project.project_id = project.id
+        ) task_project
+    ON task.id = task_project.task_id
+    LEFT JOIN task_tag
     ON task.id = task_tag.task_id
+    LEFT JOIN tag
+    ON task_tag.tag_id = tag.id
     INNER JOIN task_state
     on task.id = task_state.task_id
     INNER JOIN state
@@ -27,15 +45,41 @@ pub fn get_base(tx: &Transaction, conditions: &str) -> Result<Vec<Task>, CoreErr
         ) priority_task
     on task.id = priority_task.task_id
     LEFT JOIN dependency
-    ON task.id = dependency.task_id");
+    ON task.id = dependency.task_id
+    LEFT JOIN working_set
+    ON task.id = working_set.task_id
+    LEFT JOIN
+        (
+        SELECT task_id, SUM(
+            strftime('%s', IFNULL(stopped_at, CURRENT_TIMESTAMP)) - strftime('%s', started_at)
+        ) as seconds FROM time_interval GROUP BY task_id
+        ) time_tracked
+    ON task.id = time_tracked.task_id
+    LEFT JOIN
+        (
+        SELECT child.parent_id, COUNT(*) as total, SUM(child_state.name = 'completed') as completed
+        FROM task child
+        INNER JOIN task_state child_task_state ON child.id = child_task_state.task_id
+        INNER JOIN state child_state ON child_task_state.state_id = child_state.id
+        WHERE child.parent_id IS NOT NULL AND child.deleted_at IS NULL
+        GROUP BY child.parent_id
+        ) subtasks
+    ON task.id = subtasks.parent_id");
+
+    if trashed {
+        query.push_str(" Where task.deleted_at IS NOT NULL");
+    } else {
+        query.push_str(" Where task.deleted_at IS NULL");
+    }
 
-    if !conditions.is_empty() {
-        query.push_str(format!(" Where {}", conditions).as_str());
+    let (conditions, params) = filter.to_sql();
+    if !filter.is_empty() {
+        query.push_str(format!(" and ({})", conditions).as_str());
     }
 
     query.push_str(" Group By task.id");
 
-    debug!("Running select query \n{}", query);
+    debug!("Running select query \n{} with {:?}", query, params);
     let mut statement = tx.prepare(&query)?;
 
     // let names = statement
@@ -46,6 +90,11 @@ pub fn get_base(tx: &Transaction, conditions: &str) -> Result<Vec<Task>, CoreErr
 
     // println!("Names {:?}", names);
 
-    let mut rows = statement.query([])?;
-    return Ok(convert_rows_into_task(&mut rows));
+    let mut rows = statement.query(params_from_iter(params))?;
+    let mut tasks = convert_rows_into_task(&mut rows);
+    attach_notes(tx, &mut tasks)?;
+    attach_attachments(tx, &mut tasks)?;
+    attach_checklists(tx, &mut tasks)?;
+    attach_udas(tx, &mut tasks)?;
+    Ok(tasks)
 }
@@ -0,0 +1,90 @@
+use crate::db::task_helper::{format_estimate, Task};
+use crate::error::CoreError;
+use log::debug;
+use rusqlite::{named_params, Result, Transaction};
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct TaskChange {
+    pub task_id: i64,
+    pub field: String,
+    pub old_value: String,
+    pub new_value: String,
+    pub changed_at: String,
+}
+
+// The fields that are tracked, named after `Task::get_property_value`
+fn tracked_values(task: &Task) -> Vec<(&'static str, String)> {
+    let mut tags = task.tags.clone();
+    tags.sort();
+    vec![
+        ("body", task.body.clone()),
+        ("priority", task.priority.clone()),
+        ("context", task.context.clone()),
+        ("project", task.project.clone()),
+        ("tags", tags.join(",")),
+        ("date_due", task.date_due.clone()),
+        ("date_scheduled", task.date_scheduled.clone()),
+        ("repetition_due", task.repetition_due.clone()),
+        ("repetition_scheduled", task.repetition_scheduled.clone()),
+        ("state", task.state.clone()),
+        ("estimate", task.estimate.map_or(String::new(), format_estimate)),
+        ("udas", task.format_udas()),
+        ("parent_id", task.parent_id.map_or(String::new(), |id| id.to_string())),
+    ]
+}
+
+// Record the fields that are different between `before` and `after`,
+// use None as `before` for a task that was just created.
+pub fn record_changes(tx: &Transaction, before: Option<&Task>, after: &Task) -> Result<(), CoreError> {
+    let old_values = before.map(tracked_values);
+    let mut statement = tx.prepare_cached(
+        "INSERT INTO task_history (task_id, field, old_value, new_value)
+        VALUES (
+            :task_id, :field,
+            CASE :field WHEN 'body' THEN taskoo_encrypt(:old_value) ELSE :old_value END,
+            CASE :field WHEN 'body' THEN taskoo_encrypt(:new_value) ELSE :new_value END
+        )",
+    )?;
+
+    for (index, (field, new_value)) in tracked_values(after).iter().enumerate() {
+        let old_value = match &old_values {
+            Some(values) => values[index].1.clone(),
+            None => String::new(),
+        };
+        if &old_value != new_value {
+            debug!(
+                "Task {} changed {}: {:?} -> {:?}",
+                after.id, field, old_value, new_value
+            );
+            statement.execute(named_params! {
+                ":task_id": after.id,
+                ":field": field,
+                ":old_value": old_value,
+                ":new_value": new_value,
+            })?;
+        }
+    }
+    Ok(())
+}
+
+pub fn get_history(tx: &Transaction, task_id: i64) -> Result<Vec<TaskChange>, CoreError> {
+    let mut statement = tx.prepare(
+        "SELECT task_id, field, taskoo_decrypt(old_value) as old_value,
+        taskoo_decrypt(new_value) as new_value, changed_at FROM task_history
+        WHERE task_id = :task_id ORDER BY id",
+    )?;
+    let mut rows = statement.query(named_params! {":task_id": task_id})?;
+
+    let mut changes = vec![];
+    while let Some(row) = rows.next()? {
+        changes.push(TaskChange {
+            task_id: row.get("task_id")?,
+            field: row.get("field")?,
+            old_value: row.get("old_value")?,
+            new_value: row.get("new_value")?,
+            changed_at: row.get("changed_at")?,
+        });
+    }
+    Ok(changes)
+}
@@ -0,0 +1,605 @@
+use super::selection::Selection;
+use crate::error::CoreError;
+use log::{debug, info};
+use rusqlite::types::{Value, ValueRef};
+use rusqlite::{named_params, params_from_iter, OptionalExtension, Result, Transaction};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+// Only keep this many entries, older ones can't be undone anymore
+const JOURNAL_SIZE: i64 = 100;
+
+// Tables that hold the data of a task, in the order they need to be
+// restored; rows are removed in the reverse order. A row belongs to the
+// tasks in its owner columns.
+//
+// The history records the changes rather than the state of a task, so
+// undoing a change doesn't take back its history. Only the snapshots that
+// move a task out of the task tables and back, like the archive and the
+// purge, keep the history rows.
+const TASK_TABLES: &[(&str, &[&str])] = &[
+    ("task", &["id"]),
+    ("task_context", &["task_id"]),
+    ("task_state", &["task_id"]),
+    ("task_tag", &["task_id"]),
+    ("priority_task", &["task_id"]),
+    ("dependency", &["task_id", "parent_task_id"]),
+    ("task_history", &["task_id"]),
+    ("note", &["task_id"]),
+    ("time_interval", &["task_id"]),
+    ("task_uda", &["task_id"]),
+    ("task_project", &["task_id"]),
+    ("attachment", &["task_id"]),
+    ("checklist_item", &["task_id"]),
+    ("archive", &["task_id"]),
+];
+
+const HISTORY_TABLE: &str = "task_history";
+
+// Matches the rows that belong to one of the tasks in `Selection::Journal`
+fn selected_rows_condition(owner_columns: &[&str]) -> String {
+    owner_columns
+        .iter()
+        .map(|column| format!("{} IN ({})", column, Selection::Journal.ids()))
+        .collect::<Vec<String>>()
+        .join(" OR ")
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+enum Cell {
+    Null,
+    Integer(i64),
+    Real(f64),
+    Text(String),
+    Blob(Vec<u8>),
+}
+
+impl From<ValueRef<'_>> for Cell {
+    fn from(value: ValueRef) -> Self {
+        match value {
+            ValueRef::Null => Cell::Null,
+            ValueRef::Integer(i) => Cell::Integer(i),
+            ValueRef::Real(f) => Cell::Real(f),
+            ValueRef::Text(t) => Cell::Text(String::from_utf8_lossy(t).to_string()),
+            ValueRef::Blob(b) => Cell::Blob(b.to_vec()),
+        }
+    }
+}
+
+impl From<&Cell> for Value {
+    fn from(cell: &Cell) -> Self {
+        match cell {
+            Cell::Null => Value::Null,
+            Cell::Integer(i) => Value::Integer(*i),
+            Cell::Real(f) => Value::Real(*f),
+            Cell::Text(t) => Value::Text(t.clone()),
+            Cell::Blob(b) => Value::Blob(b.clone()),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct TableRows {
+    table: String,
+    columns: Vec<String>,
+    rows: Vec<Vec<Cell>>,
+}
+
+// Every row that belongs to a task at a point of time. A snapshot without
+// any rows means the task doesn't exist.
+//
+// The task is the one with the UUID, the id of the snapshot can be taken
+// by another task by the time it's restored, see `resolve`. Snapshots
+// recorded before the UUIDs were kept only have the id.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct TaskSnapshot {
+    task_id: i64,
+    #[serde(default)]
+    uuid: Option<String>,
+    // Whether the history rows are part of the snapshot, the snapshots
+    // recorded before they were left out have them
+    #[serde(default = "default_with_history")]
+    with_history: bool,
+    tables: Vec<TableRows>,
+}
+
+fn default_with_history() -> bool {
+    true
+}
+
+impl TaskSnapshot {
+    pub fn missing(task_id: i64) -> TaskSnapshot {
+        TaskSnapshot {
+            task_id,
+            uuid: None,
+            with_history: false,
+            tables: vec![],
+        }
+    }
+
+    fn is_missing(&self) -> bool {
+        self.tables.is_empty()
+    }
+
+    // Whether restoring the snapshot replaces the history rows of the task
+    fn replaces_history(&self) -> bool {
+        self.with_history || self.is_missing()
+    }
+
+    // Give the task another id, in the rows of the task and in the rows
+    // that refer to it
+    fn replace_task_id(&mut self, from: i64, to: i64) {
+        if self.task_id == from {
+            self.task_id = to;
+        }
+        for table_rows in self.tables.iter_mut() {
+            let is_task = table_rows.table == "task";
+            let indexes: Vec<usize> = table_rows
+                .columns
+                .iter()
+                .enumerate()
+                .filter(|(_, column)| match is_task {
+                    true => *column == "id" || *column == "parent_id",
+                    false => *column == "task_id" || *column == "parent_task_id",
+                })
+                .map(|(index, _)| index)
+                .collect();
+            for row in table_rows.rows.iter_mut() {
+                for index in indexes.iter() {
+                    if row[*index] == Cell::Integer(from) {
+                        row[*index] = Cell::Integer(to);
+                    }
+                }
+            }
+        }
+    }
+
+    // Change the value of `column` from `from` to `to` in the rows of `table`
+    pub fn replace_value(&mut self, table: &str, column: &str, from: i64, to: i64) {
+        for table_rows in self.tables.iter_mut().filter(|t| t.table == table) {
+            if let Some(index) = table_rows.columns.iter().position(|c| c == column) {
+                for row in table_rows.rows.iter_mut() {
+                    if row[index] == Cell::Integer(from) {
+                        row[index] = Cell::Integer(to);
+                    }
+                }
+            }
+        }
+    }
+
+    // Drop the dependency rows that refer to another task for which
+    // `exists` returns false
+    pub fn retain_dependencies(&mut self, exists: impl Fn(i64) -> bool) {
+        let task_id = self.task_id;
+        for table_rows in self.tables.iter_mut().filter(|t| t.table == "dependency") {
+            let indexes: Vec<usize> = table_rows
+                .columns
+                .iter()
+                .enumerate()
+                .filter(|(_, column)| *column == "task_id" || *column == "parent_task_id")
+                .map(|(index, _)| index)
+                .collect();
+            table_rows.rows.retain(|row| {
+                indexes.iter().all(|index| match row[*index] {
+                    Cell::Integer(id) => id == task_id || exists(id),
+                    _ => true,
+                })
+            });
+        }
+    }
+
+    // Replace the text of `column` in the rows of `table` with `convert` of
+    // it, only in the rows whose column in `condition` has the given value
+    pub fn map_text(
+        &mut self,
+        table: &str,
+        column: &str,
+        condition: Option<(&str, &str)>,
+        mut convert: impl FnMut(&str) -> Result<String, CoreError>,
+    ) -> Result<(), CoreError> {
+        for table_rows in self.tables.iter_mut().filter(|t| t.table == table) {
+            let index = match table_rows.columns.iter().position(|c| c == column) {
+                Some(index) => index,
+                None => continue,
+            };
+            let condition = condition.map(|(name, value)| {
+                (table_rows.columns.iter().position(|c| c == name), value)
+            });
+            for row in table_rows.rows.iter_mut() {
+                if let Some((condition_index, value)) = condition {
+                    let matches = condition_index
+                        .map_or(false, |i| row[i] == Cell::Text(value.to_string()));
+                    if !matches {
+                        continue;
+                    }
+                }
+                if let Cell::Text(text) = &row[index] {
+                    row[index] = Cell::Text(convert(text)?);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // Make the task a top level task if `exists` returns false for its parent
+    pub fn retain_parent(&mut self, exists: impl Fn(i64) -> bool) {
+        for table_rows in self.tables.iter_mut().filter(|t| t.table == "task") {
+            if let Some(index) = table_rows.columns.iter().position(|c| c == "parent_id") {
+                for row in table_rows.rows.iter_mut() {
+                    if let Cell::Integer(parent_id) = row[index] {
+                        if !exists(parent_id) {
+                            row[index] = Cell::Null;
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+// The rows of the tasks, without their history
+pub fn snapshot(tx: &Transaction, task_ids: &Vec<i64>) -> Result<Vec<TaskSnapshot>, CoreError> {
+    take_snapshots(tx, task_ids, false)
+}
+
+// The rows of the tasks along with their history, for the changes that
+// remove the history rows with the task
+pub fn snapshot_with_history(
+    tx: &Transaction,
+    task_ids: &Vec<i64>,
+) -> Result<Vec<TaskSnapshot>, CoreError> {
+    take_snapshots(tx, task_ids, true)
+}
+
+fn take_snapshots(
+    tx: &Transaction,
+    task_ids: &Vec<i64>,
+    with_history: bool,
+) -> Result<Vec<TaskSnapshot>, CoreError> {
+    Selection::Journal.fill(tx, task_ids)?;
+    let mut selected: HashSet<i64> = task_ids.iter().copied().collect();
+    let mut tables_by_task: HashMap<i64, Vec<TableRows>> = HashMap::new();
+    for (table, owner_columns) in TASK_TABLES.iter() {
+        if *table == HISTORY_TABLE && !with_history {
+            continue;
+        }
+        let mut statement = tx.prepare(&format!(
+            "SELECT * FROM {} WHERE {}",
+            table,
+            selected_rows_condition(owner_columns)
+        ))?;
+        let columns: Vec<String> = statement
+            .column_names()
+            .into_iter()
+            .map(|name| name.to_string())
+            .collect();
+        let owner_indexes: Vec<usize> = owner_columns
+            .iter()
+            .filter_map(|owner| columns.iter().position(|column| column == owner))
+            .collect();
+
+        let mut rows_by_task: HashMap<i64, Vec<Vec<Cell>>> = HashMap::new();
+        let mut rows = statement.query([])?;
+        while let Some(row) = rows.next()? {
+            let mut cell_row = vec![];
+            for index in 0..columns.len() {
+                cell_row.push(Cell::from(row.get_ref(index)?));
+            }
+            let mut owners: Vec<i64> = vec![];
+            for index in owner_indexes.iter() {
+                let owner: i64 = row.get(*index)?;
+                // A dependency between two of the tasks is kept by both
+                if selected.contains(&owner) && !owners.contains(&owner) {
+                    owners.push(owner);
+                }
+            }
+            for owner in owners {
+                rows_by_task.entry(owner).or_default().push(cell_row.clone());
+            }
+        }
+
+        for (task_id, cells) in rows_by_task {
+            tables_by_task.entry(task_id).or_default().push(TableRows {
+                table: table.to_string(),
+                columns: columns.clone(),
+                rows: cells,
+            });
+        }
+    }
+
+    let mut snapshots: Vec<TaskSnapshot> = vec![];
+    for task_id in task_ids.iter() {
+        if !selected.remove(task_id) {
+            continue;
+        }
+        let tables = tables_by_task.remove(task_id).unwrap_or_default();
+        snapshots.push(TaskSnapshot {
+            task_id: *task_id,
+            uuid: get_uuid(&tables),
+            with_history,
+            tables,
+        });
+    }
+    Ok(snapshots)
+}
+
+// The UUID in the task row of the snapshot
+fn get_uuid(tables: &Vec<TableRows>) -> Option<String> {
+    let task_rows = tables.iter().find(|t| t.table == "task")?;
+    let index = task_rows.columns.iter().position(|c| c == "uuid")?;
+    match task_rows.rows.first().map(|row| &row[index]) {
+        Some(Cell::Text(uuid)) => Some(uuid.clone()),
+        _ => None,
+    }
+}
+
+// Whether the id belongs to a task or an archived task other than the one
+// with the UUID
+fn is_taken(tx: &Transaction, task_id: i64, uuid: &str) -> Result<bool, CoreError> {
+    Ok(tx.query_row(
+        "SELECT EXISTS (SELECT 1 FROM task WHERE id = :task_id AND IFNULL(uuid, '') != :uuid)
+        OR EXISTS (
+            SELECT 1 FROM archive WHERE task_id = :task_id
+            AND IFNULL(json_extract(taskoo_decrypt(task), '$.uuid'), '') != :uuid
+        )",
+        named_params! {":task_id": task_id, ":uuid": uuid},
+        |row| row.get(0),
+    )?)
+}
+
+// Point the snapshots at the current ids of their tasks. A task that's
+// restored while another task took its id gets a new id, a task that's
+// removed but doesn't exist anymore is left out.
+fn resolve(
+    tx: &Transaction,
+    snapshots: &Vec<TaskSnapshot>,
+) -> Result<Vec<TaskSnapshot>, CoreError> {
+    let mut resolved = snapshots.clone();
+    // A new id is neither used by a task, an archived task, nor another
+    // task of the entry
+    let mut next_id: i64 = tx.query_row(
+        "SELECT MAX(IFNULL((SELECT MAX(id) FROM task), 0),
+            IFNULL((SELECT MAX(task_id) FROM archive), 0)) + 1",
+        [],
+        |row| row.get(0),
+    )?;
+    next_id = next_id.max(snapshots.iter().map(|s| s.task_id + 1).max().unwrap_or(0));
+
+    let mut index = 0;
+    while index < resolved.len() {
+        let uuid = match resolved[index].uuid.clone() {
+            Some(uuid) => uuid,
+            None => {
+                index += 1;
+                continue;
+            }
+        };
+        let from = resolved[index].task_id;
+        let current_id: Option<i64> = tx
+            .query_row(
+                "SELECT id FROM task WHERE uuid = :uuid",
+                named_params! {":uuid": uuid},
+                |row| row.get(0),
+            )
+            .optional()?;
+        let to = match current_id {
+            Some(id) => id,
+            None if resolved[index].is_missing() => {
+                debug!("Task {} is gone already", uuid);
+                resolved.remove(index);
+                continue;
+            }
+            None if is_taken(tx, from, &uuid)? => {
+                next_id += 1;
+                next_id - 1
+            }
+            None => from,
+        };
+        if to != from {
+            info!("Restoring task {} as task {}, its id is taken", uuid, to);
+            for snapshot in resolved.iter_mut() {
+                snapshot.replace_task_id(from, to);
+            }
+        }
+        index += 1;
+    }
+    Ok(resolved)
+}
+
+// Fill in the UUIDs of the missing tasks from the snapshots of the other
+// side of the change, they are the tasks that are added or removed
+fn with_uuids(snapshots: &Vec<TaskSnapshot>, other: &Vec<TaskSnapshot>) -> Vec<TaskSnapshot> {
+    let mut snapshots = snapshots.clone();
+    for snapshot in snapshots.iter_mut().filter(|snapshot| snapshot.uuid.is_none()) {
+        snapshot.uuid = other
+            .iter()
+            .find(|other| other.task_id == snapshot.task_id)
+            .and_then(|other| other.uuid.clone());
+    }
+    snapshots
+}
+
+// Put the rows of the given tasks back to what the snapshots recorded
+pub fn restore(tx: &Transaction, snapshots: &Vec<TaskSnapshot>) -> Result<(), CoreError> {
+    let task_ids: Vec<i64> = snapshots.iter().map(|snapshot| snapshot.task_id).collect();
+    Selection::Journal.fill(tx, &task_ids)?;
+    // The subtasks of a task refer to it while its row is put back, the
+    // foreign keys are checked when the transaction is committed instead
+    tx.execute_batch("PRAGMA defer_foreign_keys = ON")?;
+    for (table, owner_columns) in TASK_TABLES.iter().rev() {
+        if *table == HISTORY_TABLE {
+            continue;
+        }
+        tx.execute(
+            &format!("DELETE FROM {} WHERE {}", table, selected_rows_condition(owner_columns)),
+            [],
+        )?;
+    }
+    let history_task_ids: Vec<String> = snapshots
+        .iter()
+        .filter(|snapshot| snapshot.replaces_history())
+        .map(|snapshot| snapshot.task_id.to_string())
+        .collect();
+    if !history_task_ids.is_empty() {
+        tx.execute(
+            &format!(
+                "DELETE FROM {} WHERE task_id IN ({})",
+                HISTORY_TABLE,
+                history_task_ids.join(", ")
+            ),
+            [],
+        )?;
+    }
+
+    for (table, _) in TASK_TABLES.iter() {
+        for snapshot in snapshots.iter() {
+            for table_rows in snapshot.tables.iter().filter(|t| &t.table == table) {
+                let placeholders = vec!["?"; table_rows.columns.len()].join(", ");
+                // Two tasks of the same snapshot can share a dependency row
+                let mut statement = tx.prepare_cached(&format!(
+                    "INSERT OR IGNORE INTO {} ({}) VALUES ({})",
+                    table,
+                    table_rows.columns.join(", "),
+                    placeholders
+                ))?;
+                for row in table_rows.rows.iter() {
+                    statement.execute(params_from_iter(row.iter().map(Value::from)))?;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+// Completing, deleting or restoring a task changes the state of the
+// tasks that are blocked by it, so they have to be part of the journal
+// entry as well.
+pub fn with_dependents(tx: &Transaction, task_ids: &Vec<i64>) -> Result<Vec<i64>, CoreError> {
+    Selection::Journal.fill(tx, task_ids)?;
+    let mut statement = tx.prepare(&format!(
+        "SELECT DISTINCT task_id FROM dependency
+        WHERE parent_task_id IN ({ids}) AND task_id NOT IN ({ids})
+        ORDER BY task_id",
+        ids = Selection::Journal.ids()
+    ))?;
+    let mut rows = statement.query([])?;
+    let mut result = task_ids.clone();
+    while let Some(row) = rows.next()? {
+        result.push(row.get(0)?);
+    }
+    Ok(result)
+}
+
+pub fn to_json(snapshots: &Vec<TaskSnapshot>) -> Result<String, CoreError> {
+    serde_json::to_string(snapshots).map_err(|error| {
+        CoreError::UnexpetedError(format!("Failed to serialize the journal entry: {}", error))
+    })
+}
+
+pub fn from_json(data: &str) -> Result<Vec<TaskSnapshot>, CoreError> {
+    serde_json::from_str(data).map_err(|error| {
+        CoreError::UnexpetedError(format!("Failed to read the journal entry: {}", error))
+    })
+}
+
+// Forget every change, they can't be undone or redone anymore
+pub fn clear(tx: &Transaction) -> Result<(), CoreError> {
+    tx.execute("DELETE FROM journal", [])?;
+    Ok(())
+}
+
+// Record a change, `before` is used by undo and `after` is used by redo
+pub fn record(
+    tx: &Transaction,
+    operation: &str,
+    before: &Vec<TaskSnapshot>,
+    after: &Vec<TaskSnapshot>,
+) -> Result<(), CoreError> {
+    debug!("Recording {} into the journal", operation);
+    // A new change makes the undone changes impossible to redo
+    tx.execute("DELETE FROM journal WHERE undone = 1", [])?;
+    tx.execute(
+        "INSERT INTO journal (operation, undo, redo) VALUES (:operation, :undo, :redo)",
+        named_params! {
+            ":operation": operation,
+            ":undo": to_json(&with_uuids(before, after))?,
+            ":redo": to_json(&with_uuids(after, before))?,
+        },
+    )?;
+    tx.execute(
+        "DELETE FROM journal WHERE id <= (SELECT MAX(id) FROM journal) - :size",
+        named_params! {":size": JOURNAL_SIZE},
+    )?;
+    Ok(())
+}
+
+pub struct JournalEntry {
+    pub id: i64,
+    pub operation: String,
+    snapshots: Vec<TaskSnapshot>,
+}
+
+impl JournalEntry {
+    pub fn task_ids(&self) -> Vec<i64> {
+        self.snapshots.iter().map(|snapshot| snapshot.task_id).collect()
+    }
+
+    // The entry with the current ids of its tasks, see `resolve`
+    pub fn resolve(&self, tx: &Transaction) -> Result<JournalEntry, CoreError> {
+        Ok(JournalEntry {
+            id: self.id,
+            operation: self.operation.clone(),
+            snapshots: resolve(tx, &self.snapshots)?,
+        })
+    }
+}
+
+fn latest_entry(tx: &Transaction, query: &str) -> Result<Option<JournalEntry>, CoreError> {
+    let entry: Option<(i64, String, String)> = tx
+        .query_row(query, [], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))
+        .optional()?;
+    match entry {
+        Some((id, operation, data)) => Ok(Some(JournalEntry {
+            id,
+            operation,
+            snapshots: from_json(&data)?,
+        })),
+        None => Ok(None),
+    }
+}
+
+// The latest change that hasn't been undone
+pub fn next_undo(tx: &Transaction) -> Result<Option<JournalEntry>, CoreError> {
+    latest_entry(
+        tx,
+        "SELECT id, operation, undo FROM journal WHERE undone = 0 ORDER BY id DESC LIMIT 1",
+    )
+}
+
+// The change that was undone most recently
+pub fn next_redo(tx: &Transaction) -> Result<Option<JournalEntry>, CoreError> {
+    latest_entry(
+        tx,
+        "SELECT id, operation, redo FROM journal WHERE undone = 1 ORDER BY id ASC LIMIT 1",
+    )
+}
+
+pub fn undo(tx: &Transaction, entry: &JournalEntry) -> Result<(), CoreError> {
+    info!("Undoing journal entry {} ({})", entry.id, entry.operation);
+    restore(tx, &entry.snapshots)?;
+    tx.execute(
+        "UPDATE journal SET undone = 1 WHERE id = :id",
+        named_params! {":id": entry.id},
+    )?;
+    Ok(())
+}
+
+pub fn redo(tx: &Transaction, entry: &JournalEntry) -> Result<(), CoreError> {
+    info!("Redoing journal entry {} ({})", entry.id, entry.operation);
+    restore(tx, &entry.snapshots)?;
+    tx.execute(
+        "UPDATE journal SET undone = 0 WHERE id = :id",
+        named_params! {":id": entry.id},
+    )?;
+    Ok(())
+}
@@ -0,0 +1,343 @@
+use crate::db::query_helper::{
+    CREATE_CONTEXT_TABLE_QUERY, CREATE_DEPENDENCY_TABLE_QUERY, CREATE_STATE_TABLE_QUERY,
+    CREATE_TAG_TABLE_QUERY, CREATE_TASK_TABLE_QUERY, CREATE_TASK_TAG_TABLE_QUERY,
+    CREATE_PRIORITY_TABLE_QUERY, CREATE_PRIORITY_TASK_TABLE_QUERY, CREATE_TASK_CONTEXT_TABLE_QUERY,
+    CREATE_TASK_STATE_TABLE_QUERY, CREATE_SCHEMA_VERSION_TABLE_QUERY, CREATE_JOURNAL_TABLE_QUERY,
+    CREATE_TASK_HISTORY_TABLE_QUERY, CREATE_ARCHIVE_TABLE_QUERY, CREATE_TASK_FTS_TABLE_QUERIES,
+    CREATE_NOTE_TABLE_QUERY, NEW_UUID, CREATE_WORKING_SET_TABLE_QUERY,
+    CREATE_TIME_INTERVAL_TABLE_QUERIES, CREATE_TASK_UDA_TABLE_QUERY, CREATE_PROJECT_TABLE_QUERIES,
+    CREATE_ATTACHMENT_TABLE_QUERIES, CREATE_CHECKLIST_ITEM_TABLE_QUERIES,
+    CREATE_ENCRYPTION_TABLE_QUERY,
+};
+use crate::error::CoreError;
+use log::info;
+use rusqlite::{named_params, Connection, Result, Transaction};
+
+pub struct Migration {
+    pub version: i64,
+    pub description: &'static str,
+    pub up: fn(&Transaction) -> Result<(), CoreError>,
+}
+
+// All schema changes go through this list. Migrations are applied in
+// order and each one runs in its own transaction, so a failing migration
+// leaves the database at the previous version.
+//
+// Never edit a migration that has been released, append a new one instead.
+pub const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        description: "Create the initial tables",
+        up: create_initial_tables,
+    },
+    Migration {
+        version: 2,
+        description: "Create the journal table for undo and redo",
+        up: create_journal_table,
+    },
+    Migration {
+        version: 3,
+        description: "Create the task_history table",
+        up: create_task_history_table,
+    },
+    Migration {
+        version: 4,
+        description: "Add deleted_at to the task table for the trash",
+        up: add_task_deleted_at,
+    },
+    Migration {
+        version: 5,
+        description: "Create the archive table",
+        up: create_archive_table,
+    },
+    Migration {
+        version: 6,
+        description: "Create the full-text search index of the tasks",
+        up: create_task_fts_table,
+    },
+    Migration {
+        version: 7,
+        description: "Create the note table",
+        up: create_note_table,
+    },
+    Migration {
+        version: 8,
+        description: "Add uuid to the task table",
+        up: add_task_uuid,
+    },
+    Migration {
+        version: 9,
+        description: "Create the working_set table for the display ids",
+        up: create_working_set_table,
+    },
+    Migration {
+        version: 10,
+        description: "Add modified_at, started_at and completed_at to the task table",
+        up: add_task_timestamps,
+    },
+    Migration {
+        version: 11,
+        description: "Create the time_interval table for time tracking",
+        up: create_time_interval_table,
+    },
+    Migration {
+        version: 12,
+        description: "Add estimate to the task table",
+        up: add_task_estimate,
+    },
+    Migration {
+        version: 13,
+        description: "Create the task_uda table for user-defined attributes",
+        up: create_task_uda_table,
+    },
+    Migration {
+        version: 14,
+        description: "Add parent_id to the task table for subtasks",
+        up: add_task_parent_id,
+    },
+    Migration {
+        version: 15,
+        description: "Create the project and task_project tables",
+        up: create_project_tables,
+    },
+    Migration {
+        version: 16,
+        description: "Create the attachment and attachment_blob tables",
+        up: create_attachment_tables,
+    },
+    Migration {
+        version: 17,
+        description: "Create the checklist_item table",
+        up: create_checklist_item_table,
+    },
+    Migration {
+        version: 18,
+        description: "Create the encryption table",
+        up: create_encryption_table,
+    },
+];
+
+// Databases created before `schema_version` existed already have these
+// tables, `if not exists` makes this migration a no-op for them.
+fn create_initial_tables(tx: &Transaction) -> Result<(), CoreError> {
+    tx.execute(CREATE_TASK_TABLE_QUERY, [])?;
+    tx.execute(CREATE_TAG_TABLE_QUERY, [])?;
+    tx.execute(CREATE_TASK_TAG_TABLE_QUERY, [])?;
+    tx.execute(CREATE_DEPENDENCY_TABLE_QUERY, [])?;
+    tx.execute(CREATE_CONTEXT_TABLE_QUERY, [])?;
+    tx.execute(CREATE_TASK_CONTEXT_TABLE_QUERY, [])?;
+    tx.execute(CREATE_STATE_TABLE_QUERY, [])?;
+    tx.execute(CREATE_TASK_STATE_TABLE_QUERY, [])?;
+    tx.execute(CREATE_PRIORITY_TABLE_QUERY, [])?;
+    tx.execute(CREATE_PRIORITY_TASK_TABLE_QUERY, [])?;
+    Ok(())
+}
+
+fn create_journal_table(tx: &Transaction) -> Result<(), CoreError> {
+    tx.execute(CREATE_JOURNAL_TABLE_QUERY, [])?;
+    Ok(())
+}
+
+fn create_task_history_table(tx: &Transaction) -> Result<(), CoreError> {
+    tx.execute(CREATE_TASK_HISTORY_TABLE_QUERY, [])?;
+    tx.execute(
+        "CREATE INDEX IF NOT EXISTS task_history_task_id ON task_history (task_id)",
+        [],
+    )?;
+    Ok(())
+}
+
+fn add_task_deleted_at(tx: &Transaction) -> Result<(), CoreError> {
+    tx.execute("ALTER TABLE task ADD COLUMN deleted_at Text", [])?;
+    Ok(())
+}
+
+fn create_archive_table(tx: &Transaction) -> Result<(), CoreError> {
+    tx.execute(CREATE_ARCHIVE_TABLE_QUERY, [])?;
+    Ok(())
+}
+
+fn create_task_fts_table(tx: &Transaction) -> Result<(), CoreError> {
+    for query in CREATE_TASK_FTS_TABLE_QUERIES.iter() {
+        tx.execute(query, [])?;
+    }
+    Ok(())
+}
+
+fn create_note_table(tx: &Transaction) -> Result<(), CoreError> {
+    tx.execute(CREATE_NOTE_TABLE_QUERY, [])?;
+    tx.execute("CREATE INDEX IF NOT EXISTS note_task_id ON note (task_id)", [])?;
+    Ok(())
+}
+
+// The existing and the archived tasks get their UUIDs here, new tasks
+// get one when they are added.
+fn add_task_uuid(tx: &Transaction) -> Result<(), CoreError> {
+    tx.execute("ALTER TABLE task ADD COLUMN uuid Text", [])?;
+    tx.execute(&format!("UPDATE task SET uuid = {}", NEW_UUID), [])?;
+    tx.execute(
+        &format!("UPDATE archive SET task = json_set(task, '$.uuid', {})", NEW_UUID),
+        [],
+    )?;
+    tx.execute("CREATE UNIQUE INDEX IF NOT EXISTS task_uuid ON task (uuid)", [])?;
+    Ok(())
+}
+
+fn create_working_set_table(tx: &Transaction) -> Result<(), CoreError> {
+    tx.execute(CREATE_WORKING_SET_TABLE_QUERY, [])?;
+    Ok(())
+}
+
+// The existing tasks get their timestamps from the history, the ones
+// without history use their creation time
+fn add_task_timestamps(tx: &Transaction) -> Result<(), CoreError> {
+    tx.execute("ALTER TABLE task ADD COLUMN modified_at Text", [])?;
+    tx.execute("ALTER TABLE task ADD COLUMN started_at Text", [])?;
+    tx.execute("ALTER TABLE task ADD COLUMN completed_at Text", [])?;
+    tx.execute(
+        "UPDATE task SET modified_at = COALESCE(
+            (SELECT MAX(changed_at) FROM task_history WHERE task_history.task_id = task.id),
+            created_at
+        )",
+        [],
+    )?;
+    for (column, state) in [("started_at", "started"), ("completed_at", "completed")] {
+        tx.execute(
+            &format!(
+                "UPDATE task SET {} = COALESCE(
+                    (
+                    SELECT MAX(changed_at) FROM task_history
+                    WHERE task_history.task_id = task.id
+                    AND field = 'state' AND new_value = :state
+                    ),
+                    created_at
+                )
+                WHERE id IN (
+                    SELECT task_id FROM task_state
+                    INNER JOIN state ON task_state.state_id = state.id
+                    WHERE state.name = :state
+                )",
+                column
+            ),
+            named_params! {":state": state},
+        )?;
+    }
+    Ok(())
+}
+
+fn create_time_interval_table(tx: &Transaction) -> Result<(), CoreError> {
+    for query in CREATE_TIME_INTERVAL_TABLE_QUERIES.iter() {
+        tx.execute(query, [])?;
+    }
+    Ok(())
+}
+
+fn add_task_estimate(tx: &Transaction) -> Result<(), CoreError> {
+    tx.execute("ALTER TABLE task ADD COLUMN estimate integer", [])?;
+    Ok(())
+}
+
+fn create_task_uda_table(tx: &Transaction) -> Result<(), CoreError> {
+    tx.execute(CREATE_TASK_UDA_TABLE_QUERY, [])?;
+    Ok(())
+}
+
+fn add_task_parent_id(tx: &Transaction) -> Result<(), CoreError> {
+    tx.execute("ALTER TABLE task ADD COLUMN parent_id integer REFERENCES task(id)", [])?;
+    tx.execute("CREATE INDEX IF NOT EXISTS task_parent_id ON task (parent_id)", [])?;
+    Ok(())
+}
+
+fn create_project_tables(tx: &Transaction) -> Result<(), CoreError> {
+    for query in CREATE_PROJECT_TABLE_QUERIES.iter() {
+        tx.execute(query, [])?;
+    }
+    Ok(())
+}
+
+fn create_attachment_tables(tx: &Transaction) -> Result<(), CoreError> {
+    for query in CREATE_ATTACHMENT_TABLE_QUERIES.iter() {
+        tx.execute(query, [])?;
+    }
+    Ok(())
+}
+
+fn create_checklist_item_table(tx: &Transaction) -> Result<(), CoreError> {
+    for query in CREATE_CHECKLIST_ITEM_TABLE_QUERIES.iter() {
+        tx.execute(query, [])?;
+    }
+    Ok(())
+}
+
+fn create_encryption_table(tx: &Transaction) -> Result<(), CoreError> {
+    tx.execute(CREATE_ENCRYPTION_TABLE_QUERY, [])?;
+    Ok(())
+}
+
+pub fn latest_version(migrations: &[Migration]) -> i64 {
+    migrations.last().map(|migration| migration.version).unwrap_or(0)
+}
+
+// Return the version of the schema, 0 means no migration has been applied
+pub fn current_version(conn: &Connection) -> Result<i64, CoreError> {
+    conn.execute(CREATE_SCHEMA_VERSION_TABLE_QUERY, [])?;
+    let version = conn.query_row(
+        "SELECT IFNULL(MAX(version), 0) FROM schema_version",
+        [],
+        |row| row.get(0),
+    )?;
+    Ok(version)
+}
+
+// Bring the database up to the latest version
+pub fn migrate(conn: &mut Connection) -> Result<(), CoreError> {
+    migrate_to(conn, MIGRATIONS, latest_version(MIGRATIONS))
+}
+
+pub fn migrate_to(
+    conn: &mut Connection,
+    migrations: &[Migration],
+    target_version: i64,
+) -> Result<(), CoreError> {
+    let version = current_version(conn)?;
+    let latest = latest_version(migrations);
+    if version > latest {
+        return Err(CoreError::MigrationError {
+            version,
+            reason: format!(
+                "the database was created by a newer taskoo, the latest supported version is {}",
+                latest
+            ),
+        });
+    }
+
+    for migration in migrations
+        .iter()
+        .filter(|migration| migration.version > version && migration.version <= target_version)
+    {
+        info!(
+            "Migrating database to version {}: {}",
+            migration.version, migration.description
+        );
+        apply(conn, migration).map_err(|error| CoreError::MigrationError {
+            version: migration.version,
+            reason: format!("{} ({})", migration.description, error),
+        })?;
+    }
+    Ok(())
+}
+
+fn apply(conn: &mut Connection, migration: &Migration) -> Result<(), CoreError> {
+    // Dropping the transaction without committing rolls the migration back
+    let tx = conn.transaction()?;
+    (migration.up)(&tx)?;
+    tx.execute(
+        "INSERT INTO schema_version (version, description) VALUES (:version, :description)",
+        named_params! {
+            ":version": migration.version,
+            ":description": migration.description,
+        },
+    )?;
+    tx.commit()?;
+    Ok(())
+}
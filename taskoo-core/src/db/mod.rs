@@ -1,15 +1,33 @@
+pub mod attachment;
+pub mod backup;
+pub mod checklist;
+pub mod doctor;
+pub mod encryption;
+pub mod history;
+pub mod note;
+pub mod project;
+pub mod search;
+pub mod subtask;
 pub mod task_helper;
 pub mod task_manager;
+pub mod time_tracking;
+pub mod uda;
 
 mod add;
 mod agenda;
+mod archive;
 mod delete;
 mod get;
 mod modify;
 mod view;
+mod working_set;
 
 mod get_base;
+mod journal;
+mod migration;
+mod query_builder;
 mod query_helper;
+mod selection;
 
 #[cfg(test)]
 mod tests;
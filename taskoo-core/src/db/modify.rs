@@ -1,224 +1,288 @@
-use super::query_helper::generate_condition;
+use super::query_helper::generate_assignments;
 use super::get_base::get_base;
+use super::query_builder::Filter;
+use super::history::record_changes;
+use super::selection::Selection;
+use super::time_tracking::track_started_task;
+use super::project::set_project;
+use super::subtask::set_parent;
+use super::uda::set_udas;
 use crate::db::task_helper::Task;
 use crate::db::task_manager::TaskManager;
 use crate::error::CoreError;
 use log::debug;
 use log::info;
-use rusqlite::{named_params, Result, Transaction};
+use rusqlite::types::Value;
+use rusqlite::{named_params, params_from_iter, Result, Transaction};
+use std::collections::{HashMap, HashSet};
 
-fn update_state(
-    conn: &Transaction,
-    task_ids: &Vec<i64>,
-    state_id: &Option<i64>,
-) -> Result<(), CoreError> {
+fn update_state(conn: &Transaction, state_id: &Option<i64>) -> Result<(), CoreError> {
     info!("Updating state");
     if let Some(state_id) = state_id {
-        let mut statement = conn
-            .prepare("Update task_state SET state_id = :state_id WHERE task_id = :task_id")
-            .expect("Failed to prepare the Update task_state query");
-
-        for task_id in task_ids.iter() {
-            statement.execute(named_params! {
-            ":task_id": task_id,
-            ":state_id": state_id})?;
-        }
+        conn.execute(
+            &format!(
+                "Update task_state SET state_id = :state_id WHERE task_id IN ({})",
+                Selection::Task.ids()
+            ),
+            named_params! {":state_id": state_id},
+        )?;
     }
     Ok(())
 }
 
-fn update_context(
-    conn: &Transaction,
-    task_ids: &Vec<i64>,
-    context_id: &Option<i64>,
-) -> Result<(), CoreError> {
+// Keep `started_at` and `completed_at` in line with the state of the
+// selected tasks. They are set when a task becomes started or completed,
+// a completed task keeps the time it was started, and both are cleared
+// when the task is reopened.
+pub fn update_state_timestamps(conn: &Transaction, selection: Selection) -> Result<(), CoreError> {
+    conn.execute(
+        &format!(
+            "Update task SET
+            started_at = CASE state.name
+                WHEN 'started' THEN IFNULL(task.started_at, CURRENT_TIMESTAMP)
+                WHEN 'completed' THEN task.started_at
+                ELSE NULL END,
+            completed_at = CASE state.name
+                WHEN 'completed' THEN IFNULL(task.completed_at, CURRENT_TIMESTAMP)
+                ELSE NULL END
+            FROM task_state INNER JOIN state ON task_state.state_id = state.id
+            WHERE task_state.task_id = task.id AND task.id IN ({})",
+            selection.ids()
+        ),
+        [],
+    )?;
+    Ok(())
+}
+
+fn update_modified_at(conn: &Transaction, selection: Selection) -> Result<(), CoreError> {
+    conn.execute(
+        &format!(
+            "Update task SET modified_at = CURRENT_TIMESTAMP WHERE id IN ({})",
+            selection.ids()
+        ),
+        [],
+    )?;
+    Ok(())
+}
+
+fn update_context(conn: &Transaction, context_id: &Option<i64>) -> Result<(), CoreError> {
     info!("Updating context");
     if let Some(context_id) = context_id {
-        let mut statement = conn
-            .prepare("Update task_context SET context_id = :context_id WHERE task_id = :task_id")
-            .expect("Failed to prepare the Update task_context query");
-
-        for task_id in task_ids.iter() {
-            statement.execute(named_params! {
-            ":task_id": task_id,
-            ":context_id": context_id})?;
-        }
+        conn.execute(
+            &format!(
+                "Update task_context SET context_id = :context_id WHERE task_id IN ({})",
+                Selection::Task.ids()
+            ),
+            named_params! {":context_id": context_id},
+        )?;
     }
     Ok(())
 }
 
-// XXX Why task_ids is reference?
-fn add_tag(conn: &Transaction, task_ids: &Vec<i64>, tag_ids: Vec<i64>) -> Result<(), CoreError> {
-    let mut statement = conn
-        .prepare(
-            "INSERT OR IGNORE INTO task_tag
-        (task_id, tag_id)
-        VALUES (:task_id, :tag_id)",
-        )
-        .expect("Failed to prepare the INSERT INTO task_tag query");
-    for task_id in task_ids.iter() {
-        for tag_id in tag_ids.iter() {
-            statement.execute(named_params! {
-            ":task_id": task_id,
-            ":tag_id": tag_id})?;
-        }
+fn add_tag(conn: &Transaction, tag_ids: Vec<i64>) -> Result<(), CoreError> {
+    let mut statement = conn.prepare(&format!(
+        "INSERT OR IGNORE INTO task_tag (task_id, tag_id) SELECT task_id, :tag_id FROM ({})",
+        Selection::Task.ids()
+    ))?;
+    for tag_id in tag_ids.iter() {
+        statement.execute(named_params! {":tag_id": tag_id})?;
     }
     Ok(())
 }
 
-fn remove_tag(conn: &Transaction, task_ids: &Vec<i64>, tag_ids: Vec<i64>) -> Result<(), CoreError> {
-    info!(
-        "Removing tag_ids: {:?} from task_ids {:?}",
-        tag_ids, task_ids
-    );
-    let mut statement = conn
-        .prepare("DELETE FROM task_tag WHERE task_id = :task_id and tag_id = :tag_id")
-        .expect("Failed to prepare the DELETE FROM task_tag query");
-
-    for task_id in task_ids.iter() {
-        for tag_id in tag_ids.iter() {
-            debug!("Removing tag_id: {} from task_id {}", tag_id, task_id);
-            statement.execute(named_params! {
-            ":task_id": task_id,
-            ":tag_id": tag_id})?;
-        }
+fn remove_tag(conn: &Transaction, tag_ids: Vec<i64>) -> Result<(), CoreError> {
+    info!("Removing tag_ids: {:?}", tag_ids);
+    let mut statement = conn.prepare(&format!(
+        "DELETE FROM task_tag WHERE tag_id = :tag_id and task_id IN ({})",
+        Selection::Task.ids()
+    ))?;
+    for tag_id in tag_ids.iter() {
+        statement.execute(named_params! {":tag_id": tag_id})?;
     }
     Ok(())
 }
 
-fn insert_or_replace_priority(
-    conn: &Transaction,
-    task_ids: &Vec<i64>,
-    priority_id: &i64,
-) -> Result<(), CoreError> {
-    info!(
-        "InsertOrReplacePriority tag_ids: {:?} and priority_id {:?}",
-        task_ids, priority_id
-    );
-    let mut statement = conn.prepare(
-        "INSERT OR REPLACE INTO priority_task (task_id, priority_id) VALUES (:task_id, :priority_id)",
+fn insert_or_replace_priority(conn: &Transaction, priority_id: &i64) -> Result<(), CoreError> {
+    info!("InsertOrReplacePriority priority_id {:?}", priority_id);
+    conn.execute(
+        &format!(
+            "INSERT OR REPLACE INTO priority_task (task_id, priority_id)
+            SELECT task_id, :priority_id FROM ({})",
+            Selection::Task.ids()
+        ),
+        named_params! {":priority_id": priority_id},
     )?;
-
-    for task_id in task_ids.iter() {
-        statement.execute(named_params! {
-        ":task_id": task_id,
-        ":priority_id": priority_id})?;
-    }
     Ok(())
 }
 
-// Check all tasks that are depended on this task_id, and update their state to
-// completed if all of their depended tasks are completed
-fn update_dependency(conn: &Transaction, task_id: &i64) -> Result<(), CoreError> {
-    let mut get_child_tasks_statement =
-        conn.prepare("SELECT task_id FROM dependency WHERE parent_task_id = :parent_task_id")?;
-    let mut child_tasks_rows = get_child_tasks_statement.query(named_params! {
-        ":parent_task_id": task_id
-    })?;
-
-    while let Some(child_task_row) = child_tasks_rows.next()? {
-        let child_id: i64 = child_task_row.get(0)?;
-        let mut get_parent_for_this_child =
-            conn.prepare("SELECT parent_task_id FROM dependency WHERE task_id = :child_id")?;
-        let mut parent_rows =
-            get_parent_for_this_child.query(named_params! {":child_id": child_id})?;
-
-        let mut are_all_parents_completed = true;
-        while let Some(parent_row) = parent_rows.next()? {
-            let parent_id: i64 = parent_row.get(0)?;
-            let mut get_parent_state =
-                conn.prepare("SELECT state_id FROM task_state where task_id = :parent_id")?;
-            let mut parent_state_rows = get_parent_state.query(named_params! {
-                ":parent_id": parent_id
-            })?;
-
-            let parent_state_row = parent_state_rows.next()?;
-
-            let parent_state_id: i64 = parent_state_row.unwrap().get(0)?;
-
-            let mut convert_state_to_id =
-                conn.prepare("SELECT name FROM state where id = :state_id")?;
-            let mut state_to_id_rows = convert_state_to_id.query(named_params! {
-                ":state_id": parent_state_id
-            })?;
-
-            let state_to_id_row = state_to_id_rows.next()?;
-            let state: String = state_to_id_row.unwrap().get(0)?;
-            if state != "completed" {
-                are_all_parents_completed = false;
-            }
-        }
-
-        // Change the state to ready
-        if are_all_parents_completed {
-            let mut update_to_ready_statement =
-                conn.prepare("Update task_state SET state_id = 1 WHERE task_id = :child_id")?;
-            update_to_ready_statement.execute(named_params! {
-                ":child_id": child_id
-            })?;
+// Record the changes between the tasks in `before` and `after` that have
+// the same id
+fn record_all_changes(
+    conn: &Transaction,
+    before: &Vec<Task>,
+    after: &Vec<Task>,
+) -> Result<(), CoreError> {
+    let before: HashMap<i64, &Task> = before.iter().map(|task| (task.id, task)).collect();
+    for task in after.iter() {
+        if let Some(previous_task) = before.get(&task.id) {
+            record_changes(conn, Some(previous_task), task)?;
         }
     }
     Ok(())
 }
 
-fn update_schedule_at_for_repeat(conn: &Transaction, task_id: &i64) -> Result<(), CoreError> {
-    let get_task_repetition_query = format!(
-        "SELECT due_repeat, scheduled_repeat from task where id = {}",
-        task_id
-    );
-    let mut statement = conn.prepare(&get_task_repetition_query)?;
+// The tasks that are blocked by the selected tasks become ready once all
+// of the tasks they depend on are completed or in the trash
+pub fn update_dependency(conn: &Transaction) -> Result<(), CoreError> {
+    // Dependencies of tasks that don't exist are left to `taskoo doctor`,
+    // and a parent without a valid state isn't completed
+    let mut statement = conn.prepare(&format!(
+        "SELECT DISTINCT dependency.task_id FROM dependency
+        INNER JOIN task ON dependency.task_id = task.id
+        INNER JOIN task_state ON dependency.task_id = task_state.task_id
+        INNER JOIN state ON task_state.state_id = state.id
+        WHERE dependency.parent_task_id IN ({}) AND task.deleted_at IS NULL
+        AND state.name = 'blocked'
+        AND NOT EXISTS (
+            SELECT 1 FROM dependency parent
+            LEFT JOIN task parent_task ON parent.parent_task_id = parent_task.id
+            LEFT JOIN task_state parent_task_state
+            ON parent.parent_task_id = parent_task_state.task_id
+            LEFT JOIN state parent_state ON parent_task_state.state_id = parent_state.id
+            WHERE parent.task_id = dependency.task_id AND parent_state.name IS NOT 'completed'
+            AND parent_task.deleted_at IS NULL
+        )",
+        Selection::Task.ids()
+    ))?;
     let mut rows = statement.query([])?;
+    let mut child_ids: Vec<i64> = vec![];
+    while let Some(row) = rows.next()? {
+        child_ids.push(row.get(0)?);
+    }
+    set_dependent_state(conn, &child_ids, "ready")
+}
 
-    let first_row = rows.next().expect("We should always have a row");
-
-    let data = first_row.expect("We should always have some data");
+// The ready tasks that depend on the selected tasks are blocked again by
+// the ones that aren't completed, like when they are restored from the trash
+pub fn block_dependents(conn: &Transaction) -> Result<(), CoreError> {
+    let mut statement = conn.prepare(&format!(
+        "SELECT DISTINCT dependency.task_id FROM dependency
+        INNER JOIN task ON dependency.task_id = task.id
+        INNER JOIN task_state ON dependency.task_id = task_state.task_id
+        INNER JOIN state ON task_state.state_id = state.id
+        LEFT JOIN task_state parent_task_state
+        ON dependency.parent_task_id = parent_task_state.task_id
+        LEFT JOIN state parent_state ON parent_task_state.state_id = parent_state.id
+        WHERE dependency.parent_task_id IN ({}) AND task.deleted_at IS NULL
+        AND state.name = 'ready' AND parent_state.name IS NOT 'completed'",
+        Selection::Task.ids()
+    ))?;
+    let mut rows = statement.query([])?;
+    let mut child_ids: Vec<i64> = vec![];
+    while let Some(row) = rows.next()? {
+        child_ids.push(row.get(0)?);
+    }
+    set_dependent_state(conn, &child_ids, "blocked")
+}
 
-    let due_repetition: String = data.get(0)?;
-    let scheduled_repetition: String = data.get(1)?;
+// Change the state of the dependents and record it in their history
+fn set_dependent_state(
+    conn: &Transaction,
+    child_ids: &Vec<i64>,
+    state: &str,
+) -> Result<(), CoreError> {
+    if child_ids.is_empty() {
+        return Ok(());
+    }
 
-    if due_repetition.len() > 0 {
-        debug!("Due Repetition for task {}: {}", task_id, due_repetition);
+    Selection::Dependent.fill(conn, child_ids)?;
+    let before = get_base(conn, &Filter::selected(Selection::Dependent))?;
+    conn.execute(
+        &format!(
+            "Update task_state SET state_id = (SELECT id FROM state WHERE name = :state)
+            WHERE task_id IN ({})",
+            Selection::Dependent.ids()
+        ),
+        named_params! {":state": state},
+    )?;
+    update_state_timestamps(conn, Selection::Dependent)?;
+    update_modified_at(conn, Selection::Dependent)?;
+    let after = get_base(conn, &Filter::selected(Selection::Dependent))?;
+    record_all_changes(conn, &before, &after)
+}
 
-        let new_due_date = TaskManager::parse_date_string(&due_repetition)?;
-        debug!("Parsed schedule at {}", new_due_date);
+// The selected tasks in the order of `task_ids`, without duplicates
+fn get_tasks(conn: &Transaction, task_ids: &Vec<i64>) -> Result<Vec<Task>, CoreError> {
+    let mut found: HashMap<i64, Task> = get_base(conn, &Filter::selected(Selection::Task))?
+        .into_iter()
+        .map(|task| (task.id, task))
+        .collect();
 
-        let mut update_task_stmt =
-            conn.prepare("Update task SET due_date = :due_date WHERE id = :id")?;
-        let mut update_state_stmt =
-            conn.prepare("Update task_state SET state_id = 1 WHERE task_id = :id")?;
-        update_task_stmt.execute(named_params! {
-            ":due_date": new_due_date,
-            ":id": task_id
-        })?;
-        update_state_stmt.execute(named_params! {
-            ":id": task_id
-        })?;
-    } else {
-        info!("No due repetition");
+    let mut tasks = vec![];
+    let mut seen = HashSet::new();
+    for task_id in task_ids.iter() {
+        if !seen.insert(*task_id) {
+            continue;
+        }
+        match found.remove(task_id) {
+            Some(task) => tasks.push(task),
+            None => {
+                return Err(CoreError::ArgumentError(format!(
+                    "Task {} doesn't exist",
+                    task_id
+                )))
+            }
+        }
     }
+    Ok(tasks)
+}
 
-    if scheduled_repetition.len() > 0 {
-        debug!(
-            "Scheduled Repetition for task {}: {}",
-            task_id, scheduled_repetition
-        );
+// Move the dates of the selected tasks that repeat and make them ready
+// again with their checklists unchecked. The new date only depends on the
+// repetition, so it's computed once for every distinct repetition.
+fn update_schedule_at_for_repeat(conn: &Transaction) -> Result<(), CoreError> {
+    let columns = [("due_date", "due_repeat"), ("scheduled_at", "scheduled_repeat")];
+    for (date_column, repeat_column) in columns.iter() {
+        let mut statement = conn.prepare(&format!(
+            "SELECT DISTINCT {repeat} FROM task WHERE id IN ({ids}) AND IFNULL({repeat}, '') <> ''",
+            repeat = repeat_column,
+            ids = Selection::Task.ids()
+        ))?;
+        let mut rows = statement.query([])?;
+        let mut repetitions: Vec<String> = vec![];
+        while let Some(row) = rows.next()? {
+            repetitions.push(row.get(0)?);
+        }
 
-        let new_schedule_at = TaskManager::parse_date_string(&scheduled_repetition)?;
-        debug!("Parsed schedule at {}", new_schedule_at);
+        for repetition in repetitions.iter() {
+            let new_date = TaskManager::parse_date_string(repetition)?;
+            debug!("Repetition {} of {} moves it to {}", repetition, date_column, new_date);
+            conn.execute(
+                &format!(
+                    "Update task SET {date} = :date WHERE id IN ({ids}) AND {repeat} = :repeat",
+                    date = date_column,
+                    repeat = repeat_column,
+                    ids = Selection::Task.ids()
+                ),
+                named_params! {":date": new_date, ":repeat": repetition},
+            )?;
+        }
+    }
 
-        let mut update_task_stmt =
-            conn.prepare("Update task SET scheduled_at = :scheduled_at WHERE id = :id")?;
-        let mut update_state_stmt =
-            conn.prepare("Update task_state SET state_id = 1 WHERE task_id = :id")?;
-        update_task_stmt.execute(named_params! {
-            ":scheduled_at": new_schedule_at,
-            ":id": task_id
-        })?;
-        update_state_stmt.execute(named_params! {
-            ":id": task_id
-        })?;
-    } else {
-        info!("No schedule repetition");
+    let resets = [("task_state", "state_id = 1"), ("checklist_item", "done = 0")];
+    for (table, assignment) in resets.iter() {
+        conn.execute(
+            &format!(
+                "Update {} SET {} WHERE task_id IN (
+                    SELECT id FROM task WHERE id IN ({})
+                    AND (IFNULL(due_repeat, '') <> '' OR IFNULL(scheduled_repeat, '') <> '')
+                )",
+                table,
+                assignment,
+                Selection::Task.ids()
+            ),
+            [],
+        )?;
     }
     Ok(())
 }
@@ -236,75 +300,98 @@ pub fn modify(
     recurrence: &Option<&str>,
     state_id: &Option<i64>,
     tag_ids_to_remove: Vec<i64>,
+    estimate: &Option<Option<i64>>, // Some(None) removes the estimate
+    udas: &Vec<(String, Option<String>)>, // None removes the UDA
+    parent_id: &Option<Option<i64>>,      // Some(None) makes them top level tasks
+    project_id: &Option<Option<i64>>,     // Some(None) takes them out of their project
 ) -> Result<Vec<Task>, CoreError> {
     // Prepare the statement
-    let conditions = generate_condition(
-        body,
-        context_id,
-        date_due,
-        date_scheduled,
-        repeat,
-        recurrence,
-        state_id,
-    );
+    let mut assignments = generate_assignments(body, date_due, date_scheduled, repeat, recurrence);
+    if let Some(estimate) = estimate {
+        assignments.push(("estimate", estimate.map_or(Value::Null, Value::from)));
+    }
 
     // TODO: Return Error here
-    if conditions.is_empty()
+    if assignments.is_empty()
         && tag_ids.is_empty()
         && tag_ids_to_remove.is_empty()
         && state_id.is_none()
         && context_id.is_none()
         && priority.is_none()
+        && udas.is_empty()
+        && parent_id.is_none()
+        && project_id.is_none()
     {
         info!(
             "
-            conditions, tag_ids, tag_ids_to_remove,
-            state_id and context_id and priority are all empty, nothing is going to be modified"
+            assignments, tag_ids, tag_ids_to_remove,
+            state_id, context_id, priority, udas, parent_id and project_id are all empty,
+            nothing is going to be modified"
         );
         return Ok(vec![]);
     }
 
-    if !conditions.is_empty() {
+    Selection::Task.fill(tx, task_ids)?;
+    let previous_tasks = get_tasks(tx, task_ids)?;
+
+    if !assignments.is_empty() {
+        let columns: Vec<String> = assignments
+            .iter()
+            .map(|(column, _)| match *column {
+                // See `encryption`
+                "body" => format!("{} = taskoo_encrypt(?)", column),
+                _ => format!("{} = ?", column),
+            })
+            .collect();
         let final_argument = format!(
-            "Update task SET {} WHERE id = :task_id",
-            conditions.join(",")
+            "Update task SET {} WHERE id IN ({})",
+            columns.join(","),
+            Selection::Task.ids()
         );
 
         debug!("Running modify with query \n {}", final_argument);
-        let mut statement = tx.prepare(&final_argument)?;
-        for task_id in task_ids.iter() {
-            statement.execute(named_params! {
-                ":task_id": task_id
-            })?;
-        }
+        let params = assignments.iter().map(|(_, value)| value.clone());
+        tx.execute(&final_argument, params_from_iter(params))?;
     }
 
-    add_tag(&tx, &task_ids, tag_ids)?;
-    remove_tag(&tx, &task_ids, tag_ids_to_remove)?;
+    add_tag(tx, tag_ids)?;
+    remove_tag(tx, tag_ids_to_remove)?;
 
-    update_context(&tx, &task_ids, context_id);
-    update_state(&tx, &task_ids, state_id);
+    update_context(tx, context_id)?;
+    update_state(tx, state_id)?;
 
     if let Some(priority_id) = priority {
-        insert_or_replace_priority(&tx, &task_ids, &priority_id)?;
+        insert_or_replace_priority(tx, priority_id)?;
+    }
+    set_udas(tx, Selection::Task, udas)?;
+    if let Some(parent_id) = parent_id {
+        set_parent(tx, Selection::Task, task_ids, parent_id)?;
+    }
+    if let Some(project_id) = project_id {
+        set_project(tx, Selection::Task, project_id)?;
     }
+    update_state_timestamps(tx, Selection::Task)?;
+    update_modified_at(tx, Selection::Task)?;
+    if state_id.is_some() {
+        track_started_task(tx, task_ids)?;
+    }
+
+    // Record the changes before the repetition and the dependencies
+    // get updated, so completing a recurring task is kept in the history.
+    let mut tasks = get_tasks(tx, task_ids)?;
+    record_all_changes(tx, &previous_tasks, &tasks)?;
 
     // If the task is marked to completed, update the scheduled_at
     // based on repeat
     if let Some(2) = state_id {
         info!("Task is marked as completed, updating scheduled_at");
-        for task_id in task_ids.iter() {
-            update_schedule_at_for_repeat(&tx, &task_id)?;
-            update_dependency(&tx, &task_id)?;
-        }
-    }
-    let mut tasks = vec![];
+        update_schedule_at_for_repeat(tx)?;
+        update_state_timestamps(tx, Selection::Task)?;
+        let repeated_tasks = get_tasks(tx, task_ids)?;
+        record_all_changes(tx, &tasks, &repeated_tasks)?;
 
-    for task_id in task_ids.iter() {
-        let mut modified_tasks = get_base(&tx, &format!("task.id = {}", task_id))?;
-        assert_eq!(modified_tasks.len(), 1);
-        let task = modified_tasks.remove(0);
-        tasks.push(task);
+        update_dependency(tx)?;
+        tasks = get_tasks(tx, task_ids)?;
     }
     Ok(tasks)
 }
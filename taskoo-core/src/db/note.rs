@@ -0,0 +1,88 @@
+use super::query_builder::placeholders;
+use crate::db::task_helper::Task;
+use crate::error::CoreError;
+use rusqlite::{named_params, params_from_iter, OptionalExtension, Result, Transaction};
+use serde::{Deserialize, Serialize};
+
+// Notes are looked up for this many tasks at a time, so the number of
+// bound parameters stays far below the limit of SQLite.
+const TASK_IDS_PER_QUERY: usize = 500;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Note {
+    pub id: i64,
+    pub task_id: i64,
+    pub body: String,
+    pub created_at: String,
+    // Empty if the note has never been edited
+    pub updated_at: String,
+}
+
+// Fill in the notes of the tasks, oldest first
+pub fn attach_notes(tx: &Transaction, tasks: &mut Vec<Task>) -> Result<(), CoreError> {
+    let task_ids: Vec<i64> = tasks.iter().map(|task| task.id).collect();
+    for chunk in task_ids.chunks(TASK_IDS_PER_QUERY) {
+        let mut statement = tx.prepare(&format!(
+            "SELECT id, task_id, taskoo_decrypt(body), created_at, IFNULL(updated_at, '') FROM note
+            WHERE task_id IN ({}) ORDER BY created_at, id",
+            placeholders(chunk.len())
+        ))?;
+        let mut rows = statement.query(params_from_iter(chunk.iter()))?;
+        while let Some(row) = rows.next()? {
+            let note = Note {
+                id: row.get(0)?,
+                task_id: row.get(1)?,
+                body: row.get(2)?,
+                created_at: row.get(3)?,
+                updated_at: row.get(4)?,
+            };
+            if let Some(task) = tasks.iter_mut().find(|task| task.id == note.task_id) {
+                task.notes.push(note);
+            }
+        }
+    }
+    Ok(())
+}
+
+pub fn add_note(tx: &Transaction, task_id: i64, body: &str) -> Result<(), CoreError> {
+    tx.execute(
+        "INSERT INTO note (task_id, body) VALUES (:task_id, taskoo_encrypt(:body))",
+        named_params! {":task_id": task_id, ":body": body},
+    )?;
+    Ok(())
+}
+
+// The id of the task that the note belongs to
+pub fn get_note_task_id(tx: &Transaction, note_id: i64) -> Result<i64, CoreError> {
+    let task_id = tx
+        .query_row(
+            "SELECT task_id FROM note WHERE id = :note_id",
+            named_params! {":note_id": note_id},
+            |row| row.get(0),
+        )
+        .optional()?;
+    match task_id {
+        Some(task_id) => Ok(task_id),
+        None => Err(CoreError::ArgumentError(format!(
+            "Note {} doesn't exist",
+            note_id
+        ))),
+    }
+}
+
+pub fn edit_note(tx: &Transaction, note_id: i64, body: &str) -> Result<(), CoreError> {
+    tx.execute(
+        "UPDATE note SET body = taskoo_encrypt(:body), updated_at = CURRENT_TIMESTAMP
+        WHERE id = :note_id",
+        named_params! {":note_id": note_id, ":body": body},
+    )?;
+    Ok(())
+}
+
+pub fn delete_note(tx: &Transaction, note_id: i64) -> Result<(), CoreError> {
+    tx.execute(
+        "DELETE FROM note WHERE id = :note_id",
+        named_params! {":note_id": note_id},
+    )?;
+    Ok(())
+}
@@ -0,0 +1,185 @@
+use super::selection::Selection;
+use crate::db::task_manager::TaskManager;
+use crate::error::CoreError;
+use rusqlite::{named_params, OptionalExtension, Result, Transaction};
+use serde::{Deserialize, Serialize};
+
+// A project is an outcome that takes more than one task, tasks join one
+// with `proj:<name>`. Unlike a tag it has its own due date, status and
+// description.
+
+pub const PROJECT_STATUSES: [&str; 3] = ["active", "on-hold", "done"];
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Project {
+    pub id: i64,
+    pub name: String,
+    pub description: String,
+    pub date_due: String,
+    pub status: String,
+    // The tasks of the project that aren't deleted, and how many of them
+    // are completed
+    pub tasks_total: i64,
+    pub tasks_completed: i64,
+    // The ready task that is due first, the oldest one among those without
+    // a due date
+    pub next_action_id: Option<i64>,
+    pub next_action: String,
+}
+
+impl Project {
+    // An active project without a ready task doesn't move forward
+    pub fn is_stalled(&self) -> bool {
+        self.status == PROJECT_STATUSES[0] && self.next_action_id.is_none()
+    }
+
+    // N/M of the tasks are completed
+    pub fn format_progress(&self) -> String {
+        format!("{}/{}", self.tasks_completed, self.tasks_total)
+    }
+}
+
+pub fn is_valid_project_name(name: &str) -> bool {
+    !name.is_empty() && !name.contains(|c: char| c.is_whitespace() || c == ',')
+}
+
+pub fn get_project_id(tx: &Transaction, name: &str) -> Result<Option<i64>, CoreError> {
+    Ok(tx
+        .query_row(
+            "SELECT id FROM project WHERE name = :name",
+            named_params! {":name": name.to_lowercase()},
+            |row| row.get(0),
+        )
+        .optional()?)
+}
+
+// Projects are created the first time a task joins them, like tags
+pub fn get_or_create_project(tx: &Transaction, name: &str) -> Result<i64, CoreError> {
+    if !is_valid_project_name(name) {
+        return Err(CoreError::ArgumentError(format!("Invalid project name {}", name)));
+    }
+    if let Some(project_id) = get_project_id(tx, name)? {
+        return Ok(project_id);
+    }
+    tx.execute(
+        "INSERT INTO project (name) VALUES (:name)",
+        named_params! {":name": name.to_lowercase()},
+    )?;
+    Ok(tx.last_insert_rowid())
+}
+
+// Move the selected tasks into the project, or out of their project
+// without one
+pub fn set_project(
+    tx: &Transaction,
+    selection: Selection,
+    project_id: &Option<i64>,
+) -> Result<(), CoreError> {
+    match project_id {
+        Some(project_id) => tx.execute(
+            &format!(
+                "INSERT OR REPLACE INTO task_project (task_id, project_id)
+                SELECT task_id, :project_id FROM ({})",
+                selection.ids()
+            ),
+            named_params! {":project_id": project_id},
+        )?,
+        None => tx.execute(
+            &format!("DELETE FROM task_project WHERE task_id IN ({})", selection.ids()),
+            [],
+        )?,
+    };
+    Ok(())
+}
+
+pub fn get_projects(tx: &Transaction) -> Result<Vec<Project>, CoreError> {
+    let mut statement = tx.prepare(
+        "SELECT project.id, project.name, taskoo_decrypt(project.description) as description,
+            IFNULL(project.due_date, '') as due_date, project.status,
+            COUNT(task.id) as total, IFNULL(SUM(state.name = 'completed'), 0) as completed,
+            (
+            SELECT next.id FROM task next
+            INNER JOIN task_project next_project ON next.id = next_project.task_id
+            INNER JOIN task_state next_task_state ON next.id = next_task_state.task_id
+            INNER JOIN state next_state ON next_task_state.state_id = next_state.id
+            WHERE next_project.project_id = project.id AND next.deleted_at IS NULL
+            AND next_state.name = 'ready'
+            ORDER BY IFNULL(next.due_date, '') = '', next.due_date, next.id LIMIT 1
+            ) as next_action_id
+        FROM project
+        LEFT JOIN task_project ON project.id = task_project.project_id
+        LEFT JOIN task ON task_project.task_id = task.id AND task.deleted_at IS NULL
+        LEFT JOIN task_state ON task.id = task_state.task_id
+        LEFT JOIN state ON task_state.state_id = state.id
+        GROUP BY project.id ORDER BY project.name",
+    )?;
+    let mut rows = statement.query([])?;
+    let mut projects = vec![];
+    while let Some(row) = rows.next()? {
+        projects.push(Project {
+            id: row.get("id")?,
+            name: row.get("name")?,
+            description: row.get("description")?,
+            date_due: row.get("due_date")?,
+            status: row.get("status")?,
+            tasks_total: row.get("total")?,
+            tasks_completed: row.get("completed")?,
+            next_action_id: row.get("next_action_id")?,
+            next_action: String::new(),
+        });
+    }
+
+    for project in projects.iter_mut() {
+        if let Some(task_id) = project.next_action_id {
+            project.next_action = tx.query_row(
+                "SELECT taskoo_decrypt(body) FROM task WHERE id = :task_id",
+                named_params! {":task_id": task_id},
+                |row| row.get(0),
+            )?;
+        }
+    }
+    Ok(projects)
+}
+
+// Change the description, due date or status of a project, it's created
+// if it doesn't exist. An empty due date removes it.
+pub fn update_project(
+    tx: &Transaction,
+    name: &str,
+    description: &Option<String>,
+    date_due: &Option<String>,
+    status: &Option<String>,
+) -> Result<(), CoreError> {
+    let project_id = get_or_create_project(tx, name)?;
+    if let Some(description) = description {
+        tx.execute(
+            "UPDATE project SET description = taskoo_encrypt(:description) WHERE id = :id",
+            named_params! {":description": description, ":id": project_id},
+        )?;
+    }
+    if let Some(date_due) = date_due {
+        let date_due = match date_due.as_str() {
+            "" => None,
+            date_due => Some(TaskManager::parse_date_string(date_due)?),
+        };
+        tx.execute(
+            "UPDATE project SET due_date = :due_date WHERE id = :id",
+            named_params! {":due_date": date_due, ":id": project_id},
+        )?;
+    }
+    if let Some(status) = status {
+        let status = status.to_lowercase();
+        if !PROJECT_STATUSES.contains(&status.as_str()) {
+            return Err(CoreError::ArgumentError(format!(
+                "Invalid project status {}, use {}",
+                status,
+                PROJECT_STATUSES.join(", ")
+            )));
+        }
+        tx.execute(
+            "UPDATE project SET status = :status WHERE id = :id",
+            named_params! {":status": status, ":id": project_id},
+        )?;
+    }
+    Ok(())
+}
@@ -0,0 +1,288 @@
+use super::selection::Selection;
+use rusqlite::types::Value;
+
+// The columns of the `get_base` query that tasks can be filtered by
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Column {
+    TaskId,
+    DueDate,
+    ScheduledAt,
+    DeletedAt,
+    ContextName,
+    PriorityId,
+}
+
+impl Column {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Column::TaskId => "task.id",
+            Column::DueDate => "task.due_date",
+            Column::ScheduledAt => "task.scheduled_at",
+            Column::DeletedAt => "task.deleted_at",
+            Column::ContextName => "context.name",
+            Column::PriorityId => "priority_task.priority_id",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Condition {
+    Eq(Column, Value),
+    NotEq(Column, Value),
+    Lt(Column, Value),
+    Le(Column, Value),
+    // The task is one of the ids in the selection, for more ids than `In`
+    // can bind
+    Selected(Selection),
+    // The task is in the context or one of its subcontexts
+    InContext(i64),
+    // The task has every one of the tags, or one of their subtags
+    HasAllTags(Vec<i64>),
+    // The task has none of the tags and their subtags
+    HasNoTags(Vec<i64>),
+    // At least one of the conditions holds, none of them matches nothing
+    Any(Vec<Condition>),
+    // Every condition holds, none of them matches everything
+    All(Vec<Condition>),
+}
+
+impl Condition {
+    // The date is set and before `date`
+    pub fn before(column: Column, date: &str) -> Condition {
+        Condition::All(vec![
+            Condition::Lt(column, Value::from(date.to_string())),
+            Condition::NotEq(column, Value::from(String::new())),
+        ])
+    }
+
+    // The date is set and not after `date`
+    pub fn not_after(column: Column, date: &str) -> Condition {
+        Condition::All(vec![
+            Condition::Le(column, Value::from(date.to_string())),
+            Condition::NotEq(column, Value::from(String::new())),
+        ])
+    }
+
+    fn write(&self, sql: &mut String, params: &mut Vec<Value>) {
+        match self {
+            Condition::Eq(column, value) => write_comparison(sql, params, column, "=", value),
+            Condition::NotEq(column, value) => write_comparison(sql, params, column, "<>", value),
+            Condition::Lt(column, value) => write_comparison(sql, params, column, "<", value),
+            Condition::Le(column, value) => write_comparison(sql, params, column, "<=", value),
+            Condition::Selected(selection) => {
+                sql.push_str(&format!("task.id IN ({})", selection.ids()));
+            }
+            Condition::InContext(context_id) => {
+                sql.push_str(&format!(
+                    "task_context.context_id IN ({})",
+                    within_labels("context", "within.id", "?")
+                ));
+                params.push(Value::from(*context_id));
+            }
+            Condition::HasAllTags(tag_ids) if tag_ids.is_empty() => sql.push('1'),
+            Condition::HasAllTags(tag_ids) => {
+                sql.push_str(&format!(
+                    "task.id IN (SELECT task_tag.task_id FROM task_tag \
+                     INNER JOIN ({}) label_tag ON task_tag.tag_id = label_tag.id \
+                     GROUP BY task_tag.task_id HAVING COUNT(DISTINCT label_tag.label_id) = ?)",
+                    within_labels(
+                        "tag",
+                        "within.id, label.id as label_id",
+                        &placeholders(tag_ids.len())
+                    )
+                ));
+                params.extend(tag_ids.iter().map(|tag_id| Value::from(*tag_id)));
+                params.push(Value::from(distinct_count(tag_ids)));
+            }
+            Condition::HasNoTags(tag_ids) if tag_ids.is_empty() => sql.push('1'),
+            Condition::HasNoTags(tag_ids) => {
+                sql.push_str(&format!(
+                    "task.id NOT IN (SELECT task_id FROM task_tag WHERE tag_id IN ({}))",
+                    within_labels("tag", "within.id", &placeholders(tag_ids.len()))
+                ));
+                params.extend(tag_ids.iter().map(|tag_id| Value::from(*tag_id)));
+            }
+            Condition::Any(conditions) => write_joined(sql, params, conditions, " or ", '0'),
+            Condition::All(conditions) => write_joined(sql, params, conditions, " and ", '1'),
+        }
+    }
+}
+
+fn write_comparison(
+    sql: &mut String,
+    params: &mut Vec<Value>,
+    column: &Column,
+    operator: &str,
+    value: &Value,
+) {
+    sql.push_str(&format!("{} {} ?", column.name(), operator));
+    params.push(value.clone());
+}
+
+fn write_joined(
+    sql: &mut String,
+    params: &mut Vec<Value>,
+    conditions: &Vec<Condition>,
+    separator: &str,
+    empty: char,
+) {
+    if conditions.is_empty() {
+        sql.push(empty);
+        return;
+    }
+    sql.push('(');
+    for (index, condition) in conditions.iter().enumerate() {
+        if index > 0 {
+            sql.push_str(separator);
+        }
+        condition.write(sql, params);
+    }
+    sql.push(')');
+}
+
+// Select the `columns` of the labels in `table` that are within the labels
+// with the ids `label_ids`, see `is_within_label`. The matching labels are
+// `within`, the labels they're within are `label`.
+fn within_labels(table: &str, columns: &str, label_ids: &str) -> String {
+    format!(
+        "SELECT {columns} FROM {table} within INNER JOIN {table} label \
+         ON within.id = label.id \
+         OR substr(within.name, 1, length(label.name) + 1) = label.name || '.' \
+         WHERE label.id IN ({label_ids})",
+        table = table,
+        columns = columns,
+        label_ids = label_ids
+    )
+}
+
+fn distinct_count(ids: &Vec<i64>) -> i64 {
+    let mut ids = ids.clone();
+    ids.sort();
+    ids.dedup();
+    ids.len() as i64
+}
+
+// `?, ?, ?` for `count` parameters
+pub fn placeholders(count: usize) -> String {
+    vec!["?"; count].join(", ")
+}
+
+// The conditions of a task query, they are joined by `and`. Values are
+// never formatted into the SQL, they are bound to the `?` placeholders.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Filter {
+    conditions: Vec<Condition>,
+}
+
+impl Filter {
+    pub fn new() -> Filter {
+        Filter::default()
+    }
+
+    pub fn task_id(task_id: &i64) -> Filter {
+        Filter::new().and(Condition::Eq(Column::TaskId, Value::from(*task_id)))
+    }
+
+    // The tasks in the selection, it has to be filled before the query runs
+    pub fn selected(selection: Selection) -> Filter {
+        Filter::new().and(Condition::Selected(selection))
+    }
+
+    pub fn and(mut self, condition: Condition) -> Filter {
+        self.push(condition);
+        self
+    }
+
+    pub fn push(&mut self, condition: Condition) {
+        self.conditions.push(condition);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.conditions.is_empty()
+    }
+
+    // The SQL with `?` placeholders and the values to bind to them in order
+    pub fn to_sql(&self) -> (String, Vec<Value>) {
+        let mut sql = String::new();
+        let mut params = vec![];
+        for (index, condition) in self.conditions.iter().enumerate() {
+            if index > 0 {
+                sql.push_str(" and ");
+            }
+            condition.write(&mut sql, &mut params);
+        }
+        (sql, params)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_filter_binds_values() {
+        let filter = Filter::new()
+            .and(Condition::Eq(
+                Column::ContextName,
+                Value::from(String::from("work' OR 1=1 --")),
+            ))
+            .and(Condition::before(Column::DueDate, "2021-01-01"));
+        let (sql, params) = filter.to_sql();
+        assert_eq!(
+            sql,
+            "context.name = ? and (task.due_date < ? and task.due_date <> ?)"
+        );
+        assert_eq!(
+            params,
+            vec![
+                Value::from(String::from("work' OR 1=1 --")),
+                Value::from(String::from("2021-01-01")),
+                Value::from(String::new()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_filter_in_lists() {
+        let (sql, params) = Filter::selected(Selection::Task).to_sql();
+        assert_eq!(sql, "task.id IN (SELECT task_id FROM temp.selected_task)");
+        assert!(params.is_empty());
+
+        let (sql, params) = Filter::new()
+            .and(Condition::HasAllTags(vec![1, 2, 2]))
+            .to_sql();
+        assert_eq!(
+            sql,
+            "task.id IN (SELECT task_tag.task_id FROM task_tag \
+             INNER JOIN (SELECT within.id, label.id as label_id FROM tag within \
+             INNER JOIN tag label ON within.id = label.id \
+             OR substr(within.name, 1, length(label.name) + 1) = label.name || '.' \
+             WHERE label.id IN (?, ?, ?)) label_tag ON task_tag.tag_id = label_tag.id \
+             GROUP BY task_tag.task_id HAVING COUNT(DISTINCT label_tag.label_id) = ?)"
+        );
+        assert_eq!(
+            params,
+            vec![Value::from(1), Value::from(2), Value::from(2), Value::from(2)]
+        );
+
+        let (sql, params) = Filter::new().and(Condition::InContext(3)).to_sql();
+        assert_eq!(
+            sql,
+            "task_context.context_id IN (SELECT within.id FROM context within \
+             INNER JOIN context label ON within.id = label.id \
+             OR substr(within.name, 1, length(label.name) + 1) = label.name || '.' \
+             WHERE label.id IN (?))"
+        );
+        assert_eq!(params, vec![Value::from(3)]);
+    }
+
+    #[test]
+    fn test_filter_empty_groups() {
+        let (sql, _) = Filter::new()
+            .and(Condition::Any(vec![]))
+            .and(Condition::All(vec![]))
+            .to_sql();
+        assert_eq!(sql, "0 and 1");
+        assert!(Filter::new().is_empty());
+    }
+}
@@ -1,4 +1,8 @@
 use chrono::{NaiveDate, Duration};
+use rusqlite::types::Value;
+
+use super::query_builder::{Column, Condition, Filter};
+
 pub const CREATE_TASK_TABLE_QUERY: &str = "
     create table if not exists task (
         id integer primary key,
@@ -82,153 +86,286 @@ pub const CREATE_PRIORITY_TASK_TABLE_QUERY: &str = "
     )
 ";
 
-pub fn generate_agenda_condition(start_day: &NaiveDate) -> Vec<String> {
-    let mut conditions: Vec<String> = vec![];
-
-    let start_end_day = start_day.clone() + Duration::days(1);
-
-    // Includes both DUE and OVERDUE tasks
-    conditions.push(
-        format!(
-            "(due_date < '{}' and due_date <> '')",
-            start_end_day.format("%Y-%m-%d")
-        )
-        .as_str()
-        .to_string(),
-    );
-    // Includes both SCHEDULED and OVERSCHEDULED tasks
-    conditions.push(
-        format!(
-            "(scheduled_at < '{}' and scheduled_at <> '')",
-            start_end_day.format("%Y-%m-%d")
-        )
-        .as_str()
-        .to_string(),
-    );
-    return conditions;
+pub const CREATE_SCHEMA_VERSION_TABLE_QUERY: &str = "
+    CREATE TABLE IF NOT EXISTS schema_version (
+        version integer primary key,
+        description Text not null,
+        applied_at Text DEFAULT CURRENT_TIMESTAMP
+    )
+";
+
+pub const CREATE_JOURNAL_TABLE_QUERY: &str = "
+    CREATE TABLE IF NOT EXISTS journal (
+        id integer primary key,
+        operation Text not null,
+        undo Text not null,
+        redo Text not null,
+        undone integer not null DEFAULT 0,
+        created_at Text DEFAULT CURRENT_TIMESTAMP
+    )
+";
+
+pub const CREATE_TASK_HISTORY_TABLE_QUERY: &str = "
+    CREATE TABLE IF NOT EXISTS task_history (
+        id integer primary key,
+        task_id integer not null,
+        field Text not null,
+        old_value Text not null,
+        new_value Text not null,
+        changed_at Text DEFAULT CURRENT_TIMESTAMP
+    )
+";
+
+// `task` is the task as it's returned by `get_base`, `snapshot` holds its
+// rows to restore it and `labels` the names of the context, state, tags
+// and priority it refers to.
+pub const CREATE_ARCHIVE_TABLE_QUERY: &str = "
+    CREATE TABLE IF NOT EXISTS archive (
+        task_id integer primary key,
+        task Text not null,
+        snapshot Text not null,
+        labels Text not null,
+        completed_at Text not null,
+        archived_at Text DEFAULT CURRENT_TIMESTAMP
+    )
+";
+
+// SQL expression of a random (version 4) UUID, SQLite evaluates it
+// again for every row.
+pub const NEW_UUID: &str = "(lower(hex(randomblob(4))) || '-' || lower(hex(randomblob(2))) || '-4' ||
+    substr(lower(hex(randomblob(2))), 2) || '-' || substr('89ab', 1 + (abs(random()) % 4), 1) ||
+    substr(lower(hex(randomblob(2))), 2) || '-' || lower(hex(randomblob(6))))";
+
+// `autoincrement` keeps the ids of the deleted notes from being reused,
+// so undo and unarchive can put them back.
+pub const CREATE_NOTE_TABLE_QUERY: &str = "
+    CREATE TABLE IF NOT EXISTS note (
+        id integer primary key autoincrement,
+        task_id integer not null,
+        body Text not null,
+        created_at Text DEFAULT CURRENT_TIMESTAMP,
+        updated_at Text nullable,
+        FOREIGN KEY (task_id) REFERENCES task(id)
+    )
+";
+
+// Time spent on the tasks, `stopped_at` is NULL while the interval is
+// running. The unique index allows only one running interval.
+pub const CREATE_TIME_INTERVAL_TABLE_QUERIES: [&str; 3] = [
+    "
+    CREATE TABLE IF NOT EXISTS time_interval (
+        id integer primary key autoincrement,
+        task_id integer not null,
+        started_at Text not null,
+        stopped_at Text nullable,
+        FOREIGN KEY (task_id) REFERENCES task(id)
+    )
+    ",
+    "CREATE INDEX IF NOT EXISTS time_interval_task_id ON time_interval (task_id)",
+    "CREATE UNIQUE INDEX IF NOT EXISTS time_interval_running
+    ON time_interval ((stopped_at IS NULL)) WHERE stopped_at IS NULL",
+];
+
+// User-defined attributes of the tasks, see `uda`
+pub const CREATE_TASK_UDA_TABLE_QUERY: &str = "
+    CREATE TABLE IF NOT EXISTS task_uda (
+        task_id integer not null,
+        name Text not null,
+        value Text not null,
+        PRIMARY KEY (task_id, name),
+        FOREIGN KEY (task_id) REFERENCES task(id)
+    )
+";
+
+// Projects and the tasks that belong to them, see `project`. A task is
+// in one project at most.
+pub const CREATE_PROJECT_TABLE_QUERIES: [&str; 3] = [
+    "
+    CREATE TABLE IF NOT EXISTS project (
+        id integer primary key,
+        name Text not null unique,
+        description Text not null default '',
+        due_date Text nullable,
+        status Text not null default 'active',
+        created_at Text DEFAULT CURRENT_TIMESTAMP
+    )
+    ",
+    "
+    CREATE TABLE IF NOT EXISTS task_project (
+        task_id integer not null,
+        project_id integer not null,
+        PRIMARY KEY (task_id),
+        FOREIGN KEY (task_id) REFERENCES task(id),
+        FOREIGN KEY (project_id) REFERENCES project(id)
+    )
+    ",
+    "CREATE INDEX IF NOT EXISTS task_project_project_id ON task_project (project_id)",
+];
+
+// Files and links attached to the tasks, see `attachment`. The copies of
+// files live in attachment_blob so the journal and the archive only keep
+// the small attachment rows, a copy stays until its task is purged.
+pub const CREATE_ATTACHMENT_TABLE_QUERIES: [&str; 4] = [
+    "
+    CREATE TABLE IF NOT EXISTS attachment (
+        id integer primary key autoincrement,
+        task_id integer not null,
+        kind Text not null,
+        location Text not null,
+        blob_id integer nullable,
+        created_at Text DEFAULT CURRENT_TIMESTAMP,
+        FOREIGN KEY (task_id) REFERENCES task(id)
+    )
+    ",
+    "CREATE INDEX IF NOT EXISTS attachment_task_id ON attachment (task_id)",
+    "
+    CREATE TABLE IF NOT EXISTS attachment_blob (
+        id integer primary key autoincrement,
+        task_id integer not null,
+        data Blob not null
+    )
+    ",
+    "CREATE INDEX IF NOT EXISTS attachment_blob_task_id ON attachment_blob (task_id)",
+];
+
+// The items of the checklists, see `checklist`
+pub const CREATE_CHECKLIST_ITEM_TABLE_QUERIES: [&str; 2] = [
+    "
+    CREATE TABLE IF NOT EXISTS checklist_item (
+        id integer primary key autoincrement,
+        task_id integer not null,
+        position integer not null,
+        body Text not null,
+        done integer not null default 0,
+        FOREIGN KEY (task_id) REFERENCES task(id)
+    )
+    ",
+    "CREATE INDEX IF NOT EXISTS checklist_item_task_id ON checklist_item (task_id)",
+];
+
+// The key of an encrypted database, see `encryption`. There's one row at
+// most, `key_check` is a known text encrypted with the key.
+pub const CREATE_ENCRYPTION_TABLE_QUERY: &str = "
+    CREATE TABLE IF NOT EXISTS encryption (
+        id integer primary key,
+        salt Text not null,
+        key_check Text not null,
+        rounds integer not null,
+        created_at Text DEFAULT CURRENT_TIMESTAMP
+    )
+";
+
+// Short ids of the pending tasks, see `working_set::sync_working_set`
+pub const CREATE_WORKING_SET_TABLE_QUERY: &str = "
+    CREATE TABLE IF NOT EXISTS working_set (
+        display_id integer primary key,
+        task_id integer not null unique
+    )
+";
+
+// The index follows the task table through the triggers, so every
+// change of a task (including undo and the archive) is searchable.
+pub const CREATE_TASK_FTS_TABLE_QUERIES: &[&str] = &[
+    "CREATE VIRTUAL TABLE IF NOT EXISTS task_fts USING fts5(
+        body, annotation, content='task', content_rowid='id'
+    )",
+    "CREATE TRIGGER IF NOT EXISTS task_fts_insert AFTER INSERT ON task BEGIN
+        INSERT INTO task_fts (rowid, body, annotation)
+        VALUES (new.id, new.body, new.annotation);
+    END",
+    "CREATE TRIGGER IF NOT EXISTS task_fts_delete AFTER DELETE ON task BEGIN
+        INSERT INTO task_fts (task_fts, rowid, body, annotation)
+        VALUES ('delete', old.id, old.body, old.annotation);
+    END",
+    "CREATE TRIGGER IF NOT EXISTS task_fts_update AFTER UPDATE OF body, annotation ON task BEGIN
+        INSERT INTO task_fts (task_fts, rowid, body, annotation)
+        VALUES ('delete', old.id, old.body, old.annotation);
+        INSERT INTO task_fts (rowid, body, annotation)
+        VALUES (new.id, new.body, new.annotation);
+    END",
+    // Index the existing tasks
+    "INSERT INTO task_fts (task_fts) VALUES ('rebuild')",
+];
+
+pub fn generate_agenda_condition(start_day: &NaiveDate) -> Condition {
+    let start_end_day = (start_day.clone() + Duration::days(1))
+        .format("%Y-%m-%d")
+        .to_string();
+
+    Condition::Any(vec![
+        // Includes both DUE and OVERDUE tasks
+        Condition::before(Column::DueDate, &start_end_day),
+        // Includes both SCHEDULED and OVERSCHEDULED tasks
+        Condition::before(Column::ScheduledAt, &start_end_day),
+    ])
 }
 
 pub fn generate_view_condition(
-    context_id: &i64,
+    _context_id: &i64,
     _view_range_start: &Option<String>,
     view_range_end: &String,
     view_type: &Option<String>,
-) -> Vec<String> {
-    let mut conditions =
-        generate_condition(&None, &Some(*context_id), &None, &None, &None, &None, &None);
+) -> Filter {
+    let mut filter = Filter::new();
     if view_type == &Some("overdue".to_string()) {
-        conditions.push(
-            format!("due_date < '{}'", view_range_end)
-                .as_str()
-                .to_string(),
-        );
+        filter.push(Condition::Lt(
+            Column::DueDate,
+            Value::from(view_range_end.clone()),
+        ));
     } else if view_type == &Some("due".to_string()) {
-        conditions.push(
-            format!("(due_date <= '{}' and due_date <> '')", view_range_end)
-                .as_str()
-                .to_string(),
-        );
-        conditions.push("due_date <> ''".to_string());
+        filter.push(Condition::not_after(Column::DueDate, view_range_end));
     } else if view_type == &Some("schedule".to_string()) {
-        conditions.push(
-            format!(
-                "(scheduled_at <= '{}' and scheduled_at <> '')",
-                view_range_end
-            )
-            .as_str()
-            .to_string(),
-        );
-    //conditions.push("scheduled_at <> ''".to_string());
+        filter.push(Condition::not_after(Column::ScheduledAt, view_range_end));
     } else if view_type == &Some("all".to_string()) {
-        conditions.push(
-            format!("((scheduled_at <= '{}' and scheduled_at <> '') or (due_date <= '{}' and due_date <> ''))", view_range_end, view_range_end)
-                .as_str()
-                .to_string(),
-        );
+        filter.push(Condition::Any(vec![
+            Condition::not_after(Column::ScheduledAt, view_range_end),
+            Condition::not_after(Column::DueDate, view_range_end),
+        ]));
     }
-    return conditions;
+    return filter;
 }
 
 pub fn generate_get_condition(
-    body: &Option<&str>,
     context_id: &Option<i64>,
     due_date: &Option<&str>,
     scheduled_at: &Option<&str>,
-) -> Vec<String> {
-    let mut default_conditions = generate_condition(
-        body,
-        context_id,
-        due_date,
-        scheduled_at,
-        &None,
-        &None,
-        &None,
-    );
-
-    if context_id.is_some() {
-        default_conditions.push(
-            format!("task_context.context_id = {}", context_id.unwrap())
-                .as_str()
-                .to_string(),
-        );
+) -> Filter {
+    let mut filter = Filter::new();
+    if let Some(due_date) = due_date {
+        filter.push(Condition::Eq(Column::DueDate, Value::from(due_date.to_string())));
     }
 
-    return default_conditions;
+    if let Some(scheduled_at) = scheduled_at {
+        filter.push(Condition::Eq(
+            Column::ScheduledAt,
+            Value::from(scheduled_at.to_string()),
+        ));
+    }
+
+    if let Some(context_id) = context_id {
+        filter.push(Condition::InContext(*context_id));
+    }
+
+    return filter;
 }
 
-pub fn generate_condition(
+// The columns of the task table to update and their new values
+pub fn generate_assignments(
     body: &Option<&str>,
-    _context_id: &Option<i64>,
     due_date: &Option<&str>,
     scheduled_at: &Option<&str>,
     due_repeat: &Option<&str>,
     scheduled_repeat: &Option<&str>,
-    _state_id: &Option<i64>,
-) -> Vec<String> {
-    let mut conditions: Vec<String> = vec![];
-    if body.is_some() {
-        conditions.push(format!("body = '{}'", body.unwrap()).as_str().to_string());
-    }
-
-    if due_date.is_some() {
-        conditions.push(
-            format!("due_date = '{}'", due_date.unwrap())
-                .as_str()
-                .to_string(),
-        );
-    }
-
-    if scheduled_at.is_some() {
-        conditions.push(
-            format!("scheduled_at = '{}'", scheduled_at.unwrap())
-                .as_str()
-                .to_string(),
-        );
-    }
-
-    if due_repeat.is_some() {
-        conditions.push(
-            format!("due_repeat = '{}'", due_repeat.unwrap())
-                .as_str()
-                .to_string(),
-        );
-    }
-
-    if scheduled_repeat.is_some() {
-        conditions.push(
-            format!("scheduled_repeat = '{}'", scheduled_repeat.unwrap())
-                .as_str()
-                .to_string(),
-        );
-    }
-
-    // if state_id.is_some() {
-    //     conditions.push(
-    //         format!("state_id = {}", state_id.unwrap())
-    //             .as_str()
-    //             .to_string(),
-    //     );
-    // }
-
-    return conditions;
+) -> Vec<(&'static str, Value)> {
+    let columns = [
+        ("body", body),
+        ("due_date", due_date),
+        ("scheduled_at", scheduled_at),
+        ("due_repeat", due_repeat),
+        ("scheduled_repeat", scheduled_repeat),
+    ];
+    columns
+        .iter()
+        .filter_map(|(column, value)| value.map(|value| (*column, Value::from(value.to_string()))))
+        .collect()
 }
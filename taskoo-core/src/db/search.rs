@@ -0,0 +1,149 @@
+use super::encryption;
+use super::get_base::get_base;
+use super::query_builder::Filter;
+use crate::db::task_helper::Task;
+use crate::error::CoreError;
+use log::debug;
+use rusqlite::{named_params, Result, Transaction};
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct SearchResult {
+    pub task: Task,
+    // The best matching part of the body or the annotation, the matched
+    // words are wrapped by the highlight markers.
+    pub snippet: String,
+    // bm25 score, a lower rank is a better match
+    pub rank: f64,
+}
+
+// Turn the user input into a FTS5 query: every word must match, either
+// completely or as the prefix of a word. The words are quoted, so the
+// FTS5 operators and punctuation in the input don't cause syntax errors.
+fn to_match_query(query: &str) -> String {
+    query
+        .split_whitespace()
+        .map(|word| format!("\"{}\"*", word.replace('"', "\"\"")))
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+// Search the body and the annotation of the tasks, the best match first.
+// Tasks in the trash are excluded.
+pub fn search(
+    tx: &Transaction,
+    query: &str,
+    highlight_start: &str,
+    highlight_end: &str,
+) -> Result<Vec<SearchResult>, CoreError> {
+    let match_query = to_match_query(query);
+    if match_query.is_empty() {
+        return Ok(vec![]);
+    }
+    if encryption::is_enabled(tx)? {
+        return scan(tx, query, highlight_start, highlight_end);
+    }
+    debug!("Running full-text search with {}", match_query);
+
+    let mut statement = tx.prepare(
+        "SELECT rowid, snippet(task_fts, -1, :start, :end, '...', 12), bm25(task_fts) as rank
+        FROM task_fts WHERE task_fts MATCH :query ORDER BY rank",
+    )?;
+    let mut rows = statement.query(named_params! {
+        ":start": highlight_start,
+        ":end": highlight_end,
+        ":query": match_query,
+    })?;
+
+    let mut results = vec![];
+    while let Some(row) = rows.next()? {
+        let task_id: i64 = row.get(0)?;
+        let mut tasks = get_base(tx, &Filter::task_id(&task_id))?;
+        if tasks.is_empty() {
+            continue;
+        }
+        results.push(SearchResult {
+            task: tasks.remove(0),
+            snippet: row.get(1)?,
+            rank: row.get(2)?,
+        });
+    }
+    Ok(results)
+}
+
+// Wrap the words starting with one of the query words by the markers,
+// the number of wrapped words is returned with the text
+fn highlight(text: &str, words: &[String], start: &str, end: &str) -> (String, usize) {
+    let mut output = String::new();
+    let mut matches = 0;
+    let mut rest = text;
+    while !rest.is_empty() {
+        let word_end = rest
+            .find(|c: char| !c.is_alphanumeric())
+            .unwrap_or(rest.len());
+        let (word, tail) = rest.split_at(word_end);
+        let lowercase = word.to_lowercase();
+        if !word.is_empty()
+            && words
+                .iter()
+                .any(|query| lowercase.starts_with(query.as_str()))
+        {
+            output.push_str(&format!("{}{}{}", start, word, end));
+            matches += 1;
+        } else {
+            output.push_str(word);
+        }
+        let separator_end = tail
+            .find(|c: char| c.is_alphanumeric())
+            .unwrap_or(tail.len());
+        output.push_str(&tail[..separator_end]);
+        rest = &tail[separator_end..];
+    }
+    (output, matches)
+}
+
+// The full-text index only has the encrypted text when the database is
+// encrypted, so the decrypted tasks are matched one by one instead. The
+// rank is the negated number of matched words.
+fn scan(
+    tx: &Transaction,
+    query: &str,
+    highlight_start: &str,
+    highlight_end: &str,
+) -> Result<Vec<SearchResult>, CoreError> {
+    debug!("Scanning the encrypted tasks for {}", query);
+    let words: Vec<String> = query
+        .to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(String::from)
+        .collect();
+    let mut results = vec![];
+    for task in get_base(tx, &Filter::new())? {
+        let text = format!("{} {}", task.body, task.annotation).to_lowercase();
+        let text_words: Vec<&str> = text.split(|c: char| !c.is_alphanumeric()).collect();
+        let all_match = words.iter().all(|query| {
+            text_words
+                .iter()
+                .any(|word| word.starts_with(query.as_str()))
+        });
+        if words.is_empty() || !all_match {
+            continue;
+        }
+        let (body, body_matches) = highlight(&task.body, &words, highlight_start, highlight_end);
+        let (annotation, annotation_matches) =
+            highlight(&task.annotation, &words, highlight_start, highlight_end);
+        let snippet = if body_matches == 0 && annotation_matches > 0 {
+            annotation
+        } else {
+            body
+        };
+        results.push(SearchResult {
+            task,
+            snippet,
+            rank: -((body_matches + annotation_matches) as f64),
+        });
+    }
+    results.sort_by(|a, b| a.rank.partial_cmp(&b.rank).unwrap());
+    Ok(results)
+}
@@ -0,0 +1,55 @@
+use crate::error::CoreError;
+use rusqlite::{Result, Transaction};
+
+// A set of task ids kept in a temporary table, so a statement can work on
+// all of them at once by joining it. Binding the ids as `IN (?, ?, ...)`
+// stops working past SQLite's limit of 32766 parameters.
+//
+// Every user has its own table, so the selections of the operation and of
+// the journal don't overwrite each other.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Selection {
+    Task,
+    // The tasks that are unblocked by the selected tasks
+    Dependent,
+    Journal,
+    // The tasks whose subtasks or parents are looked up
+    Subtask,
+}
+
+impl Selection {
+    pub fn table(&self) -> &'static str {
+        match self {
+            Selection::Task => "temp.selected_task",
+            Selection::Dependent => "temp.dependent_task",
+            Selection::Journal => "temp.journal_task",
+            Selection::Subtask => "temp.subtask_task",
+        }
+    }
+
+    // Replace the ids in the table with `task_ids`, duplicates are dropped
+    pub fn fill(&self, tx: &Transaction, task_ids: &Vec<i64>) -> Result<(), CoreError> {
+        tx.execute(
+            &format!(
+                "CREATE TABLE IF NOT EXISTS {} (task_id integer primary key)",
+                self.table()
+            ),
+            [],
+        )?;
+        tx.execute(&format!("DELETE FROM {}", self.table()), [])?;
+
+        let mut statement = tx.prepare_cached(&format!(
+            "INSERT OR IGNORE INTO {} (task_id) VALUES (?)",
+            self.table()
+        ))?;
+        for task_id in task_ids.iter() {
+            statement.execute([task_id])?;
+        }
+        Ok(())
+    }
+
+    // The ids as a subquery, e.g. `task_id IN (SELECT ...)`
+    pub fn ids(&self) -> String {
+        format!("SELECT task_id FROM {}", self.table())
+    }
+}
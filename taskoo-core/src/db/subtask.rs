@@ -0,0 +1,157 @@
+use super::selection::Selection;
+use crate::error::CoreError;
+use rusqlite::{named_params, OptionalExtension, Result, Transaction};
+use std::collections::HashMap;
+
+// Subtasks break a task down into steps, unlike the dependencies which
+// block a task until others are completed. A subtask has one parent in
+// `task.parent_id`, the parent shows how many of its subtasks are done.
+
+// The setting that completes a parent when all of its subtasks are completed
+pub const AUTO_COMPLETE_PARENT_SETTING: &str = "auto_complete_parent";
+
+pub fn is_auto_complete_parent(setting: &HashMap<String, String>) -> Result<bool, CoreError> {
+    match setting.get(AUTO_COMPLETE_PARENT_SETTING).map(|value| value.trim()) {
+        None | Some("false") => Ok(false),
+        Some("true") => Ok(true),
+        Some(value) => Err(CoreError::ArgumentError(format!(
+            "{} must be true or false, got {}",
+            AUTO_COMPLETE_PARENT_SETTING, value
+        ))),
+    }
+}
+
+// Run `query` over the ids of `task_ids`, which it selects with `{ids}`
+fn query_ids(tx: &Transaction, query: &str, task_ids: &Vec<i64>) -> Result<Vec<i64>, CoreError> {
+    Selection::Subtask.fill(tx, task_ids)?;
+    let mut statement = tx.prepare(&query.replace("{ids}", &Selection::Subtask.ids()))?;
+    let mut rows = statement.query([])?;
+    let mut ids = vec![];
+    while let Some(row) = rows.next()? {
+        ids.push(row.get(0)?);
+    }
+    Ok(ids)
+}
+
+// The subtasks of the tasks and their subtasks, the deleted ones excluded
+pub fn get_subtask_ids(tx: &Transaction, task_ids: &Vec<i64>) -> Result<Vec<i64>, CoreError> {
+    query_ids(
+        tx,
+        "WITH RECURSIVE subtask(id) AS (
+            SELECT id FROM task WHERE parent_id IN ({ids}) AND deleted_at IS NULL
+            UNION
+            SELECT task.id FROM task INNER JOIN subtask ON task.parent_id = subtask.id
+            WHERE task.deleted_at IS NULL
+        )
+        SELECT id FROM subtask ORDER BY id",
+        task_ids,
+    )
+}
+
+// The direct subtasks of the tasks, they're re-parented when the tasks are
+// deleted
+pub fn get_child_ids(tx: &Transaction, task_ids: &Vec<i64>) -> Result<Vec<i64>, CoreError> {
+    query_ids(
+        tx,
+        "SELECT id FROM task WHERE parent_id IN ({ids}) AND deleted_at IS NULL ORDER BY id",
+        task_ids,
+    )
+}
+
+// The parents of the tasks and their parents
+pub fn get_ancestor_ids(tx: &Transaction, task_ids: &Vec<i64>) -> Result<Vec<i64>, CoreError> {
+    query_ids(
+        tx,
+        "WITH RECURSIVE ancestor(id) AS (
+            SELECT parent_id FROM task WHERE id IN ({ids}) AND parent_id IS NOT NULL
+            UNION
+            SELECT task.parent_id FROM task INNER JOIN ancestor ON task.id = ancestor.id
+            WHERE task.parent_id IS NOT NULL
+        )
+        SELECT id FROM ancestor ORDER BY id",
+        task_ids,
+    )
+}
+
+// Make the selected tasks subtasks of `parent_id`, or top level tasks
+// without it. A task can't be a subtask of itself or of its subtasks.
+pub fn set_parent(
+    tx: &Transaction,
+    selection: Selection,
+    task_ids: &Vec<i64>,
+    parent_id: &Option<i64>,
+) -> Result<(), CoreError> {
+    if let Some(parent_id) = parent_id {
+        let exists = tx
+            .query_row(
+                "SELECT 1 FROM task WHERE id = :parent_id AND deleted_at IS NULL",
+                named_params! {":parent_id": parent_id},
+                |_| Ok(()),
+            )
+            .optional()?;
+        if exists.is_none() {
+            return Err(CoreError::ArgumentError(format!(
+                "Parent task {} doesn't exist",
+                parent_id
+            )));
+        }
+        if task_ids.contains(parent_id) || get_subtask_ids(tx, task_ids)?.contains(parent_id) {
+            return Err(CoreError::ArgumentError(format!(
+                "Task {} is one of the tasks or their subtasks, it can't be their parent",
+                parent_id
+            )));
+        }
+    }
+    tx.execute(
+        &format!("UPDATE task SET parent_id = :parent_id WHERE id IN ({})", selection.ids()),
+        named_params! {":parent_id": parent_id},
+    )?;
+    Ok(())
+}
+
+// The subtasks of the selected tasks, which are being deleted, move up to
+// the parent of the deleted task. The subtasks that are deleted too keep
+// their parent, so they can be restored together.
+pub fn reparent_children(tx: &Transaction, selection: Selection) -> Result<(), CoreError> {
+    // Repeated for the deleted tasks that are subtasks of deleted tasks
+    loop {
+        let changes = tx.execute(
+            &format!(
+                "UPDATE task SET parent_id = (
+                    SELECT parent.parent_id FROM task AS parent WHERE parent.id = task.parent_id
+                )
+                WHERE parent_id IN ({ids}) AND id NOT IN ({ids})",
+                ids = selection.ids()
+            ),
+            [],
+        )?;
+        if changes == 0 {
+            return Ok(());
+        }
+    }
+}
+
+// The parents of the tasks that aren't completed while all of their
+// subtasks are
+pub fn get_completable_parent_ids(
+    tx: &Transaction,
+    task_ids: &Vec<i64>,
+) -> Result<Vec<i64>, CoreError> {
+    query_ids(
+        tx,
+        "SELECT parent.id FROM task AS parent
+        INNER JOIN task_state ON parent.id = task_state.task_id
+        INNER JOIN state ON task_state.state_id = state.id
+        WHERE parent.id IN (SELECT parent_id FROM task WHERE id IN ({ids}))
+        AND parent.deleted_at IS NULL AND state.name <> 'completed'
+        AND NOT EXISTS (
+            SELECT 1 FROM task AS child
+            INNER JOIN task_state AS child_state ON child.id = child_state.task_id
+            INNER JOIN state AS child_state_name ON child_state.state_id = child_state_name.id
+            WHERE child.parent_id = parent.id AND child.deleted_at IS NULL
+            AND child_state_name.name <> 'completed'
+        )
+        ORDER BY parent.id",
+        task_ids,
+    )
+}
@@ -1,7 +1,11 @@
 use serde::{Serialize, Deserialize};
+use std::collections::BTreeMap;
 
 use rusqlite::Rows;
-use crate::error::ArgumentError;
+use crate::db::attachment::Attachment;
+use crate::db::checklist::ChecklistItem;
+use crate::db::note::Note;
+use crate::error::{ArgumentError, CoreError};
 
 pub const TASK_STATES: [&'static str; 4] = ["ready", "completed", "blocked", "started"];
 
@@ -9,12 +13,23 @@ pub const DEFAULT_CONTEXT: [&'static str; 1] = ["inbox"];
 
 pub const PRIORITIES: [&'static str; 3] = ["H", "M", "L"];
 
-#[derive(Serialize, Deserialize, Debug)]
+pub const DEFAULT_ARCHIVE_AFTER_DAYS: i64 = 30;
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Task {
     pub id: i64,
+    // Stable across databases, unlike `id`
+    #[serde(default)]
+    pub uuid: String,
+    // Short id while the task is pending, see `working_set`
+    #[serde(default)]
+    pub display_id: Option<i64>,
     pub body: String,
     pub priority: String,
     pub context: String,
+    // The project the task belongs to, empty without one, see `db::project`
+    #[serde(default)]
+    pub project: String,
     pub tags: Vec<String>,
     pub tag_ids: Vec<i64>,
     pub date_created: String,
@@ -25,22 +40,68 @@ pub struct Task {
     pub state: String,
     pub annotation: String,
     pub parent_task_ids: Vec<String>,
+    // In UTC like `date_created`, empty when the task was never modified,
+    // isn't started or isn't completed
+    #[serde(default)]
+    pub date_modified: String,
+    #[serde(default)]
+    pub date_started: String,
+    #[serde(default)]
+    pub date_completed: String,
+    // The estimated effort in minutes
+    #[serde(default)]
+    pub estimate: Option<i64>,
+    // The seconds tracked on the task, including the running interval
+    #[serde(default)]
+    pub time_tracked: i64,
+    // Tasks archived before notes existed don't have them
+    #[serde(default)]
+    pub notes: Vec<Note>,
+    // The metadata of the files and links attached to the task
+    #[serde(default)]
+    pub attachments: Vec<Attachment>,
+    // The steps of the task in their order, see `db::checklist`
+    #[serde(default)]
+    pub checklist: Vec<ChecklistItem>,
+    // The task this one is a subtask of, see `db::subtask`
+    #[serde(default)]
+    pub parent_id: Option<i64>,
+    // The subtasks that aren't deleted, and how many of them are completed
+    #[serde(default)]
+    pub subtasks_total: i64,
+    #[serde(default)]
+    pub subtasks_completed: i64,
+    // The user-defined attributes by name, see `db::uda`
+    #[serde(default)]
+    pub udas: BTreeMap<String, String>,
 }
 
 impl Task {
     pub fn get_property_value(&self, attr: &str) -> Result<String, ArgumentError> {
         match attr {
+            "uuid" => Ok(self.uuid.clone()),
+            "display_id" => Ok(self.display_id.map_or(String::new(), |id| id.to_string())),
             "priority" => Ok(self.priority.clone()),
             "context" => Ok(self.context.clone()),
+            "project" => Ok(self.project.clone()),
             "tags" => Ok(self.tags.join(",")),
             "date_created" => Ok(self.date_created.clone()),
+            "date_modified" => Ok(self.date_modified.clone()),
+            "date_started" => Ok(self.date_started.clone()),
+            "date_completed" => Ok(self.date_completed.clone()),
             "date_due" => Ok(self.date_due.clone()),
             "date_scheduled" => Ok(self.date_scheduled.clone()),
             "repetition_due" => Ok(self.repetition_due.clone()),
             "repetition_scheduled" => Ok(self.repetition_scheduled.clone()),
             "state" => Ok(self.state.clone()),
             "annotation" => Ok(self.annotation.clone()),
+            "estimate" => Ok(self.estimate.map_or(String::new(), format_estimate)),
+            "time_tracked" => Ok(format_estimate(self.time_tracked / 60)),
             "parent_task_ids" => Ok(self.parent_task_ids.join(",")),
+            "parent_id" => Ok(self.parent_id.map_or(String::new(), |id| id.to_string())),
+            "subtasks" => Ok(self.format_subtask_progress()),
+            "checklist" => Ok(self.format_checklist_progress()),
+            name if self.udas.contains_key(name) => Ok(self.udas[name].clone()),
             _ => Err(ArgumentError::InvalidOption(format!(
                 "{} is not a supported property",
                 attr
@@ -62,6 +123,112 @@ impl Task {
     pub fn is_blocked(&self) -> bool {
         return self.state == "blocked";
     }
+    // N/M of the subtasks are completed, empty without subtasks
+    pub fn format_subtask_progress(&self) -> String {
+        match self.subtasks_total {
+            0 => String::new(),
+            total => format!("{}/{}", self.subtasks_completed, total),
+        }
+    }
+
+    // N/M of the checklist items are done, empty without a checklist
+    pub fn format_checklist_progress(&self) -> String {
+        match self.checklist.len() {
+            0 => String::new(),
+            total => format!(
+                "{}/{}",
+                self.checklist.iter().filter(|item| item.done).count(),
+                total
+            ),
+        }
+    }
+
+    // The UDAs as `name:value` tokens, the way they're provided
+    pub fn format_udas(&self) -> String {
+        let udas: Vec<String> =
+            self.udas.iter().map(|(name, value)| format!("{}:{}", name, value)).collect();
+        udas.join(" ")
+    }
+}
+
+// Parse an effort like 45m, 2h or 1h30m into minutes
+pub fn parse_estimate(estimate: &str) -> Result<i64, CoreError> {
+    let invalid = || CoreError::ArgumentError(format!("Invalid estimate {}", estimate));
+    let mut minutes = 0;
+    let mut number = String::new();
+    for c in estimate.trim().to_lowercase().chars() {
+        match c {
+            '0'..='9' => number.push(c),
+            'h' | 'm' if !number.is_empty() => {
+                let value: i64 = number.parse().map_err(|_| invalid())?;
+                minutes += if c == 'h' { value * 60 } else { value };
+                number.clear();
+            }
+            _ => return Err(invalid()),
+        }
+    }
+    if !number.is_empty() || minutes == 0 {
+        return Err(invalid());
+    }
+    Ok(minutes)
+}
+
+// The reverse of `parse_estimate`, e.g. 90 is 1h30m
+pub fn format_estimate(minutes: i64) -> String {
+    match (minutes / 60, minutes % 60) {
+        (0, minutes) => format!("{}m", minutes),
+        (hours, 0) => format!("{}h", hours),
+        (hours, minutes) => format!("{}h{}m", hours, minutes),
+    }
+}
+
+// Contexts and tags are dotted hierarchies, `work.clienta` is a subcontext
+// of `work`. The parts of a name can't be empty.
+pub fn is_valid_label_name(name: &str) -> bool {
+    !name.starts_with('.') && !name.ends_with('.') && !name.contains("..")
+}
+
+// The label is `ancestor` or one of its descendants
+pub fn is_within_label(name: &str, ancestor: &str) -> bool {
+    name.strip_prefix(ancestor).map_or(false, |rest| rest.is_empty() || rest.starts_with('.'))
+}
+
+// The ancestors of a label, the root first, e.g. `a` and `a.b` for `a.b.c`
+pub fn get_label_ancestors(name: &str) -> Vec<&str> {
+    name.match_indices('.').map(|(index, _)| &name[..index]).collect()
+}
+
+// Sort the labels as a tree, the parents before their descendants
+pub fn sort_label_tree(names: &mut Vec<String>) {
+    names.sort_by(|a, b| a.split('.').cmp(b.split('.')));
+}
+
+// The effort of a group of tasks, estimates are in minutes and the
+// tracked time in seconds like `Task`
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq)]
+pub struct Effort {
+    // The estimates of the tasks that aren't completed
+    pub remaining: i64,
+    // The estimates of all the tasks, and the time tracked on them
+    pub estimated: i64,
+    pub tracked: i64,
+}
+
+impl Effort {
+    // Only the tasks with an estimate count
+    pub fn of<'a>(tasks: impl IntoIterator<Item = &'a Task>) -> Effort {
+        let mut effort = Effort::default();
+        for task in tasks {
+            if let Some(estimate) = task.estimate {
+                if !task.is_completed() {
+                    effort.remaining += estimate;
+                }
+                effort.estimated += estimate;
+                effort.tracked += task.time_tracked;
+            }
+        }
+        effort
+    }
 }
 
 pub fn convert_rows_into_task(rows: &mut Rows) -> Vec<Task> {
@@ -100,6 +267,8 @@ pub fn convert_rows_into_task(rows: &mut Rows) -> Vec<Task> {
 
         tasks.push(Task {
             id: row.get("id").unwrap(),
+            uuid: row.get("uuid").unwrap_or("".to_string()),
+            display_id: row.get("display_id").unwrap_or(None),
             body: row.get("body").unwrap(),
             priority: row.get("priority").unwrap_or("".to_string()),
             tags: tag_names,
@@ -110,9 +279,22 @@ pub fn convert_rows_into_task(rows: &mut Rows) -> Vec<Task> {
             repetition_due: row.get("due_repeat").unwrap(),
             repetition_scheduled: row.get("scheduled_repeat").unwrap(),
             context: row.get("context").unwrap(),
+            project: row.get("project").unwrap_or_default(),
             state: row.get("state").unwrap(),
             annotation: row.get("annotation").unwrap_or("".to_string()),
             parent_task_ids: parent_task_ids,
+            date_modified: row.get("modified_at").unwrap_or_default(),
+            date_started: row.get("started_at").unwrap_or_default(),
+            date_completed: row.get("completed_at").unwrap_or_default(),
+            estimate: row.get("estimate").unwrap_or(None),
+            time_tracked: row.get("time_tracked").unwrap_or(0),
+            notes: vec![],
+            attachments: vec![],
+            checklist: vec![],
+            udas: BTreeMap::new(),
+            parent_id: row.get("parent_id").unwrap_or(None),
+            subtasks_total: row.get("subtasks_total").unwrap_or(0),
+            subtasks_completed: row.get("subtasks_completed").unwrap_or(0),
         });
     }
 
@@ -1,21 +1,49 @@
 use crate::db::add::{add, add_annotation};
-use crate::db::delete::delete;
-use crate::db::get::get;
+use crate::db::delete::{delete, get_deletable_ids, get_purgeable, purge, restore};
+use crate::db::doctor::{diagnose, Problem};
+use crate::db::encryption;
+use crate::db::encryption::{load_key, register_functions, KeyState, PASSPHRASE_SETTING};
+use crate::db::get::{get, get_task_id_by_uuid};
+use crate::db::get_base::{get_base, get_trash};
+use crate::db::query_builder::Filter;
+use crate::db::selection::Selection;
+use crate::db::history::{get_history, record_changes, TaskChange};
+use crate::db::attachment::{
+    add_attachment, delete_attachment, get_attachment, get_attachment_data, Attachment,
+};
+use crate::db::backup;
+use crate::db::backup::{get_backup_dir, is_auto_backup, Backup};
+use crate::db::checklist::{add_item, check_item, delete_item, move_item};
+use crate::db::note::{add_note, delete_note, edit_note, get_note_task_id};
+use crate::db::project::get_or_create_project;
+use crate::db::search::{search, SearchResult};
+use crate::db::subtask::{
+    get_ancestor_ids, get_child_ids, get_completable_parent_ids, get_subtask_ids,
+    is_auto_complete_parent,
+};
+use crate::db::journal;
+use crate::db::journal::{JournalEntry, TaskSnapshot};
 use crate::db::modify::modify;
 use crate::db::agenda::agenda;
-use crate::db::query_helper::{
-    CREATE_CONTEXT_TABLE_QUERY, CREATE_DEPENDENCY_TABLE_QUERY, CREATE_STATE_TABLE_QUERY,
-    CREATE_TAG_TABLE_QUERY, CREATE_TASK_TABLE_QUERY, CREATE_TASK_TAG_TABLE_QUERY,
-    CREATE_PRIORITY_TABLE_QUERY, CREATE_PRIORITY_TASK_TABLE_QUERY, CREATE_TASK_CONTEXT_TABLE_QUERY,
-    CREATE_TASK_STATE_TABLE_QUERY,
+use crate::db::archive::{archive, get_archivable, get_archived, unarchive};
+use crate::db::migration::{current_version, latest_version, migrate, MIGRATIONS};
+use crate::db::task_helper::{
+    get_label_ancestors, is_valid_label_name, is_within_label, parse_estimate, Task,
+    DEFAULT_ARCHIVE_AFTER_DAYS, DEFAULT_CONTEXT, TASK_STATES, PRIORITIES,
+};
+use crate::db::time_tracking::{
+    add_interval, delete_interval, edit_interval, get_interval_task_id, get_intervals,
+    get_tracked_task_id, parse_local_time, TimeInterval,
 };
-use crate::db::task_helper::{Task, DEFAULT_CONTEXT, TASK_STATES, PRIORITIES};
+use crate::db::uda::{get_uda_types, resolve_udas, UdaType};
 use crate::db::view::view;
+use crate::db::working_set::{attach_display_ids, get_task_ids_by_display_id, sync_working_set};
 use crate::error::{CoreError, ArgumentError};
-use chrono::{DateTime, Duration, Local, NaiveDate, NaiveDateTime};
+use chrono::{DateTime, Duration, Local, NaiveDate, NaiveDateTime, Utc};
 use log::{info, debug};
 use rusqlite::{named_params, Connection, Result, Transaction};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
 
 #[derive(Debug)]
 pub struct TaskManager {
@@ -24,21 +52,22 @@ pub struct TaskManager {
 }
 
 impl TaskManager {
-    // ensure the database is created
-    pub fn new(setting: &HashMap<String, String>) -> TaskManager {
+    // ensure the database is created, and migrated to the latest schema
+    pub fn new(setting: &HashMap<String, String>) -> Result<TaskManager, CoreError> {
         // We don't handle the Result here, because it's okay
         // to ignore errors.
         //
         env_logger::try_init().ok();
-        let conn = Connection::open(setting.get("db_path").unwrap()).unwrap();
+        let db_path = setting.get("db_path").ok_or_else(|| {
+            CoreError::ArgumentError(String::from("The setting doesn't have a db_path"))
+        })?;
+        let conn = Connection::open(db_path)?;
         let mut manager = TaskManager {
             conn: conn,
             setting: setting.clone(),
         };
-        manager
-            .create_table_if_needed(DEFAULT_CONTEXT)
-            .expect("Failed to create all required tables");
-        return manager;
+        manager.create_table_if_needed(DEFAULT_CONTEXT)?;
+        Ok(manager)
     }
 
     pub fn get_context_names_from_config(&self) -> Vec<String> {
@@ -46,6 +75,11 @@ impl TaskManager {
         return context.split(",").map(|s| s.to_string()).collect();
     }
 
+    // The UDAs declared in the [uda] section of the config
+    pub fn get_uda_types(&self) -> Result<BTreeMap<String, UdaType>, CoreError> {
+        get_uda_types(&self.setting)
+    }
+
     pub fn add(
         &mut self,
         body: &str,
@@ -59,8 +93,13 @@ impl TaskManager {
         annotation: &Option<&str>,
         state_name: &Option<String>,
         parent_task_ids: &Option<Vec<i64>>,
+        estimate: &Option<&str>,
+        udas: &Vec<(String, String)>,
+        parent_id: &Option<i64>,
+        project: &Option<String>,
     ) -> Result<Vec<Task>, CoreError> {
         debug!("Add start! self={:p}", self);
+        let udas = resolve_udas(&self.get_uda_types()?, udas)?;
         let mut tx = self.conn.transaction()?;
 
         // Each task must have a context associated with it
@@ -68,6 +107,10 @@ impl TaskManager {
             Some(context) => TaskManager::convert_context_name_to_id(&tx, &context, true)?,
             None => 1, // default to `Inbox` context
         };
+        let project_id = match project {
+            Some(name) => Some(get_or_create_project(&tx, name)?),
+            None => None,
+        };
 
         // Always respect client provided state_name, however if the
         // client doesn't provide one, and the task is blocked by
@@ -139,7 +182,11 @@ impl TaskManager {
             )?),
             None => None,
         };
-        let tasks = add(
+        let parsed_estimate = estimate.map(parse_estimate).transpose()?;
+        // Adding a started task stops the time tracking of another task
+        let tracked_task_ids: Vec<i64> = get_tracked_task_id(&tx)?.into_iter().collect();
+        let tracked_before = journal::snapshot(&tx, &tracked_task_ids)?;
+        let mut tasks = add(
             &mut tx,
             &body,
             &priority_id,
@@ -152,7 +199,18 @@ impl TaskManager {
             &annotation,
             &state_id,
             &parent_task_ids,
+            &parsed_estimate,
+            &udas,
+            &parent_id,
+            &project_id,
         )?;
+        let mut task_ids: Vec<i64> = tasks.iter().map(|task| task.id).collect();
+        let mut before: Vec<TaskSnapshot> =
+            task_ids.iter().map(|id| TaskSnapshot::missing(*id)).collect();
+        before.extend(tracked_before);
+        task_ids.extend(tracked_task_ids);
+        journal::record(&tx, "add", &before, &journal::snapshot(&tx, &task_ids)?)?;
+        TaskManager::update_working_set(&tx, &mut tasks)?;
         tx.commit()?;
         debug!("Add done! self={:p}", self);
         Ok(tasks)
@@ -164,11 +222,222 @@ impl TaskManager {
         annotation: String,
     ) -> Result<Vec<Task>, CoreError> {
         let mut tx = self.conn.transaction()?;
+        let before = journal::snapshot(&tx, &vec![task_id])?;
         let tasks = add_annotation(&mut tx, task_id, annotation)?;
+        journal::record(&tx, "annotate", &before, &journal::snapshot(&tx, &vec![task_id])?)?;
+        tx.commit()?;
+        Ok(tasks)
+    }
+
+    // Append a note to the task, the task is returned with all its notes
+    pub fn add_note(&mut self, task_id: i64, body: &str) -> Result<Vec<Task>, CoreError> {
+        let tx = self.conn.transaction()?;
+        if get_base(&tx, &Filter::task_id(&task_id))?.is_empty() {
+            return Err(CoreError::ArgumentError(format!(
+                "Task {} doesn't exist",
+                task_id
+            )));
+        }
+        let before = journal::snapshot(&tx, &vec![task_id])?;
+        add_note(&tx, task_id, body)?;
+        journal::record(&tx, "note", &before, &journal::snapshot(&tx, &vec![task_id])?)?;
+        let tasks = get_base(&tx, &Filter::task_id(&task_id))?;
+        tx.commit()?;
+        Ok(tasks)
+    }
+
+    pub fn edit_note(&mut self, note_id: i64, body: &str) -> Result<Vec<Task>, CoreError> {
+        let tx = self.conn.transaction()?;
+        let task_id = get_note_task_id(&tx, note_id)?;
+        let before = journal::snapshot(&tx, &vec![task_id])?;
+        edit_note(&tx, note_id, body)?;
+        journal::record(&tx, "note", &before, &journal::snapshot(&tx, &vec![task_id])?)?;
+        let tasks = get_base(&tx, &Filter::task_id(&task_id))?;
+        tx.commit()?;
+        Ok(tasks)
+    }
+
+    pub fn delete_note(&mut self, note_id: i64) -> Result<Vec<Task>, CoreError> {
+        let tx = self.conn.transaction()?;
+        let task_id = get_note_task_id(&tx, note_id)?;
+        let before = journal::snapshot(&tx, &vec![task_id])?;
+        delete_note(&tx, note_id)?;
+        journal::record(&tx, "note", &before, &journal::snapshot(&tx, &vec![task_id])?)?;
+        let tasks = get_base(&tx, &Filter::task_id(&task_id))?;
+        tx.commit()?;
+        Ok(tasks)
+    }
+
+    // Attach a URL or a file to the task, see `attachment::add_attachment`
+    pub fn add_attachment(
+        &mut self,
+        task_id: i64,
+        location: &str,
+        copy: bool,
+    ) -> Result<Vec<Task>, CoreError> {
+        let tx = self.conn.transaction()?;
+        if get_base(&tx, &Filter::task_id(&task_id))?.is_empty() {
+            return Err(CoreError::ArgumentError(format!(
+                "Task {} doesn't exist",
+                task_id
+            )));
+        }
+        let before = journal::snapshot(&tx, &vec![task_id])?;
+        add_attachment(&tx, task_id, location, copy)?;
+        journal::record(&tx, "attach", &before, &journal::snapshot(&tx, &vec![task_id])?)?;
+        let tasks = get_base(&tx, &Filter::task_id(&task_id))?;
+        tx.commit()?;
+        Ok(tasks)
+    }
+
+    pub fn delete_attachment(&mut self, attachment_id: i64) -> Result<Vec<Task>, CoreError> {
+        let tx = self.conn.transaction()?;
+        let task_id = get_attachment(&tx, attachment_id)?.task_id;
+        let before = journal::snapshot(&tx, &vec![task_id])?;
+        delete_attachment(&tx, attachment_id)?;
+        journal::record(&tx, "detach", &before, &journal::snapshot(&tx, &vec![task_id])?)?;
+        let tasks = get_base(&tx, &Filter::task_id(&task_id))?;
+        tx.commit()?;
+        Ok(tasks)
+    }
+
+    // The attachment with its copied data, the data is None for URLs and
+    // paths
+    pub fn get_attachment(
+        &mut self,
+        attachment_id: i64,
+    ) -> Result<(Attachment, Option<Vec<u8>>), CoreError> {
+        let tx = self.conn.transaction()?;
+        let attachment = get_attachment(&tx, attachment_id)?;
+        let data = get_attachment_data(&tx, attachment_id)?;
+        tx.commit()?;
+        // Undoing a purge brings the attachments back without their copies
+        if attachment.is_copy() && data.is_none() {
+            return Err(CoreError::ArgumentError(format!(
+                "The copy of attachment {} was purged",
+                attachment_id
+            )));
+        }
+        Ok((attachment, data))
+    }
+
+    // Append an item to the checklist of the task, see `db::checklist`
+    pub fn add_checklist_item(&mut self, task_id: i64, body: &str) -> Result<Vec<Task>, CoreError> {
+        self.change_checklist(task_id, |tx| add_item(tx, task_id, body))
+    }
+
+    pub fn check_checklist_item(
+        &mut self,
+        task_id: i64,
+        position: i64,
+        done: bool,
+    ) -> Result<Vec<Task>, CoreError> {
+        self.change_checklist(task_id, |tx| check_item(tx, task_id, position, done))
+    }
+
+    pub fn move_checklist_item(
+        &mut self,
+        task_id: i64,
+        from: i64,
+        to: i64,
+    ) -> Result<Vec<Task>, CoreError> {
+        self.change_checklist(task_id, |tx| move_item(tx, task_id, from, to))
+    }
+
+    pub fn delete_checklist_item(
+        &mut self,
+        task_id: i64,
+        position: i64,
+    ) -> Result<Vec<Task>, CoreError> {
+        self.change_checklist(task_id, |tx| delete_item(tx, task_id, position))
+    }
+
+    // Every checklist change is journaled like a note change
+    fn change_checklist<F>(&mut self, task_id: i64, change: F) -> Result<Vec<Task>, CoreError>
+    where
+        F: FnOnce(&Transaction) -> Result<(), CoreError>,
+    {
+        let tx = self.conn.transaction()?;
+        if get_base(&tx, &Filter::task_id(&task_id))?.is_empty() {
+            return Err(CoreError::ArgumentError(format!(
+                "Task {} doesn't exist",
+                task_id
+            )));
+        }
+        let before = journal::snapshot(&tx, &vec![task_id])?;
+        change(&tx)?;
+        journal::record(&tx, "checklist", &before, &journal::snapshot(&tx, &vec![task_id])?)?;
+        let tasks = get_base(&tx, &Filter::task_id(&task_id))?;
         tx.commit()?;
         Ok(tasks)
     }
 
+    // The time intervals that overlap the period from `start` to `end`,
+    // both in UTC, see `time_tracking::get_intervals`
+    pub fn get_intervals(
+        &mut self,
+        start: &Option<String>,
+        end: &Option<String>,
+        task_id: &Option<i64>,
+    ) -> Result<Vec<TimeInterval>, CoreError> {
+        let tx = self.conn.transaction()?;
+        let intervals = get_intervals(&tx, start, end, task_id)?;
+        tx.commit()?;
+        Ok(intervals)
+    }
+
+    // Record the time spent on the task, the times are parsed like the
+    // other dates. The task is returned with all its intervals.
+    pub fn add_interval(
+        &mut self,
+        task_id: i64,
+        started_at: &str,
+        stopped_at: &str,
+    ) -> Result<Vec<TimeInterval>, CoreError> {
+        let tx = self.conn.transaction()?;
+        if get_base(&tx, &Filter::task_id(&task_id))?.is_empty() {
+            return Err(CoreError::ArgumentError(format!(
+                "Task {} doesn't exist",
+                task_id
+            )));
+        }
+        let before = journal::snapshot(&tx, &vec![task_id])?;
+        add_interval(&tx, task_id, &parse_local_time(started_at)?, &parse_local_time(stopped_at)?)?;
+        journal::record(&tx, "time", &before, &journal::snapshot(&tx, &vec![task_id])?)?;
+        let intervals = get_intervals(&tx, &None, &None, &Some(task_id))?;
+        tx.commit()?;
+        Ok(intervals)
+    }
+
+    pub fn edit_interval(
+        &mut self,
+        interval_id: i64,
+        started_at: &Option<&str>,
+        stopped_at: &Option<&str>,
+    ) -> Result<Vec<TimeInterval>, CoreError> {
+        let tx = self.conn.transaction()?;
+        let task_id = get_interval_task_id(&tx, interval_id)?;
+        let started_at = started_at.map(parse_local_time).transpose()?;
+        let stopped_at = stopped_at.map(parse_local_time).transpose()?;
+        let before = journal::snapshot(&tx, &vec![task_id])?;
+        edit_interval(&tx, interval_id, &started_at.as_deref(), &stopped_at.as_deref())?;
+        journal::record(&tx, "time", &before, &journal::snapshot(&tx, &vec![task_id])?)?;
+        let intervals = get_intervals(&tx, &None, &None, &Some(task_id))?;
+        tx.commit()?;
+        Ok(intervals)
+    }
+
+    pub fn delete_interval(&mut self, interval_id: i64) -> Result<Vec<TimeInterval>, CoreError> {
+        let tx = self.conn.transaction()?;
+        let task_id = get_interval_task_id(&tx, interval_id)?;
+        let before = journal::snapshot(&tx, &vec![task_id])?;
+        delete_interval(&tx, interval_id)?;
+        journal::record(&tx, "time", &before, &journal::snapshot(&tx, &vec![task_id])?)?;
+        let intervals = get_intervals(&tx, &None, &None, &Some(task_id))?;
+        tx.commit()?;
+        Ok(intervals)
+    }
+
     pub fn get(
         &mut self,
         priority: &Option<String>,
@@ -219,6 +488,10 @@ impl TaskManager {
             }
         };
 
+        // Listing the tasks renumbers the display ids, a single task is
+        // looked up without changing the ids that were shown last
+        sync_working_set(&tx, task_id.is_none())?;
+
         let tasks = get(
             &tx,
             &priority_id,
@@ -234,14 +507,209 @@ impl TaskManager {
         Ok(tasks)
     }
 
+    // The subtasks of the deleted tasks move up to the parent of the task,
+    // use `get_subtask_ids` to delete them too
     pub fn delete(&mut self, task_ids: &Vec<i64>) -> Result<Vec<Task>, CoreError> {
         info!("deleting tasks {:?}", task_ids);
+        // A snapshot is only taken when there's something to delete
+        let tx = self.conn.transaction()?;
+        let deletable_ids = get_deletable_ids(&tx, task_ids)?;
+        tx.commit()?;
+        if deletable_ids.is_empty() {
+            return Ok(vec![]);
+        }
+        self.auto_backup("delete")?;
+
+        let tx = self.conn.transaction()?;
+        let mut journal_task_ids = journal::with_dependents(&tx, task_ids)?;
+        journal_task_ids.extend(get_child_ids(&tx, task_ids)?);
+        let before = journal::snapshot(&tx, &journal_task_ids)?;
+        let mut tasks = delete(&tx, &task_ids)?;
+        if !tasks.is_empty() {
+            let after = journal::snapshot(&tx, &journal_task_ids)?;
+            journal::record(&tx, "delete", &before, &after)?;
+        }
+        TaskManager::update_working_set(&tx, &mut tasks)?;
+        tx.commit()?;
+        Ok(tasks)
+    }
+
+    // The subtasks of the tasks and their subtasks
+    pub fn get_subtask_ids(&mut self, task_ids: &Vec<i64>) -> Result<Vec<i64>, CoreError> {
+        let tx = self.conn.transaction()?;
+        let subtask_ids = get_subtask_ids(&tx, task_ids)?;
+        tx.commit()?;
+        Ok(subtask_ids)
+    }
+
+    pub fn trash(&mut self) -> Result<Vec<Task>, CoreError> {
+        let tx = self.conn.transaction()?;
+        let tasks = get_trash(&tx, &Filter::new())?;
+        tx.commit()?;
+        Ok(tasks)
+    }
+
+    pub fn restore(&mut self, task_ids: &Vec<i64>) -> Result<Vec<Task>, CoreError> {
+        info!("restoring tasks {:?}", task_ids);
+        let tx = self.conn.transaction()?;
+        let journal_task_ids = journal::with_dependents(&tx, task_ids)?;
+        let before = journal::snapshot(&tx, &journal_task_ids)?;
+        let mut tasks = restore(&tx, &task_ids)?;
+        if !tasks.is_empty() {
+            let after = journal::snapshot(&tx, &journal_task_ids)?;
+            journal::record(&tx, "restore", &before, &after)?;
+        }
+        TaskManager::update_working_set(&tx, &mut tasks)?;
+        tx.commit()?;
+        Ok(tasks)
+    }
+
+    // Permanently remove the tasks in the trash. `older_than` is either a
+    // period like 30days, which is counted backwards from now, or a date.
+    pub fn purge(&mut self, older_than: &Option<&str>) -> Result<Vec<Task>, CoreError> {
+        let deleted_before = match older_than {
+            Some(period)
+                if period.ends_with("hours")
+                    || period.ends_with("days")
+                    || period.ends_with("weeks") =>
+            {
+                Some(TaskManager::parse_date_string(&format!("-{}", period))?)
+            }
+            Some(date) => Some(TaskManager::parse_date_string(date)?),
+            None => None,
+        };
+        info!("purging tasks deleted before {:?}", deleted_before);
+        let tx = self.conn.transaction()?;
+        let purgeable = get_purgeable(&tx, &deleted_before)?;
+        tx.commit()?;
+        if purgeable.is_empty() {
+            return Ok(vec![]);
+        }
+        self.auto_backup("purge")?;
+
+        let tx = self.conn.transaction()?;
+        let task_ids: Vec<i64> = get_purgeable(&tx, &deleted_before)?
+            .iter()
+            .map(|task| task.id)
+            .collect();
+        // The history goes with the purged tasks, so it's taken back with them
+        let before = journal::snapshot_with_history(&tx, &task_ids)?;
+        let tasks = purge(&tx, &deleted_before)?;
+        if !tasks.is_empty() {
+            let after = journal::snapshot_with_history(&tx, &task_ids)?;
+            journal::record(&tx, "purge", &before, &after)?;
+        }
+        tx.commit()?;
+        Ok(tasks)
+    }
+
+    // Move the tasks that were completed more than `older_than_days` ago
+    // into the archive, `archive_after_days` of the setting is used when
+    // it's not provided.
+    pub fn archive(&mut self, older_than_days: &Option<i64>) -> Result<Vec<Task>, CoreError> {
+        let days = match older_than_days {
+            Some(days) => *days,
+            None => match self.setting.get("archive_after_days") {
+                Some(days) => days.trim().parse::<i64>().map_err(|_| {
+                    CoreError::ArgumentError(format!(
+                        "archive_after_days must be a number of days, got {}",
+                        days
+                    ))
+                })?,
+                None => DEFAULT_ARCHIVE_AFTER_DAYS,
+            },
+        };
+        // The completion time is recorded in UTC
+        let completed_before = (Utc::now() - Duration::days(days))
+            .format("%Y-%m-%d %H:%M:%S")
+            .to_string();
+        info!("archiving tasks completed before {}", completed_before);
+
+        let tx = self.conn.transaction()?;
+        let task_ids = get_archivable(&tx, &completed_before)?;
+        // The subtasks that aren't archived get another parent
+        let mut journal_task_ids = task_ids.clone();
+        for child_id in get_child_ids(&tx, &task_ids)? {
+            if !task_ids.contains(&child_id) {
+                journal_task_ids.push(child_id);
+            }
+        }
+        // The history moves into the archive with the tasks
+        let before = journal::snapshot_with_history(&tx, &journal_task_ids)?;
+        let tasks = archive(&tx, &task_ids)?;
+        if !tasks.is_empty() {
+            let after = journal::snapshot_with_history(&tx, &journal_task_ids)?;
+            journal::record(&tx, "archive", &before, &after)?;
+        }
+        tx.commit()?;
+        Ok(tasks)
+    }
+
+    pub fn unarchive(&mut self, task_ids: &Vec<i64>) -> Result<Vec<Task>, CoreError> {
+        info!("restoring tasks {:?} from the archive", task_ids);
         let tx = self.conn.transaction()?;
-        let tasks = delete(&tx, &task_ids)?;
+        let before = journal::snapshot_with_history(&tx, &task_ids)?;
+        let mut tasks = unarchive(&tx, &task_ids)?;
+        if !tasks.is_empty() {
+            let after = journal::snapshot_with_history(&tx, &task_ids)?;
+            journal::record(&tx, "unarchive", &before, &after)?;
+        }
+        TaskManager::update_working_set(&tx, &mut tasks)?;
         tx.commit()?;
         Ok(tasks)
     }
 
+    // Same filters as `get`, applied to the archived tasks. All contexts
+    // are included when no context is provided.
+    pub fn get_archived(
+        &mut self,
+        priority: &Option<String>,
+        context: &Option<String>,
+        tags: &Vec<String>,
+        date_due: &Option<&str>,
+        date_scheduled: &Option<&str>,
+        task_id: &Option<i64>,
+        not_tags: &Option<Vec<String>>,
+    ) -> Result<Vec<Task>, CoreError> {
+        let date_due = match date_due {
+            Some(date) => Some(TaskManager::parse_date_string(date)?),
+            None => None,
+        };
+        let date_scheduled = match date_scheduled {
+            Some(date) => Some(TaskManager::parse_date_string(date)?),
+            None => None,
+        };
+
+        let tx = self.conn.transaction()?;
+        let tasks = get_archived(&tx)?;
+        tx.commit()?;
+
+        // The labels of the archived tasks are matched with their descendants
+        // like the labels of the other tasks
+        let is_within = |name: &String, ancestor: &String| {
+            is_within_label(&name.to_lowercase(), &ancestor.to_lowercase())
+        };
+        let has_tag = |task: &Task, tag: &String| task.tags.iter().any(|name| is_within(name, tag));
+        let matches = |task: &Task| {
+            task_id.map_or(true, |id| task.id == id)
+                && context
+                    .as_ref()
+                    .map_or(true, |name| is_within(&task.context, name))
+                && priority
+                    .as_ref()
+                    .map_or(true, |name| task.priority.eq_ignore_ascii_case(name))
+                && tags.iter().all(|tag| has_tag(task, tag))
+                && not_tags
+                    .as_ref()
+                    .map_or(true, |tags| !tags.iter().any(|tag| has_tag(task, tag)))
+                && date_due.as_ref().map_or(true, |date| &task.date_due == date)
+                && date_scheduled
+                    .as_ref()
+                    .map_or(true, |date| &task.date_scheduled == date)
+        };
+        Ok(tasks.into_iter().filter(|task| matches(task)).collect())
+    }
+
     pub fn modify(
         &mut self,
         task_ids: &Vec<i64>,
@@ -255,7 +723,14 @@ impl TaskManager {
         repetition_scheduled: &Option<&str>,
         state: &Option<&str>,
         tags_to_remove: &Vec<String>,
+        estimate: &Option<&str>,
+        udas: &Vec<(String, String)>,
+        parent_id: &Option<Option<i64>>,
+        project: &Option<String>,
     ) -> Result<Vec<Task>, CoreError> {
+        let udas = resolve_udas(&self.get_uda_types()?, udas)?;
+        let auto_complete_parent =
+            *state == Some(TASK_STATES[1]) && is_auto_complete_parent(&self.setting)?;
         let mut tx = self.conn.transaction()?;
         if task_ids.is_empty() {
             Err(ArgumentError::InvalidOption(
@@ -266,6 +741,12 @@ impl TaskManager {
             Some(name) => Some(TaskManager::convert_context_name_to_id(&tx, &name, true)?),
             None => None,
         };
+        // An empty name takes the tasks out of their project
+        let project_id = match project.as_deref() {
+            Some("") => Some(None),
+            Some(name) => Some(Some(get_or_create_project(&tx, name)?)),
+            None => None,
+        };
 
         let state_id = match state {
             Some(name) => Some(TaskManager::convert_state_name_to_id(
@@ -317,7 +798,28 @@ impl TaskManager {
             None => None,
         };
 
-        let tasks = modify(
+        let parsed_estimate = match estimate {
+            // Empty string removes the estimate
+            Some("") => Some(None),
+            Some(estimate) => Some(Some(parse_estimate(estimate)?)),
+            None => None,
+        };
+
+        // The parents that get completed with their subtasks are in the
+        // journal too
+        let mut changed_task_ids = task_ids.clone();
+        if auto_complete_parent {
+            changed_task_ids.extend(get_ancestor_ids(&tx, task_ids)?);
+        }
+        let mut journal_task_ids = journal::with_dependents(&tx, &changed_task_ids)?;
+        // A state change can stop the time tracking of another task
+        if let Some(task_id) = get_tracked_task_id(&tx)? {
+            if state.is_some() && !journal_task_ids.contains(&task_id) {
+                journal_task_ids.push(task_id);
+            }
+        }
+        let before = journal::snapshot(&tx, &journal_task_ids)?;
+        let mut tasks = modify(
             &mut tx,
             &task_ids,
             &body,
@@ -330,8 +832,301 @@ impl TaskManager {
             &repetition_scheduled,
             &state_id,
             tag_ids_to_remove,
+            &parsed_estimate,
+            &udas,
+            &parent_id,
+            &project_id,
         )?;
+        if auto_complete_parent {
+            let mut completed_task_ids = task_ids.clone();
+            loop {
+                let parent_ids = get_completable_parent_ids(&tx, &completed_task_ids)?;
+                if parent_ids.is_empty() {
+                    break;
+                }
+                info!("Completing the parents {:?} of the completed subtasks", parent_ids);
+                tasks.extend(modify(
+                    &mut tx,
+                    &parent_ids,
+                    &None,
+                    &None,
+                    &None,
+                    vec![],
+                    &None,
+                    &None,
+                    &None,
+                    &None,
+                    &state_id,
+                    vec![],
+                    &None,
+                    &vec![],
+                    &None,
+                    &None,
+                )?);
+                completed_task_ids = parent_ids;
+            }
+        }
+        if !tasks.is_empty() {
+            let after = journal::snapshot(&tx, &journal_task_ids)?;
+            journal::record(&tx, "modify", &before, &after)?;
+        }
+        TaskManager::update_working_set(&tx, &mut tasks)?;
+        tx.commit()?;
+        Ok(tasks)
+    }
+
+    // Turn UUIDs or unique UUID prefixes into task ids
+    pub fn get_task_ids_by_uuid(&mut self, uuids: &Vec<String>) -> Result<Vec<i64>, CoreError> {
+        let tx = self.conn.transaction()?;
+        let mut task_ids = vec![];
+        for uuid in uuids.iter() {
+            task_ids.push(get_task_id_by_uuid(&tx, uuid)?);
+        }
+        tx.commit()?;
+        Ok(task_ids)
+    }
+
+    // Turn the short display ids of the pending tasks into task ids
+    pub fn get_task_ids_by_display_id(
+        &mut self,
+        display_ids: &Vec<i64>,
+    ) -> Result<Vec<i64>, CoreError> {
+        let tx = self.conn.transaction()?;
+        let task_ids = get_task_ids_by_display_id(&tx, display_ids)?;
+        tx.commit()?;
+        Ok(task_ids)
+    }
+
+    // Check the database for inconsistencies and repair them when `fix` is
+    // set, the repairs can be undone
+    pub fn doctor(&mut self, fix: bool) -> Result<Vec<Problem>, CoreError> {
+        let tx = self.conn.transaction()?;
+        let problems = diagnose(&tx, false)?;
+        if !fix || problems.is_empty() {
+            return Ok(problems);
+        }
+
+        let mut task_ids: Vec<i64> = problems
+            .iter()
+            .flat_map(|problem| problem.task_ids.clone())
+            .collect();
+        task_ids.sort();
+        task_ids.dedup();
+        let before = journal::snapshot(&tx, &task_ids)?;
+        let problems = diagnose(&tx, true)?;
+        journal::record(&tx, "doctor", &before, &journal::snapshot(&tx, &task_ids)?)?;
+        sync_working_set(&tx, false)?;
+        tx.commit()?;
+        Ok(problems)
+    }
+
+    // Every recorded change of the task, oldest first
+    pub fn history(&mut self, task_id: i64) -> Result<Vec<TaskChange>, CoreError> {
+        let tx = self.conn.transaction()?;
+        if get_base(&tx, &Filter::task_id(&task_id))?.is_empty() {
+            return Err(CoreError::ArgumentError(format!(
+                "Task {} doesn't exist",
+                task_id
+            )));
+        }
+        let changes = get_history(&tx, task_id)?;
+        tx.commit()?;
+        Ok(changes)
+    }
+
+    // Full-text search over the body and the annotation, the matched words
+    // in the snippets are wrapped by `highlight_start` and `highlight_end`.
+    pub fn search(
+        &mut self,
+        query: &str,
+        highlight_start: &str,
+        highlight_end: &str,
+    ) -> Result<Vec<SearchResult>, CoreError> {
+        let tx = self.conn.transaction()?;
+        let results = search(&tx, query, highlight_start, highlight_end)?;
         tx.commit()?;
+        Ok(results)
+    }
+
+    // Take a snapshot of the database, then delete the ones that the
+    // rotation doesn't keep
+    pub fn backup(&mut self) -> Result<Backup, CoreError> {
+        let dir = self.require_backup_dir()?;
+        let snapshot = backup::backup(&self.conn, &dir, "manual")?;
+        backup::rotate(&dir, &self.setting)?;
+        Ok(snapshot)
+    }
+
+    // Take a snapshot before an operation that removes data, unless the
+    // setting disables it or there's nowhere to keep it
+    pub fn auto_backup(&mut self, reason: &str) -> Result<Option<Backup>, CoreError> {
+        let dir = match get_backup_dir(&self.setting) {
+            Some(dir) if is_auto_backup(&self.setting) => dir,
+            _ => return Ok(None),
+        };
+        let snapshot = backup::backup(&self.conn, &dir, reason)?;
+        backup::rotate(&dir, &self.setting)?;
+        Ok(Some(snapshot))
+    }
+
+    // The snapshots, the latest first
+    pub fn get_backups(&mut self) -> Result<Vec<Backup>, CoreError> {
+        match get_backup_dir(&self.setting) {
+            Some(dir) => backup::get_backups(&dir),
+            None => Ok(vec![]),
+        }
+    }
+
+    // Replace the database with the snapshot. The current database is kept
+    // as a snapshot first, it's returned.
+    pub fn restore_backup(&mut self, name: &str) -> Result<Option<Backup>, CoreError> {
+        let dir = self.require_backup_dir()?;
+        if !backup::get_backups(&dir)?.iter().any(|snapshot| snapshot.name == name) {
+            return Err(CoreError::ArgumentError(format!(
+                "Snapshot {} doesn't exist",
+                name
+            )));
+        }
+        // Rotated once restored, so the rotation can't remove the snapshot
+        let snapshot = if is_auto_backup(&self.setting) {
+            Some(backup::backup(&self.conn, &dir, "restore")?)
+        } else {
+            None
+        };
+        backup::restore(&mut self.conn, &dir, name)?;
+        // The snapshot may be older than the schema or have another key
+        self.create_table_if_needed(DEFAULT_CONTEXT)?;
+        backup::rotate(&dir, &self.setting)?;
+        Ok(snapshot)
+    }
+
+    fn require_backup_dir(&self) -> Result<PathBuf, CoreError> {
+        get_backup_dir(&self.setting).ok_or_else(|| {
+            CoreError::BackupError(String::from(
+                "The database is in memory, set backup_dir to keep its snapshots",
+            ))
+        })
+    }
+
+    // Whether the database is encrypted, whether or not it's unlocked
+    pub fn is_encrypted(&mut self) -> Result<bool, CoreError> {
+        encryption::is_enabled(&self.conn)
+    }
+
+    // Encrypt the bodies, the annotations and the notes with a key derived
+    // from the passphrase. Like rotating and disabling it, it clears the
+    // undo history. Returns how many snapshots were replaced, see
+    // `replace_backups`.
+    pub fn enable_encryption(&mut self, passphrase: &str) -> Result<usize, CoreError> {
+        self.change_key(|tx, key| encryption::enable(tx, key, passphrase))?;
+        self.setting
+            .insert(PASSPHRASE_SETTING.to_owned(), passphrase.to_owned());
+        self.replace_backups()
+    }
+
+    // Encrypt the database again with a key derived from the new passphrase
+    pub fn rotate_encryption_key(&mut self, passphrase: &str) -> Result<usize, CoreError> {
+        self.change_key(|tx, key| encryption::rotate(tx, key, passphrase))?;
+        self.setting
+            .insert(PASSPHRASE_SETTING.to_owned(), passphrase.to_owned());
+        self.replace_backups()
+    }
+
+    pub fn disable_encryption(&mut self) -> Result<(), CoreError> {
+        self.change_key(encryption::disable)?;
+        self.setting.remove(PASSPHRASE_SETTING);
+        Ok(())
+    }
+
+    // The snapshots taken before the key changed hold the plain text or the
+    // text encrypted with the old key, they're replaced by a snapshot with
+    // the new key
+    fn replace_backups(&mut self) -> Result<usize, CoreError> {
+        match get_backup_dir(&self.setting) {
+            Some(dir) => backup::replace(&self.conn, &dir, "encryption"),
+            None => Ok(0),
+        }
+    }
+
+    fn change_key<F>(&mut self, change: F) -> Result<(), CoreError>
+    where
+        F: FnOnce(&Transaction, &KeyState) -> Result<KeyState, CoreError>,
+    {
+        let tx = self.conn.transaction()?;
+        let key = load_key(&tx, &self.setting)?;
+        let new_key = change(&tx, &key)?;
+        // The index keeps its own copy of the text, it's rebuilt from the
+        // converted tasks
+        tx.execute("INSERT INTO task_fts (task_fts) VALUES ('rebuild')", [])?;
+        tx.commit()?;
+        register_functions(&self.conn, &new_key)?;
+        // The text encrypted with the old key, or not at all, stays in the
+        // free pages of the file until it's rebuilt
+        self.conn.execute_batch("VACUUM")?;
+        Ok(())
+    }
+
+    // Revert the latest add, modify, delete or annotation. The changed
+    // tasks are returned, tasks that don't exist anymore are returned as
+    // they were before the undo.
+    pub fn undo(&mut self) -> Result<Vec<Task>, CoreError> {
+        let tx = self.conn.transaction()?;
+        let entry = match journal::next_undo(&tx)? {
+            Some(entry) => entry,
+            None => return Err(CoreError::ArgumentError(String::from("Nothing to undo"))),
+        };
+        let mut tasks = TaskManager::replay_journal_entry(&tx, &entry, journal::undo)?;
+        TaskManager::update_working_set(&tx, &mut tasks)?;
+        tx.commit()?;
+        Ok(tasks)
+    }
+
+    // Apply the change that was undone most recently
+    pub fn redo(&mut self) -> Result<Vec<Task>, CoreError> {
+        let tx = self.conn.transaction()?;
+        let entry = match journal::next_redo(&tx)? {
+            Some(entry) => entry,
+            None => return Err(CoreError::ArgumentError(String::from("Nothing to redo"))),
+        };
+        let mut tasks = TaskManager::replay_journal_entry(&tx, &entry, journal::redo)?;
+        TaskManager::update_working_set(&tx, &mut tasks)?;
+        tx.commit()?;
+        Ok(tasks)
+    }
+
+    fn replay_journal_entry(
+        tx: &Transaction,
+        entry: &JournalEntry,
+        replay: fn(&Transaction, &JournalEntry) -> Result<(), CoreError>,
+    ) -> Result<Vec<Task>, CoreError> {
+        // The ids of the tasks can be taken by other tasks since the entry
+        // was recorded
+        let entry = entry.resolve(tx)?;
+        let task_ids = entry.task_ids();
+        let selected = Filter::selected(Selection::Task);
+        Selection::Task.fill(tx, &task_ids)?;
+        let mut previous_tasks: HashMap<i64, Task> =
+            get_base(tx, &selected)?.into_iter().map(|task| (task.id, task)).collect();
+
+        replay(tx, &entry)?;
+
+        let mut current_tasks: HashMap<i64, Task> =
+            get_base(tx, &selected)?.into_iter().map(|task| (task.id, task)).collect();
+        // Undoing a change is a change of its own in the history
+        for task_id in task_ids.iter() {
+            if let (Some(previous), Some(current)) =
+                (previous_tasks.get(task_id), current_tasks.get(task_id))
+            {
+                record_changes(tx, Some(previous), current)?;
+            }
+        }
+        let mut tasks = vec![];
+        for task_id in task_ids.iter() {
+            let task = current_tasks.remove(task_id).or_else(|| previous_tasks.remove(task_id));
+            if let Some(task) = task {
+                tasks.push(task);
+            }
+        }
         Ok(tasks)
     }
 
@@ -570,17 +1365,26 @@ impl TaskManager {
         Ok(parsed_date_string.format("%Y-%m-%d %H:%M:%S").to_string())
     }
 
+    // Tasks that were added, completed or deleted join or leave the working
+    // set, the display ids of the other tasks stay the same.
+    fn update_working_set(tx: &Transaction, tasks: &mut Vec<Task>) -> Result<(), CoreError> {
+        sync_working_set(tx, false)?;
+        attach_display_ids(tx, tasks)
+    }
+
     fn create_table_if_needed(&mut self, context: [&'static str; 1]) -> Result<(), CoreError> {
-        self.conn.execute(CREATE_TASK_TABLE_QUERY, [])?;
-        self.conn.execute(CREATE_TAG_TABLE_QUERY, [])?;
-        self.conn.execute(CREATE_TASK_TAG_TABLE_QUERY, [])?;
-        self.conn.execute(CREATE_DEPENDENCY_TABLE_QUERY, [])?;
-        self.conn.execute(CREATE_CONTEXT_TABLE_QUERY, [])?;
-        self.conn.execute(CREATE_TASK_CONTEXT_TABLE_QUERY, [])?;
-        self.conn.execute(CREATE_STATE_TABLE_QUERY, [])?;
-        self.conn.execute(CREATE_TASK_STATE_TABLE_QUERY, [])?;
-        self.conn.execute(CREATE_PRIORITY_TABLE_QUERY, [])?;
-        self.conn.execute(CREATE_PRIORITY_TASK_TABLE_QUERY, [])?;
+        // Create the tables for new databases and upgrade the existing ones,
+        // the existing ones are kept as they were in a snapshot
+        let is_new: bool = self.conn.query_row(
+            "SELECT COUNT(*) = 0 FROM sqlite_master WHERE type = 'table' AND name = 'task'",
+            [],
+            |row| row.get(0),
+        )?;
+        if !is_new && current_version(&self.conn)? < latest_version(MIGRATIONS) {
+            self.auto_backup("migration")?;
+        }
+        migrate(&mut self.conn)?;
+        register_functions(&self.conn, &load_key(&self.conn, &self.setting)?)?;
 
         let tx = self.conn.transaction()?;
         {
@@ -606,17 +1410,32 @@ impl TaskManager {
     }
 
     // Create a new context and return the id
+    // The parents of a dotted context are created with it, so that they can
+    // be filtered on
     fn create_context(tx: &Transaction, context_name: &String) -> Result<i64, CoreError> {
+        let context_name = context_name.trim();
+        if !is_valid_label_name(context_name) {
+            return Err(ArgumentError::InvalidContext(context_name.to_string()))?;
+        }
         let mut insert_into_context =
             tx.prepare("INSERT OR IGNORE INTO context (name) VALUES (:name)")?;
-        insert_into_context.execute(named_params! {":name": context_name.trim()})?;
+        for name in get_label_ancestors(context_name).into_iter().chain([context_name]) {
+            insert_into_context.execute(named_params! {":name": name})?;
+        }
         info!("Created context {}", context_name);
         Ok(tx.last_insert_rowid())
     }
 
+    // Like the contexts, the parents of a dotted tag are created with it
     fn create_tag(tx: &Transaction, tag_name: &String) -> Result<i64, CoreError> {
+        let tag_name = tag_name.trim();
+        if !is_valid_label_name(tag_name) {
+            return Err(ArgumentError::InvalidTag(tag_name.to_string()))?;
+        }
         let mut insert_into_tag = tx.prepare("INSERT OR IGNORE INTO tag (name) VALUES (:name)")?;
-        insert_into_tag.execute(named_params! {":name": tag_name.trim()})?;
+        for name in get_label_ancestors(tag_name).into_iter().chain([tag_name]) {
+            insert_into_tag.execute(named_params! {":name": name})?;
+        }
         info!("Added a new tag: {}", tag_name);
         Ok(tx.last_insert_rowid())
     }
@@ -1,3 +1,27 @@
+use rusqlite::Result;
+use std::collections::HashMap;
+
+use crate::core::Operation;
+use crate::db::task_helper::Task;
+use crate::db::task_manager::TaskManager;
+use crate::error::CoreError;
+use crate::operation::{execute, Add};
+
+// An in-memory database with the default contexts, shared by the tests
+pub fn get_setting() -> HashMap<String, String> {
+    let mut setting = HashMap::new();
+    setting.insert("db_path".to_owned(), ":memory:".to_owned());
+    setting.insert("context".to_owned(), "inbox, Work, Life".to_owned());
+    return setting;
+}
+
+// Add a task with nothing but its body
+pub fn add_task(database_manager: &mut TaskManager, body: &str) -> Result<Task, CoreError> {
+    let mut operation = Add::new_with_task_manager(body, database_manager);
+    execute(&mut operation)?;
+    Ok(operation.get_result()[0].clone())
+}
+
 #[cfg(test)]
 mod test_add;
 
@@ -18,3 +42,69 @@ mod test_view;
 
 #[cfg(test)]
 mod test_agenda;
+
+#[cfg(test)]
+mod test_migration;
+
+#[cfg(test)]
+mod test_journal;
+
+#[cfg(test)]
+mod test_history;
+
+#[cfg(test)]
+mod test_archive;
+
+#[cfg(test)]
+mod test_search;
+
+#[cfg(test)]
+mod test_query_builder;
+
+#[cfg(test)]
+mod test_note;
+
+#[cfg(test)]
+mod test_uuid;
+
+#[cfg(test)]
+mod test_working_set;
+
+#[cfg(test)]
+mod test_doctor;
+
+#[cfg(test)]
+mod test_bulk;
+
+#[cfg(test)]
+mod test_timestamps;
+
+#[cfg(test)]
+mod test_time_tracking;
+
+#[cfg(test)]
+mod test_estimate;
+
+#[cfg(test)]
+mod test_uda;
+
+#[cfg(test)]
+mod test_subtask;
+
+#[cfg(test)]
+mod test_project;
+
+#[cfg(test)]
+mod test_hierarchy;
+
+#[cfg(test)]
+mod test_attachment;
+
+#[cfg(test)]
+mod test_checklist;
+
+#[cfg(test)]
+mod test_encryption;
+
+#[cfg(test)]
+mod test_backup;
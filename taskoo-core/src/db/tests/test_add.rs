@@ -21,7 +21,7 @@ fn get_setting() -> HashMap<String, String> {
 
 #[test]
 fn test_add_simple() -> Result<(), CoreError> {
-    let mut database_manager = TaskManager::new(&get_setting());
+    let mut database_manager = TaskManager::new(&get_setting())?;
 
     let mut operation = Add::new_with_task_manager("Test Body", &mut database_manager);
     execute(&mut operation)?;
@@ -57,7 +57,7 @@ fn test_add_simple() -> Result<(), CoreError> {
 
 #[test]
 fn test_add_complex() -> Result<(), CoreError> {
-    let mut database_manager = TaskManager::new(&get_setting());
+    let mut database_manager = TaskManager::new(&get_setting())?;
 
     let mut operation = Add::new_with_task_manager("Test Body", &mut database_manager);
     operation.priority = Some(String::from("H"));
@@ -103,7 +103,7 @@ fn test_add_complex() -> Result<(), CoreError> {
 // Performing the add query should also add the tag
 #[test]
 fn test_add_exist_tag() -> Result<(), CoreError> {
-    let mut database_manager = TaskManager::new(&get_setting());
+    let mut database_manager = TaskManager::new(&get_setting())?;
 
     let mut operation = Add::new_with_task_manager("Test Body", &mut database_manager);
     operation.priority = Some(String::from("H"));
@@ -131,7 +131,7 @@ fn test_add_exist_tag() -> Result<(), CoreError> {
 // Performing the add query should also add the tag
 #[test]
 fn test_add_scheduled_at_days() -> Result<(), CoreError> {
-    let mut database_manager = TaskManager::new(&get_setting());
+    let mut database_manager = TaskManager::new(&get_setting())?;
 
     let start = Local::today() + Duration::days(2);
 
@@ -169,7 +169,7 @@ fn test_add_scheduled_at_days() -> Result<(), CoreError> {
 // Performing the add query should also add the tag
 #[test]
 fn test_add_scheduled_at_hours() -> Result<(), CoreError> {
-    let mut database_manager = TaskManager::new(&get_setting());
+    let mut database_manager = TaskManager::new(&get_setting())?;
 
     let start = (Local::now() + Duration::hours(11))
         .format("%Y-%m-%d %H:%M:%S")
@@ -210,7 +210,7 @@ fn test_add_scheduled_at_hours() -> Result<(), CoreError> {
 
 #[test]
 fn test_add_scheduled_at_weeks() -> Result<(), CoreError> {
-    let mut database_manager = TaskManager::new(&get_setting());
+    let mut database_manager = TaskManager::new(&get_setting())?;
 
     let start = Local::today() + Duration::weeks(1);
     let mut operation = Add::new_with_task_manager("Test Body", &mut database_manager);
@@ -246,7 +246,7 @@ fn test_add_scheduled_at_weeks() -> Result<(), CoreError> {
 
 #[test]
 fn test_add_scheduled_at_raw_timestamp() -> Result<(), CoreError> {
-    let mut database_manager = TaskManager::new(&get_setting());
+    let mut database_manager = TaskManager::new(&get_setting())?;
 
     let mut operation = Add::new_with_task_manager("Test Body", &mut database_manager);
     operation.priority = Some(String::from("H"));
@@ -275,7 +275,7 @@ fn test_add_scheduled_at_raw_timestamp() -> Result<(), CoreError> {
 
 #[test]
 fn test_add_scheduled_at_tmr() -> Result<(), CoreError> {
-    let mut database_manager = TaskManager::new(&get_setting());
+    let mut database_manager = TaskManager::new(&get_setting())?;
 
     let expected = Local::today() + Duration::days(1);
 
@@ -310,7 +310,7 @@ fn test_add_scheduled_at_tmr() -> Result<(), CoreError> {
 
 #[test]
 fn test_add_scheduled_at_today() -> Result<(), CoreError> {
-    let mut database_manager = TaskManager::new(&get_setting());
+    let mut database_manager = TaskManager::new(&get_setting())?;
 
     let expected = Local::today() + Duration::days(0);
 
@@ -344,7 +344,7 @@ fn test_add_scheduled_at_today() -> Result<(), CoreError> {
 }
 #[test]
 fn test_add_completed_task() -> Result<(), CoreError> {
-    let mut database_manager = TaskManager::new(&get_setting());
+    let mut database_manager = TaskManager::new(&get_setting())?;
 
     let mut operation = Add::new_with_task_manager("Test Body", &mut database_manager);
     operation.set_state_to_completed();
@@ -361,7 +361,7 @@ fn test_add_completed_task() -> Result<(), CoreError> {
 
 #[test]
 fn test_add_repeat_scheduled_task() -> Result<(), CoreError> {
-    let mut database_manager = TaskManager::new(&get_setting());
+    let mut database_manager = TaskManager::new(&get_setting())?;
 
     let mut operation = Add::new_with_task_manager("Test Body", &mut database_manager);
     operation.tags = vec!["Completed".to_owned()];
@@ -394,6 +394,10 @@ fn test_add_repeat_scheduled_task() -> Result<(), CoreError> {
             &None,
             &Some("completed"),
             &vec![],
+            &None,
+            &vec![],
+            &None,
+            &None,
         )
         .unwrap();
 
@@ -411,7 +415,7 @@ fn test_add_repeat_scheduled_task() -> Result<(), CoreError> {
 
 #[test]
 fn test_add_repeat_due_task() -> Result<(), CoreError> {
-    let mut database_manager = TaskManager::new(&get_setting());
+    let mut database_manager = TaskManager::new(&get_setting())?;
 
     let mut operation = Add::new_with_task_manager("Test Body", &mut database_manager);
     operation.tags = vec!["Completed".to_owned()];
@@ -447,6 +451,10 @@ fn test_add_repeat_due_task() -> Result<(), CoreError> {
             &None,
             &Some("completed"),
             &vec![],
+            &None,
+            &vec![],
+            &None,
+            &None,
         )
         .unwrap();
 
@@ -464,7 +472,7 @@ fn test_add_repeat_due_task() -> Result<(), CoreError> {
 
 #[test]
 fn test_add_annotation() -> Result<(), CoreError> {
-    let mut database_manager = TaskManager::new(&get_setting());
+    let mut database_manager = TaskManager::new(&get_setting())?;
 
     let mut operation = Add::new_with_task_manager("Test Body", &mut database_manager);
     execute(&mut operation)?;
@@ -491,7 +499,7 @@ fn test_add_annotation() -> Result<(), CoreError> {
 
 #[test]
 fn test_add_dependency() -> Result<(), CoreError> {
-    let mut database_manager = TaskManager::new(&get_setting());
+    let mut database_manager = TaskManager::new(&get_setting())?;
     let mut operation = Add::new_with_task_manager("Test Body", &mut database_manager);
     execute(&mut operation)?;
 
@@ -510,7 +518,7 @@ fn test_add_dependency() -> Result<(), CoreError> {
 
 #[test]
 fn test_add_dependency_parent_not_exist() -> Result<(), CoreError> {
-    let mut database_manager = TaskManager::new(&get_setting());
+    let mut database_manager = TaskManager::new(&get_setting())?;
 
     let mut operation = Add::new_with_task_manager("Test Body 2", &mut database_manager);
     operation.parent_task_ids = Some(vec![1, 2]);
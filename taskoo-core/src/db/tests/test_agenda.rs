@@ -17,7 +17,7 @@ fn get_setting() -> HashMap<String, String> {
 
 #[test]
 fn test_agenda_single_day_due() -> Result<(), CoreError> {
-    let mut database_manager = TaskManager::new(&get_setting());
+    let mut database_manager = TaskManager::new(&get_setting())?;
 
     let mut operation = Add::new_with_task_manager("Test Body", &mut database_manager);
     operation.date_due = Some("2020-11-14");
@@ -41,7 +41,7 @@ fn test_agenda_single_day_due() -> Result<(), CoreError> {
 
 #[test]
 fn test_agenda_single_day_due_and_overdue() -> Result<(), CoreError> {
-    let mut database_manager = TaskManager::new(&get_setting());
+    let mut database_manager = TaskManager::new(&get_setting())?;
 
     let mut operation = Add::new_with_task_manager("Test Body", &mut database_manager);
     operation.date_due = Some("2020-11-15");
@@ -69,7 +69,7 @@ fn test_agenda_single_day_due_and_overdue() -> Result<(), CoreError> {
 
 #[test]
 fn test_agenda_single_day_scheduled_and_overscheduled() -> Result<(), CoreError> {
-    let mut database_manager = TaskManager::new(&get_setting());
+    let mut database_manager = TaskManager::new(&get_setting())?;
 
     let mut operation = Add::new_with_task_manager("Test Body", &mut database_manager);
     operation.date_due = Some("2020-11-15");
@@ -97,7 +97,7 @@ fn test_agenda_single_day_scheduled_and_overscheduled() -> Result<(), CoreError>
 
 #[test]
 fn test_agenda_single_day_due_and_scheduled() -> Result<(), CoreError> {
-    let mut database_manager = TaskManager::new(&get_setting());
+    let mut database_manager = TaskManager::new(&get_setting())?;
 
     let mut operation = Add::new_with_task_manager("Test Body", &mut database_manager);
     operation.date_due = Some("2020-11-15");
@@ -121,7 +121,7 @@ fn test_agenda_single_day_due_and_scheduled() -> Result<(), CoreError> {
 
 #[test]
 fn test_agenda_multiple_day_due_and_scheduled() -> Result<(), CoreError> {
-    let mut database_manager = TaskManager::new(&get_setting());
+    let mut database_manager = TaskManager::new(&get_setting())?;
 
     let mut operation = Add::new_with_task_manager("Test Body", &mut database_manager);
     operation.date_due = Some("2020-11-15");
@@ -155,7 +155,7 @@ fn test_agenda_multiple_day_due_and_scheduled() -> Result<(), CoreError> {
 
 #[test]
 fn test_agenda_filters_by_context() -> Result<(), CoreError> {
-    let mut database_manager = TaskManager::new(&get_setting());
+    let mut database_manager = TaskManager::new(&get_setting())?;
 
     let mut operation = Add::new_with_task_manager("Work Task", &mut database_manager);
     operation.context = Some("work".to_string());
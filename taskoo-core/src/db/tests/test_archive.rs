@@ -0,0 +1,229 @@
+use rusqlite::Result;
+
+use crate::command::{SimpleCommand, TagCommand};
+use crate::core::Operation;
+use crate::db::task_manager::TaskManager;
+use crate::error::CoreError;
+use crate::operation::{Add, Get, execute};
+use super::get_setting;
+
+fn complete(database_manager: &mut TaskManager, task_ids: &Vec<i64>) -> Result<(), CoreError> {
+    database_manager.modify(
+        task_ids,
+        &None,
+        &None,
+        &None,
+        &vec![],
+        &None,
+        &None,
+        &None,
+        &None,
+        &Some("completed"),
+        &vec![],
+        &None,
+        &vec![],
+        &None,
+        &None,
+    )?;
+    Ok(())
+}
+
+fn get_all(database_manager: &mut TaskManager) -> Vec<crate::db::task_helper::Task> {
+    database_manager
+        .get(&None, &None, &vec![], &None, &None, &None, &None)
+        .unwrap()
+}
+
+fn get_all_archived(database_manager: &mut TaskManager) -> Vec<crate::db::task_helper::Task> {
+    database_manager
+        .get_archived(&None, &None, &vec![], &None, &None, &None, &None)
+        .unwrap()
+}
+
+// Pretend the tasks were completed `days` ago
+fn set_completed_days_ago(database_manager: &mut TaskManager, days: i64) -> Result<(), CoreError> {
+    database_manager.conn.execute(
+        "UPDATE task_history SET changed_at = datetime('now', :modifier)
+        WHERE field = 'state' AND new_value = 'completed'",
+        rusqlite::named_params! {":modifier": format!("-{} days", days)},
+    )?;
+    database_manager.conn.execute(
+        "UPDATE task SET completed_at = datetime('now', :modifier)
+        WHERE completed_at IS NOT NULL",
+        rusqlite::named_params! {":modifier": format!("-{} days", days)},
+    )?;
+    Ok(())
+}
+
+#[test]
+fn test_archive_completed_tasks() -> Result<(), CoreError> {
+    let mut database_manager = TaskManager::new(&get_setting())?;
+    let mut operation = Add::new_with_task_manager("Old", &mut database_manager);
+    operation.tags = vec!["tag1".to_owned()];
+    execute(&mut operation)?;
+    let mut operation = Add::new_with_task_manager("Ready", &mut database_manager);
+    execute(&mut operation)?;
+
+    complete(&mut database_manager, &vec![1])?;
+    set_completed_days_ago(&mut database_manager, 40)?;
+    let mut operation = Add::new_with_task_manager("Recent", &mut database_manager);
+    execute(&mut operation)?;
+    complete(&mut database_manager, &vec![3])?;
+
+    let archived = database_manager.archive(&None)?;
+    assert_eq!(archived.len(), 1);
+    assert_eq!(archived[0].id, 1);
+
+    let tasks = get_all(&mut database_manager);
+    assert_eq!(tasks.iter().map(|task| task.id).collect::<Vec<i64>>(), vec![2, 3]);
+
+    let archived = get_all_archived(&mut database_manager);
+    assert_eq!(archived.len(), 1);
+    assert_eq!(archived[0].body, "Old");
+    assert_eq!(archived[0].tags, vec!["tag1"]);
+    assert!(archived[0].is_completed());
+
+    // The rows are moved out of the task tables
+    let count: i64 =
+        database_manager
+            .conn
+            .query_row("SELECT COUNT(*) FROM task_tag", [], |row| row.get(0))?;
+    assert_eq!(count, 0);
+    Ok(())
+}
+
+#[test]
+fn test_archive_older_than_days() -> Result<(), CoreError> {
+    let mut database_manager = TaskManager::new(&get_setting())?;
+    let mut operation = Add::new_with_task_manager("Test Body", &mut database_manager);
+    execute(&mut operation)?;
+    complete(&mut database_manager, &vec![1])?;
+    set_completed_days_ago(&mut database_manager, 5)?;
+
+    assert!(database_manager.archive(&None)?.is_empty());
+    assert_eq!(database_manager.archive(&Some(3))?.len(), 1);
+    Ok(())
+}
+
+#[test]
+fn test_archive_after_days_setting() -> Result<(), CoreError> {
+    let mut setting = get_setting();
+    setting.insert("archive_after_days".to_owned(), "3".to_owned());
+    let mut database_manager = TaskManager::new(&setting)?;
+    let mut operation = Add::new_with_task_manager("Test Body", &mut database_manager);
+    execute(&mut operation)?;
+    complete(&mut database_manager, &vec![1])?;
+    set_completed_days_ago(&mut database_manager, 5)?;
+
+    assert_eq!(database_manager.archive(&None)?.len(), 1);
+    Ok(())
+}
+
+#[test]
+fn test_get_archived_filters() -> Result<(), CoreError> {
+    let mut database_manager = TaskManager::new(&get_setting())?;
+    let mut operation = Add::new_with_task_manager("Work task", &mut database_manager);
+    operation.context = Some("Work".to_owned());
+    operation.tags = vec!["tag1".to_owned()];
+    execute(&mut operation)?;
+    let mut operation = Add::new_with_task_manager("Inbox task", &mut database_manager);
+    execute(&mut operation)?;
+    complete(&mut database_manager, &vec![1, 2])?;
+    database_manager.archive(&Some(0))?;
+
+    let tasks = database_manager.get_archived(
+        &None,
+        &Some("work".to_owned()),
+        &vec![],
+        &None,
+        &None,
+        &None,
+        &None,
+    )?;
+    assert_eq!(tasks.len(), 1);
+    assert_eq!(tasks[0].body, "Work task");
+
+    let tasks = database_manager.get_archived(
+        &None,
+        &None,
+        &vec![],
+        &None,
+        &None,
+        &None,
+        &Some(vec!["tag1".to_owned()]),
+    )?;
+    assert_eq!(tasks.len(), 1);
+    assert_eq!(tasks[0].body, "Inbox task");
+    Ok(())
+}
+
+#[test]
+fn test_get_with_archived() -> Result<(), CoreError> {
+    let mut database_manager = TaskManager::new(&get_setting())?;
+    for body in ["Archived", "Ready"] {
+        let mut operation = Add::new_with_task_manager(body, &mut database_manager);
+        execute(&mut operation)?;
+    }
+    complete(&mut database_manager, &vec![1])?;
+    database_manager.archive(&Some(0))?;
+
+    let mut operation = Get::new_with_store(Some(database_manager));
+    execute(&mut operation)?;
+    assert_eq!(operation.get_result().len(), 1);
+
+    operation.with_archived = true;
+    execute(&mut operation)?;
+    let bodies: Vec<&str> = operation.get_result().iter().map(|task| task.body.as_str()).collect();
+    assert_eq!(bodies, vec!["Ready", "Archived"]);
+    Ok(())
+}
+
+#[test]
+fn test_unarchive() -> Result<(), CoreError> {
+    let mut database_manager = TaskManager::new(&get_setting())?;
+    let mut operation = Add::new_with_task_manager("Parent", &mut database_manager);
+    operation.tags = vec!["tag1".to_owned()];
+    operation.priority = Some("H".to_owned());
+    execute(&mut operation)?;
+    let mut operation = Add::new_with_task_manager("Child", &mut database_manager);
+    operation.parent_task_ids = Some(vec![1]);
+    execute(&mut operation)?;
+    complete(&mut database_manager, &vec![1])?;
+
+    database_manager.archive(&Some(0))?;
+    assert_eq!(get_all(&mut database_manager)[0].parent_task_ids.len(), 0);
+
+    // The tag isn't used anymore, so it can be cleaned
+    TagCommand::new_with_manager(&mut database_manager).delete(vec!["tag1".to_owned()])?;
+
+    // Archived ids are never reused
+    let mut operation = Add::new_with_task_manager("New", &mut database_manager);
+    execute(&mut operation)?;
+    assert_eq!(get_all(&mut database_manager).last().unwrap().id, 3);
+
+    let restored = database_manager.unarchive(&vec![1])?;
+    assert_eq!(restored.len(), 1);
+    assert_eq!(restored[0].body, "Parent");
+    assert_eq!(restored[0].tags, vec!["tag1"]);
+    assert_eq!(restored[0].priority, "h");
+    assert!(restored[0].is_completed());
+    assert!(get_all_archived(&mut database_manager).is_empty());
+
+    let child = database_manager.get(&None, &None, &vec![], &None, &None, &Some(2), &None)?;
+    assert_eq!(child[0].parent_task_ids, vec!["1"]);
+    Ok(())
+}
+
+#[test]
+fn test_undo_archive() -> Result<(), CoreError> {
+    let mut database_manager = TaskManager::new(&get_setting())?;
+    let mut operation = Add::new_with_task_manager("Test Body", &mut database_manager);
+    execute(&mut operation)?;
+    complete(&mut database_manager, &vec![1])?;
+    database_manager.archive(&Some(0))?;
+
+    database_manager.undo()?;
+    assert_eq!(get_all(&mut database_manager).len(), 1);
+    assert!(get_all_archived(&mut database_manager).is_empty());
+    Ok(())
+}
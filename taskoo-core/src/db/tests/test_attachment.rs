@@ -0,0 +1,137 @@
+use rusqlite::Result;
+use std::path::PathBuf;
+
+use crate::core::Operation;
+use crate::db::attachment::is_url;
+use crate::db::task_helper::Task;
+use crate::db::task_manager::TaskManager;
+use crate::error::CoreError;
+use crate::operation::{execute, execute_open_attachment, AddAttachment, OpenAttachment};
+use super::{add_task, get_setting};
+
+// A file to attach, in the temporary directory like the migration fixtures
+fn write_file(name: &str, contents: &str) -> PathBuf {
+    let mut path = std::env::temp_dir();
+    path.push(format!("taskoo_{}_{}", std::process::id(), name));
+    std::fs::write(&path, contents).expect("the file to attach is written");
+    path
+}
+
+fn get_task(database_manager: &mut TaskManager, task_id: i64) -> Result<Task, CoreError> {
+    let tasks = database_manager.get(&None, &None, &vec![], &None, &None, &Some(task_id), &None)?;
+    Ok(tasks[0].clone())
+}
+
+#[test]
+fn test_attach_url_and_path() -> Result<(), CoreError> {
+    assert!(is_url("https://example.com/spec"));
+    assert!(is_url("mailto:vendor@example.com"));
+    assert!(!is_url("./spec.pdf"));
+
+    let mut database_manager = TaskManager::new(&get_setting())?;
+    add_task(&mut database_manager, "Review the spec")?;
+    let path = write_file("spec.txt", "The spec");
+
+    database_manager.add_attachment(1, "https://example.com/spec", false)?;
+    let tasks = database_manager.add_attachment(1, path.to_str().unwrap(), false)?;
+    let attachments = &tasks[0].attachments;
+    assert_eq!(attachments.len(), 2);
+    assert!(attachments[0].is_url());
+    assert_eq!(attachments[0].location, "https://example.com/spec");
+    assert_eq!(attachments[1].kind, "path");
+    assert_eq!(PathBuf::from(&attachments[1].location), std::fs::canonicalize(&path).unwrap());
+    assert_eq!(attachments[1].size, None);
+
+    // The metadata is in the JSON of the task
+    let json = serde_json::to_value(&tasks[0]).expect("tasks are serialized");
+    assert_eq!(json["attachments"][0]["kind"], "url");
+
+    assert!(database_manager.add_attachment(1, "https://example.com", true).is_err());
+    assert!(database_manager.add_attachment(1, "/no/such/file", false).is_err());
+    assert!(database_manager.add_attachment(1, "  ", false).is_err());
+    assert!(database_manager.add_attachment(9, "https://example.com", false).is_err());
+
+    // Opening a URL or a path doesn't read anything from the database
+    let (attachment, data) = database_manager.get_attachment(1)?;
+    assert_eq!(attachment.task_id, 1);
+    assert_eq!(data, None);
+    assert!(database_manager.get_attachment(9).is_err());
+    std::fs::remove_file(&path).ok();
+    Ok(())
+}
+
+#[test]
+fn test_copy_and_detach() -> Result<(), CoreError> {
+    let mut database_manager = TaskManager::new(&get_setting())?;
+    add_task(&mut database_manager, "Sign the contract")?;
+    let path = write_file("contract.txt", "Signed");
+
+    let mut operation = AddAttachment::new(1, path.to_str().unwrap().to_string(), true);
+    operation.database_manager = Some(database_manager);
+    execute(&mut operation)?;
+    let attachment = operation.get_result()[0].attachments[0].clone();
+    assert!(attachment.is_copy());
+    assert!(attachment.location.ends_with("contract.txt"));
+    assert_eq!(attachment.size, Some(6));
+
+    // The copy doesn't depend on the file anymore
+    std::fs::remove_file(&path).ok();
+    let database_manager = operation.database_manager.take();
+    let mut operation = OpenAttachment::new(attachment.id);
+    operation.database_manager = database_manager;
+    execute_open_attachment(&mut operation)?;
+    let (_, data) = operation.get_result().clone().unwrap();
+    assert_eq!(data, Some(b"Signed".to_vec()));
+
+    // Undoing the detach brings the copy back
+    let mut database_manager = operation.database_manager.take().unwrap();
+    let tasks = database_manager.delete_attachment(attachment.id)?;
+    assert!(tasks[0].attachments.is_empty());
+    assert!(database_manager.delete_attachment(attachment.id).is_err());
+    database_manager.undo()?;
+    assert_eq!(get_task(&mut database_manager, 1)?.attachments, vec![attachment.clone()]);
+    assert_eq!(database_manager.get_attachment(attachment.id)?.1, Some(b"Signed".to_vec()));
+
+    // The copies go with their task
+    database_manager.delete(&vec![1])?;
+    database_manager.purge(&None)?;
+    let copies: i64 =
+        database_manager.conn.query_row("SELECT COUNT(*) FROM attachment_blob", [], |row| {
+            row.get(0)
+        })?;
+    assert_eq!(copies, 0);
+    Ok(())
+}
+
+#[test]
+fn test_archive_keeps_attachments() -> Result<(), CoreError> {
+    let mut database_manager = TaskManager::new(&get_setting())?;
+    add_task(&mut database_manager, "File the receipts")?;
+    let path = write_file("receipt.txt", "42 EUR");
+    database_manager.add_attachment(1, path.to_str().unwrap(), true)?;
+    std::fs::remove_file(&path).ok();
+
+    database_manager.modify(
+        &vec![1],
+        &None,
+        &None,
+        &None,
+        &vec![],
+        &None,
+        &None,
+        &None,
+        &None,
+        &Some("completed"),
+        &vec![],
+        &None,
+        &vec![],
+        &None,
+        &None,
+    )?;
+    database_manager.archive(&Some(0))?;
+    let restored = database_manager.unarchive(&vec![1])?;
+    assert_eq!(restored[0].attachments.len(), 1);
+    let (_, data) = database_manager.get_attachment(restored[0].attachments[0].id)?;
+    assert_eq!(data, Some(b"42 EUR".to_vec()));
+    Ok(())
+}
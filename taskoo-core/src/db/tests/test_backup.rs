@@ -0,0 +1,226 @@
+use chrono::NaiveDate;
+use rusqlite::{Connection, Result};
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use crate::db::backup::{get_expired, Backup};
+use crate::db::encryption::is_encrypted_text;
+use crate::db::migration::{migrate_to, MIGRATIONS};
+use crate::db::task_manager::TaskManager;
+use crate::error::CoreError;
+use crate::operation::{execute_backup, BackupAction, Backups};
+use super::add_task;
+
+fn fixture_path(name: &str) -> PathBuf {
+    let mut path = std::env::temp_dir();
+    path.push(format!("taskoo_{}_{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&path);
+    std::fs::create_dir_all(&path).unwrap();
+    path
+}
+
+// A database file with its snapshots in the `backups` directory next to it
+fn get_setting(dir: &PathBuf) -> HashMap<String, String> {
+    let mut setting = HashMap::new();
+    setting.insert(
+        "db_path".to_owned(),
+        dir.join("tasks.db").to_str().unwrap().to_owned(),
+    );
+    setting.insert(
+        "backup_dir".to_owned(),
+        dir.join("backups").to_str().unwrap().to_owned(),
+    );
+    setting.insert("context".to_owned(), "inbox, Work, Life".to_owned());
+    return setting;
+}
+
+fn get_bodies(database_manager: &mut TaskManager) -> Vec<String> {
+    database_manager
+        .get(&None, &None, &vec![], &None, &None, &None, &None)
+        .unwrap()
+        .iter()
+        .map(|task| task.body.clone())
+        .collect()
+}
+
+fn get_reasons(database_manager: &mut TaskManager) -> Vec<String> {
+    database_manager
+        .get_backups()
+        .unwrap()
+        .iter()
+        .map(|backup| backup.reason.clone())
+        .collect()
+}
+
+fn snapshot(name: &str) -> Backup {
+    Backup {
+        name: name.to_owned(),
+        reason: String::from("delete"),
+        created_at: String::new(),
+        size: 0,
+    }
+}
+
+#[test]
+fn test_snapshots_before_destructive_operations() -> Result<(), CoreError> {
+    let dir = fixture_path("backup");
+    let mut database_manager = TaskManager::new(&get_setting(&dir))?;
+    add_task(&mut database_manager, "Kept")?;
+    add_task(&mut database_manager, "Deleted")?;
+
+    let mut operation = Backups::new(BackupAction::Create);
+    operation.database_manager = Some(database_manager);
+    execute_backup(&mut operation)?;
+    let manual = operation.get_result()[0].clone();
+    assert_eq!(manual.reason, "manual");
+    assert!(manual.size > 0);
+    let mut database_manager = operation.database_manager.take().unwrap();
+
+    database_manager.delete(&vec![2])?;
+    database_manager.purge(&None)?;
+    assert_eq!(
+        get_reasons(&mut database_manager),
+        vec!["purge", "delete", "manual"]
+    );
+    // Nothing to delete or purge, nothing to keep a snapshot of
+    assert!(database_manager.delete(&vec![2, 9])?.is_empty());
+    assert!(database_manager.purge(&None)?.is_empty());
+    assert_eq!(get_reasons(&mut database_manager).len(), 3);
+    assert_eq!(get_bodies(&mut database_manager), vec!["Kept"]);
+
+    let previous = database_manager.restore_backup(&manual.name)?;
+    assert_eq!(previous.unwrap().reason, "restore");
+    assert_eq!(get_bodies(&mut database_manager), vec!["Kept", "Deleted"]);
+    add_task(&mut database_manager, "Added after the restore")?;
+    assert!(database_manager
+        .restore_backup("20200101-000000-manual")
+        .is_err());
+
+    std::fs::remove_dir_all(&dir).ok();
+    Ok(())
+}
+
+#[test]
+fn test_snapshot_settings() -> Result<(), CoreError> {
+    // There's no directory for the snapshots of a database in memory
+    let mut setting = HashMap::new();
+    setting.insert("db_path".to_owned(), ":memory:".to_owned());
+    let mut database_manager = TaskManager::new(&setting)?;
+    add_task(&mut database_manager, "Deleted")?;
+    database_manager.delete(&vec![1])?;
+    assert!(database_manager.get_backups()?.is_empty());
+    assert!(database_manager.backup().is_err());
+
+    let dir = fixture_path("backup_settings");
+    let mut setting = get_setting(&dir);
+    setting.insert("auto_backup".to_owned(), "false".to_owned());
+    let mut database_manager = TaskManager::new(&setting)?;
+    add_task(&mut database_manager, "Deleted")?;
+    database_manager.delete(&vec![1])?;
+    assert!(database_manager.get_backups()?.is_empty());
+
+    setting.insert("auto_backup".to_owned(), "true".to_owned());
+    setting.insert("backup_keep_last".to_owned(), "2".to_owned());
+    setting.insert("backup_keep_daily".to_owned(), "0".to_owned());
+    let mut database_manager = TaskManager::new(&setting)?;
+    let mut latest = database_manager.backup()?;
+    for _ in 0..3 {
+        latest = database_manager.backup()?;
+    }
+    let backups = database_manager.get_backups()?;
+    assert_eq!(backups.len(), 2);
+    // The latest first, even within the same second
+    assert_eq!(backups[0], latest);
+
+    setting.insert("backup_keep_last".to_owned(), "many".to_owned());
+    let mut database_manager = TaskManager::new(&setting)?;
+    assert!(database_manager.backup().is_err());
+
+    std::fs::remove_dir_all(&dir).ok();
+    Ok(())
+}
+
+#[test]
+fn test_snapshot_before_migration() -> Result<(), CoreError> {
+    let dir = fixture_path("backup_migration");
+    let setting = get_setting(&dir);
+    {
+        let mut conn = Connection::open(&setting["db_path"])?;
+        migrate_to(&mut conn, MIGRATIONS, 10)?;
+    }
+
+    let mut database_manager = TaskManager::new(&setting)?;
+    assert_eq!(get_reasons(&mut database_manager), vec!["migration"]);
+    // Up to date databases are opened without one
+    let mut database_manager = TaskManager::new(&setting)?;
+    assert_eq!(get_reasons(&mut database_manager), vec!["migration"]);
+
+    std::fs::remove_dir_all(&dir).ok();
+    Ok(())
+}
+
+#[test]
+fn test_key_change_replaces_the_snapshots() -> Result<(), CoreError> {
+    let dir = fixture_path("backup_encryption");
+    let mut database_manager = TaskManager::new(&get_setting(&dir))?;
+    add_task(&mut database_manager, "Secret")?;
+    database_manager.backup()?;
+    database_manager.backup()?;
+
+    // The plain text snapshots are gone, the new one is encrypted
+    assert_eq!(database_manager.enable_encryption("first")?, 2);
+    let backups = database_manager.get_backups()?;
+    assert_eq!(get_reasons(&mut database_manager), vec!["encryption"]);
+    let conn = Connection::open(dir.join("backups").join(format!("{}.db", backups[0].name)))?;
+    let body: String = conn.query_row("SELECT body FROM task", [], |row| row.get(0))?;
+    assert!(is_encrypted_text(&body));
+
+    assert_eq!(database_manager.rotate_encryption_key("second")?, 1);
+    let rotated = database_manager.get_backups()?;
+    assert_eq!(rotated.len(), 1);
+    assert_ne!(rotated[0].name, backups[0].name);
+    // The snapshot with the new key restores with it
+    database_manager.restore_backup(&rotated[0].name)?;
+    assert_eq!(get_bodies(&mut database_manager), vec!["Secret"]);
+
+    // The ones taken before disabling it are kept
+    database_manager.disable_encryption()?;
+    assert_eq!(database_manager.get_backups()?.len(), 2);
+
+    std::fs::remove_dir_all(&dir).ok();
+    Ok(())
+}
+
+#[test]
+fn test_rotation_keeps_the_latest_and_daily_snapshots() {
+    let backups = vec![
+        snapshot("20261018-120000-delete"),
+        snapshot("20261018-110000-delete"),
+        snapshot("20261018-100000-delete"),
+        snapshot("20261017-090000-delete"),
+        snapshot("20261017-080000-delete"),
+        snapshot("20261016-070000-delete"),
+        snapshot("20261015-060000-delete"),
+        snapshot("20261014-050000-delete"),
+    ];
+    let today = NaiveDate::from_ymd(2026, 10, 18);
+
+    let expired: Vec<String> = get_expired(&backups, 2, 3, today)
+        .iter()
+        .map(|backup| backup.name.clone())
+        .collect();
+    assert_eq!(
+        expired,
+        vec![
+            "20261018-100000-delete",
+            "20261017-080000-delete",
+            "20261015-060000-delete",
+            "20261014-050000-delete",
+        ]
+    );
+
+    assert_eq!(get_expired(&backups, 10, 7, today).len(), 0);
+    assert_eq!(get_expired(&backups, 0, 0, today).len(), backups.len());
+    // The latest snapshot of every day within the week
+    assert_eq!(get_expired(&backups, 0, 7, today).len(), 3);
+}
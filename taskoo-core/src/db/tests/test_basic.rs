@@ -15,7 +15,7 @@ fn get_setting() -> HashMap<String, String> {
 
 #[test]
 fn test_create_table_if_needed() -> Result<(), DbError> {
-    let database_manager = TaskManager::new(&get_setting());
+    let database_manager = TaskManager::new(&get_setting())?;
 
     let mut tables = database_manager
         .conn
@@ -33,6 +33,7 @@ fn test_create_table_if_needed() -> Result<(), DbError> {
     assert_eq!(
         names,
         [
+            "schema_version",
             "task",
             "tag",
             "task_tag",
@@ -42,7 +43,26 @@ fn test_create_table_if_needed() -> Result<(), DbError> {
             "state",
             "task_state",
             "priority",
-            "priority_task"
+            "priority_task",
+            "journal",
+            "task_history",
+            "archive",
+            "task_fts",
+            "task_fts_data",
+            "task_fts_idx",
+            "task_fts_docsize",
+            "task_fts_config",
+            "note",
+            "sqlite_sequence",
+            "working_set",
+            "time_interval",
+            "task_uda",
+            "project",
+            "task_project",
+            "attachment",
+            "attachment_blob",
+            "checklist_item",
+            "encryption"
         ]
     );
 
@@ -51,7 +71,7 @@ fn test_create_table_if_needed() -> Result<(), DbError> {
 
 #[test]
 fn test_ensure_context_is_created() -> Result<(), DbError> {
-    let database_manager = TaskManager::new(&get_setting());
+    let database_manager = TaskManager::new(&get_setting())?;
 
     let mut context = database_manager
         .conn
@@ -69,7 +89,7 @@ fn test_ensure_context_is_created() -> Result<(), DbError> {
 
 #[test]
 fn test_ensure_state_is_created() -> Result<(), DbError> {
-    let database_manager = TaskManager::new(&get_setting());
+    let database_manager = TaskManager::new(&get_setting())?;
 
     let mut context = database_manager
         .conn
@@ -87,7 +107,7 @@ fn test_ensure_state_is_created() -> Result<(), DbError> {
 
 #[test]
 fn test_ensure_priority_are_created() -> Result<(), DbError> {
-    let database_manager = TaskManager::new(&get_setting());
+    let database_manager = TaskManager::new(&get_setting())?;
 
     let mut context = database_manager
         .conn
@@ -0,0 +1,237 @@
+use rusqlite::Result;
+use std::time::Instant;
+
+use crate::db::task_manager::TaskManager;
+use crate::error::CoreError;
+use super::get_setting;
+
+// Insert `count` ready tasks in the inbox directly, adding them one by one
+// would take longer than the operations that are measured
+fn add_tasks(database_manager: &mut TaskManager, count: i64) -> Result<Vec<i64>, CoreError> {
+    database_manager.conn.execute_batch(&format!(
+        "WITH RECURSIVE ids(id) AS (SELECT 1 UNION ALL SELECT id + 1 FROM ids WHERE id < {})
+        INSERT INTO task
+            (id, uuid, body, due_date, scheduled_at, due_repeat, scheduled_repeat, annotation)
+        SELECT id, printf('%08x-0000-4000-8000-000000000000', id), 'Task ' || id, '', '', '', '', ''
+        FROM ids;
+        INSERT INTO task_context (task_id, context_id) SELECT id, 1 FROM task;
+        INSERT INTO task_state (task_id, state_id) SELECT id, 1 FROM task;",
+        count
+    ))?;
+    Ok((1..=count).collect())
+}
+
+fn modify_tags(
+    database_manager: &mut TaskManager,
+    task_ids: &Vec<i64>,
+    tags: Vec<String>,
+    tags_to_remove: Vec<String>,
+) -> Result<Vec<crate::db::task_helper::Task>, CoreError> {
+    database_manager.modify(
+        task_ids,
+        &None,
+        &None,
+        &None,
+        &tags,
+        &None,
+        &None,
+        &None,
+        &None,
+        &None,
+        &tags_to_remove,
+        &None,
+        &vec![],
+        &None,
+        &None,
+    )
+}
+
+fn complete(
+    database_manager: &mut TaskManager,
+    task_ids: &Vec<i64>,
+) -> Result<Vec<crate::db::task_helper::Task>, CoreError> {
+    database_manager.modify(
+        task_ids,
+        &None,
+        &None,
+        &None,
+        &vec![],
+        &None,
+        &None,
+        &None,
+        &None,
+        &Some("completed"),
+        &vec![],
+        &None,
+        &vec![],
+        &None,
+        &None,
+    )
+}
+
+fn count(database_manager: &TaskManager, query: &str) -> i64 {
+    database_manager.conn.query_row(query, [], |row| row.get(0)).unwrap()
+}
+
+// More ids than SQLite accepts as bound parameters in one statement
+const MANY_TASKS: i64 = 33_000;
+// Past two of the chunks of 500 tasks that the notes and the UDAs are
+// looked up by
+const CHUNKED_TASKS: i64 = 1_001;
+
+#[test]
+fn test_bulk_modify_and_delete() -> Result<(), CoreError> {
+    check_modify_and_delete(CHUNKED_TASKS)
+}
+
+// Run with `cargo test --release bulk_modify_and_delete -- --ignored`
+#[test]
+#[ignore]
+fn test_bulk_modify_and_delete_past_the_parameter_limit() -> Result<(), CoreError> {
+    check_modify_and_delete(MANY_TASKS)
+}
+
+#[test]
+fn test_bulk_get_across_chunks() -> Result<(), CoreError> {
+    let mut database_manager = TaskManager::new(&get_setting())?;
+    add_tasks(&mut database_manager, CHUNKED_TASKS)?;
+    // The first and last task of each chunk
+    let edges = vec![1, 500, 501, 1000, 1001];
+    for task_id in edges.iter() {
+        database_manager.conn.execute(
+            "INSERT INTO note (task_id, body) VALUES (?1, 'Note ' || ?1)",
+            [task_id],
+        )?;
+        database_manager.conn.execute(
+            "INSERT INTO task_uda (task_id, name, value) VALUES (?1, 'client', 'acme')",
+            [task_id],
+        )?;
+    }
+
+    let tasks = database_manager.get(&None, &None, &vec![], &None, &None, &None, &None)?;
+    assert_eq!(tasks.len() as i64, CHUNKED_TASKS);
+    for task in tasks.iter() {
+        if edges.contains(&task.id) {
+            assert_eq!(task.notes.len(), 1);
+            assert_eq!(task.notes[0].body, format!("Note {}", task.id));
+            assert_eq!(task.udas.get("client"), Some(&String::from("acme")));
+        } else {
+            assert!(task.notes.is_empty());
+            assert!(task.udas.is_empty());
+        }
+    }
+    Ok(())
+}
+
+fn check_modify_and_delete(task_count: i64) -> Result<(), CoreError> {
+    let mut database_manager = TaskManager::new(&get_setting())?;
+    let task_ids = add_tasks(&mut database_manager, task_count)?;
+
+    let tasks = modify_tags(&mut database_manager, &task_ids, vec![String::from("bulk")], vec![])?;
+    assert_eq!(tasks.len(), task_ids.len());
+    assert_eq!(tasks[0].tags, vec!["bulk"]);
+    assert_eq!(tasks.last().unwrap().id, task_count);
+    assert_eq!(count(&database_manager, "SELECT COUNT(*) FROM task_tag"), task_count);
+
+    let deleted = database_manager.delete(&task_ids)?;
+    assert_eq!(deleted.len(), task_ids.len());
+    assert!(deleted.iter().all(|task| task.display_id.is_none()));
+    assert_eq!(count(&database_manager, "SELECT COUNT(*) FROM working_set"), 0);
+
+    // The whole delete is undone at once
+    database_manager.undo()?;
+    assert_eq!(
+        count(&database_manager, "SELECT COUNT(*) FROM task WHERE deleted_at IS NULL"),
+        task_count
+    );
+
+    database_manager.delete(&task_ids)?;
+    let purged = database_manager.purge(&None)?;
+    assert_eq!(purged.len(), task_ids.len());
+    assert_eq!(count(&database_manager, "SELECT COUNT(*) FROM task"), 0);
+    assert_eq!(count(&database_manager, "SELECT COUNT(*) FROM task_tag"), 0);
+    Ok(())
+}
+
+#[test]
+fn test_bulk_complete_updates_dependents() -> Result<(), CoreError> {
+    let mut database_manager = TaskManager::new(&get_setting())?;
+    let task_ids = add_tasks(&mut database_manager, 1_000)?;
+    // Every odd task is blocked by the next task
+    database_manager.conn.execute_batch(
+        "INSERT INTO dependency (task_id, parent_task_id)
+            SELECT id, id + 1 FROM task WHERE id % 2 = 1;
+        UPDATE task_state SET state_id = 3 WHERE task_id % 2 = 1;
+        UPDATE task SET due_repeat = '1days' WHERE id = 2;",
+    )?;
+
+    let parent_ids: Vec<i64> = task_ids.iter().filter(|id| *id % 2 == 0).copied().collect();
+    let tasks = complete(&mut database_manager, &parent_ids)?;
+    assert_eq!(tasks.len(), parent_ids.len());
+    // The repeating task is ready again, so its child stays blocked
+    assert_eq!(tasks[0].state, "ready");
+    assert_ne!(tasks[0].date_due, "");
+    assert_eq!(tasks[1].state, "completed");
+
+    let blocked = count(
+        &database_manager,
+        "SELECT COUNT(*) FROM task_state WHERE task_id % 2 = 1 AND state_id = 3",
+    );
+    assert_eq!(blocked, 1);
+    let history = database_manager.history(3)?;
+    assert_eq!(history.last().unwrap().new_value, "ready");
+
+    database_manager.undo()?;
+    let blocked = count(
+        &database_manager,
+        "SELECT COUNT(*) FROM task_state WHERE task_id % 2 = 1 AND state_id = 3",
+    );
+    assert_eq!(blocked, 500);
+    Ok(())
+}
+
+#[test]
+fn test_bulk_modify_unknown_task() -> Result<(), CoreError> {
+    let mut database_manager = TaskManager::new(&get_setting())?;
+    add_tasks(&mut database_manager, 3)?;
+    database_manager.delete(&vec![3])?;
+
+    // The trash and unknown ids make the whole modification fail
+    let tags = vec![String::from("a")];
+    assert!(modify_tags(&mut database_manager, &vec![1, 3], tags.clone(), vec![]).is_err());
+    assert!(modify_tags(&mut database_manager, &vec![1, 9], tags.clone(), vec![]).is_err());
+    let tasks = database_manager.get(&None, &None, &tags, &None, &None, &None, &None)?;
+    assert!(tasks.is_empty());
+    Ok(())
+}
+
+// Run with `cargo test --release bench_bulk -- --ignored --nocapture`
+#[test]
+#[ignore]
+fn bench_bulk_operations_on_50k_tasks() -> Result<(), CoreError> {
+    const TASKS: i64 = 50_000;
+    let mut database_manager = TaskManager::new(&get_setting())?;
+    let task_ids = add_tasks(&mut database_manager, TASKS)?;
+
+    let mut measure = |name: &str, run: &mut dyn FnMut(&mut TaskManager) -> Result<(), CoreError>| {
+        let start = Instant::now();
+        let result = run(&mut database_manager);
+        match result {
+            Ok(()) => println!("{:<32} {:>10.2?}", name, start.elapsed()),
+            Err(error) => println!("{:<32} failed: {}", name, error),
+        }
+    };
+
+    measure("modify: add a tag", &mut |manager| {
+        modify_tags(manager, &task_ids, vec![String::from("bulk")], vec![]).map(|_| ())
+    });
+    measure("modify: remove the tag", &mut |manager| {
+        modify_tags(manager, &task_ids, vec![], vec![String::from("bulk")]).map(|_| ())
+    });
+    measure("modify: complete", &mut |manager| complete(manager, &task_ids).map(|_| ()));
+    measure("delete", &mut |manager| manager.delete(&task_ids).map(|_| ()));
+    measure("undo the delete", &mut |manager| manager.undo().map(|_| ()));
+    measure("delete again", &mut |manager| manager.delete(&task_ids).map(|_| ()));
+    measure("purge", &mut |manager| manager.purge(&None).map(|_| ()));
+    Ok(())
+}
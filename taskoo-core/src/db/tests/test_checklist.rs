@@ -0,0 +1,139 @@
+use rusqlite::Result;
+
+use crate::core::Operation;
+use crate::db::task_helper::Task;
+use crate::db::task_manager::TaskManager;
+use crate::error::CoreError;
+use crate::operation::{execute, AddChecklistItem};
+use super::{add_task, get_setting};
+
+fn get_task(database_manager: &mut TaskManager, task_id: i64) -> Result<Task, CoreError> {
+    let mut tasks =
+        database_manager.get(&None, &None, &vec![], &None, &None, &Some(task_id), &None)?;
+    assert_eq!(tasks.len(), 1);
+    Ok(tasks.remove(0))
+}
+
+fn item_bodies(task: &Task) -> Vec<String> {
+    task.checklist.iter().map(|item| item.body.clone()).collect()
+}
+
+fn complete(database_manager: &mut TaskManager, task_id: i64) -> Result<Vec<Task>, CoreError> {
+    database_manager.modify(
+        &vec![task_id],
+        &None,
+        &None,
+        &None,
+        &vec![],
+        &None,
+        &None,
+        &None,
+        &None,
+        &Some("completed"),
+        &vec![],
+        &None,
+        &vec![],
+        &None,
+        &None,
+    )
+}
+
+#[test]
+fn test_add_and_check_items() -> Result<(), CoreError> {
+    let mut database_manager = TaskManager::new(&get_setting())?;
+    add_task(&mut database_manager, "Pack for the trip")?;
+    add_task(&mut database_manager, "Other task")?;
+    assert_eq!(get_task(&mut database_manager, 1)?.format_checklist_progress(), "");
+
+    let mut operation = AddChecklistItem::new(1, String::from("  passport "));
+    operation.database_manager = Some(database_manager);
+    execute(&mut operation)?;
+    assert_eq!(item_bodies(&operation.get_result()[0]), vec!["passport"]);
+    let mut database_manager = operation.database_manager.take().unwrap();
+
+    let mut operation = AddChecklistItem::new(1, String::from("   "));
+    operation.database_manager = Some(database_manager);
+    assert!(execute(&mut operation).is_err());
+    let mut database_manager = operation.database_manager.take().unwrap();
+
+    database_manager.add_checklist_item(1, "charger")?;
+    database_manager.add_checklist_item(1, "tickets")?;
+    let tasks = database_manager.check_checklist_item(1, 2, true)?;
+    let positions: Vec<i64> = tasks[0].checklist.iter().map(|item| item.position).collect();
+    assert_eq!(positions, vec![1, 2, 3]);
+    assert!(tasks[0].checklist[1].done);
+    assert_eq!(tasks[0].format_checklist_progress(), "1/3");
+    assert_eq!(tasks[0].get_property_value("checklist").unwrap(), "1/3");
+
+    let tasks = database_manager.check_checklist_item(1, 2, false)?;
+    assert_eq!(tasks[0].format_checklist_progress(), "0/3");
+
+    // Positions are counted within the task
+    assert!(database_manager.check_checklist_item(1, 4, true).is_err());
+    assert!(database_manager.check_checklist_item(1, 0, true).is_err());
+    assert!(database_manager.check_checklist_item(2, 1, true).is_err());
+    assert!(database_manager.add_checklist_item(9, "nothing").is_err());
+
+    // The checklist is in the JSON of the task
+    let json = serde_json::to_value(get_task(&mut database_manager, 1)?)
+        .expect("tasks are serialized");
+    assert_eq!(json["checklist"][2]["body"], "tickets");
+    assert_eq!(json["checklist"][2]["done"], false);
+    Ok(())
+}
+
+#[test]
+fn test_move_and_delete_items() -> Result<(), CoreError> {
+    let mut database_manager = TaskManager::new(&get_setting())?;
+    add_task(&mut database_manager, "Release")?;
+    for body in ["tag", "build", "upload", "announce"].iter() {
+        database_manager.add_checklist_item(1, body)?;
+    }
+
+    let tasks = database_manager.move_checklist_item(1, 1, 3)?;
+    assert_eq!(item_bodies(&tasks[0]), vec!["build", "upload", "tag", "announce"]);
+    let tasks = database_manager.move_checklist_item(1, 4, 1)?;
+    assert_eq!(item_bodies(&tasks[0]), vec!["announce", "build", "upload", "tag"]);
+    let tasks = database_manager.move_checklist_item(1, 2, 2)?;
+    assert_eq!(item_bodies(&tasks[0]), vec!["announce", "build", "upload", "tag"]);
+    assert!(database_manager.move_checklist_item(1, 1, 5).is_err());
+
+    let tasks = database_manager.delete_checklist_item(1, 1)?;
+    assert_eq!(item_bodies(&tasks[0]), vec!["build", "upload", "tag"]);
+    let positions: Vec<i64> = tasks[0].checklist.iter().map(|item| item.position).collect();
+    assert_eq!(positions, vec![1, 2, 3]);
+
+    // New items go to the end after a delete
+    let tasks = database_manager.add_checklist_item(1, "announce")?;
+    assert_eq!(item_bodies(&tasks[0]), vec!["build", "upload", "tag", "announce"]);
+
+    database_manager.undo()?;
+    database_manager.undo()?;
+    assert_eq!(
+        item_bodies(&get_task(&mut database_manager, 1)?),
+        vec!["announce", "build", "upload", "tag"]
+    );
+    Ok(())
+}
+
+#[test]
+fn test_repeating_task_unchecks_its_checklist() -> Result<(), CoreError> {
+    let mut database_manager = TaskManager::new(&get_setting())?;
+    add_task(&mut database_manager, "Water the plants")?;
+    add_task(&mut database_manager, "Once")?;
+    database_manager
+        .conn
+        .execute("UPDATE task SET due_repeat = '1weeks' WHERE id = 1", [])?;
+    for task_id in [1, 2].iter() {
+        database_manager.add_checklist_item(*task_id, "balcony")?;
+        database_manager.check_checklist_item(*task_id, 1, true)?;
+    }
+
+    let tasks = complete(&mut database_manager, 1)?;
+    assert_eq!(tasks[0].state, "ready");
+    assert_eq!(tasks[0].format_checklist_progress(), "0/1");
+
+    let tasks = complete(&mut database_manager, 2)?;
+    assert_eq!(tasks[0].format_checklist_progress(), "1/1");
+    Ok(())
+}
@@ -16,7 +16,7 @@ fn get_setting() -> HashMap<String, String> {
 
 #[test]
 fn test_delete_simple() -> Result<(), CoreError> {
-    let mut database_manager = TaskManager::new(&get_setting());
+    let mut database_manager = TaskManager::new(&get_setting())?;
 
     let mut operation = Add::new_with_task_manager("Test Body", &mut database_manager);
     execute(&mut operation)?;
@@ -39,7 +39,7 @@ fn test_delete_simple() -> Result<(), CoreError> {
 
 #[test]
 fn test_delete_multiple() -> Result<(), CoreError> {
-    let mut database_manager = TaskManager::new(&get_setting());
+    let mut database_manager = TaskManager::new(&get_setting())?;
 
     let mut operation = Add::new_with_task_manager("Test Body", &mut database_manager);
     execute(&mut operation)?;
@@ -61,3 +61,131 @@ fn test_delete_multiple() -> Result<(), CoreError> {
     assert_eq!(bb.len(), 0);
     Ok(())
 }
+
+#[test]
+fn test_delete_moves_to_trash() -> Result<(), CoreError> {
+    let mut database_manager = TaskManager::new(&get_setting())?;
+
+    let mut operation = Add::new_with_task_manager("Test Body", &mut database_manager);
+    execute(&mut operation)?;
+    let mut operation = Add::new_with_task_manager("Test Body 2", &mut database_manager);
+    execute(&mut operation)?;
+
+    let deleted = database_manager.delete(&vec![1])?;
+    assert_eq!(deleted.len(), 1);
+    assert_eq!(deleted[0].body, "Test Body");
+
+    let trash = database_manager.trash()?;
+    assert_eq!(trash.len(), 1);
+    assert_eq!(trash[0].id, 1);
+
+    // Deleting a task in the trash again does nothing
+    assert!(database_manager.delete(&vec![1])?.is_empty());
+    Ok(())
+}
+
+#[test]
+fn test_restore() -> Result<(), CoreError> {
+    let mut database_manager = TaskManager::new(&get_setting())?;
+
+    let mut operation = Add::new_with_task_manager("Test Body", &mut database_manager);
+    operation.tags = vec!["tag1".to_owned()];
+    execute(&mut operation)?;
+
+    database_manager.delete(&vec![1])?;
+    let restored = database_manager.restore(&vec![1, 2])?;
+    assert_eq!(restored.len(), 1);
+    assert_eq!(restored[0].tags, vec!["tag1"]);
+
+    let rows = database_manager
+        .get(&None, &None, &vec![], &None, &None, &Some(1), &None)
+        .unwrap();
+    assert_eq!(rows.len(), 1);
+    assert!(database_manager.trash()?.is_empty());
+    Ok(())
+}
+
+#[test]
+fn test_purge() -> Result<(), CoreError> {
+    let mut database_manager = TaskManager::new(&get_setting())?;
+
+    let mut operation = Add::new_with_task_manager("Test Body", &mut database_manager);
+    execute(&mut operation)?;
+    let mut operation = Add::new_with_task_manager("Test Body 2", &mut database_manager);
+    execute(&mut operation)?;
+
+    database_manager.delete(&vec![1])?;
+    let purged = database_manager.purge(&None)?;
+    assert_eq!(purged.len(), 1);
+    assert_eq!(purged[0].id, 1);
+    assert!(database_manager.trash()?.is_empty());
+
+    // Purged tasks can't be restored
+    assert!(database_manager.restore(&vec![1])?.is_empty());
+
+    let rows = database_manager
+        .get(&None, &None, &vec![], &None, &None, &None, &None)
+        .unwrap();
+    assert_eq!(rows.len(), 1);
+    Ok(())
+}
+
+#[test]
+fn test_purge_older_than() -> Result<(), CoreError> {
+    let mut database_manager = TaskManager::new(&get_setting())?;
+
+    let mut operation = Add::new_with_task_manager("Test Body", &mut database_manager);
+    execute(&mut operation)?;
+    let mut operation = Add::new_with_task_manager("Test Body 2", &mut database_manager);
+    execute(&mut operation)?;
+
+    database_manager.delete(&vec![1, 2])?;
+    database_manager.conn.execute(
+        "UPDATE task SET deleted_at = '2020-01-01 00:00:00' WHERE id = 1",
+        [],
+    )?;
+
+    let purged = database_manager.purge(&Some("30days"))?;
+    assert_eq!(purged.len(), 1);
+    assert_eq!(purged[0].id, 1);
+
+    let trash = database_manager.trash()?;
+    assert_eq!(trash.len(), 1);
+    assert_eq!(trash[0].id, 2);
+
+    assert!(database_manager.purge(&Some("soon")).is_err());
+    Ok(())
+}
+
+#[test]
+fn test_trashed_parent_does_not_block() -> Result<(), CoreError> {
+    let mut database_manager = TaskManager::new(&get_setting())?;
+    for body in ["Parent 1", "Parent 2"].iter() {
+        let mut operation = Add::new_with_task_manager(body, &mut database_manager);
+        execute(&mut operation)?;
+    }
+    let mut operation = Add::new_with_task_manager("Child", &mut database_manager);
+    operation.parent_task_ids = Some(vec![1, 2]);
+    execute(&mut operation)?;
+
+    let get_state = |database_manager: &mut TaskManager| {
+        database_manager
+            .get(&None, &None, &vec![], &None, &None, &Some(3), &None)
+            .unwrap()[0]
+            .state
+            .clone()
+    };
+    assert_eq!(get_state(&mut database_manager), "blocked");
+
+    // Still blocked by the other parent
+    database_manager.delete(&vec![1])?;
+    assert_eq!(get_state(&mut database_manager), "blocked");
+    database_manager.delete(&vec![2])?;
+    assert_eq!(get_state(&mut database_manager), "ready");
+
+    database_manager.restore(&vec![2])?;
+    assert_eq!(get_state(&mut database_manager), "blocked");
+    database_manager.undo()?;
+    assert_eq!(get_state(&mut database_manager), "ready");
+    Ok(())
+}
@@ -0,0 +1,217 @@
+use rusqlite::Result;
+
+use crate::db::doctor::{Problem, ProblemKind};
+use crate::db::task_manager::TaskManager;
+use crate::error::CoreError;
+use crate::operation::{Add, execute};
+use super::get_setting;
+
+fn add_task(
+    database_manager: &mut TaskManager,
+    body: &str,
+    context: Option<&str>,
+    tags: Vec<&str>,
+    parent_task_ids: Option<Vec<i64>>,
+) -> Result<(), CoreError> {
+    let mut operation = Add::new_with_task_manager(body, database_manager);
+    operation.context = context.map(|name| name.to_owned());
+    operation.tags = tags.iter().map(|tag| tag.to_string()).collect();
+    operation.parent_task_ids = parent_task_ids;
+    execute(&mut operation)?;
+    Ok(())
+}
+
+// Break the database the way SQLite builds without foreign key
+// enforcement allow
+fn corrupt(database_manager: &mut TaskManager, queries: &str) -> Result<(), CoreError> {
+    database_manager.conn.execute_batch(&format!(
+        "PRAGMA foreign_keys = OFF; {} PRAGMA foreign_keys = ON;",
+        queries
+    ))?;
+    Ok(())
+}
+
+fn kinds(problems: &Vec<Problem>) -> Vec<ProblemKind> {
+    problems.iter().map(|problem| problem.kind).collect()
+}
+
+#[test]
+fn test_healthy_database() -> Result<(), CoreError> {
+    let mut database_manager = TaskManager::new(&get_setting())?;
+    add_task(&mut database_manager, "Parent", Some("work"), vec!["tag1"], None)?;
+    add_task(&mut database_manager, "Child", None, vec![], Some(vec![1]))?;
+    database_manager.add_note(1, "called vendor")?;
+    database_manager.delete(&vec![2])?;
+
+    assert!(database_manager.doctor(false)?.is_empty());
+    assert!(database_manager.doctor(true)?.is_empty());
+    Ok(())
+}
+
+#[test]
+fn test_deleted_context_and_tag() -> Result<(), CoreError> {
+    let mut database_manager = TaskManager::new(&get_setting())?;
+    add_task(&mut database_manager, "Task", Some("work"), vec!["tag1", "tag2"], None)?;
+    // What ContextCommand::delete and TagCommand::delete leave behind
+    corrupt(
+        &mut database_manager,
+        "DELETE FROM context WHERE name = 'work'; DELETE FROM tag WHERE name = 'tag1';",
+    )?;
+
+    let problems = database_manager.doctor(false)?;
+    assert_eq!(
+        kinds(&problems),
+        vec![ProblemKind::OrphanedRow, ProblemKind::MissingContext]
+    );
+    assert_eq!(problems[0].task_ids, vec![1]);
+
+    // Reporting doesn't change anything
+    assert_eq!(database_manager.doctor(false)?, problems);
+
+    assert_eq!(database_manager.doctor(true)?, problems);
+    assert!(database_manager.doctor(false)?.is_empty());
+    let tasks = database_manager.get(&None, &None, &vec![], &None, &None, &Some(1), &None)?;
+    assert_eq!(tasks[0].context, "inbox");
+    assert_eq!(tasks[0].tags, vec!["tag2"]);
+    Ok(())
+}
+
+#[test]
+fn test_orphaned_rows_and_missing_state() -> Result<(), CoreError> {
+    let mut database_manager = TaskManager::new(&get_setting())?;
+    add_task(&mut database_manager, "Task", None, vec![], None)?;
+    corrupt(
+        &mut database_manager,
+        "INSERT INTO task_tag (task_id, tag_id) VALUES (7, 1);
+        INSERT INTO note (task_id, body) VALUES (7, 'lost');
+        DELETE FROM task_state WHERE task_id = 1;",
+    )?;
+
+    let problems = database_manager.doctor(true)?;
+    assert_eq!(
+        kinds(&problems),
+        vec![
+            ProblemKind::OrphanedRow,
+            ProblemKind::OrphanedRow,
+            ProblemKind::MissingState
+        ]
+    );
+    assert_eq!(problems[0].task_ids, vec![7]);
+
+    let count: i64 = database_manager.conn.query_row(
+        "SELECT (SELECT COUNT(*) FROM task_tag) + (SELECT COUNT(*) FROM note)",
+        [],
+        |row| row.get(0),
+    )?;
+    assert_eq!(count, 0);
+    let tasks = database_manager.get(&None, &None, &vec![], &None, &None, &Some(1), &None)?;
+    assert_eq!(tasks[0].state, "ready");
+    Ok(())
+}
+
+#[test]
+fn test_dependencies() -> Result<(), CoreError> {
+    let mut database_manager = TaskManager::new(&get_setting())?;
+    for body in ["One", "Two", "Three", "Four"] {
+        add_task(&mut database_manager, body, None, vec![], None)?;
+    }
+    corrupt(
+        &mut database_manager,
+        "INSERT INTO dependency (task_id, parent_task_id) VALUES
+            (1, 2), (2, 3), (3, 1), (4, 4), (4, 9);",
+    )?;
+
+    let problems = database_manager.doctor(false)?;
+    assert_eq!(
+        kinds(&problems),
+        vec![
+            ProblemKind::DanglingDependency,
+            ProblemKind::DependencyCycle,
+            ProblemKind::DependencyCycle
+        ]
+    );
+    assert_eq!(problems[0].task_ids, vec![4, 9]);
+    assert_eq!(problems[1].task_ids, vec![1, 2, 3]);
+    assert_eq!(problems[2].task_ids, vec![4]);
+
+    database_manager.doctor(true)?;
+    assert!(database_manager.doctor(false)?.is_empty());
+    let remaining: i64 =
+        database_manager
+            .conn
+            .query_row("SELECT COUNT(*) FROM dependency", [], |row| row.get(0))?;
+    assert_eq!(remaining, 2);
+    Ok(())
+}
+
+#[test]
+fn test_invalid_dates() -> Result<(), CoreError> {
+    let mut database_manager = TaskManager::new(&get_setting())?;
+    add_task(&mut database_manager, "Task", None, vec![], None)?;
+    add_task(&mut database_manager, "Other", None, vec![], None)?;
+    database_manager.conn.execute_batch(
+        "UPDATE task SET due_date = 'next tuesday', scheduled_at = '2026-07-08',
+            due_repeat = 'fortnightly' WHERE id = 1;
+        UPDATE task SET created_at = 'yesterday' WHERE id = 2;",
+    )?;
+
+    let problems = database_manager.doctor(true)?;
+    assert_eq!(
+        problems.iter().map(|problem| problem.task_ids[0]).collect::<Vec<i64>>(),
+        vec![1, 1, 2]
+    );
+    assert!(database_manager.doctor(false)?.is_empty());
+
+    let tasks = database_manager.get(&None, &None, &vec![], &None, &None, &None, &None)?;
+    assert_eq!(tasks[0].date_due, "");
+    assert_eq!(tasks[0].repetition_due, "");
+    assert_eq!(tasks[0].date_scheduled, "2026-07-08");
+    assert_ne!(tasks[1].date_created, "yesterday");
+    Ok(())
+}
+
+#[test]
+fn test_undo_repairs() -> Result<(), CoreError> {
+    let mut database_manager = TaskManager::new(&get_setting())?;
+    add_task(&mut database_manager, "Task", None, vec![], None)?;
+    database_manager
+        .conn
+        .execute("DELETE FROM task_context WHERE task_id = 1", [])?;
+
+    database_manager.doctor(true)?;
+    database_manager.undo()?;
+    assert_eq!(kinds(&database_manager.doctor(false)?), vec![ProblemKind::MissingContext]);
+    Ok(())
+}
+
+#[test]
+fn test_complete_parent_without_valid_state() -> Result<(), CoreError> {
+    let mut database_manager = TaskManager::new(&get_setting())?;
+    add_task(&mut database_manager, "Parent", None, vec![], None)?;
+    add_task(&mut database_manager, "Other parent", None, vec![], None)?;
+    add_task(&mut database_manager, "Child", None, vec![], Some(vec![1, 2]))?;
+    corrupt(
+        &mut database_manager,
+        "UPDATE task_state SET state_id = 99 WHERE task_id = 2;",
+    )?;
+
+    // Used to panic while looking for the state of the other parent
+    database_manager.modify(
+        &vec![1],
+        &None,
+        &None,
+        &None,
+        &vec![],
+        &None,
+        &None,
+        &None,
+        &None,
+        &Some("completed"),
+        &vec![],
+        &None,
+        &vec![],
+        &None,
+        &None,
+    )?;
+    Ok(())
+}
@@ -0,0 +1,258 @@
+use chrono::NaiveDate;
+use rusqlite::Result;
+use std::path::PathBuf;
+
+use crate::command::{ProjectCommand, SimpleCommand};
+use crate::db::encryption::{is_encrypted_blob, is_encrypted_text};
+use crate::db::task_manager::TaskManager;
+use crate::error::CoreError;
+use crate::operation::{execute, execute_encryption, Add, Encryption, EncryptionChange};
+use super::get_setting;
+
+fn fixture_path(name: &str) -> PathBuf {
+    let mut path = std::env::temp_dir();
+    path.push(format!("taskoo_{}_{}.db", name, std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    path
+}
+
+fn add_task(
+    database_manager: &mut TaskManager,
+    body: &str,
+    date_due: &str,
+) -> Result<(), CoreError> {
+    let mut operation = Add::new_with_task_manager(body, database_manager);
+    operation.date_due = Some(date_due);
+    operation.annotation = Some("Secret annotation");
+    execute(&mut operation)?;
+    Ok(())
+}
+
+fn get_all(
+    database_manager: &mut TaskManager,
+) -> Result<Vec<crate::db::task_helper::Task>, CoreError> {
+    database_manager.get(&None, &None, &vec![], &None, &None, &None, &None)
+}
+
+// The stored text of the column, as it's in the file
+fn raw_values(database_manager: &TaskManager, query: &str) -> Result<Vec<String>, CoreError> {
+    let mut statement = database_manager.conn.prepare(query)?;
+    let rows = statement.query_map([], |row| row.get(0))?;
+    Ok(rows.collect::<Result<Vec<String>>>()?)
+}
+
+fn write_file(name: &str, content: &str) -> PathBuf {
+    let path = fixture_path(name);
+    std::fs::write(&path, content).unwrap();
+    path
+}
+
+#[test]
+fn test_encrypted_text_is_stored_encrypted() -> Result<(), CoreError> {
+    let mut database_manager = TaskManager::new(&get_setting())?;
+    add_task(&mut database_manager, "Plain before", "2020-11-14")?;
+    database_manager.add_note(1, "Plain note")?;
+    database_manager.add_checklist_item(1, "Plain item")?;
+    database_manager.add_attachment(1, "https://example.com/plain", false)?;
+    let path = write_file("encrypted_copy", "Plain copy");
+    database_manager.add_attachment(1, path.to_str().unwrap(), true)?;
+    std::fs::remove_file(&path).ok();
+    ProjectCommand::new_with_manager(&mut database_manager).update(
+        "Plans",
+        &Some(String::from("Plain description")),
+        &None,
+        &None,
+    )?;
+    assert!(!database_manager.is_encrypted()?);
+
+    let mut operation = Encryption::new(EncryptionChange::Enable(String::from("hunter2")));
+    operation.database_manager = Some(database_manager);
+    execute_encryption(&mut operation)?;
+    let mut database_manager = operation.database_manager.take().unwrap();
+    assert!(database_manager.is_encrypted()?);
+
+    add_task(&mut database_manager, "Added after", "2020-11-15")?;
+    database_manager.add_note(2, "Another note")?;
+    let bodies = raw_values(&database_manager, "SELECT body FROM task")?;
+    let annotations = raw_values(&database_manager, "SELECT annotation FROM task")?;
+    let notes = raw_values(&database_manager, "SELECT body FROM note")?;
+    let history = raw_values(
+        &database_manager,
+        "SELECT new_value FROM task_history WHERE field = 'body'",
+    )?;
+    let items = raw_values(&database_manager, "SELECT body FROM checklist_item")?;
+    let locations = raw_values(&database_manager, "SELECT location FROM attachment")?;
+    let descriptions = raw_values(&database_manager, "SELECT description FROM project")?;
+    for value in bodies
+        .iter()
+        .chain(&annotations)
+        .chain(&notes)
+        .chain(&history)
+        .chain(&items)
+        .chain(&locations)
+        .chain(&descriptions)
+    {
+        assert!(is_encrypted_text(value), "{} isn't encrypted", value);
+    }
+    let copy: Vec<u8> =
+        database_manager
+            .conn
+            .query_row("SELECT data FROM attachment_blob", [], |row| row.get(0))?;
+    assert!(is_encrypted_blob(&copy));
+    // The index is rebuilt from the encrypted text
+    let indexed: i64 = database_manager.conn.query_row(
+        "SELECT COUNT(*) FROM task_fts WHERE task_fts MATCH 'plain'",
+        [],
+        |row| row.get(0),
+    )?;
+    assert_eq!(indexed, 0);
+    // The other columns stay readable
+    assert_eq!(
+        raw_values(&database_manager, "SELECT due_date FROM task ORDER BY id")?,
+        vec!["2020-11-14 00:00:00", "2020-11-15 00:00:00"]
+    );
+
+    let tasks = get_all(&mut database_manager)?;
+    assert_eq!(tasks[0].body, "Plain before");
+    assert_eq!(tasks[0].annotation, "Secret annotation");
+    assert_eq!(tasks[0].notes[0].body, "Plain note");
+    assert_eq!(tasks[0].checklist[0].body, "Plain item");
+    assert_eq!(tasks[0].attachments[0].location, "https://example.com/plain");
+    assert_eq!(tasks[0].attachments[1].size, Some(10));
+    let copy_id = tasks[0].attachments[1].id;
+    assert_eq!(
+        database_manager.get_attachment(copy_id)?.1,
+        Some(b"Plain copy".to_vec())
+    );
+    let projects = ProjectCommand::new_with_manager(&mut database_manager).projects()?;
+    assert_eq!(projects[0].description, "Plain description");
+    assert_eq!(tasks[1].body, "Added after");
+    assert_eq!(database_manager.history(2)?[0].new_value, "Added after");
+
+    // Enabling it twice would encrypt the text twice
+    assert!(database_manager.enable_encryption("other").is_err());
+    Ok(())
+}
+
+#[test]
+fn test_filtering_agenda_and_search_keep_working() -> Result<(), CoreError> {
+    let mut database_manager = TaskManager::new(&get_setting())?;
+    database_manager.enable_encryption("hunter2")?;
+    let mut operation = Add::new_with_task_manager("Call the vendor", &mut database_manager);
+    operation.context = Some("Work".to_owned());
+    operation.date_due = Some("2020-11-14");
+    operation.annotation = Some("Secret annotation");
+    execute(&mut operation)?;
+    add_task(&mut database_manager, "Buy milk", "2020-11-20")?;
+
+    let tasks = database_manager.get(
+        &None,
+        &Some("Work".to_owned()),
+        &vec![],
+        &None,
+        &None,
+        &None,
+        &None,
+    )?;
+    assert_eq!(tasks.len(), 1);
+    assert_eq!(tasks[0].body, "Call the vendor");
+
+    let rows = database_manager.view_agenda(String::from("2020-11-14"), None, None)?;
+    assert_eq!(rows.len(), 1);
+    assert_eq!(rows[0].0, NaiveDate::from_ymd(2020, 11, 14));
+    assert_eq!(rows[0].1[0].body, "Call the vendor");
+
+    let results = database_manager.search("VEN call", "[", "]")?;
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].snippet, "[Call] the [vendor]");
+    let results = database_manager.search("secret", "[", "]")?;
+    assert_eq!(results.len(), 2);
+    assert_eq!(results[0].snippet, "[Secret] annotation");
+    assert!(database_manager.search("vendors", "[", "]")?.is_empty());
+
+    database_manager.modify(
+        &vec![2],
+        &Some("Buy oat milk"),
+        &None,
+        &None,
+        &vec![],
+        &None,
+        &None,
+        &None,
+        &None,
+        &None,
+        &vec![],
+        &None,
+        &vec![],
+        &None,
+        &None,
+    )?;
+    assert_eq!(get_all(&mut database_manager)?[1].body, "Buy oat milk");
+    assert!(is_encrypted_text(
+        &raw_values(&database_manager, "SELECT body FROM task WHERE id = 2")?[0]
+    ));
+    Ok(())
+}
+
+#[test]
+fn test_rotate_and_disable() -> Result<(), CoreError> {
+    let path = fixture_path("encryption");
+    let mut setting = get_setting();
+    setting.insert("db_path".to_owned(), path.to_str().unwrap().to_owned());
+
+    {
+        let mut database_manager = TaskManager::new(&setting)?;
+        add_task(&mut database_manager, "Rotated", "2020-11-14")?;
+        add_task(&mut database_manager, "Archived", "2020-11-14")?;
+        database_manager.conn.execute(
+            "UPDATE task_state SET state_id = (SELECT id FROM state WHERE name = 'completed')
+            WHERE task_id = 2",
+            [],
+        )?;
+        database_manager.conn.execute(
+            "UPDATE task SET completed_at = datetime('now', '-40 days') WHERE id = 2",
+            [],
+        )?;
+        assert_eq!(database_manager.archive(&Some(30))?.len(), 1);
+        database_manager.enable_encryption("first")?;
+        database_manager.rotate_encryption_key("second")?;
+        // The undo history can't be restored with the new key
+        assert!(database_manager.undo().is_err());
+    }
+
+    // Without the passphrase the tasks can't be read
+    let mut database_manager = TaskManager::new(&setting)?;
+    assert!(database_manager.is_encrypted()?);
+    match get_all(&mut database_manager) {
+        Err(CoreError::SqliteError(_)) => {}
+        result => panic!("Expected the database to be locked, got {:?}", result),
+    }
+    assert!(database_manager.disable_encryption().is_err());
+
+    setting.insert("passphrase".to_owned(), "first".to_owned());
+    let mut database_manager = TaskManager::new(&setting)?;
+    assert!(get_all(&mut database_manager).is_err());
+
+    setting.insert("passphrase".to_owned(), "second".to_owned());
+    let mut database_manager = TaskManager::new(&setting)?;
+    assert_eq!(get_all(&mut database_manager)?[0].body, "Rotated");
+    let archived =
+        database_manager.get_archived(&None, &None, &vec![], &None, &None, &None, &None)?;
+    assert_eq!(archived[0].body, "Archived");
+
+    database_manager.disable_encryption()?;
+    assert!(!database_manager.is_encrypted()?);
+    assert_eq!(
+        raw_values(&database_manager, "SELECT body FROM task")?,
+        vec!["Rotated"]
+    );
+    assert!(!is_encrypted_text(
+        &raw_values(&database_manager, "SELECT task FROM archive")?[0]
+    ));
+    assert_eq!(database_manager.search("rot", "[", "]")?.len(), 1);
+
+    database_manager.unarchive(&vec![2])?;
+    assert_eq!(get_all(&mut database_manager)?[1].body, "Archived");
+    std::fs::remove_file(&path).ok();
+    Ok(())
+}
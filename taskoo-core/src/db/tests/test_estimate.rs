@@ -0,0 +1,149 @@
+use chrono::Local;
+use rusqlite::Result;
+
+use crate::core::Operation;
+use crate::db::task_helper::{format_estimate, parse_estimate, Effort, Task};
+use crate::db::task_manager::TaskManager;
+use crate::error::CoreError;
+use crate::operation::{execute, execute_agenda, Add, Agenda};
+use super::get_setting;
+
+fn add_task(
+    database_manager: &mut TaskManager,
+    body: &str,
+    context: Option<&str>,
+    estimate: Option<&str>,
+) -> Result<Task, CoreError> {
+    let mut operation = Add::new_with_task_manager(body, database_manager);
+    operation.context = context.map(String::from);
+    operation.date_due = Some("today");
+    operation.estimate = estimate;
+    execute(&mut operation)?;
+    Ok(operation.get_result()[0].clone())
+}
+
+fn modify(
+    database_manager: &mut TaskManager,
+    task_id: i64,
+    state: Option<&str>,
+    estimate: Option<&str>,
+) -> Result<Task, CoreError> {
+    let tasks = database_manager.modify(
+        &vec![task_id],
+        &None,
+        &None,
+        &None,
+        &vec![],
+        &None,
+        &None,
+        &None,
+        &None,
+        &state,
+        &vec![],
+        &estimate,
+        &vec![],
+        &None,
+        &None,
+    )?;
+    Ok(tasks[0].clone())
+}
+
+#[test]
+fn test_parse_and_format_estimate() -> Result<(), CoreError> {
+    assert_eq!(parse_estimate("45m")?, 45);
+    assert_eq!(parse_estimate("2h")?, 120);
+    assert_eq!(parse_estimate("1H30m")?, 90);
+    for invalid in ["", "0m", "45", "m", "1.5h", "2d", "-1h"] {
+        assert!(parse_estimate(invalid).is_err(), "{} is invalid", invalid);
+    }
+    assert_eq!(format_estimate(45), "45m");
+    assert_eq!(format_estimate(120), "2h");
+    assert_eq!(format_estimate(90), "1h30m");
+    assert_eq!(format_estimate(0), "0m");
+    Ok(())
+}
+
+#[test]
+fn test_add_and_modify_estimate() -> Result<(), CoreError> {
+    let mut database_manager = TaskManager::new(&get_setting())?;
+    let task = add_task(&mut database_manager, "Write the report", None, Some("1h30m"))?;
+    assert_eq!(task.estimate, Some(90));
+    assert_eq!(task.get_property_value("estimate")?, "1h30m");
+    assert!(add_task(&mut database_manager, "Call the vendor", None, Some("soon")).is_err());
+
+    let task = modify(&mut database_manager, 1, None, Some("2h"))?;
+    assert_eq!(task.estimate, Some(120));
+    let history = database_manager.history(1)?;
+    let change = history.last().unwrap();
+    assert_eq!(
+        (change.field.as_str(), change.old_value.as_str(), change.new_value.as_str()),
+        ("estimate", "1h30m", "2h")
+    );
+
+    // Other changes keep the estimate, an empty one removes it
+    let task = modify(&mut database_manager, 1, Some("started"), None)?;
+    assert_eq!(task.estimate, Some(120));
+    let task = modify(&mut database_manager, 1, None, Some(""))?;
+    assert_eq!(task.estimate, None);
+    assert_eq!(task.get_property_value("estimate")?, "");
+
+    database_manager.undo()?;
+    let tasks = database_manager.get(&None, &None, &vec![], &None, &None, &Some(1), &None)?;
+    assert_eq!(tasks[0].estimate, Some(120));
+    Ok(())
+}
+
+#[test]
+fn test_effort_of_tasks() -> Result<(), CoreError> {
+    let mut database_manager = TaskManager::new(&get_setting())?;
+    add_task(&mut database_manager, "Write the report", None, Some("1h"))?;
+    add_task(&mut database_manager, "Call the vendor", None, Some("30m"))?;
+    add_task(&mut database_manager, "Read the mail", None, None)?;
+    database_manager.add_interval(1, "2026-07-01 09:00:00", "2026-07-01 09:45:00")?;
+    database_manager.add_interval(3, "2026-07-01 10:00:00", "2026-07-01 11:00:00")?;
+    modify(&mut database_manager, 2, Some("completed"), None)?;
+
+    let tasks = database_manager.get(&None, &None, &vec![], &None, &None, &None, &None)?;
+    assert_eq!(tasks[0].time_tracked, 2700);
+    assert_eq!(tasks[0].get_property_value("time_tracked")?, "45m");
+    assert_eq!(tasks[2].time_tracked, 3600);
+
+    // The task without an estimate doesn't count
+    assert_eq!(
+        Effort::of(&tasks),
+        Effort {
+            remaining: 60,
+            estimated: 90,
+            tracked: 2700,
+        }
+    );
+    Ok(())
+}
+
+#[test]
+fn test_agenda_effort_per_context() -> Result<(), CoreError> {
+    let mut database_manager = TaskManager::new(&get_setting())?;
+    add_task(&mut database_manager, "Write the report", Some("work"), Some("2h"))?;
+    add_task(&mut database_manager, "Review the budget", Some("work"), Some("45m"))?;
+    add_task(&mut database_manager, "Book the flights", None, Some("15m"))?;
+    add_task(&mut database_manager, "Read the mail", Some("life"), None)?;
+
+    let mut operation = Agenda::new_with_store(
+        String::from("today"),
+        None,
+        None,
+        Some(database_manager),
+    );
+    execute_agenda(&mut operation)?;
+    let efforts = operation.get_effort();
+    assert_eq!(efforts.len(), 1);
+    assert_eq!(efforts[0].0, Local::now().naive_local().date());
+
+    let contexts: Vec<(&str, i64)> = efforts[0]
+        .1
+        .iter()
+        .map(|(context, effort)| (context.as_str(), effort.remaining))
+        .collect();
+    assert_eq!(contexts, vec![("inbox", 15), ("life", 0), ("work", 165)]);
+    Ok(())
+}
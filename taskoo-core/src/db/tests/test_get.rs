@@ -17,7 +17,7 @@ fn get_setting() -> HashMap<String, String> {
 
 #[test]
 fn test_get_simple() -> Result<(), CoreError> {
-    let mut database_manager = TaskManager::new(&get_setting());
+    let mut database_manager = TaskManager::new(&get_setting())?;
 
     let mut operation = Add::new_with_task_manager("Test Body", &mut database_manager);
 
@@ -52,7 +52,7 @@ fn test_get_simple() -> Result<(), CoreError> {
 
 #[test]
 fn test_get_all_for_context() -> Result<(), CoreError> {
-    let mut database_manager = TaskManager::new(&get_setting());
+    let mut database_manager = TaskManager::new(&get_setting())?;
     let mut operation = Add::new_with_task_manager("Test Body", &mut database_manager);
     operation.context = Some(String::from("Work"));
     execute(&mut operation)?;
@@ -83,7 +83,7 @@ fn test_get_all_for_context() -> Result<(), CoreError> {
 
 #[test]
 fn test_get_with_tag_ids() -> Result<(), CoreError> {
-    let mut database_manager = TaskManager::new(&get_setting());
+    let mut database_manager = TaskManager::new(&get_setting())?;
 
     let mut operation = Add::new_with_task_manager("Test Body", &mut database_manager);
     execute(&mut operation)?;
@@ -121,7 +121,7 @@ fn test_get_with_tag_ids() -> Result<(), CoreError> {
 
 #[test]
 fn test_get_with_not_tag_ids() -> Result<(), CoreError> {
-    let mut database_manager = TaskManager::new(&get_setting());
+    let mut database_manager = TaskManager::new(&get_setting())?;
 
     let mut operation = Add::new_with_task_manager("Test Body", &mut database_manager);
     execute(&mut operation)?;
@@ -157,7 +157,7 @@ fn test_get_with_not_tag_ids() -> Result<(), CoreError> {
 
 #[test]
 fn test_get_with_priority() -> Result<(), CoreError> {
-    let mut database_manager = TaskManager::new(&get_setting());
+    let mut database_manager = TaskManager::new(&get_setting())?;
 
     let mut operation = Add::new_with_task_manager("High Priority", &mut database_manager);
     operation.priority = Some(String::from("H"));
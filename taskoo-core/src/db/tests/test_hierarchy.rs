@@ -0,0 +1,191 @@
+use rusqlite::Result;
+use std::collections::HashMap;
+
+use crate::command::{ContextCommand, SimpleCommand, TagCommand};
+use crate::core::Operation;
+use crate::db::task_helper::{
+    get_label_ancestors, is_valid_label_name, is_within_label, sort_label_tree, Task,
+};
+use crate::db::task_manager::TaskManager;
+use crate::error::CoreError;
+use crate::operation::{execute, Add, Get};
+
+fn get_setting() -> HashMap<String, String> {
+    let mut setting = HashMap::new();
+    setting.insert("db_path".to_owned(), ":memory:".to_owned());
+    setting.insert("context".to_owned(), "inbox, work, life".to_owned());
+    return setting;
+}
+
+fn add_task(
+    database_manager: &mut TaskManager,
+    body: &str,
+    context: &str,
+    tags: &[&str],
+) -> Result<Task, CoreError> {
+    let mut operation = Add::new_with_task_manager(body, database_manager);
+    operation.context = Some(context.to_string());
+    operation.tags = tags.iter().map(|tag| tag.to_string()).collect();
+    operation.date_due = Some("2026-07-01");
+    execute(&mut operation)?;
+    Ok(operation.get_result()[0].clone())
+}
+
+// Call the client (1) in work.clienta, Ship the release (2) in
+// work.clientb.backend and Buy screws (3) in life
+fn add_tasks(database_manager: &mut TaskManager) -> Result<(), CoreError> {
+    add_task(database_manager, "Call the client", "work.clientA", &["errand.phone"])?;
+    add_task(database_manager, "Ship the release", "work.clientb.backend", &["urgent"])?;
+    add_task(database_manager, "Buy screws", "life", &["errand.hardware", "urgent"])?;
+    Ok(())
+}
+
+fn get(
+    database_manager: TaskManager,
+    context: &str,
+    tags: &[&str],
+    not_tags: &[&str],
+) -> Result<Vec<String>, CoreError> {
+    let mut operation = Get::new_with_store(Some(database_manager));
+    operation.context = Some(context.to_string());
+    operation.tags = tags.iter().map(|tag| tag.to_string()).collect();
+    if !not_tags.is_empty() {
+        operation.not_tags = Some(not_tags.iter().map(|tag| tag.to_string()).collect());
+    }
+    execute(&mut operation)?;
+    Ok(operation.get_result().iter().map(|task| task.body.clone()).collect())
+}
+
+#[test]
+fn test_label_names() {
+    assert!(is_valid_label_name("work.clienta"));
+    for invalid in [".work", "work.", "work..clienta"] {
+        assert!(!is_valid_label_name(invalid), "{} is invalid", invalid);
+    }
+    assert!(is_within_label("work.clienta", "work"));
+    assert!(is_within_label("work", "work"));
+    assert!(!is_within_label("workshop", "work"));
+    assert_eq!(get_label_ancestors("a.b.c"), vec!["a", "a.b"]);
+
+    let mut names = vec!["work.a", "work-b", "life", "work", "work.a.x"]
+        .into_iter()
+        .map(String::from)
+        .collect();
+    sort_label_tree(&mut names);
+    assert_eq!(names, vec!["life", "work", "work.a", "work.a.x", "work-b"]);
+}
+
+#[test]
+fn test_filter_by_parent_context() -> Result<(), CoreError> {
+    let mut database_manager = TaskManager::new(&get_setting())?;
+    add_tasks(&mut database_manager)?;
+    assert!(add_task(&mut database_manager, "Call", "work..clienta", &[]).is_err());
+    assert!(add_task(&mut database_manager, "Call", "inbox", &["errand."]).is_err());
+
+    // The parents of a context are created with it
+    let mut command = ContextCommand::new_with_manager(&mut database_manager);
+    assert_eq!(
+        command.get_tree(&None)?,
+        vec!["inbox", "life", "work", "work.clienta", "work.clientb", "work.clientb.backend"]
+    );
+    assert_eq!(
+        command.get_tree(&Some(vec![String::from("Work.ClientB")]))?,
+        vec!["work.clientb", "work.clientb.backend"]
+    );
+    let bodies = get(database_manager, "work", &[], &[])?;
+    assert_eq!(bodies, vec!["Call the client", "Ship the release"]);
+
+    let mut database_manager = TaskManager::new(&get_setting())?;
+    add_tasks(&mut database_manager)?;
+    let mut operation = Get::new_with_store(Some(database_manager));
+    operation.context = Some(String::from("work"));
+    operation.exact_context = true;
+    execute(&mut operation)?;
+    assert!(operation.get_result().is_empty());
+    Ok(())
+}
+
+#[test]
+fn test_filter_by_parent_tag() -> Result<(), CoreError> {
+    let bodies = |context: &str, tags: &[&str], not_tags: &[&str]| {
+        let mut database_manager = TaskManager::new(&get_setting())?;
+        add_tasks(&mut database_manager)?;
+        get(database_manager, context, tags, not_tags)
+    };
+    assert_eq!(bodies("life", &["errand"], &[])?, vec!["Buy screws"]);
+    assert_eq!(bodies("work", &["errand"], &[])?, vec!["Call the client"]);
+    assert_eq!(bodies("life", &["errand", "urgent"], &[])?, vec!["Buy screws"]);
+    assert!(bodies("work", &["errand", "urgent"], &[])?.is_empty());
+    assert_eq!(bodies("work", &[], &["errand"])?, vec!["Ship the release"]);
+    assert!(bodies("life", &["errand.phone"], &[])?.is_empty());
+    Ok(())
+}
+
+#[test]
+fn test_agenda_and_archive_of_parent_context() -> Result<(), CoreError> {
+    let mut database_manager = TaskManager::new(&get_setting())?;
+    add_tasks(&mut database_manager)?;
+    let agenda = database_manager.view_agenda(
+        String::from("2026-07-01"),
+        None,
+        Some(String::from("work")),
+    )?;
+    assert_eq!(agenda[0].1.len(), 2);
+
+    database_manager.modify(
+        &vec![1, 2, 3],
+        &None,
+        &None,
+        &None,
+        &vec![],
+        &None,
+        &None,
+        &None,
+        &None,
+        &Some("completed"),
+        &vec![],
+        &None,
+        &vec![],
+        &None,
+        &None,
+    )?;
+    database_manager.archive(&Some(0))?;
+    let archived = database_manager.get_archived(
+        &None,
+        &Some(String::from("work")),
+        &vec![String::from("errand")],
+        &None,
+        &None,
+        &None,
+        &None,
+    )?;
+    assert_eq!(archived.len(), 1);
+    assert_eq!(archived[0].body, "Call the client");
+    Ok(())
+}
+
+#[test]
+fn test_count_rolls_up_to_parents() -> Result<(), CoreError> {
+    let mut database_manager = TaskManager::new(&get_setting())?;
+    add_tasks(&mut database_manager)?;
+    {
+        let mut command = ContextCommand::new_with_manager(&mut database_manager);
+        assert_eq!(command.get_count("work")?, 2);
+        assert_eq!(command.get_count("work.clientb")?, 1);
+        assert_eq!(command.get_count("work.client")?, 0);
+    }
+    {
+        let mut command = TagCommand::new_with_manager(&mut database_manager);
+        assert_eq!(command.get_count("errand")?, 2);
+        assert_eq!(command.get_count("errand.hardware")?, 1);
+    }
+
+    // A parent is deleted with its subcontexts, which have no tasks left
+    database_manager.delete(&vec![1, 2])?;
+    database_manager.purge(&None)?;
+    let mut command = ContextCommand::new_with_manager(&mut database_manager);
+    assert_eq!(command.get_count("work")?, 0);
+    command.delete(vec![String::from("work")])?;
+    assert_eq!(command.get_all()?, vec!["inbox", "life"]);
+    Ok(())
+}
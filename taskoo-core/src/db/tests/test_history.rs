@@ -0,0 +1,187 @@
+use rusqlite::Result;
+
+use crate::db::history::TaskChange;
+use crate::db::task_manager::TaskManager;
+use crate::error::CoreError;
+use crate::operation::{Add, execute};
+use super::get_setting;
+
+fn modify_state(
+    database_manager: &mut TaskManager,
+    task_ids: &Vec<i64>,
+    state: &str,
+) -> Result<(), CoreError> {
+    database_manager.modify(
+        task_ids,
+        &None,
+        &None,
+        &None,
+        &vec![],
+        &None,
+        &None,
+        &None,
+        &None,
+        &Some(state),
+        &vec![],
+        &None,
+        &vec![],
+        &None,
+        &None,
+    )?;
+    Ok(())
+}
+
+fn changes_of<'a>(changes: &'a Vec<TaskChange>, field: &str) -> Vec<&'a TaskChange> {
+    changes.iter().filter(|change| change.field == field).collect()
+}
+
+#[test]
+fn test_history_add() -> Result<(), CoreError> {
+    let mut database_manager = TaskManager::new(&get_setting())?;
+    let mut operation = Add::new_with_task_manager("Test Body", &mut database_manager);
+    operation.tags = vec!["tag2".to_owned(), "tag1".to_owned()];
+    execute(&mut operation)?;
+
+    let changes = database_manager.history(1)?;
+    let fields: Vec<&str> = changes.iter().map(|change| change.field.as_str()).collect();
+    assert_eq!(fields, ["body", "context", "tags", "state"]);
+    assert!(changes.iter().all(|change| change.old_value.is_empty()));
+    assert_eq!(changes_of(&changes, "tags")[0].new_value, "tag1,tag2");
+    assert_eq!(changes_of(&changes, "state")[0].new_value, "ready");
+    Ok(())
+}
+
+#[test]
+fn test_history_modify() -> Result<(), CoreError> {
+    let mut database_manager = TaskManager::new(&get_setting())?;
+    let mut operation = Add::new_with_task_manager("Test Body", &mut database_manager);
+    operation.tags = vec!["tag1".to_owned()];
+    execute(&mut operation)?;
+
+    database_manager.modify(
+        &vec![1],
+        &None,
+        &Some(String::from("h")),
+        &Some(String::from("Work")),
+        &vec!["tag2".to_owned()],
+        &Some("2020-11-10"),
+        &None,
+        &None,
+        &None,
+        &None,
+        &vec!["tag1".to_owned()],
+        &None,
+        &vec![],
+        &None,
+        &None,
+    )?;
+
+    let changes = database_manager.history(1)?;
+    let changes: Vec<&TaskChange> = changes.iter().skip(4).collect();
+    assert_eq!(changes.len(), 4);
+
+    assert_eq!(changes[0].field, "priority");
+    assert_eq!(changes[0].old_value, "");
+    assert_eq!(changes[0].new_value, "h");
+    assert_eq!(changes[1].field, "context");
+    assert_eq!(changes[1].old_value, "inbox");
+    assert_eq!(changes[1].new_value, "Work");
+    assert_eq!(changes[2].field, "tags");
+    assert_eq!(changes[2].old_value, "tag1");
+    assert_eq!(changes[2].new_value, "tag2");
+    assert_eq!(changes[3].field, "date_due");
+    assert_eq!(changes[3].new_value, "2020-11-10 00:00:00");
+    Ok(())
+}
+
+#[test]
+fn test_history_modify_without_changes() -> Result<(), CoreError> {
+    let mut database_manager = TaskManager::new(&get_setting())?;
+    let mut operation = Add::new_with_task_manager("Test Body", &mut database_manager);
+    execute(&mut operation)?;
+
+    let recorded = database_manager.history(1)?.len();
+    modify_state(&mut database_manager, &vec![1], "ready")?;
+    assert_eq!(database_manager.history(1)?.len(), recorded);
+    Ok(())
+}
+
+#[test]
+fn test_history_complete_repeat_task() -> Result<(), CoreError> {
+    let mut database_manager = TaskManager::new(&get_setting())?;
+    let mut operation = Add::new_with_task_manager("Test Body", &mut database_manager);
+    operation.date_due = Some("2020-11-10");
+    operation.repetition_due = Some("1weeks");
+    execute(&mut operation)?;
+
+    modify_state(&mut database_manager, &vec![1], "completed")?;
+
+    let changes = database_manager.history(1)?;
+    let states = changes_of(&changes, "state");
+    assert_eq!(states.len(), 3);
+    assert_eq!(states[1].old_value, "ready");
+    assert_eq!(states[1].new_value, "completed");
+    assert_eq!(states[2].old_value, "completed");
+    assert_eq!(states[2].new_value, "ready");
+
+    let due_dates = changes_of(&changes, "date_due");
+    assert_eq!(due_dates.len(), 2);
+    assert_eq!(due_dates[1].old_value, "2020-11-10 00:00:00");
+    assert_ne!(due_dates[1].new_value, "2020-11-10 00:00:00");
+    Ok(())
+}
+
+#[test]
+fn test_history_unblocked_by_dependency() -> Result<(), CoreError> {
+    let mut database_manager = TaskManager::new(&get_setting())?;
+    let mut operation = Add::new_with_task_manager("Parent", &mut database_manager);
+    execute(&mut operation)?;
+    let mut operation = Add::new_with_task_manager("Child", &mut database_manager);
+    operation.parent_task_ids = Some(vec![1]);
+    execute(&mut operation)?;
+
+    modify_state(&mut database_manager, &vec![1], "completed")?;
+
+    let changes = database_manager.history(2)?;
+    let states = changes_of(&changes, "state");
+    assert_eq!(states.len(), 2);
+    assert_eq!(states[0].new_value, "blocked");
+    assert_eq!(states[1].old_value, "blocked");
+    assert_eq!(states[1].new_value, "ready");
+    Ok(())
+}
+
+#[test]
+fn test_history_purged_task() -> Result<(), CoreError> {
+    let mut database_manager = TaskManager::new(&get_setting())?;
+    let mut operation = Add::new_with_task_manager("Test Body", &mut database_manager);
+    execute(&mut operation)?;
+    database_manager.delete(&vec![1])?;
+    assert!(database_manager.history(1).is_err());
+
+    database_manager.purge(&None)?;
+
+    // The id is reused by the next task, which starts with a clean history
+    let mut operation = Add::new_with_task_manager("New Body", &mut database_manager);
+    execute(&mut operation)?;
+    let changes = database_manager.history(1)?;
+    assert_eq!(changes_of(&changes, "body")[0].new_value, "New Body");
+    assert_eq!(changes_of(&changes, "body").len(), 1);
+    Ok(())
+}
+
+#[test]
+fn test_history_modify_missing_task() -> Result<(), CoreError> {
+    let mut database_manager = TaskManager::new(&get_setting())?;
+    let mut operation = Add::new_with_task_manager("Test Body", &mut database_manager);
+    execute(&mut operation)?;
+
+    // Nothing is modified or recorded when one of the tasks doesn't exist
+    let recorded = database_manager.history(1)?.len();
+    match modify_state(&mut database_manager, &vec![1, 5], "completed") {
+        Err(CoreError::ArgumentError(message)) => assert_eq!(message, "Task 5 doesn't exist"),
+        result => panic!("Expected an ArgumentError, got {:?}", result),
+    }
+    assert_eq!(database_manager.history(1)?.len(), recorded);
+    Ok(())
+}
@@ -0,0 +1,299 @@
+use rusqlite::Result;
+
+use crate::db::task_manager::TaskManager;
+use crate::operation::{Add, execute};
+use crate::error::CoreError;
+use super::get_setting;
+
+fn get_all(database_manager: &mut TaskManager) -> Vec<crate::db::task_helper::Task> {
+    database_manager
+        .get(&None, &None, &vec![], &None, &None, &None, &None)
+        .unwrap()
+}
+
+fn get_one(database_manager: &mut TaskManager, task_id: i64) -> crate::db::task_helper::Task {
+    let mut tasks = database_manager
+        .get(&None, &None, &vec![], &None, &None, &Some(task_id), &None)
+        .unwrap();
+    assert_eq!(tasks.len(), 1);
+    tasks.remove(0)
+}
+
+fn modify_context_and_tags(
+    database_manager: &mut TaskManager,
+    task_ids: &Vec<i64>,
+    context: &str,
+    tags: Vec<String>,
+    tags_to_remove: Vec<String>,
+) -> Result<(), CoreError> {
+    database_manager.modify(
+        task_ids,
+        &None,
+        &None,
+        &Some(context.to_string()),
+        &tags,
+        &None,
+        &None,
+        &None,
+        &None,
+        &None,
+        &tags_to_remove,
+        &None,
+        &vec![],
+        &None,
+        &None,
+    )?;
+    Ok(())
+}
+
+fn modify_state(
+    database_manager: &mut TaskManager,
+    task_ids: &Vec<i64>,
+    state: &str,
+) -> Result<(), CoreError> {
+    database_manager.modify(
+        task_ids,
+        &None,
+        &None,
+        &None,
+        &vec![],
+        &None,
+        &None,
+        &None,
+        &None,
+        &Some(state),
+        &vec![],
+        &None,
+        &vec![],
+        &None,
+        &None,
+    )?;
+    Ok(())
+}
+
+#[test]
+fn test_undo_add() -> Result<(), CoreError> {
+    let mut database_manager = TaskManager::new(&get_setting())?;
+    let mut operation = Add::new_with_task_manager("Test Body", &mut database_manager);
+    operation.tags = vec!["tag1".to_owned()];
+    execute(&mut operation)?;
+
+    let tasks = database_manager.undo()?;
+    assert_eq!(tasks.len(), 1);
+    assert_eq!(tasks[0].body, "Test Body");
+    assert!(get_all(&mut database_manager).is_empty());
+
+    let tasks = database_manager.redo()?;
+    assert_eq!(tasks.len(), 1);
+    let task = get_one(&mut database_manager, 1);
+    assert_eq!(task.body, "Test Body");
+    assert_eq!(task.tags, vec!["tag1"]);
+    Ok(())
+}
+
+#[test]
+fn test_undo_modify() -> Result<(), CoreError> {
+    let mut database_manager = TaskManager::new(&get_setting())?;
+    for body in ["Task 1", "Task 2"].iter() {
+        let mut operation = Add::new_with_task_manager(body, &mut database_manager);
+        operation.tags = vec!["old".to_owned()];
+        execute(&mut operation)?;
+    }
+
+    modify_context_and_tags(
+        &mut database_manager,
+        &vec![1, 2],
+        "wrok",
+        vec!["new".to_string()],
+        vec!["old".to_string()],
+    )?;
+    assert_eq!(get_one(&mut database_manager, 1).context, "wrok");
+
+    let tasks = database_manager.undo()?;
+    assert_eq!(tasks.len(), 2);
+    for task_id in [1, 2].iter() {
+        let task = get_one(&mut database_manager, *task_id);
+        assert_eq!(task.context, "inbox");
+        assert_eq!(task.tags, vec!["old"]);
+    }
+
+    database_manager.redo()?;
+    for task_id in [1, 2].iter() {
+        let task = get_one(&mut database_manager, *task_id);
+        assert_eq!(task.context, "wrok");
+        assert_eq!(task.tags, vec!["new"]);
+    }
+    Ok(())
+}
+
+#[test]
+fn test_undo_delete() -> Result<(), CoreError> {
+    let mut database_manager = TaskManager::new(&get_setting())?;
+    let mut operation = Add::new_with_task_manager("Parent", &mut database_manager);
+    operation.priority = Some(String::from("H"));
+    execute(&mut operation)?;
+    let mut operation = Add::new_with_task_manager("Child", &mut database_manager);
+    operation.tags = vec!["tag1".to_owned()];
+    operation.parent_task_ids = Some(vec![1]);
+    execute(&mut operation)?;
+
+    database_manager.delete(&vec![1, 2])?;
+    assert!(get_all(&mut database_manager).is_empty());
+
+    let tasks = database_manager.undo()?;
+    assert_eq!(tasks.len(), 2);
+
+    let parent = get_one(&mut database_manager, 1);
+    assert_eq!(parent.priority, "h");
+    let child = get_one(&mut database_manager, 2);
+    assert_eq!(child.tags, vec!["tag1"]);
+    assert_eq!(child.parent_task_ids, vec!["1"]);
+    assert!(child.is_blocked());
+    Ok(())
+}
+
+#[test]
+fn test_undo_annotation() -> Result<(), CoreError> {
+    let mut database_manager = TaskManager::new(&get_setting())?;
+    let mut operation = Add::new_with_task_manager("Test Body", &mut database_manager);
+    execute(&mut operation)?;
+
+    database_manager.add_annotation(1, String::from("First"))?;
+    database_manager.add_annotation(1, String::from("Second"))?;
+
+    database_manager.undo()?;
+    assert_eq!(get_one(&mut database_manager, 1).annotation, "First");
+    database_manager.undo()?;
+    assert_eq!(get_one(&mut database_manager, 1).annotation, "");
+    Ok(())
+}
+
+#[test]
+fn test_undo_complete_restores_dependent_state() -> Result<(), CoreError> {
+    let mut database_manager = TaskManager::new(&get_setting())?;
+    let mut operation = Add::new_with_task_manager("Parent", &mut database_manager);
+    execute(&mut operation)?;
+    let mut operation = Add::new_with_task_manager("Child", &mut database_manager);
+    operation.parent_task_ids = Some(vec![1]);
+    execute(&mut operation)?;
+    assert!(get_one(&mut database_manager, 2).is_blocked());
+
+    modify_state(&mut database_manager, &vec![1], "completed")?;
+    assert!(get_one(&mut database_manager, 2).is_ready());
+
+    let tasks = database_manager.undo()?;
+    assert_eq!(tasks.len(), 2);
+    assert!(get_one(&mut database_manager, 1).is_ready());
+    assert!(get_one(&mut database_manager, 2).is_blocked());
+    Ok(())
+}
+
+#[test]
+fn test_new_change_clears_redo() -> Result<(), CoreError> {
+    let mut database_manager = TaskManager::new(&get_setting())?;
+    let mut operation = Add::new_with_task_manager("Test Body", &mut database_manager);
+    execute(&mut operation)?;
+
+    modify_state(&mut database_manager, &vec![1], "started")?;
+    database_manager.undo()?;
+    modify_state(&mut database_manager, &vec![1], "blocked")?;
+
+    assert!(database_manager.redo().is_err());
+    assert!(get_one(&mut database_manager, 1).is_blocked());
+    Ok(())
+}
+
+#[test]
+fn test_nothing_to_undo() -> Result<(), CoreError> {
+    let mut database_manager = TaskManager::new(&get_setting())?;
+    assert!(database_manager.undo().is_err());
+    assert!(database_manager.redo().is_err());
+    Ok(())
+}
+
+#[test]
+fn test_undo_modify_keeps_history() -> Result<(), CoreError> {
+    let mut database_manager = TaskManager::new(&get_setting())?;
+    let mut operation = Add::new_with_task_manager("Task 1", &mut database_manager);
+    execute(&mut operation)?;
+    modify_context_and_tags(&mut database_manager, &vec![1], "work", vec![], vec![])?;
+    let history = database_manager.history(1)?;
+    assert!(history.iter().any(|change| change.new_value == "work"));
+
+    // The history records that the context changed and changed back
+    database_manager.undo()?;
+    assert_eq!(get_one(&mut database_manager, 1).context, "inbox");
+    let history_after_undo = database_manager.history(1)?;
+    assert_eq!(history_after_undo.len(), history.len() + 1);
+    let last = history_after_undo.last().unwrap();
+    assert_eq!(last.field, "context");
+    assert_eq!((last.old_value.as_str(), last.new_value.as_str()), ("work", "inbox"));
+    Ok(())
+}
+
+#[test]
+fn test_undo_purge_keeps_the_task_that_took_the_id() -> Result<(), CoreError> {
+    let mut database_manager = TaskManager::new(&get_setting())?;
+    let mut operation = Add::new_with_task_manager("Purged", &mut database_manager);
+    execute(&mut operation)?;
+    let purged_uuid = get_one(&mut database_manager, 1).uuid;
+    database_manager.delete(&vec![1])?;
+    database_manager.purge(&None)?;
+
+    let mut operation = Add::new_with_task_manager("Reused", &mut database_manager);
+    execute(&mut operation)?;
+    assert_eq!(get_one(&mut database_manager, 1).body, "Reused");
+    // The entry of the add is trimmed from the journal, the purge is the
+    // latest change to undo
+    database_manager
+        .conn
+        .execute("DELETE FROM journal WHERE operation = 'add'", [])?;
+
+    let tasks = database_manager.undo()?;
+    assert_eq!(tasks.len(), 1);
+    assert_eq!(tasks[0].uuid, purged_uuid);
+    assert_eq!(get_one(&mut database_manager, 1).body, "Reused");
+    let restored_id = database_manager.get_task_ids_by_uuid(&vec![purged_uuid.clone()])?;
+    assert_eq!(restored_id, vec![2]);
+    assert_eq!(get_one(&mut database_manager, 2).body, "Purged");
+
+    // Purging it again removes the restored task only
+    database_manager.redo()?;
+    assert_eq!(get_all(&mut database_manager).len(), 1);
+    assert_eq!(get_one(&mut database_manager, 1).body, "Reused");
+    Ok(())
+}
+
+#[test]
+fn test_undo_partial_purge() -> Result<(), CoreError> {
+    let mut database_manager = TaskManager::new(&get_setting())?;
+    for body in ["Old", "Recent"] {
+        let mut operation = Add::new_with_task_manager(body, &mut database_manager);
+        execute(&mut operation)?;
+    }
+    database_manager.delete(&vec![1, 2])?;
+    database_manager.conn.execute(
+        "UPDATE task SET deleted_at = '2020-01-01 00:00:00' WHERE id = 1",
+        [],
+    )?;
+    assert_eq!(database_manager.purge(&Some("30days"))?.len(), 1);
+
+    // Only the purged task is taken back, the one left in the trash isn't
+    // part of the purge
+    let tasks = database_manager.undo()?;
+    assert_eq!(tasks.len(), 1);
+    assert_eq!(tasks[0].body, "Old");
+    let mut trash: Vec<String> = database_manager
+        .trash()?
+        .iter()
+        .map(|task| task.body.clone())
+        .collect();
+    trash.sort();
+    assert_eq!(trash, vec!["Old", "Recent"]);
+
+    database_manager.redo()?;
+    let trash = database_manager.trash()?;
+    assert_eq!(trash.len(), 1);
+    assert_eq!(trash[0].body, "Recent");
+    Ok(())
+}
@@ -0,0 +1,285 @@
+use rusqlite::{named_params, Connection, Result, Transaction};
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use crate::db::migration::{current_version, latest_version, migrate_to, Migration, MIGRATIONS};
+use crate::db::query_helper::{
+    CREATE_CONTEXT_TABLE_QUERY, CREATE_DEPENDENCY_TABLE_QUERY, CREATE_STATE_TABLE_QUERY,
+    CREATE_TAG_TABLE_QUERY, CREATE_TASK_TABLE_QUERY, CREATE_TASK_TAG_TABLE_QUERY,
+    CREATE_PRIORITY_TABLE_QUERY, CREATE_PRIORITY_TASK_TABLE_QUERY, CREATE_TASK_CONTEXT_TABLE_QUERY,
+    CREATE_TASK_STATE_TABLE_QUERY,
+};
+use crate::db::task_manager::TaskManager;
+use crate::error::CoreError;
+
+fn get_setting(db_path: &PathBuf) -> HashMap<String, String> {
+    let mut setting = HashMap::new();
+    setting.insert("db_path".to_owned(), db_path.to_str().unwrap().to_owned());
+    setting.insert("context".to_owned(), "inbox, Work, Life".to_owned());
+    return setting;
+}
+
+// Fixture databases live in a file, because the upgrade path we care
+// about is `TaskManager::new` opening a database created by an older
+// taskoo.
+fn fixture_path(name: &str) -> PathBuf {
+    let mut path = std::env::temp_dir();
+    path.push(format!("taskoo_{}_{}.db", name, std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    path
+}
+
+// Insert a task with the columns of the very first schema, so the
+// same fixture works for every version.
+fn insert_fixture_task(conn: &Connection) -> Result<(), CoreError> {
+    conn.execute("INSERT OR IGNORE INTO context (id, name) VALUES (1, 'inbox')", [])?;
+    conn.execute("INSERT OR IGNORE INTO state (id, name) VALUES (1, 'ready')", [])?;
+    conn.execute(
+        "INSERT INTO task (id, body, due_date, scheduled_at, due_repeat, scheduled_repeat, annotation)
+        VALUES (1, :body, '', '', '', '', 'Old annotation')",
+        named_params! {":body": "Task from an old database"},
+    )?;
+    conn.execute("INSERT INTO task_context (task_id, context_id) VALUES (1, 1)", [])?;
+    conn.execute("INSERT INTO task_state (task_id, state_id) VALUES (1, 1)", [])?;
+    Ok(())
+}
+
+// A database created before `schema_version` existed
+fn create_legacy_database(path: &PathBuf) -> Result<(), CoreError> {
+    let conn = Connection::open(path)?;
+    for query in [
+        CREATE_TASK_TABLE_QUERY,
+        CREATE_TAG_TABLE_QUERY,
+        CREATE_TASK_TAG_TABLE_QUERY,
+        CREATE_DEPENDENCY_TABLE_QUERY,
+        CREATE_CONTEXT_TABLE_QUERY,
+        CREATE_TASK_CONTEXT_TABLE_QUERY,
+        CREATE_STATE_TABLE_QUERY,
+        CREATE_TASK_STATE_TABLE_QUERY,
+        CREATE_PRIORITY_TABLE_QUERY,
+        CREATE_PRIORITY_TASK_TABLE_QUERY,
+    ]
+    .iter()
+    {
+        conn.execute(query, [])?;
+    }
+    insert_fixture_task(&conn)?;
+    Ok(())
+}
+
+fn create_database_at_version(path: &PathBuf, version: i64) -> Result<(), CoreError> {
+    let mut conn = Connection::open(path)?;
+    migrate_to(&mut conn, MIGRATIONS, version)?;
+    assert_eq!(current_version(&conn)?, version);
+    insert_fixture_task(&conn)?;
+    Ok(())
+}
+
+fn assert_fixture_is_upgraded(path: &PathBuf) -> Result<(), CoreError> {
+    let mut database_manager = TaskManager::new(&get_setting(path))?;
+    assert_eq!(
+        current_version(&database_manager.conn)?,
+        latest_version(MIGRATIONS)
+    );
+
+    let tasks = database_manager.get(&None, &None, &vec![], &None, &None, &Some(1), &None)?;
+    assert_eq!(tasks.len(), 1);
+    assert_eq!(tasks[0].body, "Task from an old database");
+    assert_eq!(tasks[0].annotation, "Old annotation");
+    assert_eq!(tasks[0].context, "inbox");
+    assert_eq!(tasks[0].state, "ready");
+    Ok(())
+}
+
+#[test]
+fn test_new_database_is_at_latest_version() -> Result<(), CoreError> {
+    let mut setting = HashMap::new();
+    setting.insert("db_path".to_owned(), ":memory:".to_owned());
+    let database_manager = TaskManager::new(&setting)?;
+
+    let mut statement = database_manager
+        .conn
+        .prepare("SELECT version FROM schema_version ORDER BY version")?;
+    let mut rows = statement.query([])?;
+    let mut versions: Vec<i64> = vec![];
+    while let Some(row) = rows.next()? {
+        versions.push(row.get(0)?);
+    }
+
+    let expected: Vec<i64> = MIGRATIONS.iter().map(|migration| migration.version).collect();
+    assert_eq!(versions, expected);
+    Ok(())
+}
+
+#[test]
+fn test_migrations_are_ordered() {
+    for pair in MIGRATIONS.windows(2) {
+        assert_eq!(pair[0].version + 1, pair[1].version);
+    }
+    assert_eq!(MIGRATIONS[0].version, 1);
+}
+
+#[test]
+fn test_upgrade_legacy_database() -> Result<(), CoreError> {
+    let path = fixture_path("legacy");
+    create_legacy_database(&path)?;
+    assert_fixture_is_upgraded(&path)?;
+    std::fs::remove_file(&path).ok();
+    Ok(())
+}
+
+#[test]
+fn test_upgrade_from_every_older_version() -> Result<(), CoreError> {
+    for version in 1..=latest_version(MIGRATIONS) {
+        let path = fixture_path(&format!("version_{}", version));
+        create_database_at_version(&path, version)?;
+        assert_fixture_is_upgraded(&path)?;
+        std::fs::remove_file(&path).ok();
+    }
+    Ok(())
+}
+
+#[test]
+fn test_opening_an_upgraded_database_again_is_a_noop() -> Result<(), CoreError> {
+    let path = fixture_path("reopen");
+    create_legacy_database(&path)?;
+    assert_fixture_is_upgraded(&path)?;
+    assert_fixture_is_upgraded(&path)?;
+
+    let conn = Connection::open(&path)?;
+    let count: i64 = conn.query_row("SELECT COUNT(*) FROM schema_version", [], |row| row.get(0))?;
+    assert_eq!(count, latest_version(MIGRATIONS));
+    std::fs::remove_file(&path).ok();
+    Ok(())
+}
+
+#[test]
+fn test_uuids_are_backfilled() -> Result<(), CoreError> {
+    let path = fixture_path("uuid");
+    create_database_at_version(&path, 7)?;
+    let conn = Connection::open(&path)?;
+    conn.execute(
+        "INSERT INTO task (id, body, due_date, scheduled_at, due_repeat, scheduled_repeat, annotation)
+        VALUES (2, 'Another old task', '', '', '', '', '')",
+        [],
+    )?;
+    conn.execute("INSERT INTO task_context (task_id, context_id) VALUES (2, 1)", [])?;
+    conn.execute("INSERT INTO task_state (task_id, state_id) VALUES (2, 1)", [])?;
+    drop(conn);
+
+    let mut database_manager = TaskManager::new(&get_setting(&path))?;
+    let tasks = database_manager.get(&None, &None, &vec![], &None, &None, &None, &None)?;
+    assert_eq!(tasks.len(), 2);
+    assert_eq!(tasks[0].uuid.len(), 36);
+    assert_eq!(tasks[1].uuid.len(), 36);
+    assert_ne!(tasks[0].uuid, tasks[1].uuid);
+    std::fs::remove_file(&path).ok();
+    Ok(())
+}
+
+fn create_broken_table(tx: &Transaction) -> Result<(), CoreError> {
+    tx.execute("CREATE TABLE half_done (id integer primary key)", [])?;
+    tx.execute("ALTER TABLE missing_table ADD COLUMN name Text", [])?;
+    Ok(())
+}
+
+#[test]
+fn test_failed_migration_is_rolled_back() -> Result<(), CoreError> {
+    let migrations = [
+        Migration {
+            version: 1,
+            description: "Create the initial tables",
+            up: MIGRATIONS[0].up,
+        },
+        Migration {
+            version: 2,
+            description: "Broken migration",
+            up: create_broken_table,
+        },
+    ];
+
+    let mut conn = Connection::open_in_memory()?;
+    let result = migrate_to(&mut conn, &migrations, 2);
+    match result {
+        Err(CoreError::MigrationError { version, reason }) => {
+            assert_eq!(version, 2);
+            assert!(reason.contains("Broken migration"));
+        }
+        _ => panic!("The broken migration should fail"),
+    }
+
+    assert_eq!(current_version(&conn)?, 1);
+    let count: i64 = conn.query_row(
+        "SELECT COUNT(*) FROM sqlite_master WHERE name = 'half_done'",
+        [],
+        |row| row.get(0),
+    )?;
+    assert_eq!(count, 0);
+    Ok(())
+}
+
+#[test]
+fn test_newer_database_is_rejected() -> Result<(), CoreError> {
+    let mut conn = Connection::open_in_memory()?;
+    migrate_to(&mut conn, MIGRATIONS, latest_version(MIGRATIONS))?;
+    conn.execute(
+        "INSERT INTO schema_version (version, description) VALUES (:version, 'From the future')",
+        named_params! {":version": latest_version(MIGRATIONS) + 1},
+    )?;
+
+    match migrate_to(&mut conn, MIGRATIONS, latest_version(MIGRATIONS)) {
+        Err(CoreError::MigrationError { version, .. }) => {
+            assert_eq!(version, latest_version(MIGRATIONS) + 1)
+        }
+        _ => panic!("A database from a newer taskoo should be rejected"),
+    }
+    Ok(())
+}
+
+#[test]
+fn test_opening_a_newer_database_returns_the_error() -> Result<(), CoreError> {
+    let path = fixture_path("newer");
+    let mut conn = Connection::open(&path)?;
+    migrate_to(&mut conn, MIGRATIONS, latest_version(MIGRATIONS))?;
+    conn.execute(
+        "INSERT INTO schema_version (version, description) VALUES (:version, 'From the future')",
+        named_params! {":version": latest_version(MIGRATIONS) + 1},
+    )?;
+    drop(conn);
+
+    match TaskManager::new(&get_setting(&path)) {
+        Err(CoreError::MigrationError { version, .. }) => {
+            assert_eq!(version, latest_version(MIGRATIONS) + 1)
+        }
+        _ => panic!("Opening a database from a newer taskoo should fail"),
+    }
+    std::fs::remove_file(&path).ok();
+    Ok(())
+}
+
+#[test]
+fn test_timestamps_are_backfilled() -> Result<(), CoreError> {
+    let path = fixture_path("timestamps");
+    create_database_at_version(&path, 9)?;
+    let conn = Connection::open(&path)?;
+    conn.execute_batch(
+        "INSERT INTO state (id, name) VALUES (2, 'completed');
+        INSERT INTO task (id, body, due_date, scheduled_at, due_repeat, scheduled_repeat, annotation)
+        VALUES (2, 'Completed old task', '', '', '', '', '');
+        INSERT INTO task_context (task_id, context_id) VALUES (2, 1);
+        INSERT INTO task_state (task_id, state_id) VALUES (2, 2);
+        INSERT INTO task_history (task_id, field, old_value, new_value, changed_at)
+        VALUES (2, 'state', 'ready', 'completed', '2021-03-04 05:06:07');",
+    )?;
+    drop(conn);
+
+    let mut database_manager = TaskManager::new(&get_setting(&path))?;
+    let tasks = database_manager.get(&None, &None, &vec![], &None, &None, &None, &None)?;
+    assert_eq!(tasks[0].date_modified, tasks[0].date_created);
+    assert_eq!(tasks[0].date_completed, "");
+    assert_eq!(tasks[1].date_modified, "2021-03-04 05:06:07");
+    assert_eq!(tasks[1].date_completed, "2021-03-04 05:06:07");
+    assert_eq!(tasks[1].date_started, "");
+    std::fs::remove_file(&path).ok();
+    Ok(())
+}
@@ -17,7 +17,7 @@ fn get_setting() -> HashMap<String, String> {
 
 #[test]
 fn test_modify_single() -> Result<(), CoreError> {
-    let mut database_manager = TaskManager::new(&get_setting());
+    let mut database_manager = TaskManager::new(&get_setting())?;
 
     let mut operation = Add::new_with_task_manager("Test Body", &mut database_manager);
     execute(&mut operation)?;
@@ -41,6 +41,10 @@ fn test_modify_single() -> Result<(), CoreError> {
             &None,
             &None,
             &vec![],
+            &None,
+            &vec![],
+            &None,
+            &None,
         )
         .unwrap();
 
@@ -72,7 +76,7 @@ fn test_modify_single() -> Result<(), CoreError> {
 
 #[test]
 fn test_modify_single_with_tag() -> Result<(), CoreError> {
-    let mut database_manager = TaskManager::new(&get_setting());
+    let mut database_manager = TaskManager::new(&get_setting())?;
     let mut operation = Add::new_with_task_manager("Test Body", &mut database_manager);
     execute(&mut operation)?;
 
@@ -95,6 +99,10 @@ fn test_modify_single_with_tag() -> Result<(), CoreError> {
             &None,
             &None,
             &vec![],
+            &None,
+            &vec![],
+            &None,
+            &None,
         )
         .unwrap();
 
@@ -126,7 +134,7 @@ fn test_modify_single_with_tag() -> Result<(), CoreError> {
 
 #[test]
 fn test_modify_tag_only() -> Result<(), CoreError> {
-    let mut database_manager = TaskManager::new(&get_setting());
+    let mut database_manager = TaskManager::new(&get_setting())?;
 
     let mut operation = Add::new_with_task_manager("Test Body", &mut database_manager);
     execute(&mut operation)?;
@@ -149,6 +157,10 @@ fn test_modify_tag_only() -> Result<(), CoreError> {
             &None,
             &None,
             &vec![],
+            &None,
+            &vec![],
+            &None,
+            &None,
         )
         .unwrap();
 
@@ -174,7 +186,7 @@ fn test_modify_tag_only() -> Result<(), CoreError> {
 
 #[test]
 fn test_modify_task_to_complete_should_update_dependency() -> Result<(), CoreError> {
-    let mut database_manager = TaskManager::new(&get_setting());
+    let mut database_manager = TaskManager::new(&get_setting())?;
     let mut operation = Add::new_with_task_manager("Test Body", &mut database_manager);
     execute(&mut operation)?;
 
@@ -201,6 +213,10 @@ fn test_modify_task_to_complete_should_update_dependency() -> Result<(), CoreErr
         &None,
         &Some("completed"),
         &vec![],
+        &None,
+        &vec![],
+        &None,
+        &None,
     )?;
 
     let tasks = database_manager.get(&None, &None, &vec![], &None, &None, &Some(3), &None)?;
@@ -219,6 +235,10 @@ fn test_modify_task_to_complete_should_update_dependency() -> Result<(), CoreErr
         &None,
         &Some("completed"),
         &vec![],
+        &None,
+        &vec![],
+        &None,
+        &None,
     )?;
     let tasks = database_manager.get(&None, &None, &vec![], &None, &None, &Some(3), &None)?;
     assert_eq!(tasks[0].is_blocked(), false);
@@ -228,7 +248,7 @@ fn test_modify_task_to_complete_should_update_dependency() -> Result<(), CoreErr
 
 #[test]
 fn test_modify_multiple_tasks_with_web_command_options() -> Result<(), CoreError> {
-    let mut database_manager = TaskManager::new(&get_setting());
+    let mut database_manager = TaskManager::new(&get_setting())?;
 
     let mut operation = Add::new_with_task_manager("Task One", &mut database_manager);
     execute(&mut operation)?;
@@ -249,6 +269,10 @@ fn test_modify_multiple_tasks_with_web_command_options() -> Result<(), CoreError
         &Some("daily"),
         &Some("started"),
         &vec![],
+        &None,
+        &vec![],
+        &None,
+        &None,
     )?;
 
     for task_id in [1, 2] {
@@ -277,7 +301,7 @@ fn test_modify_multiple_tasks_with_web_command_options() -> Result<(), CoreError
 
 #[test]
 fn test_modify_multiple_tasks_can_remove_tags() -> Result<(), CoreError> {
-    let mut database_manager = TaskManager::new(&get_setting());
+    let mut database_manager = TaskManager::new(&get_setting())?;
 
     let mut operation = Add::new_with_task_manager("Task One", &mut database_manager);
     operation.tags = vec!["Ready".to_string(), "Blocked".to_string()];
@@ -298,6 +322,10 @@ fn test_modify_multiple_tasks_can_remove_tags() -> Result<(), CoreError> {
         &None,
         &None,
         &vec!["blocked".to_string()],
+        &None,
+        &vec![],
+        &None,
+        &None,
     )?;
 
     let tasks = database_manager.get(
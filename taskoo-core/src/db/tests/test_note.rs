@@ -0,0 +1,121 @@
+use rusqlite::Result;
+
+use crate::db::task_manager::TaskManager;
+use crate::db::task_helper::Task;
+use crate::error::CoreError;
+use super::{add_task, get_setting};
+
+fn get_task(database_manager: &mut TaskManager, task_id: i64) -> Result<Task, CoreError> {
+    let mut tasks =
+        database_manager.get(&None, &None, &vec![], &None, &None, &Some(task_id), &None)?;
+    assert_eq!(tasks.len(), 1);
+    Ok(tasks.remove(0))
+}
+
+fn note_bodies(task: &Task) -> Vec<String> {
+    task.notes.iter().map(|note| note.body.clone()).collect()
+}
+
+#[test]
+fn test_add_notes() -> Result<(), CoreError> {
+    let mut database_manager = TaskManager::new(&get_setting())?;
+    add_task(&mut database_manager, "Test Body")?;
+    add_task(&mut database_manager, "Test Body")?;
+
+    database_manager.add_note(1, "called vendor")?;
+    let tasks = database_manager.add_note(1, "vendor called back")?;
+    assert_eq!(tasks.len(), 1);
+    assert_eq!(note_bodies(&tasks[0]), vec!["called vendor", "vendor called back"]);
+
+    let task = get_task(&mut database_manager, 1)?;
+    assert_eq!(task.notes[0].task_id, 1);
+    assert!(!task.notes[0].created_at.is_empty());
+    assert!(task.notes[0].updated_at.is_empty());
+    assert!(get_task(&mut database_manager, 2)?.notes.is_empty());
+
+    // Notes don't replace the annotation
+    assert_eq!(task.annotation, "");
+    Ok(())
+}
+
+#[test]
+fn test_add_note_to_missing_task() -> Result<(), CoreError> {
+    let mut database_manager = TaskManager::new(&get_setting())?;
+    assert!(database_manager.add_note(1, "called vendor").is_err());
+    Ok(())
+}
+
+#[test]
+fn test_edit_and_delete_note() -> Result<(), CoreError> {
+    let mut database_manager = TaskManager::new(&get_setting())?;
+    add_task(&mut database_manager, "Test Body")?;
+    database_manager.add_note(1, "called vendor")?;
+    database_manager.add_note(1, "sent invoice")?;
+
+    let tasks = database_manager.edit_note(1, "called vendor twice")?;
+    assert_eq!(note_bodies(&tasks[0]), vec!["called vendor twice", "sent invoice"]);
+    assert!(!tasks[0].notes[0].updated_at.is_empty());
+
+    let tasks = database_manager.delete_note(1)?;
+    assert_eq!(note_bodies(&tasks[0]), vec!["sent invoice"]);
+
+    assert!(database_manager.edit_note(1, "gone").is_err());
+    assert!(database_manager.delete_note(1).is_err());
+    Ok(())
+}
+
+#[test]
+fn test_undo_note() -> Result<(), CoreError> {
+    let mut database_manager = TaskManager::new(&get_setting())?;
+    add_task(&mut database_manager, "Test Body")?;
+    database_manager.add_note(1, "called vendor")?;
+    database_manager.delete_note(1)?;
+
+    database_manager.undo()?;
+    assert_eq!(
+        note_bodies(&get_task(&mut database_manager, 1)?),
+        vec!["called vendor"]
+    );
+
+    database_manager.undo()?;
+    assert!(get_task(&mut database_manager, 1)?.notes.is_empty());
+
+    database_manager.redo()?;
+    let task = get_task(&mut database_manager, 1)?;
+    assert_eq!(task.notes[0].id, 1);
+    Ok(())
+}
+
+#[test]
+fn test_purge_task_with_notes() -> Result<(), CoreError> {
+    let mut database_manager = TaskManager::new(&get_setting())?;
+    add_task(&mut database_manager, "Test Body")?;
+    database_manager.add_note(1, "called vendor")?;
+
+    database_manager.delete(&vec![1])?;
+    assert_eq!(database_manager.trash()?[0].notes.len(), 1);
+    database_manager.purge(&None)?;
+
+    let count: i64 = database_manager
+        .conn
+        .query_row("SELECT COUNT(*) FROM note", [], |row| row.get(0))?;
+    assert_eq!(count, 0);
+    Ok(())
+}
+
+#[test]
+fn test_notes_are_serialised() -> Result<(), CoreError> {
+    let mut database_manager = TaskManager::new(&get_setting())?;
+    add_task(&mut database_manager, "Test Body")?;
+    let tasks = database_manager.add_note(1, "called vendor")?;
+
+    let json = serde_json::to_value(&tasks[0]).unwrap();
+    assert_eq!(json["notes"][0]["body"], "called vendor");
+
+    // Tasks that were serialised before notes existed
+    let mut json = json;
+    json.as_object_mut().unwrap().remove("notes");
+    let task: Task = serde_json::from_value(json).unwrap();
+    assert!(task.notes.is_empty());
+    Ok(())
+}
@@ -0,0 +1,141 @@
+use rusqlite::Result;
+
+use crate::command::ProjectCommand;
+use crate::command::SimpleCommand;
+use crate::core::Operation;
+use crate::db::project::Project;
+use crate::db::task_helper::Task;
+use crate::db::task_manager::TaskManager;
+use crate::error::CoreError;
+use crate::operation::{execute, Add, Get};
+use super::get_setting;
+
+fn add_task(
+    database_manager: &mut TaskManager,
+    body: &str,
+    project: Option<&str>,
+    date_due: Option<&'static str>,
+) -> Result<Task, CoreError> {
+    let mut operation = Add::new_with_task_manager(body, database_manager);
+    operation.project = project.map(String::from);
+    operation.date_due = date_due;
+    execute(&mut operation)?;
+    Ok(operation.get_result()[0].clone())
+}
+
+fn modify(
+    database_manager: &mut TaskManager,
+    task_ids: Vec<i64>,
+    state: Option<&str>,
+    project: Option<&str>,
+) -> Result<Vec<Task>, CoreError> {
+    database_manager.modify(
+        &task_ids,
+        &None,
+        &None,
+        &None,
+        &vec![],
+        &None,
+        &None,
+        &None,
+        &None,
+        &state,
+        &vec![],
+        &None,
+        &vec![],
+        &None,
+        &project.map(String::from),
+    )
+}
+
+fn get_project(database_manager: &mut TaskManager, name: &str) -> Result<Project, CoreError> {
+    let projects = ProjectCommand::new_with_manager(database_manager).projects()?;
+    Ok(projects.into_iter().find(|project| project.name == name).unwrap())
+}
+
+#[test]
+fn test_add_and_modify_project() -> Result<(), CoreError> {
+    let mut database_manager = TaskManager::new(&get_setting())?;
+    let task = add_task(&mut database_manager, "Book the venue", Some("Wedding"), None)?;
+    assert_eq!(task.project, "wedding");
+    assert_eq!(task.get_property_value("project")?, "wedding");
+    assert!(add_task(&mut database_manager, "Invite", Some("a,b"), None).is_err());
+
+    let tasks = modify(&mut database_manager, vec![1], None, Some("garden"))?;
+    assert_eq!(tasks[0].project, "garden");
+    let history = database_manager.history(1)?;
+    let change = history.last().unwrap();
+    assert_eq!(
+        (change.field.as_str(), change.old_value.as_str(), change.new_value.as_str()),
+        ("project", "wedding", "garden")
+    );
+
+    // Other changes keep the project, an empty one removes it
+    let tasks = modify(&mut database_manager, vec![1], Some("started"), None)?;
+    assert_eq!(tasks[0].project, "garden");
+    let tasks = modify(&mut database_manager, vec![1], None, Some(""))?;
+    assert_eq!(tasks[0].project, "");
+
+    database_manager.undo()?;
+    add_task(&mut database_manager, "Plant the roses", Some("Garden"), None)?;
+    add_task(&mut database_manager, "Read the mail", None, None)?;
+    let mut operation = Get::new_with_store(Some(database_manager));
+    operation.project = Some(String::from("Garden"));
+    execute(&mut operation)?;
+    let bodies: Vec<&str> = operation.get_result().iter().map(|task| task.body.as_str()).collect();
+    assert_eq!(bodies, vec!["Book the venue", "Plant the roses"]);
+    Ok(())
+}
+
+#[test]
+fn test_project_progress_and_next_action() -> Result<(), CoreError> {
+    let mut database_manager = TaskManager::new(&get_setting())?;
+    add_task(&mut database_manager, "Book the venue", Some("wedding"), None)?;
+    add_task(&mut database_manager, "Send the invitations", Some("wedding"), Some("2026-09-01"))?;
+    add_task(&mut database_manager, "Pick a date", Some("wedding"), None)?;
+    add_task(&mut database_manager, "Buy flowers", Some("wedding"), None)?;
+    add_task(&mut database_manager, "Plant the roses", Some("garden"), None)?;
+    modify(&mut database_manager, vec![3, 5], Some("completed"), None)?;
+    database_manager.delete(&vec![4])?;
+
+    // The ready task that is due first is the next action, the deleted
+    // tasks don't count
+    let wedding = get_project(&mut database_manager, "wedding")?;
+    assert_eq!(wedding.format_progress(), "1/3");
+    assert_eq!(wedding.next_action_id, Some(2));
+    assert_eq!(wedding.next_action, "Send the invitations");
+    assert!(!wedding.is_stalled());
+
+    modify(&mut database_manager, vec![2], Some("completed"), None)?;
+    assert_eq!(get_project(&mut database_manager, "wedding")?.next_action_id, Some(1));
+
+    // The garden has nothing left to do, unless it waits on purpose
+    assert!(get_project(&mut database_manager, "garden")?.is_stalled());
+    let mut command = ProjectCommand::new_with_manager(&mut database_manager);
+    let status = Some(String::from("on-hold"));
+    command.update("garden", &None, &Some(String::from("2026-10-01")), &status)?;
+    let garden = get_project(&mut database_manager, "garden")?;
+    assert_eq!(garden.date_due, "2026-10-01 00:00:00");
+    assert!(!garden.is_stalled());
+
+    let mut command = ProjectCommand::new_with_manager(&mut database_manager);
+    command.update("garden", &None, &Some(String::new()), &None)?;
+    assert_eq!(get_project(&mut database_manager, "garden")?.date_due, "");
+    Ok(())
+}
+
+#[test]
+fn test_archive_keeps_project() -> Result<(), CoreError> {
+    let mut database_manager = TaskManager::new(&get_setting())?;
+    add_task(&mut database_manager, "Book the venue", Some("wedding"), None)?;
+    add_task(&mut database_manager, "Pick a date", Some("wedding"), None)?;
+    modify(&mut database_manager, vec![1], Some("completed"), None)?;
+
+    assert_eq!(database_manager.archive(&Some(0))?.len(), 1);
+    assert_eq!(get_project(&mut database_manager, "wedding")?.format_progress(), "0/1");
+
+    let restored = database_manager.unarchive(&vec![1])?;
+    assert_eq!(restored[0].project, "wedding");
+    assert_eq!(get_project(&mut database_manager, "wedding")?.format_progress(), "1/2");
+    Ok(())
+}
@@ -0,0 +1,176 @@
+use rusqlite::Result;
+use std::collections::HashMap;
+
+use crate::db::task_manager::TaskManager;
+use crate::operation::{Add, execute};
+use crate::error::CoreError;
+
+const HOSTILE_CONTEXT: &str = "work' or '1'='1";
+const HOSTILE_TAG: &str = "x') or 1=1 --";
+
+fn get_setting() -> HashMap<String, String> {
+    let mut setting = HashMap::new();
+    setting.insert("db_path".to_owned(), ":memory:".to_owned());
+    setting.insert("tag".to_owned(), "ready, blocked, completed".to_owned());
+    setting.insert("context".to_owned(), "inbox, work, life".to_owned());
+    return setting;
+}
+
+fn add_tasks(database_manager: &mut TaskManager) -> Result<(), CoreError> {
+    let mut operation = Add::new_with_task_manager("It's \"quoted\"", database_manager);
+    operation.context = Some(String::from(HOSTILE_CONTEXT));
+    operation.tags = vec![HOSTILE_TAG.to_owned()];
+    operation.date_due = Some("2020-11-14");
+    execute(&mut operation)?;
+
+    let mut operation = Add::new_with_task_manager("Test Body", database_manager);
+    operation.context = Some(String::from("work"));
+    operation.tags = vec!["ready".to_owned()];
+    operation.date_due = Some("2020-11-14");
+    execute(&mut operation)?;
+    Ok(())
+}
+
+#[test]
+fn test_hostile_body_is_stored_as_is() -> Result<(), CoreError> {
+    let mut database_manager = TaskManager::new(&get_setting())?;
+    add_tasks(&mut database_manager)?;
+
+    let body = "'); DROP TABLE task; --";
+    database_manager.modify(
+        &vec![2],
+        &Some(body),
+        &None,
+        &None,
+        &vec![],
+        &None,
+        &None,
+        &None,
+        &None,
+        &None,
+        &vec![],
+        &None,
+        &vec![],
+        &None,
+        &None,
+    )?;
+
+    let tasks = database_manager.get(&None, &Some(String::from("work")), &vec![], &None, &None, &Some(2), &None)?;
+    assert_eq!(tasks.len(), 1);
+    assert_eq!(tasks[0].body, body);
+
+    let tasks = database_manager.get(&None, &Some(String::from(HOSTILE_CONTEXT)), &vec![], &None, &None, &None, &None)?;
+    assert_eq!(tasks.len(), 1);
+    assert_eq!(tasks[0].body, "It's \"quoted\"");
+    Ok(())
+}
+
+#[test]
+fn test_hostile_context_in_agenda() -> Result<(), CoreError> {
+    let mut database_manager = TaskManager::new(&get_setting())?;
+    add_tasks(&mut database_manager)?;
+
+    let rows = database_manager.view_agenda(
+        String::from("2020-11-14"),
+        None,
+        Some(String::from(HOSTILE_CONTEXT)),
+    )?;
+    assert_eq!(rows[0].1.len(), 1);
+    assert_eq!(rows[0].1[0].id, 1);
+
+    let rows = database_manager.view_agenda(
+        String::from("2020-11-14"),
+        None,
+        Some(String::from("nothing' or 1=1 --")),
+    )?;
+    assert!(rows[0].1.is_empty());
+    Ok(())
+}
+
+#[test]
+fn test_hostile_tags_in_get() -> Result<(), CoreError> {
+    let mut database_manager = TaskManager::new(&get_setting())?;
+    add_tasks(&mut database_manager)?;
+    let work = Some(String::from("work"));
+    let hostile_context = Some(String::from(HOSTILE_CONTEXT));
+
+    let tasks = database_manager.get(&None, &hostile_context, &vec![HOSTILE_TAG.to_owned()], &None, &None, &None, &None)?;
+    assert_eq!(tasks.len(), 1);
+    assert_eq!(tasks[0].tags, vec![HOSTILE_TAG.to_owned()]);
+
+    let tasks = database_manager.get(&None, &work, &vec![HOSTILE_TAG.to_owned()], &None, &None, &None, &None)?;
+    assert!(tasks.is_empty());
+
+    let tasks = database_manager.get(
+        &None,
+        &work,
+        &vec![],
+        &None,
+        &None,
+        &None,
+        &Some(vec![HOSTILE_TAG.to_owned()]),
+    )?;
+    assert_eq!(tasks.len(), 1);
+    assert_eq!(tasks[0].id, 2);
+
+    // Every tag has to match
+    let tasks = database_manager.get(
+        &None,
+        &work,
+        &vec!["ready".to_owned(), HOSTILE_TAG.to_owned()],
+        &None,
+        &None,
+        &None,
+        &None,
+    )?;
+    assert!(tasks.is_empty());
+    Ok(())
+}
+
+#[test]
+fn test_hostile_date_in_get() -> Result<(), CoreError> {
+    let mut database_manager = TaskManager::new(&get_setting())?;
+    add_tasks(&mut database_manager)?;
+
+    let tasks = database_manager.get(
+        &None,
+        &Some(String::from("work")),
+        &vec![],
+        &Some("2020-11-14' or '1'='1"),
+        &None,
+        &None,
+        &None,
+    )?;
+    assert!(tasks.is_empty());
+    Ok(())
+}
+
+#[test]
+fn test_delete_restore_and_purge_task_id_sets() -> Result<(), CoreError> {
+    let mut database_manager = TaskManager::new(&get_setting())?;
+    add_tasks(&mut database_manager)?;
+    let mut operation = Add::new_with_task_manager("Test Body", &mut database_manager);
+    operation.context = Some(String::from("work"));
+    execute(&mut operation)?;
+
+    let deleted_tasks = database_manager.delete(&vec![1, 3, 42])?;
+    assert_eq!(
+        deleted_tasks.iter().map(|task| task.id).collect::<Vec<i64>>(),
+        vec![1, 3]
+    );
+
+    let restored_tasks = database_manager.restore(&vec![3, 2])?;
+    assert_eq!(
+        restored_tasks.iter().map(|task| task.id).collect::<Vec<i64>>(),
+        vec![3]
+    );
+
+    let purged_tasks = database_manager.purge(&None)?;
+    assert_eq!(purged_tasks.len(), 1);
+    assert_eq!(purged_tasks[0].id, 1);
+    assert!(database_manager.trash()?.is_empty());
+
+    let tasks = database_manager.get(&None, &Some(String::from("work")), &vec![], &None, &None, &None, &None)?;
+    assert_eq!(tasks.iter().map(|task| task.id).collect::<Vec<i64>>(), vec![2, 3]);
+    Ok(())
+}
@@ -0,0 +1,110 @@
+use rusqlite::Result;
+
+use crate::db::task_manager::TaskManager;
+use crate::error::CoreError;
+use super::{add_task, get_setting};
+
+fn search_ids(database_manager: &mut TaskManager, query: &str) -> Vec<i64> {
+    database_manager
+        .search(query, "[", "]")
+        .unwrap()
+        .iter()
+        .map(|result| result.task.id)
+        .collect()
+}
+
+#[test]
+fn test_search_body() -> Result<(), CoreError> {
+    let mut database_manager = TaskManager::new(&get_setting())?;
+    add_task(&mut database_manager, "Call the vendor about the invoice")?;
+    add_task(&mut database_manager, "Buy milk")?;
+
+    let results = database_manager.search("vendor", "[", "]")?;
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].task.id, 1);
+    assert_eq!(results[0].snippet, "Call the [vendor] about the invoice");
+
+    // Words are matched as prefixes and all of them have to match
+    assert_eq!(search_ids(&mut database_manager, "inv call"), vec![1]);
+    assert!(search_ids(&mut database_manager, "vendor milk").is_empty());
+    Ok(())
+}
+
+#[test]
+fn test_search_ranked() -> Result<(), CoreError> {
+    let mut database_manager = TaskManager::new(&get_setting())?;
+    add_task(&mut database_manager, "Write the report for the team meeting")?;
+    add_task(&mut database_manager, "Report report report")?;
+
+    assert_eq!(search_ids(&mut database_manager, "report"), vec![2, 1]);
+    Ok(())
+}
+
+#[test]
+fn test_search_annotation() -> Result<(), CoreError> {
+    let mut database_manager = TaskManager::new(&get_setting())?;
+    add_task(&mut database_manager, "Call the vendor")?;
+    database_manager.add_annotation(1, String::from("Ask about the shipping date"))?;
+
+    let results = database_manager.search("shipping", "<", ">")?;
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].snippet, "Ask about the <shipping> date");
+
+    database_manager.add_annotation(1, String::from("Paid"))?;
+    assert!(search_ids(&mut database_manager, "shipping").is_empty());
+    Ok(())
+}
+
+#[test]
+fn test_search_modified_and_deleted() -> Result<(), CoreError> {
+    let mut database_manager = TaskManager::new(&get_setting())?;
+    add_task(&mut database_manager, "Call the vendor")?;
+    add_task(&mut database_manager, "Email the vendor")?;
+
+    database_manager.modify(
+        &vec![1],
+        &Some("Call the plumber"),
+        &None,
+        &None,
+        &vec![],
+        &None,
+        &None,
+        &None,
+        &None,
+        &None,
+        &vec![],
+        &None,
+        &vec![],
+        &None,
+        &None,
+    )?;
+    assert_eq!(search_ids(&mut database_manager, "plumber"), vec![1]);
+    assert_eq!(search_ids(&mut database_manager, "vendor"), vec![2]);
+
+    // Tasks in the trash aren't found, purged ones are removed from the index
+    database_manager.delete(&vec![2])?;
+    assert!(search_ids(&mut database_manager, "vendor").is_empty());
+    database_manager.undo()?;
+    assert_eq!(search_ids(&mut database_manager, "vendor"), vec![2]);
+    database_manager.delete(&vec![2])?;
+    database_manager.purge(&None)?;
+    let count: i64 = database_manager.conn.query_row(
+        "SELECT COUNT(*) FROM task_fts WHERE task_fts MATCH 'vendor'",
+        [],
+        |row| row.get(0),
+    )?;
+    assert_eq!(count, 0);
+    Ok(())
+}
+
+#[test]
+fn test_search_hostile_input() -> Result<(), CoreError> {
+    let mut database_manager = TaskManager::new(&get_setting())?;
+    add_task(&mut database_manager, "Fix the \"quoted\" bug in foo-bar")?;
+
+    assert_eq!(search_ids(&mut database_manager, "foo-bar"), vec![1]);
+    assert_eq!(search_ids(&mut database_manager, "\"quoted"), vec![1]);
+    assert!(search_ids(&mut database_manager, "AND OR NOT ( * :").is_empty());
+    assert!(search_ids(&mut database_manager, "   ").is_empty());
+    Ok(())
+}
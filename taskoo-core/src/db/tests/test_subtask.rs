@@ -0,0 +1,192 @@
+use rusqlite::Result;
+
+use crate::core::Operation;
+use crate::db::task_helper::Task;
+use crate::db::task_manager::TaskManager;
+use crate::error::CoreError;
+use crate::operation::{execute, Add, DeleteOperation, Get};
+use super::get_setting;
+
+fn add_task(
+    database_manager: &mut TaskManager,
+    body: &str,
+    parent_id: Option<i64>,
+) -> Result<Task, CoreError> {
+    let mut operation = Add::new_with_task_manager(body, database_manager);
+    operation.parent_id = parent_id;
+    execute(&mut operation)?;
+    Ok(operation.get_result()[0].clone())
+}
+
+fn modify(
+    database_manager: &mut TaskManager,
+    task_ids: Vec<i64>,
+    state: Option<&str>,
+    parent_id: Option<Option<i64>>,
+) -> Result<Vec<Task>, CoreError> {
+    database_manager.modify(
+        &task_ids,
+        &None,
+        &None,
+        &None,
+        &vec![],
+        &None,
+        &None,
+        &None,
+        &None,
+        &state,
+        &vec![],
+        &None,
+        &vec![],
+        &parent_id,
+        &None,
+    )
+}
+
+fn get_task(database_manager: &mut TaskManager, task_id: i64) -> Result<Task, CoreError> {
+    let tasks = database_manager.get(&None, &None, &vec![], &None, &None, &Some(task_id), &None)?;
+    Ok(tasks[0].clone())
+}
+
+// Plan the trip (1) with the subtasks Book the flights (2) and Pack (3),
+// Pack has the subtask Buy a bag (4)
+fn add_trip(database_manager: &mut TaskManager) -> Result<(), CoreError> {
+    add_task(database_manager, "Plan the trip", None)?;
+    add_task(database_manager, "Book the flights", Some(1))?;
+    add_task(database_manager, "Pack", Some(1))?;
+    add_task(database_manager, "Buy a bag", Some(3))?;
+    Ok(())
+}
+
+#[test]
+fn test_subtask_progress() -> Result<(), CoreError> {
+    let mut database_manager = TaskManager::new(&get_setting())?;
+    add_trip(&mut database_manager)?;
+    assert!(add_task(&mut database_manager, "Call", Some(9)).is_err());
+
+    let parent = get_task(&mut database_manager, 1)?;
+    assert_eq!(parent.parent_id, None);
+    assert_eq!(parent.format_subtask_progress(), "0/2");
+    assert_eq!(get_task(&mut database_manager, 4)?.get_property_value("parent_id")?, "3");
+
+    modify(&mut database_manager, vec![2], Some("completed"), None)?;
+    assert_eq!(get_task(&mut database_manager, 1)?.get_property_value("subtasks")?, "1/2");
+    // Without the setting the parent stays as it is
+    assert!(!get_task(&mut database_manager, 1)?.is_completed());
+
+    assert_eq!(database_manager.get_subtask_ids(&vec![1])?, vec![2, 3, 4]);
+
+    let mut operation = Get::new_with_store(Some(database_manager));
+    operation.parent_id = Some(1);
+    execute(&mut operation)?;
+    let bodies: Vec<&str> = operation.get_result().iter().map(|task| task.body.as_str()).collect();
+    assert_eq!(bodies, vec!["Book the flights", "Pack"]);
+    Ok(())
+}
+
+#[test]
+fn test_reparent_subtasks() -> Result<(), CoreError> {
+    let mut database_manager = TaskManager::new(&get_setting())?;
+    add_trip(&mut database_manager)?;
+
+    // A task can't be the parent of itself or of its parents
+    assert!(modify(&mut database_manager, vec![1], None, Some(Some(1))).is_err());
+    assert!(modify(&mut database_manager, vec![1], None, Some(Some(4))).is_err());
+
+    modify(&mut database_manager, vec![4], None, Some(Some(1)))?;
+    assert_eq!(get_task(&mut database_manager, 1)?.format_subtask_progress(), "0/3");
+    let history = database_manager.history(4)?;
+    let change = history.last().unwrap();
+    assert_eq!(
+        (change.field.as_str(), change.old_value.as_str(), change.new_value.as_str()),
+        ("parent_id", "3", "1")
+    );
+
+    modify(&mut database_manager, vec![4], None, Some(None))?;
+    assert_eq!(get_task(&mut database_manager, 4)?.parent_id, None);
+    database_manager.undo()?;
+    assert_eq!(get_task(&mut database_manager, 4)?.parent_id, Some(1));
+    Ok(())
+}
+
+#[test]
+fn test_auto_complete_parent() -> Result<(), CoreError> {
+    let mut setting = get_setting();
+    setting.insert("auto_complete_parent".to_owned(), "true".to_owned());
+    let mut database_manager = TaskManager::new(&setting)?;
+    add_trip(&mut database_manager)?;
+
+    modify(&mut database_manager, vec![2], Some("completed"), None)?;
+    assert!(!get_task(&mut database_manager, 1)?.is_completed());
+
+    // Completing the last subtask completes its parents up the tree
+    let tasks = modify(&mut database_manager, vec![4], Some("completed"), None)?;
+    let task_ids: Vec<i64> = tasks.iter().map(|task| task.id).collect();
+    assert_eq!(task_ids, vec![4, 3, 1]);
+    assert!(get_task(&mut database_manager, 1)?.is_completed());
+
+    // And undoing it reopens them together
+    database_manager.undo()?;
+    assert!(!get_task(&mut database_manager, 1)?.is_completed());
+    assert!(!get_task(&mut database_manager, 3)?.is_completed());
+
+    setting.insert("auto_complete_parent".to_owned(), "yes".to_owned());
+    let mut database_manager = TaskManager::new(&setting)?;
+    add_trip(&mut database_manager)?;
+    assert!(modify(&mut database_manager, vec![2], Some("completed"), None).is_err());
+    Ok(())
+}
+
+#[test]
+fn test_delete_parent() -> Result<(), CoreError> {
+    // The subtasks of a deleted task move up to its parent
+    let mut database_manager = TaskManager::new(&get_setting())?;
+    add_trip(&mut database_manager)?;
+    database_manager.delete(&vec![3])?;
+    assert_eq!(get_task(&mut database_manager, 4)?.parent_id, Some(1));
+    assert_eq!(get_task(&mut database_manager, 1)?.format_subtask_progress(), "0/2");
+
+    database_manager.undo()?;
+    assert_eq!(get_task(&mut database_manager, 4)?.parent_id, Some(3));
+
+    database_manager.delete(&vec![1])?;
+    assert_eq!(get_task(&mut database_manager, 2)?.parent_id, None);
+    assert_eq!(get_task(&mut database_manager, 3)?.parent_id, None);
+
+    // Or they're deleted with it
+    let mut database_manager = TaskManager::new(&get_setting())?;
+    add_trip(&mut database_manager)?;
+    let mut operation =
+        DeleteOperation::new_with_store(&vec![String::from("id:1")], database_manager)?;
+    operation.cascade = true;
+    execute(&mut operation)?;
+    let task_ids: Vec<i64> = operation.get_result().iter().map(|task| task.id).collect();
+    assert_eq!(task_ids, vec![1, 2, 3, 4]);
+    Ok(())
+}
+
+#[test]
+fn test_archive_parent() -> Result<(), CoreError> {
+    let mut database_manager = TaskManager::new(&get_setting())?;
+    add_trip(&mut database_manager)?;
+    modify(&mut database_manager, vec![3, 4], Some("completed"), None)?;
+
+    // The trip, Pack and Buy a bag are archived, Book the flights stays
+    modify(&mut database_manager, vec![1], Some("completed"), None)?;
+    modify(&mut database_manager, vec![2], Some("ready"), None)?;
+    let archived = database_manager.archive(&Some(0))?;
+    assert_eq!(archived.len(), 3);
+    assert_eq!(get_task(&mut database_manager, 2)?.parent_id, None);
+
+    database_manager.undo()?;
+    assert_eq!(get_task(&mut database_manager, 2)?.parent_id, Some(1));
+    database_manager.archive(&Some(0))?;
+
+    // A subtask restored without its parent becomes a top level task
+    let restored = database_manager.unarchive(&vec![4])?;
+    assert_eq!(restored[0].parent_id, None);
+    let restored = database_manager.unarchive(&vec![1, 3])?;
+    assert_eq!(restored[1].parent_id, Some(1));
+    assert_eq!(get_task(&mut database_manager, 1)?.format_subtask_progress(), "1/1");
+    Ok(())
+}
@@ -0,0 +1,244 @@
+use rusqlite::Result;
+
+use crate::db::task_manager::TaskManager;
+use crate::db::time_tracking::{parse_local_time, TimeInterval};
+use crate::error::CoreError;
+use crate::operation::{execute, execute_timesheet, Add, Timesheet};
+use super::get_setting;
+
+fn add_task(
+    database_manager: &mut TaskManager,
+    body: &str,
+    context: Option<&str>,
+    tags: Vec<&str>,
+) -> Result<(), CoreError> {
+    let mut operation = Add::new_with_task_manager(body, database_manager);
+    operation.context = context.map(String::from);
+    operation.tags = tags.iter().map(|tag| tag.to_string()).collect();
+    execute(&mut operation)?;
+    Ok(())
+}
+
+fn modify_state(
+    database_manager: &mut TaskManager,
+    task_ids: &Vec<i64>,
+    state: &str,
+) -> Result<(), CoreError> {
+    database_manager.modify(
+        task_ids,
+        &None,
+        &None,
+        &None,
+        &vec![],
+        &None,
+        &None,
+        &None,
+        &None,
+        &Some(state),
+        &vec![],
+        &None,
+        &vec![],
+        &None,
+        &None,
+    )?;
+    Ok(())
+}
+
+fn get_all_intervals(database_manager: &mut TaskManager) -> Result<Vec<TimeInterval>, CoreError> {
+    database_manager.get_intervals(&None, &None, &None)
+}
+
+// The task of every interval and whether it's running
+fn interval_states(database_manager: &mut TaskManager) -> Result<Vec<(i64, bool)>, CoreError> {
+    Ok(get_all_intervals(database_manager)?
+        .iter()
+        .map(|interval| (interval.task_id, interval.stopped_at.is_empty()))
+        .collect())
+}
+
+#[test]
+fn test_start_and_stop_tracking() -> Result<(), CoreError> {
+    let mut database_manager = TaskManager::new(&get_setting())?;
+    add_task(&mut database_manager, "Call the vendor", None, vec![])?;
+    add_task(&mut database_manager, "Write the report", None, vec![])?;
+
+    modify_state(&mut database_manager, &vec![1], "started")?;
+    assert_eq!(interval_states(&mut database_manager)?, vec![(1, true)]);
+
+    // Starting a started task again doesn't open another interval
+    modify_state(&mut database_manager, &vec![1], "started")?;
+    assert_eq!(interval_states(&mut database_manager)?, vec![(1, true)]);
+
+    // Only one interval runs, the first task stays started
+    modify_state(&mut database_manager, &vec![2], "started")?;
+    assert_eq!(interval_states(&mut database_manager)?, vec![(1, false), (2, true)]);
+    let tasks = database_manager.get(&None, &None, &vec![], &None, &None, &Some(1), &None)?;
+    assert_eq!(tasks[0].state, "started");
+
+    modify_state(&mut database_manager, &vec![2], "completed")?;
+    assert_eq!(interval_states(&mut database_manager)?, vec![(1, false), (2, false)]);
+
+    // The last of the started tasks is tracked
+    modify_state(&mut database_manager, &vec![1, 2], "started")?;
+    assert_eq!(
+        interval_states(&mut database_manager)?,
+        vec![(1, false), (2, false), (2, true)]
+    );
+    modify_state(&mut database_manager, &vec![2], "ready")?;
+    assert_eq!(
+        interval_states(&mut database_manager)?,
+        vec![(1, false), (2, false), (2, false)]
+    );
+    Ok(())
+}
+
+#[test]
+fn test_tracking_of_added_and_deleted_tasks() -> Result<(), CoreError> {
+    let mut database_manager = TaskManager::new(&get_setting())?;
+    add_task(&mut database_manager, "Call the vendor", None, vec![])?;
+    modify_state(&mut database_manager, &vec![1], "started")?;
+
+    database_manager.add(
+        "Write the report",
+        &None,
+        &None,
+        &vec![],
+        &None,
+        &None,
+        &None,
+        &None,
+        &None,
+        &Some(String::from("started")),
+        &None,
+        &None,
+        &vec![],
+        &None,
+        &None,
+    )?;
+    assert_eq!(interval_states(&mut database_manager)?, vec![(1, false), (2, true)]);
+
+    // Undoing the add tracks the first task again
+    database_manager.undo()?;
+    assert_eq!(interval_states(&mut database_manager)?, vec![(1, true)]);
+
+    database_manager.delete(&vec![1])?;
+    let running: i64 = database_manager.conn.query_row(
+        "SELECT COUNT(*) FROM time_interval WHERE stopped_at IS NULL",
+        [],
+        |row| row.get(0),
+    )?;
+    assert_eq!(running, 0);
+    Ok(())
+}
+
+#[test]
+fn test_undo_start() -> Result<(), CoreError> {
+    let mut database_manager = TaskManager::new(&get_setting())?;
+    add_task(&mut database_manager, "Call the vendor", None, vec![])?;
+    add_task(&mut database_manager, "Write the report", None, vec![])?;
+    modify_state(&mut database_manager, &vec![1], "started")?;
+    modify_state(&mut database_manager, &vec![2], "started")?;
+
+    database_manager.undo()?;
+    assert_eq!(interval_states(&mut database_manager)?, vec![(1, true)]);
+    database_manager.redo()?;
+    assert_eq!(interval_states(&mut database_manager)?, vec![(1, false), (2, true)]);
+    Ok(())
+}
+
+#[test]
+fn test_edit_intervals() -> Result<(), CoreError> {
+    let mut database_manager = TaskManager::new(&get_setting())?;
+    add_task(&mut database_manager, "Call the vendor", None, vec![])?;
+
+    let intervals =
+        database_manager.add_interval(1, "2026-07-01 09:00:00", "2026-07-01 10:30:00")?;
+    assert_eq!(intervals.len(), 1);
+    // The times are given in local time and kept in UTC
+    assert_eq!(intervals[0].started_at, parse_local_time("2026-07-01 09:00:00")?);
+    assert_eq!(intervals[0].stopped_at, parse_local_time("2026-07-01 10:30:00")?);
+
+    let interval_id = intervals[0].id;
+    let intervals =
+        database_manager.edit_interval(interval_id, &None, &Some("2026-07-01 11:00:00"))?;
+    assert_eq!(intervals[0].started_at, parse_local_time("2026-07-01 09:00:00")?);
+    assert_eq!(intervals[0].stopped_at, parse_local_time("2026-07-01 11:00:00")?);
+
+    assert!(database_manager
+        .edit_interval(interval_id, &Some("2026-07-01 12:00:00"), &None)
+        .is_err());
+    let same_time =
+        database_manager.add_interval(1, "2026-07-01 09:00:00", "2026-07-01 09:00:00");
+    assert!(same_time.is_err());
+    let missing_task =
+        database_manager.add_interval(2, "2026-07-01 09:00:00", "2026-07-01 10:00:00");
+    assert!(missing_task.is_err());
+
+    assert!(database_manager.delete_interval(interval_id)?.is_empty());
+    assert!(database_manager.delete_interval(interval_id).is_err());
+
+    // Undo puts the interval back with the same id
+    database_manager.undo()?;
+    let intervals = get_all_intervals(&mut database_manager)?;
+    assert_eq!(intervals[0].id, interval_id);
+    assert_eq!(intervals[0].stopped_at, parse_local_time("2026-07-01 11:00:00")?);
+    Ok(())
+}
+
+fn timesheet(
+    database_manager: TaskManager,
+    arguments: Vec<&str>,
+) -> Result<(TaskManager, Vec<(String, i64, i64)>), CoreError> {
+    let arguments = arguments.iter().map(|argument| argument.to_string()).collect();
+    let mut operation = Timesheet::new2(&arguments)?;
+    operation.database_manager = Some(database_manager);
+    execute_timesheet(&mut operation)?;
+    let entries = operation
+        .get_result()
+        .iter()
+        .map(|entry| (entry.day.to_string(), entry.task_id, entry.seconds))
+        .collect();
+    Ok((operation.database_manager.take().unwrap(), entries))
+}
+
+#[test]
+fn test_timesheet() -> Result<(), CoreError> {
+    let mut database_manager = TaskManager::new(&get_setting())?;
+    add_task(&mut database_manager, "Call the vendor", Some("Work"), vec!["client"])?;
+    add_task(&mut database_manager, "Buy milk", None, vec![])?;
+    database_manager.add_interval(1, "2026-07-01 23:00:00", "2026-07-02 01:00:00")?;
+    database_manager.add_interval(2, "2026-07-02 09:00:00", "2026-07-02 10:00:00")?;
+    database_manager.add_interval(1, "2026-07-02 13:00:00", "2026-07-02 13:30:00")?;
+    database_manager.add_interval(1, "2026-07-05 13:00:00", "2026-07-05 13:30:00")?;
+
+    // The time is split at midnight, the inbox comes before work
+    let (database_manager, entries) =
+        timesheet(database_manager, vec!["2026-07-01", "2026-07-02"])?;
+    assert_eq!(
+        entries,
+        vec![
+            (String::from("2026-07-01"), 1, 3600),
+            (String::from("2026-07-02"), 2, 3600),
+            (String::from("2026-07-02"), 1, 5400),
+        ]
+    );
+
+    // Only the time within the range counts
+    let (database_manager, entries) = timesheet(database_manager, vec!["2026-07-02"])?;
+    assert_eq!(
+        entries,
+        vec![(String::from("2026-07-02"), 2, 3600), (String::from("2026-07-02"), 1, 5400)]
+    );
+
+    let (database_manager, entries) =
+        timesheet(database_manager, vec!["2026-07-01", "2026-07-31", "c:work"])?;
+    assert_eq!(entries.iter().map(|entry| entry.1).collect::<Vec<i64>>(), vec![1, 1, 1]);
+    let (database_manager, entries) =
+        timesheet(database_manager, vec!["2026-07-01", "2026-07-31", "+client"])?;
+    assert_eq!(entries.len(), 3);
+    let (database_manager, entries) =
+        timesheet(database_manager, vec!["2026-07-03", "2026-07-04"])?;
+    assert!(entries.is_empty());
+    assert!(timesheet(database_manager, vec!["2026-07-04", "2026-07-03"]).is_err());
+    Ok(())
+}
@@ -0,0 +1,172 @@
+use rusqlite::Result;
+
+use crate::db::task_helper::Task;
+use crate::db::task_manager::TaskManager;
+use crate::error::CoreError;
+use crate::operation::{Add, execute};
+use super::get_setting;
+
+fn modify_state(
+    database_manager: &mut TaskManager,
+    task_ids: &Vec<i64>,
+    state: &str,
+) -> Result<Vec<Task>, CoreError> {
+    database_manager.modify(
+        task_ids,
+        &None,
+        &None,
+        &None,
+        &vec![],
+        &None,
+        &None,
+        &None,
+        &None,
+        &Some(state),
+        &vec![],
+        &None,
+        &vec![],
+        &None,
+        &None,
+    )
+}
+
+// Move the timestamps of every task to the past, so the updates can be told apart
+fn make_timestamps_old(database_manager: &TaskManager) -> Result<(), CoreError> {
+    database_manager.conn.execute(
+        "UPDATE task SET modified_at = '2000-01-01 00:00:00',
+        started_at = IIF(started_at IS NULL, NULL, '2000-01-01 00:00:00'),
+        completed_at = IIF(completed_at IS NULL, NULL, '2000-01-01 00:00:00')",
+        [],
+    )?;
+    Ok(())
+}
+
+fn get_task(database_manager: &mut TaskManager, task_id: i64) -> Result<Task, CoreError> {
+    let mut tasks =
+        database_manager.get(&None, &None, &vec![], &None, &None, &Some(task_id), &None)?;
+    Ok(tasks.remove(0))
+}
+
+#[test]
+fn test_new_task_timestamps() -> Result<(), CoreError> {
+    let mut database_manager = TaskManager::new(&get_setting())?;
+    let mut operation = Add::new_with_task_manager("Ready task", &mut database_manager);
+    execute(&mut operation)?;
+    database_manager.add(
+        "Started task",
+        &None,
+        &None,
+        &vec![],
+        &None,
+        &None,
+        &None,
+        &None,
+        &None,
+        &Some(String::from("started")),
+        &None,
+        &None,
+        &vec![],
+        &None,
+        &None,
+    )?;
+
+    let task = get_task(&mut database_manager, 1)?;
+    assert_eq!(task.date_modified, task.date_created);
+    assert_eq!(task.date_started, "");
+    assert_eq!(task.date_completed, "");
+    let task = get_task(&mut database_manager, 2)?;
+    assert_eq!(task.date_started, task.date_created);
+    Ok(())
+}
+
+#[test]
+fn test_state_timestamps() -> Result<(), CoreError> {
+    let mut database_manager = TaskManager::new(&get_setting())?;
+    let mut operation = Add::new_with_task_manager("Task", &mut database_manager);
+    execute(&mut operation)?;
+    make_timestamps_old(&database_manager)?;
+
+    let task = modify_state(&mut database_manager, &vec![1], "started")?.remove(0);
+    assert_ne!(task.date_modified, "2000-01-01 00:00:00");
+    assert_ne!(task.date_started, "");
+    assert_eq!(task.date_completed, "");
+
+    // Starting or completing the task again keeps the first time
+    make_timestamps_old(&database_manager)?;
+    let task = modify_state(&mut database_manager, &vec![1], "started")?.remove(0);
+    assert_eq!(task.date_started, "2000-01-01 00:00:00");
+    let task = modify_state(&mut database_manager, &vec![1], "completed")?.remove(0);
+    assert_eq!(task.date_started, "2000-01-01 00:00:00");
+    assert_ne!(task.date_completed, "");
+    make_timestamps_old(&database_manager)?;
+    let completed = modify_state(&mut database_manager, &vec![1], "completed")?.remove(0);
+    assert_eq!(completed.date_completed, "2000-01-01 00:00:00");
+
+    // Reopening the task clears both
+    let task = modify_state(&mut database_manager, &vec![1], "ready")?.remove(0);
+    assert_eq!(task.date_started, "");
+    assert_eq!(task.date_completed, "");
+
+    // Undo puts the timestamps back
+    database_manager.undo()?;
+    let task = get_task(&mut database_manager, 1)?;
+    assert_eq!(task.date_completed, "2000-01-01 00:00:00");
+    assert_eq!(task.date_modified, completed.date_modified);
+    Ok(())
+}
+
+#[test]
+fn test_modified_at_of_every_change() -> Result<(), CoreError> {
+    let mut database_manager = TaskManager::new(&get_setting())?;
+    let mut operation = Add::new_with_task_manager("Parent", &mut database_manager);
+    execute(&mut operation)?;
+    let mut operation = Add::new_with_task_manager("Child", &mut database_manager);
+    operation.parent_task_ids = Some(vec![1]);
+    execute(&mut operation)?;
+    let mut operation = Add::new_with_task_manager("Other", &mut database_manager);
+    execute(&mut operation)?;
+    make_timestamps_old(&database_manager)?;
+
+    let tasks = database_manager.modify(
+        &vec![1],
+        &Some("Renamed"),
+        &None,
+        &None,
+        &vec![],
+        &None,
+        &None,
+        &None,
+        &None,
+        &None,
+        &vec![],
+        &None,
+        &vec![],
+        &None,
+        &None,
+    )?;
+    assert_ne!(tasks[0].date_modified, "2000-01-01 00:00:00");
+    assert_eq!(get_task(&mut database_manager, 3)?.date_modified, "2000-01-01 00:00:00");
+
+    // The child is unblocked when the parent is completed
+    make_timestamps_old(&database_manager)?;
+    modify_state(&mut database_manager, &vec![1], "completed")?;
+    let child = get_task(&mut database_manager, 2)?;
+    assert_eq!(child.state, "ready");
+    assert_ne!(child.date_modified, "2000-01-01 00:00:00");
+    assert_eq!(get_task(&mut database_manager, 3)?.date_modified, "2000-01-01 00:00:00");
+    Ok(())
+}
+
+#[test]
+fn test_repeating_task_is_not_completed() -> Result<(), CoreError> {
+    let mut database_manager = TaskManager::new(&get_setting())?;
+    let mut operation = Add::new_with_task_manager("Water the plants", &mut database_manager);
+    operation.date_due = Some("today");
+    operation.repetition_due = Some("1days");
+    execute(&mut operation)?;
+
+    let task = modify_state(&mut database_manager, &vec![1], "completed")?.remove(0);
+    assert_eq!(task.state, "ready");
+    assert_eq!(task.date_completed, "");
+    Ok(())
+}
@@ -0,0 +1,146 @@
+use rusqlite::Result;
+use std::collections::HashMap;
+
+use crate::core::Operation;
+use crate::db::task_helper::Task;
+use crate::db::task_manager::TaskManager;
+use crate::db::uda::{get_uda_types, UdaType};
+use crate::error::CoreError;
+use crate::operation::{execute, Add, Get};
+
+// The shared setting with a UDA of each type
+fn get_setting() -> HashMap<String, String> {
+    let mut setting = super::get_setting();
+    setting.insert("uda.client".to_owned(), "string".to_owned());
+    setting.insert("uda.cost".to_owned(), "number".to_owned());
+    setting.insert("uda.deadline".to_owned(), "date".to_owned());
+    setting.insert("uda.budget".to_owned(), "Duration".to_owned());
+    return setting;
+}
+
+fn udas(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+    pairs
+        .iter()
+        .map(|(name, value)| (name.to_string(), value.to_string()))
+        .collect()
+}
+
+fn add_task(
+    database_manager: &mut TaskManager,
+    body: &str,
+    pairs: &[(&str, &str)],
+) -> Result<Task, CoreError> {
+    let mut operation = Add::new_with_task_manager(body, database_manager);
+    operation.udas = udas(pairs);
+    execute(&mut operation)?;
+    Ok(operation.get_result()[0].clone())
+}
+
+fn modify(
+    database_manager: &mut TaskManager,
+    task_id: i64,
+    pairs: &[(&str, &str)],
+) -> Result<Vec<Task>, CoreError> {
+    database_manager.modify(
+        &vec![task_id],
+        &None,
+        &None,
+        &None,
+        &vec![],
+        &None,
+        &None,
+        &None,
+        &None,
+        &None,
+        &vec![],
+        &None,
+        &udas(pairs),
+        &None,
+        &None,
+    )
+}
+
+// The bodies of the tasks that Get returns from a new database
+fn get(pairs: &[(&str, &str)], sort_by: Option<&str>) -> Result<Vec<String>, CoreError> {
+    let mut database_manager = TaskManager::new(&get_setting())?;
+    add_task(&mut database_manager, "Hosting", &[("client", "acme"), ("cost", "120")])?;
+    add_task(&mut database_manager, "Domain", &[("client", "acme"), ("cost", "40")])?;
+    add_task(&mut database_manager, "Audit", &[("client", "globex"), ("cost", "9.5")])?;
+    add_task(&mut database_manager, "Inbox zero", &[])?;
+
+    let mut operation = Get::new_with_store(Some(database_manager));
+    operation.udas = udas(pairs);
+    operation.sort_by = sort_by.map(String::from);
+    execute(&mut operation)?;
+    Ok(operation.get_result().iter().map(|task| task.body.clone()).collect())
+}
+
+#[test]
+fn test_uda_types() -> Result<(), CoreError> {
+    let uda_types = get_uda_types(&get_setting())?;
+    assert_eq!(uda_types.len(), 4);
+    assert_eq!(uda_types["budget"], UdaType::Duration);
+
+    assert_eq!(UdaType::Number.normalize("40.5")?, "40.5");
+    assert!(UdaType::Number.normalize("forty").is_err());
+    assert_eq!(UdaType::Duration.normalize("90m")?, "1h30m");
+    assert_eq!(UdaType::Date.normalize("2026-07-01")?, "2026-07-01 00:00:00");
+    assert!(UdaType::Date.normalize("someday").is_err());
+
+    for (name, uda_type) in [("est", "string"), ("Client", "string"), ("cost", "money")] {
+        let mut setting = get_setting();
+        setting.insert(format!("uda.{}", name), uda_type.to_owned());
+        assert!(get_uda_types(&setting).is_err(), "uda.{} = {}", name, uda_type);
+    }
+    Ok(())
+}
+
+#[test]
+fn test_add_and_modify_udas() -> Result<(), CoreError> {
+    let mut database_manager = TaskManager::new(&get_setting())?;
+    let task = add_task(
+        &mut database_manager,
+        "Renew the domain",
+        &[("client", "acme"), ("cost", "40"), ("budget", "90m")],
+    )?;
+    assert_eq!(task.get_property_value("client")?, "acme");
+    assert_eq!(task.get_property_value("budget")?, "1h30m");
+    assert!(task.get_property_value("ticket").is_err());
+    assert!(add_task(&mut database_manager, "Call", &[("ticket", "OPS-123")]).is_err());
+    assert!(add_task(&mut database_manager, "Call", &[("cost", "a lot")]).is_err());
+
+    // An empty value removes the UDA, the others are kept
+    let tasks = modify(&mut database_manager, 1, &[("client", ""), ("cost", "45")])?;
+    assert_eq!(tasks[0].format_udas(), "budget:1h30m cost:45");
+    let history = database_manager.history(1)?;
+    let change = history.last().unwrap();
+    assert_eq!(
+        (change.field.as_str(), change.old_value.as_str(), change.new_value.as_str()),
+        ("udas", "budget:1h30m client:acme cost:40", "budget:1h30m cost:45")
+    );
+
+    // The UDAs are in the JSON of the task
+    let json = serde_json::to_value(&tasks[0]).expect("tasks are serialized");
+    assert_eq!(json["udas"]["cost"], "45");
+
+    database_manager.undo()?;
+    let tasks = database_manager.get(&None, &None, &vec![], &None, &None, &Some(1), &None)?;
+    assert_eq!(tasks[0].udas["client"], "acme");
+
+    database_manager.delete(&vec![1])?;
+    assert_eq!(database_manager.purge(&None)?.len(), 1);
+    Ok(())
+}
+
+#[test]
+fn test_filter_and_sort_by_udas() -> Result<(), CoreError> {
+    assert_eq!(get(&[("client", "acme")], None)?, vec!["Hosting", "Domain"]);
+    assert_eq!(get(&[("client", "")], None)?, vec!["Inbox zero"]);
+    assert!(get(&[("ticket", "OPS-123")], None).is_err());
+
+    // Numbers are sorted by their value, the tasks without the UDA last
+    assert_eq!(get(&[], Some("cost"))?, vec!["Audit", "Domain", "Hosting", "Inbox zero"]);
+    assert_eq!(get(&[], Some("client"))?, vec!["Hosting", "Domain", "Audit", "Inbox zero"]);
+    assert!(get(&[], Some("nothing")).is_err());
+    Ok(())
+}
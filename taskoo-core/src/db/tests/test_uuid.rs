@@ -0,0 +1,67 @@
+use rusqlite::Result;
+
+use crate::db::task_manager::TaskManager;
+use crate::error::CoreError;
+use super::{add_task, get_setting};
+
+fn set_uuid(database_manager: &TaskManager, task_id: i64, uuid: &str) -> Result<(), CoreError> {
+    database_manager.conn.execute(
+        "UPDATE task SET uuid = ?1 WHERE id = ?2",
+        rusqlite::params![uuid, task_id],
+    )?;
+    Ok(())
+}
+
+#[test]
+fn test_new_tasks_have_uuids() -> Result<(), CoreError> {
+    let mut database_manager = TaskManager::new(&get_setting())?;
+    let first = add_task(&mut database_manager, "Task One")?.uuid;
+    let second = add_task(&mut database_manager, "Task Two")?.uuid;
+
+    assert_eq!(first.len(), 36);
+    assert_eq!(&first[14..15], "4");
+    assert_ne!(first, second);
+
+    let tasks = database_manager.get(&None, &None, &vec![], &None, &None, &Some(1), &None)?;
+    assert_eq!(tasks[0].uuid, first);
+    assert_eq!(tasks[0].get_property_value("uuid").unwrap(), first);
+    Ok(())
+}
+
+#[test]
+fn test_get_task_ids_by_uuid_prefix() -> Result<(), CoreError> {
+    let mut database_manager = TaskManager::new(&get_setting())?;
+    add_task(&mut database_manager, "Task One")?;
+    add_task(&mut database_manager, "Task Two")?;
+    set_uuid(&database_manager, 1, "aaaaaaaa-1111-4111-8111-111111111111")?;
+    set_uuid(&database_manager, 2, "aaaaaaaa-2222-4222-8222-222222222222")?;
+
+    assert_eq!(
+        database_manager.get_task_ids_by_uuid(&vec![
+            String::from("aaaaaaaa-2"),
+            String::from("AAAAAAAA-1111-4111-8111-111111111111"),
+        ])?,
+        vec![2, 1]
+    );
+    // Ambiguous and unknown prefixes
+    assert!(database_manager
+        .get_task_ids_by_uuid(&vec![String::from("aaaaaaaa")])
+        .is_err());
+    assert!(database_manager
+        .get_task_ids_by_uuid(&vec![String::from("bbbbbbbb")])
+        .is_err());
+    // Archived tasks keep their UUID
+    database_manager.modify(
+        &vec![2], &None, &None, &None, &vec![], &None, &None, &None, &None, &Some("completed"), &vec![],
+        &None,
+        &vec![],
+        &None,
+        &None,
+    )?;
+    database_manager.archive(&Some(-1))?;
+    assert_eq!(
+        database_manager.get_task_ids_by_uuid(&vec![String::from("aaaaaaaa-2")])?,
+        vec![2]
+    );
+    Ok(())
+}
@@ -17,7 +17,7 @@ fn get_setting() -> HashMap<String, String> {
 
 #[test]
 fn test_view_due() -> Result<(), CoreError> {
-    let mut database_manager = TaskManager::new(&get_setting());
+    let mut database_manager = TaskManager::new(&get_setting())?;
 
     let mut operation = Add::new_with_task_manager("Test Body", &mut database_manager);
 
@@ -46,7 +46,7 @@ fn test_view_due() -> Result<(), CoreError> {
 
 #[test]
 fn test_view_overdue() -> Result<(), CoreError> {
-    let mut database_manager = TaskManager::new(&get_setting());
+    let mut database_manager = TaskManager::new(&get_setting())?;
 
     let mut operation = Add::new_with_task_manager("Test Body", &mut database_manager);
 
@@ -75,7 +75,7 @@ fn test_view_overdue() -> Result<(), CoreError> {
 
 #[test]
 fn test_view_schedule() -> Result<(), CoreError> {
-    let mut database_manager = TaskManager::new(&get_setting());
+    let mut database_manager = TaskManager::new(&get_setting())?;
 
     let mut operation = Add::new_with_task_manager("Test Body", &mut database_manager);
     operation.date_due = Some("2020-11-11");
@@ -108,7 +108,7 @@ fn test_view_schedule() -> Result<(), CoreError> {
 
 #[test]
 fn test_view_schedule_today() -> Result<(), CoreError> {
-    let mut database_manager = TaskManager::new(&get_setting());
+    let mut database_manager = TaskManager::new(&get_setting())?;
 
     let expected = Local::today() + Duration::days(0);
 
@@ -145,7 +145,7 @@ fn test_view_schedule_today() -> Result<(), CoreError> {
 
 #[test]
 fn test_view_all_today() -> Result<(), CoreError> {
-    let mut database_manager = TaskManager::new(&get_setting());
+    let mut database_manager = TaskManager::new(&get_setting())?;
 
     let expected = Local::today() + Duration::days(0);
 
@@ -0,0 +1,143 @@
+use rusqlite::Result;
+
+use crate::db::task_manager::TaskManager;
+use crate::db::task_helper::Task;
+use crate::error::CoreError;
+use crate::operation::{Add, execute};
+use super::get_setting;
+
+fn add_tasks(database_manager: &mut TaskManager, count: usize) -> Result<(), CoreError> {
+    for index in 1..=count {
+        let body = format!("Task {}", index);
+        let mut operation = Add::new_with_task_manager(&body, database_manager);
+        execute(&mut operation)?;
+    }
+    Ok(())
+}
+
+fn list(database_manager: &mut TaskManager) -> Result<Vec<Task>, CoreError> {
+    database_manager.get(&None, &None, &vec![], &None, &None, &None, &None)
+}
+
+fn complete(database_manager: &mut TaskManager, task_ids: &Vec<i64>) -> Result<Vec<Task>, CoreError> {
+    database_manager.modify(
+        task_ids,
+        &None,
+        &None,
+        &None,
+        &vec![],
+        &None,
+        &None,
+        &None,
+        &None,
+        &Some("completed"),
+        &vec![],
+        &None,
+        &vec![],
+        &None,
+        &None,
+    )
+}
+
+// (display id, task id) of the listed tasks that are pending
+fn display_ids(tasks: &Vec<Task>) -> Vec<(Option<i64>, i64)> {
+    tasks
+        .iter()
+        .filter(|task| task.state != "completed")
+        .map(|task| (task.display_id, task.id))
+        .collect()
+}
+
+#[test]
+fn test_new_tasks_get_display_ids() -> Result<(), CoreError> {
+    let mut database_manager = TaskManager::new(&get_setting())?;
+    add_tasks(&mut database_manager, 3)?;
+
+    assert_eq!(
+        display_ids(&list(&mut database_manager)?),
+        vec![(Some(1), 1), (Some(2), 2), (Some(3), 3)]
+    );
+    Ok(())
+}
+
+#[test]
+fn test_display_ids_are_renumbered_on_listing() -> Result<(), CoreError> {
+    let mut database_manager = TaskManager::new(&get_setting())?;
+    add_tasks(&mut database_manager, 4)?;
+    list(&mut database_manager)?;
+
+    let completed = complete(&mut database_manager, &vec![2])?;
+    assert_eq!(completed[0].display_id, None);
+
+    // The ids that were shown last keep working until the next listing
+    assert_eq!(database_manager.get_task_ids_by_display_id(&vec![3, 4])?, vec![3, 4]);
+    assert!(database_manager.get_task_ids_by_display_id(&vec![2]).is_err());
+
+    assert_eq!(
+        display_ids(&list(&mut database_manager)?),
+        vec![(Some(1), 1), (Some(2), 3), (Some(3), 4)]
+    );
+    assert_eq!(database_manager.get_task_ids_by_display_id(&vec![3, 2])?, vec![4, 3]);
+    Ok(())
+}
+
+#[test]
+fn test_deleted_tasks_leave_the_working_set() -> Result<(), CoreError> {
+    let mut database_manager = TaskManager::new(&get_setting())?;
+    add_tasks(&mut database_manager, 3)?;
+    database_manager.delete(&vec![1])?;
+
+    assert_eq!(
+        display_ids(&list(&mut database_manager)?),
+        vec![(Some(1), 2), (Some(2), 3)]
+    );
+    assert_eq!(database_manager.trash()?[0].display_id, None);
+
+    // Restored tasks are appended
+    let restored = database_manager.restore(&vec![1])?;
+    assert_eq!(restored[0].display_id, Some(3));
+    Ok(())
+}
+
+#[test]
+fn test_new_tasks_are_appended_without_renumbering() -> Result<(), CoreError> {
+    let mut database_manager = TaskManager::new(&get_setting())?;
+    add_tasks(&mut database_manager, 3)?;
+    complete(&mut database_manager, &vec![1])?;
+
+    let mut operation = Add::new_with_task_manager("Task 4", &mut database_manager);
+    execute(&mut operation)?;
+    let tasks = database_manager.get(&None, &None, &vec![], &None, &None, &Some(4), &None)?;
+    assert_eq!(tasks[0].display_id, Some(4));
+
+    assert_eq!(
+        display_ids(&list(&mut database_manager)?),
+        vec![(Some(1), 2), (Some(2), 3), (Some(3), 4)]
+    );
+    Ok(())
+}
+
+#[test]
+fn test_undo_brings_back_the_display_id() -> Result<(), CoreError> {
+    let mut database_manager = TaskManager::new(&get_setting())?;
+    add_tasks(&mut database_manager, 2)?;
+    complete(&mut database_manager, &vec![1])?;
+
+    let tasks = database_manager.undo()?;
+    assert_eq!(tasks[0].display_id, Some(3));
+    assert_eq!(
+        display_ids(&list(&mut database_manager)?),
+        vec![(Some(2), 1), (Some(1), 2)]
+    );
+    Ok(())
+}
+
+#[test]
+fn test_unknown_display_id() -> Result<(), CoreError> {
+    let mut database_manager = TaskManager::new(&get_setting())?;
+    add_tasks(&mut database_manager, 1)?;
+
+    assert!(database_manager.get_task_ids_by_display_id(&vec![1, 5]).is_err());
+    assert!(database_manager.get_task_ids_by_display_id(&vec![]).is_ok());
+    Ok(())
+}
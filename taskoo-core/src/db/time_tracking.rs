@@ -0,0 +1,302 @@
+use super::selection::Selection;
+use super::task_manager::TaskManager;
+use crate::error::CoreError;
+use chrono::{Duration, Local, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use rusqlite::{named_params, OptionalExtension, Result, Transaction};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+const TIME_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+// A period of time spent on a task, with the task it belongs to
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct TimeInterval {
+    pub id: i64,
+    pub task_id: i64,
+    pub body: String,
+    pub context: String,
+    pub tags: Vec<String>,
+    // In UTC like `CURRENT_TIMESTAMP`, `stopped_at` is empty while the
+    // interval is running
+    pub started_at: String,
+    pub stopped_at: String,
+}
+
+impl TimeInterval {
+    // A running interval lasts until `now` in UTC
+    pub fn seconds(&self, now: &NaiveDateTime) -> Result<i64, CoreError> {
+        let stopped_at = match self.stopped_at.as_str() {
+            "" => *now,
+            stopped_at => parse_time(stopped_at)?,
+        };
+        Ok((stopped_at - parse_time(&self.started_at)?).num_seconds())
+    }
+}
+
+// The time spent on a task in one day
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct TimesheetEntry {
+    pub day: NaiveDate,
+    pub task_id: i64,
+    pub body: String,
+    pub context: String,
+    pub seconds: i64,
+}
+
+pub fn to_utc(local: &NaiveDateTime) -> NaiveDateTime {
+    match Local.from_local_datetime(local).earliest() {
+        Some(local) => local.with_timezone(&Utc).naive_utc(),
+        // The time is skipped by a DST change
+        None => *local,
+    }
+}
+
+pub fn to_local(utc: &NaiveDateTime) -> NaiveDateTime {
+    Utc.from_utc_datetime(utc).with_timezone(&Local).naive_local()
+}
+
+pub fn parse_time(time: &str) -> Result<NaiveDateTime, CoreError> {
+    NaiveDateTime::parse_from_str(time, TIME_FORMAT)
+        .map_err(|_| CoreError::DateParseError(time.to_string()))
+}
+
+pub fn format_time(time: &NaiveDateTime) -> String {
+    time.format(TIME_FORMAT).to_string()
+}
+
+// Parse a date the way `TaskManager::parse_date_string` does, in local
+// time, and turn it into UTC
+pub fn parse_local_time(time: &str) -> Result<String, CoreError> {
+    let local = parse_time(&TaskManager::parse_date_string(time)?)?;
+    Ok(format_time(&to_utc(&local)))
+}
+
+// Close the running interval unless its task is still started and isn't
+// deleted
+pub fn stop_tracking(tx: &Transaction) -> Result<(), CoreError> {
+    tx.execute(
+        "UPDATE time_interval SET stopped_at = CURRENT_TIMESTAMP
+        WHERE stopped_at IS NULL AND task_id NOT IN (
+            SELECT task.id FROM task
+            INNER JOIN task_state ON task.id = task_state.task_id
+            INNER JOIN state ON task_state.state_id = state.id
+            WHERE state.name = 'started' AND task.deleted_at IS NULL
+        )",
+        [],
+    )?;
+    Ok(())
+}
+
+// Only one interval runs at a time, so the one of another task is closed
+pub fn start_tracking(tx: &Transaction, task_id: i64) -> Result<(), CoreError> {
+    tx.execute(
+        "UPDATE time_interval SET stopped_at = CURRENT_TIMESTAMP
+        WHERE stopped_at IS NULL AND task_id <> :task_id",
+        named_params! {":task_id": task_id},
+    )?;
+    tx.execute(
+        "INSERT INTO time_interval (task_id, started_at)
+        SELECT :task_id, CURRENT_TIMESTAMP
+        WHERE NOT EXISTS (SELECT 1 FROM time_interval WHERE stopped_at IS NULL)",
+        named_params! {":task_id": task_id},
+    )?;
+    Ok(())
+}
+
+// The task of the running interval
+pub fn get_tracked_task_id(tx: &Transaction) -> Result<Option<i64>, CoreError> {
+    Ok(tx
+        .query_row(
+            "SELECT task_id FROM time_interval WHERE stopped_at IS NULL",
+            [],
+            |row| row.get(0),
+        )
+        .optional()?)
+}
+
+// Track the last of `task_ids` that is started, `Selection::Task` has to
+// hold the same ids
+pub fn track_started_task(tx: &Transaction, task_ids: &Vec<i64>) -> Result<(), CoreError> {
+    let mut statement = tx.prepare(&format!(
+        "SELECT task_state.task_id FROM task_state
+        INNER JOIN state ON task_state.state_id = state.id
+        WHERE state.name = 'started' AND task_state.task_id IN ({})",
+        Selection::Task.ids()
+    ))?;
+    let mut rows = statement.query([])?;
+    let mut started_ids = vec![];
+    while let Some(row) = rows.next()? {
+        started_ids.push(row.get::<_, i64>(0)?);
+    }
+    stop_tracking(tx)?;
+    if let Some(task_id) = task_ids.iter().rev().find(|id| started_ids.contains(id)) {
+        start_tracking(tx, *task_id)?;
+    }
+    Ok(())
+}
+
+// The intervals of the tasks that aren't deleted, which overlap the
+// period from `start` to `end` in UTC. The period is open ended without
+// `start` or `end`, and the intervals of every task are returned without
+// `task_id`.
+pub fn get_intervals(
+    tx: &Transaction,
+    start: &Option<String>,
+    end: &Option<String>,
+    task_id: &Option<i64>,
+) -> Result<Vec<TimeInterval>, CoreError> {
+    let mut statement = tx.prepare(
+        "SELECT time_interval.id, time_interval.task_id, taskoo_decrypt(task.body), context.name,
+        GROUP_CONCAT(tag.name), time_interval.started_at, IFNULL(time_interval.stopped_at, '')
+        FROM time_interval
+        INNER JOIN task ON time_interval.task_id = task.id
+        INNER JOIN task_context ON task.id = task_context.task_id
+        INNER JOIN context ON task_context.context_id = context.id
+        LEFT JOIN task_tag ON task.id = task_tag.task_id
+        LEFT JOIN tag ON task_tag.tag_id = tag.id
+        WHERE task.deleted_at IS NULL
+        AND (:end IS NULL OR time_interval.started_at < :end)
+        AND (:start IS NULL OR time_interval.stopped_at IS NULL
+            OR time_interval.stopped_at > :start)
+        AND (:task_id IS NULL OR time_interval.task_id = :task_id)
+        GROUP BY time_interval.id
+        ORDER BY time_interval.started_at, time_interval.id",
+    )?;
+    let mut rows = statement.query(named_params! {
+        ":start": start,
+        ":end": end,
+        ":task_id": task_id,
+    })?;
+    let mut intervals = vec![];
+    while let Some(row) = rows.next()? {
+        let tags: Option<String> = row.get(4)?;
+        intervals.push(TimeInterval {
+            id: row.get(0)?,
+            task_id: row.get(1)?,
+            body: row.get(2)?,
+            context: row.get(3)?,
+            tags: tags.map_or(vec![], |tags| tags.split(',').map(String::from).collect()),
+            started_at: row.get(5)?,
+            stopped_at: row.get(6)?,
+        });
+    }
+    Ok(intervals)
+}
+
+// The task that the interval belongs to
+pub fn get_interval_task_id(tx: &Transaction, interval_id: i64) -> Result<i64, CoreError> {
+    let task_id = tx
+        .query_row(
+            "SELECT task_id FROM time_interval WHERE id = :interval_id",
+            named_params! {":interval_id": interval_id},
+            |row| row.get(0),
+        )
+        .optional()?;
+    match task_id {
+        Some(task_id) => Ok(task_id),
+        None => Err(CoreError::ArgumentError(format!(
+            "Interval {} doesn't exist",
+            interval_id
+        ))),
+    }
+}
+
+fn validate_interval(started_at: &str, stopped_at: &Option<String>) -> Result<(), CoreError> {
+    if let Some(stopped_at) = stopped_at {
+        if parse_time(stopped_at)? <= parse_time(started_at)? {
+            return Err(CoreError::ArgumentError(String::from(
+                "The interval has to stop after it starts",
+            )));
+        }
+    }
+    Ok(())
+}
+
+pub fn add_interval(
+    tx: &Transaction,
+    task_id: i64,
+    started_at: &str,
+    stopped_at: &str,
+) -> Result<(), CoreError> {
+    validate_interval(started_at, &Some(stopped_at.to_string()))?;
+    tx.execute(
+        "INSERT INTO time_interval (task_id, started_at, stopped_at)
+        VALUES (:task_id, :started_at, :stopped_at)",
+        named_params! {":task_id": task_id, ":started_at": started_at, ":stopped_at": stopped_at},
+    )?;
+    Ok(())
+}
+
+// A running interval is stopped when `stopped_at` is given
+pub fn edit_interval(
+    tx: &Transaction,
+    interval_id: i64,
+    started_at: &Option<&str>,
+    stopped_at: &Option<&str>,
+) -> Result<(), CoreError> {
+    let (current_started_at, current_stopped_at): (String, Option<String>) = tx.query_row(
+        "SELECT started_at, stopped_at FROM time_interval WHERE id = :interval_id",
+        named_params! {":interval_id": interval_id},
+        |row| Ok((row.get(0)?, row.get(1)?)),
+    )?;
+    let started_at = started_at.map_or(current_started_at, String::from);
+    let stopped_at = stopped_at.map(String::from).or(current_stopped_at);
+    validate_interval(&started_at, &stopped_at)?;
+    tx.execute(
+        "UPDATE time_interval SET started_at = :started_at, stopped_at = :stopped_at
+        WHERE id = :interval_id",
+        named_params! {
+            ":interval_id": interval_id,
+            ":started_at": started_at,
+            ":stopped_at": stopped_at,
+        },
+    )?;
+    Ok(())
+}
+
+pub fn delete_interval(tx: &Transaction, interval_id: i64) -> Result<(), CoreError> {
+    tx.execute(
+        "DELETE FROM time_interval WHERE id = :interval_id",
+        named_params! {":interval_id": interval_id},
+    )?;
+    Ok(())
+}
+
+// Sum the time of the intervals per day and task, only counting the time
+// from `start` to `end`. The days and the bounds are in local time, the
+// running intervals count until `now` in UTC.
+pub fn summarize(
+    intervals: &Vec<TimeInterval>,
+    start: &NaiveDateTime,
+    end: &NaiveDateTime,
+    now: &NaiveDateTime,
+) -> Result<Vec<TimesheetEntry>, CoreError> {
+    let mut entries: BTreeMap<(NaiveDate, String, i64), TimesheetEntry> = BTreeMap::new();
+    for interval in intervals.iter() {
+        let stopped_at = match interval.stopped_at.as_str() {
+            "" => *now,
+            stopped_at => parse_time(stopped_at)?,
+        };
+        let mut from = to_local(&parse_time(&interval.started_at)?).max(*start);
+        let to = to_local(&stopped_at).min(*end);
+
+        // Split the interval at midnight
+        while from < to {
+            let day = from.date();
+            let next_day = (day + Duration::days(1)).and_hms_opt(0, 0, 0).unwrap();
+            let until = to.min(next_day);
+            let entry = entries
+                .entry((day, interval.context.clone(), interval.task_id))
+                .or_insert_with(|| TimesheetEntry {
+                    day,
+                    task_id: interval.task_id,
+                    body: interval.body.clone(),
+                    context: interval.context.clone(),
+                    seconds: 0,
+                });
+            entry.seconds += (until - from).num_seconds();
+            from = until;
+        }
+    }
+    Ok(entries.into_values().collect())
+}
@@ -0,0 +1,181 @@
+use super::query_builder::placeholders;
+use super::selection::Selection;
+use crate::db::task_helper::{format_estimate, parse_estimate, Task};
+use crate::db::task_manager::TaskManager;
+use crate::error::CoreError;
+use rusqlite::{named_params, params_from_iter, Result, Transaction};
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashMap};
+
+// UDAs are declared in the [uda] section of the config, e.g. `cost = number`,
+// and show up in the setting as `uda.cost`
+pub const UDA_SETTING_PREFIX: &str = "uda.";
+
+// The names that can't be used by UDAs, they are the properties of `Task`
+// and the prefixes of the options
+const RESERVED_NAMES: &[&str] = &[
+    "id", "uuid", "display_id", "body", "priority", "pri", "context", "c", "tags", "tag_ids",
+    "date_created", "date_modified", "date_started", "date_completed", "date_due", "d",
+    "date_scheduled", "s", "repetition_due", "repetition_scheduled", "state", "annotation",
+    "parent_task_ids", "dep", "estimate", "est", "time_tracked", "notes", "udas", "parent",
+    "parent_id", "subtasks", "subtasks_total", "subtasks_completed", "project", "proj",
+    "attachments", "checklist",
+];
+
+// UDAs are looked up for this many tasks at a time, like the notes
+const TASK_IDS_PER_QUERY: usize = 500;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum UdaType {
+    String,
+    Number,
+    Date,     // Parsed like the due date
+    Duration, // Parsed like the estimate
+}
+
+impl UdaType {
+    pub fn from_name(name: &str) -> Result<UdaType, CoreError> {
+        match name.trim().to_lowercase().as_str() {
+            "string" => Ok(UdaType::String),
+            "number" => Ok(UdaType::Number),
+            "date" => Ok(UdaType::Date),
+            "duration" => Ok(UdaType::Duration),
+            _ => Err(CoreError::ArgumentError(format!(
+                "Invalid UDA type {}, use string, number, date or duration",
+                name
+            ))),
+        }
+    }
+
+    // The value as it's stored, e.g. dates get the time and durations are
+    // written like estimates
+    pub fn normalize(&self, value: &str) -> Result<String, CoreError> {
+        match self {
+            UdaType::String => Ok(value.to_string()),
+            UdaType::Number => match value.parse::<f64>() {
+                Ok(number) if number.is_finite() => Ok(value.to_string()),
+                _ => Err(CoreError::ArgumentError(format!("Invalid number {}", value))),
+            },
+            UdaType::Date => TaskManager::parse_date_string(value),
+            UdaType::Duration => Ok(format_estimate(parse_estimate(value)?)),
+        }
+    }
+
+    // Compare two stored values, numbers and durations by their amount
+    pub fn compare(&self, a: &str, b: &str) -> Ordering {
+        match self {
+            UdaType::Number => {
+                let parse = |value: &str| value.parse::<f64>().unwrap_or(0.0);
+                parse(a).partial_cmp(&parse(b)).unwrap_or(Ordering::Equal)
+            }
+            UdaType::Duration => {
+                let parse = |value: &str| parse_estimate(value).unwrap_or(0);
+                parse(a).cmp(&parse(b))
+            }
+            UdaType::String | UdaType::Date => a.cmp(b),
+        }
+    }
+}
+
+pub fn is_valid_uda_name(name: &str) -> bool {
+    name.starts_with(|c: char| c.is_ascii_lowercase())
+        && name.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+        && !RESERVED_NAMES.contains(&name)
+}
+
+// The UDAs declared in the setting by their name
+pub fn get_uda_types(
+    setting: &HashMap<String, String>,
+) -> Result<BTreeMap<String, UdaType>, CoreError> {
+    let mut uda_types = BTreeMap::new();
+    for (key, type_name) in setting.iter() {
+        if let Some(name) = key.strip_prefix(UDA_SETTING_PREFIX) {
+            if !is_valid_uda_name(name) {
+                return Err(CoreError::ArgumentError(format!(
+                    "Invalid UDA name {}, it's reserved or isn't made of lowercase letters, \
+                    digits and underscores",
+                    name
+                )));
+            }
+            uda_types.insert(name.to_string(), UdaType::from_name(type_name)?);
+        }
+    }
+    Ok(uda_types)
+}
+
+// Check the UDAs against the declared ones and normalize their values. An
+// empty value becomes None, which removes the UDA.
+pub fn resolve_udas(
+    uda_types: &BTreeMap<String, UdaType>,
+    udas: &Vec<(String, String)>,
+) -> Result<Vec<(String, Option<String>)>, CoreError> {
+    let mut resolved = vec![];
+    for (name, value) in udas.iter() {
+        let name = name.to_lowercase();
+        let uda_type = uda_types.get(&name).ok_or_else(|| {
+            CoreError::ArgumentError(format!(
+                "Unknown UDA {}, declare it in the [uda] section of the config",
+                name
+            ))
+        })?;
+        let value = match value.trim() {
+            "" => None,
+            value => Some(uda_type.normalize(value).map_err(|error| {
+                CoreError::ArgumentError(format!("Invalid value of UDA {}: {}", name, error))
+            })?),
+        };
+        resolved.push((name, value));
+    }
+    Ok(resolved)
+}
+
+// Fill in the UDAs of the tasks
+pub fn attach_udas(tx: &Transaction, tasks: &mut Vec<Task>) -> Result<(), CoreError> {
+    let positions: HashMap<i64, usize> =
+        tasks.iter().enumerate().map(|(index, task)| (task.id, index)).collect();
+    let task_ids: Vec<i64> = tasks.iter().map(|task| task.id).collect();
+    for chunk in task_ids.chunks(TASK_IDS_PER_QUERY) {
+        let mut statement = tx.prepare(&format!(
+            "SELECT task_id, name, value FROM task_uda WHERE task_id IN ({})",
+            placeholders(chunk.len())
+        ))?;
+        let mut rows = statement.query(params_from_iter(chunk.iter()))?;
+        while let Some(row) = rows.next()? {
+            let task_id: i64 = row.get(0)?;
+            if let Some(index) = positions.get(&task_id) {
+                tasks[*index].udas.insert(row.get(1)?, row.get(2)?);
+            }
+        }
+    }
+    Ok(())
+}
+
+// Set the UDAs of the selected tasks, the ones without a value are removed
+pub fn set_udas(
+    tx: &Transaction,
+    selection: Selection,
+    udas: &Vec<(String, Option<String>)>,
+) -> Result<(), CoreError> {
+    for (name, value) in udas.iter() {
+        match value {
+            Some(value) => tx.execute(
+                &format!(
+                    "INSERT OR REPLACE INTO task_uda (task_id, name, value)
+                    SELECT task_id, :name, :value FROM ({})",
+                    selection.ids()
+                ),
+                named_params! {":name": name, ":value": value},
+            )?,
+            None => tx.execute(
+                &format!(
+                    "DELETE FROM task_uda WHERE name = :name AND task_id IN ({})",
+                    selection.ids()
+                ),
+                named_params! {":name": name},
+            )?,
+        };
+    }
+    Ok(())
+}
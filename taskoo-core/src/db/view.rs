@@ -14,11 +14,10 @@ pub fn view(
     view_type: &Option<String>,
 ) -> Result<Vec<Task>, CoreError> {
     info!("[view] view_range_start={:?}", view_range_start);
-    let conditions =
-        generate_view_condition(context_id, view_range_start, view_range_end, view_type);
+    let filter = generate_view_condition(context_id, view_range_start, view_range_end, view_type);
 
-    assert!(!conditions.is_empty());
+    assert!(!filter.is_empty());
 
-    let tasks = get_base(&conn, &conditions.join(" and "))?;
+    let tasks = get_base(&conn, &filter)?;
     Ok(tasks)
 }
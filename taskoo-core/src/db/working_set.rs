@@ -0,0 +1,110 @@
+use super::query_builder::placeholders;
+use crate::db::task_helper::Task;
+use crate::error::CoreError;
+use rusqlite::{named_params, params_from_iter, Result, Transaction};
+use std::collections::HashMap;
+
+// Tasks that are neither completed nor in the trash
+const PENDING_TASK_IDS_QUERY: &str = "
+    SELECT task.id FROM task
+    INNER JOIN task_state ON task.id = task_state.task_id
+    INNER JOIN state ON task_state.state_id = state.id
+    WHERE task.deleted_at IS NULL AND state.name <> 'completed'";
+
+// Bring the working set up to date. Tasks that aren't pending anymore
+// leave it and new pending tasks are appended with the next display ids.
+//
+// The display ids of the remaining tasks only change when `renumber` is
+// set, which closes the gaps left by the tasks that left. It's done when
+// the tasks are listed, so the ids that were shown last keep referring
+// to the same tasks until the next listing.
+pub fn sync_working_set(tx: &Transaction, renumber: bool) -> Result<(), CoreError> {
+    tx.execute(
+        &format!(
+            "DELETE FROM working_set WHERE task_id NOT IN ({})",
+            PENDING_TASK_IDS_QUERY
+        ),
+        [],
+    )?;
+
+    if renumber {
+        // Negative ids first, so the primary key never clashes halfway
+        tx.execute(
+            "UPDATE working_set SET display_id = -ranked.position
+            FROM (
+                SELECT task_id, ROW_NUMBER() OVER (ORDER BY display_id) as position
+                FROM working_set
+            ) ranked
+            WHERE working_set.task_id = ranked.task_id",
+            [],
+        )?;
+        tx.execute("UPDATE working_set SET display_id = -display_id", [])?;
+    }
+
+    let last_display_id: i64 = tx.query_row(
+        "SELECT IFNULL(MAX(display_id), 0) FROM working_set",
+        [],
+        |row| row.get(0),
+    )?;
+    tx.execute(
+        &format!(
+            "INSERT INTO working_set (display_id, task_id)
+            SELECT :last_display_id + ROW_NUMBER() OVER (ORDER BY pending.id), pending.id
+            FROM ({}) pending
+            WHERE pending.id NOT IN (SELECT task_id FROM working_set)",
+            PENDING_TASK_IDS_QUERY
+        ),
+        named_params! {":last_display_id": last_display_id},
+    )?;
+    Ok(())
+}
+
+// The task ids of the display ids, in the same order
+pub fn get_task_ids_by_display_id(
+    tx: &Transaction,
+    display_ids: &Vec<i64>,
+) -> Result<Vec<i64>, CoreError> {
+    if display_ids.is_empty() {
+        return Ok(vec![]);
+    }
+
+    let mut statement = tx.prepare(&format!(
+        "SELECT display_id, task_id FROM working_set WHERE display_id IN ({})",
+        placeholders(display_ids.len())
+    ))?;
+    let mut rows = statement.query(params_from_iter(display_ids.iter()))?;
+    let mut found: Vec<(i64, i64)> = vec![];
+    while let Some(row) = rows.next()? {
+        found.push((row.get(0)?, row.get(1)?));
+    }
+
+    display_ids
+        .iter()
+        .map(|display_id| {
+            found
+                .iter()
+                .find(|(id, _)| id == display_id)
+                .map(|(_, task_id)| *task_id)
+                .ok_or_else(|| {
+                    CoreError::ArgumentError(format!(
+                        "No pending task has the id {}, use id:<id> for the permanent id",
+                        display_id
+                    ))
+                })
+        })
+        .collect()
+}
+
+// Refresh the display ids of tasks that were read before a sync
+pub fn attach_display_ids(tx: &Transaction, tasks: &mut Vec<Task>) -> Result<(), CoreError> {
+    let mut statement = tx.prepare("SELECT task_id, display_id FROM working_set")?;
+    let mut rows = statement.query([])?;
+    let mut display_ids: HashMap<i64, i64> = HashMap::new();
+    while let Some(row) = rows.next()? {
+        display_ids.insert(row.get(0)?, row.get(1)?);
+    }
+    for task in tasks.iter_mut() {
+        task.display_id = display_ids.get(&task.id).copied();
+    }
+    Ok(())
+}
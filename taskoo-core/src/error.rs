@@ -16,6 +16,10 @@ pub enum InitialError {
     IoError { path: String, source: IoError },
     #[error("Unable to find the path of the config directory")]
     DirError(),
+    #[error("Profile {0} doesn't exist")]
+    UnknownProfile(String),
+    #[error("Profile {0} doesn't have a db_path")]
+    MissingDatabasePath(String),
 }
 
 #[derive(Error, Debug)]
@@ -50,6 +54,12 @@ pub enum CoreError {
     UnexpetedError(String),
     #[error("CoreError: {0}")]
     CommandError(String),
+    #[error("EncryptionError: {0}")]
+    EncryptionError(String),
+    #[error("BackupError: {0}")]
+    BackupError(String),
+    #[error("Failed to migrate the database to schema version {version}: {reason}")]
+    MigrationError { version: i64, reason: String },
 }
 
 impl From<ArgumentError> for CoreError {
@@ -8,3 +8,4 @@ mod util;
 
 pub mod operation;
 pub mod option_parser;
+pub mod store;
@@ -3,9 +3,10 @@ use crate::db::task_helper::{Task, TASK_STATES};
 use crate::db::task_manager::TaskManager;
 use crate::error::*;
 use crate::option_parser::{parse_command_option, CommandError};
+use crate::store::{AddParams, ModifyParams, TaskStore};
 use log::debug;
 
-pub struct Add<'a> {
+pub struct Add<'a, S: TaskStore = TaskManager> {
     pub body: String,
     pub priority: Option<String>,
     pub context: Option<String>,
@@ -17,8 +18,12 @@ pub struct Add<'a> {
     pub repetition_scheduled: Option<&'a str>,
     pub annotation: Option<&'a str>,
     pub parent_task_ids: Option<Vec<i64>>,
-    task_manager: Option<TaskManager>,
-    task_manager_for_test: Option<&'a mut TaskManager>,
+    pub estimate: Option<&'a str>,
+    pub udas: Vec<(String, String)>, // name and value
+    pub parent_id: Option<i64>,      // Added as a subtask of this task
+    pub project: Option<String>,
+    task_manager: Option<S>,
+    task_manager_for_test: Option<&'a mut S>,
     result: Option<Vec<Task>>,
 }
 
@@ -29,17 +34,22 @@ pub struct AddAnnotation {
     result: Option<Vec<Task>>,
 }
 
-pub struct SetBody {
+pub struct SetBody<S: TaskStore = TaskManager> {
     pub task_id: i64,
     pub body: String,
-    database_manager: Option<TaskManager>,
+    database_manager: Option<S>,
     result: Option<Vec<Task>>,
 }
 
 impl Add<'_> {
     pub fn new(data: &Vec<String>) -> Result<Add, CoreError> {
-        let option =
-            parse_command_option(&data.iter().map(|s| &**s).collect(), true, false, false)?;
+        let option = parse_command_option(
+            &data.iter().map(|s| &**s).collect(),
+            true,
+            false,
+            false,
+            &ConfigManager::get_uda_names()?,
+        )?;
         let body = option
             .body
             .ok_or(CommandError::InvalidBodyError(String::from(
@@ -70,13 +80,20 @@ impl Add<'_> {
             repetition_scheduled: option.repetition_scheduled,
             annotation: None,
             parent_task_ids: option.parent_task_ids,
+            estimate: option.estimate,
+            udas: option.udas,
+            parent_id: option.parent_id.flatten(),
+            project: option.project,
             task_manager: None,
             task_manager_for_test: None,
             result: None,
         })
     }
+}
 
-    pub fn new_with_task_manager<'a>(body: &'a str, task_manager: &'a mut TaskManager) -> Add<'a> {
+impl<'a, S: TaskStore> Add<'a, S> {
+    // The task is added to `task_manager`, which can be any store
+    pub fn new_with_task_manager(body: &str, task_manager: &'a mut S) -> Add<'a, S> {
         Add {
             body: body.to_string(),
             priority: None,
@@ -89,6 +106,10 @@ impl Add<'_> {
             repetition_scheduled: None,
             annotation: None,
             parent_task_ids: None,
+            estimate: None,
+            udas: vec![],
+            parent_id: None,
+            project: None,
             task_manager: None,
             task_manager_for_test: Some(task_manager),
             result: None,
@@ -128,10 +149,17 @@ impl AddAnnotation {
 
 impl SetBody {
     pub fn new(task_id: i64, body: String) -> SetBody {
+        SetBody::new_with_store(task_id, body, None)
+    }
+}
+
+impl<S: TaskStore> SetBody<S> {
+    // A new store is opened by `init` when `store` is None
+    pub fn new_with_store(task_id: i64, body: String, store: Option<S>) -> SetBody<S> {
         SetBody {
             task_id,
             body,
-            database_manager: None,
+            database_manager: store,
             result: None,
         }
     }
@@ -140,12 +168,12 @@ impl SetBody {
 struct AddCommand;
 impl AddCommand {}
 
-impl Operation for Add<'_> {
-    fn init(&mut self) -> Result<(), InitialError> {
+impl<S: TaskStore> Operation for Add<'_, S> {
+    fn init(&mut self) -> Result<(), CoreError> {
         if self.task_manager_for_test.is_none() {
-            self.task_manager = Some(TaskManager::new(
+            self.task_manager = Some(S::open(
                 &ConfigManager::init_and_get_database_path()?,
-            ));
+            )?);
         }
         Ok(())
     }
@@ -167,41 +195,32 @@ impl Operation for Add<'_> {
 
         assert!(!(self.task_manager.is_some() && self.task_manager_for_test.is_some()));
 
+        let params = AddParams {
+            body: &self.body,
+            priority: self.priority.clone(),
+            context: self.context.clone(),
+            tags: self.tags.clone(),
+            date_due: self.date_due,
+            date_scheduled: self.date_scheduled,
+            repetition_due: self.repetition_due,
+            repetition_scheduled: self.repetition_scheduled,
+            annotation: self.annotation,
+            state_name: self.state.clone(),
+            parent_task_ids: self.parent_task_ids.clone(),
+            estimate: self.estimate,
+            udas: self.udas.clone(),
+            parent_id: self.parent_id,
+            project: self.project.clone(),
+        };
         match self.task_manager.as_mut() {
             Some(manager) => {
                 debug!("Using task_manager");
-                return TaskManager::add(
-                    manager,
-                    &self.body,
-                    &self.priority,
-                    &self.context,
-                    &self.tags,
-                    &self.date_due,
-                    &self.date_scheduled,
-                    &self.repetition_due,
-                    &self.repetition_scheduled,
-                    &self.annotation,
-                    &self.state,
-                    &self.parent_task_ids,
-                );
+                return manager.add(&params);
             }
             None => {
                 assert!(self.task_manager_for_test.is_some());
                 debug!("Using task_manager_for_test");
-                return TaskManager::add(
-                    self.task_manager_for_test.as_mut().unwrap(),
-                    &self.body,
-                    &self.priority,
-                    &self.context,
-                    &self.tags,
-                    &self.date_due,
-                    &self.date_scheduled,
-                    &self.repetition_due,
-                    &self.repetition_scheduled,
-                    &self.annotation,
-                    &self.state,
-                    &self.parent_task_ids,
-                );
+                return self.task_manager_for_test.as_mut().unwrap().add(&params);
             }
         }
     }
@@ -216,10 +235,10 @@ impl Operation for Add<'_> {
 }
 
 impl Operation for AddAnnotation {
-    fn init(&mut self) -> Result<(), InitialError> {
+    fn init(&mut self) -> Result<(), CoreError> {
         self.database_manager = Some(TaskManager::new(
             &ConfigManager::init_and_get_database_path()?,
-        ));
+        )?);
         Ok(())
     }
 
@@ -240,31 +259,23 @@ impl Operation for AddAnnotation {
     }
 }
 
-impl Operation for SetBody {
-    fn init(&mut self) -> Result<(), InitialError> {
-        self.database_manager = Some(TaskManager::new(
-            &ConfigManager::init_and_get_database_path()?,
-        ));
+impl<S: TaskStore> Operation for SetBody<S> {
+    fn init(&mut self) -> Result<(), CoreError> {
+        if self.database_manager.is_none() {
+            self.database_manager = Some(S::open(
+                &ConfigManager::init_and_get_database_path()?,
+            )?);
+        }
         Ok(())
     }
 
     fn do_work(&mut self) -> Result<Vec<Task>, CoreError> {
         let task_ids = vec![self.task_id];
-        let body = Some(self.body.as_str());
-        let tasks = TaskManager::modify(
-            self.database_manager.as_mut().unwrap(),
-            &task_ids,
-            &body,
-            &None,
-            &None,
-            &vec![],
-            &None,
-            &None,
-            &None,
-            &None,
-            &None,
-            &vec![],
-        )?;
+        let params = ModifyParams {
+            body: Some(self.body.as_str()),
+            ..Default::default()
+        };
+        let tasks = self.database_manager.as_mut().unwrap().modify(&task_ids, &params)?;
         Ok(tasks)
     }
 
@@ -1,29 +1,22 @@
 use chrono::{NaiveDate};
 use crate::core::{ConfigManager};
-use crate::db::task_helper::Task;
+use crate::db::task_helper::{Effort, Task};
+use std::collections::BTreeMap;
 use crate::db::task_manager::TaskManager;
 use crate::error::*;
+use crate::store::TaskStore;
 
 /* Some of the Agenda functionalities are overlap with list, however,
  * Agenda should provide better API for clients */
-pub struct Agenda {
+pub struct Agenda<S: TaskStore = TaskManager> {
     pub start_day: String,
     pub end_day: Option<String>,
     pub context_name: Option<String>,
-    database_manager: Option<TaskManager>,
+    database_manager: Option<S>,
     result: Vec<(NaiveDate, Vec<Task>)>,
 }
 
 impl Agenda {
-    pub fn init(&mut self) -> Result<(), InitialError> {
-        if self.database_manager.is_none() {
-            self.database_manager = Some(TaskManager::new(
-                &ConfigManager::init_and_get_database_path()?,
-            ));
-        }
-        Ok(())
-    }
-
     pub fn new2(data: &Vec<String>) -> Result<Agenda, CoreError> {
         if data.is_empty() {
             // TODO return
@@ -56,18 +49,38 @@ impl Agenda {
     }
 
     pub fn new(start_day: String, end_day: Option<String>, context_name: Option<String>) -> Agenda {
+        Agenda::new_with_store(start_day, end_day, context_name, None)
+    }
+}
+
+impl<S: TaskStore> Agenda<S> {
+    // A new store is opened by `init` when `store` is None
+    pub fn new_with_store(
+        start_day: String,
+        end_day: Option<String>,
+        context_name: Option<String>,
+        store: Option<S>,
+    ) -> Agenda<S> {
         Agenda {
             start_day: start_day,
             end_day: end_day,
             context_name,
-            database_manager: None,
+            database_manager: store,
             result: vec![],
         }
     }
 
+    pub fn init(&mut self) -> Result<(), CoreError> {
+        if self.database_manager.is_none() {
+            self.database_manager = Some(S::open(
+                &ConfigManager::init_and_get_database_path()?,
+            )?);
+        }
+        Ok(())
+    }
+
     pub fn do_work_for_agenda(&mut self) -> Result<Vec<(NaiveDate, Vec<Task>)>, CoreError> {
-        return TaskManager::view_agenda(
-            self.database_manager.as_mut().unwrap(),
+        return self.database_manager.as_mut().unwrap().view_agenda(
             self.start_day.clone(),
             self.end_day.clone(),
             self.context_name.clone(),
@@ -81,6 +94,25 @@ impl Agenda {
     pub fn get_result(&mut self) -> &Vec<(NaiveDate, Vec<Task>)> {
         return &self.result;
     }
+
+    // The effort of the tasks of each day per context, the contexts are
+    // sorted by name
+    pub fn get_effort(&self) -> Vec<(NaiveDate, Vec<(String, Effort)>)> {
+        self.result
+            .iter()
+            .map(|(day, tasks)| {
+                let mut contexts: BTreeMap<&str, Vec<&Task>> = BTreeMap::new();
+                for task in tasks.iter() {
+                    contexts.entry(&task.context).or_default().push(task);
+                }
+                let efforts = contexts
+                    .into_iter()
+                    .map(|(context, tasks)| (context.to_string(), Effort::of(tasks)))
+                    .collect();
+                (*day, efforts)
+            })
+            .collect()
+    }
 }
 
 #[cfg(test)]
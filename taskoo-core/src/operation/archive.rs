@@ -0,0 +1,96 @@
+use crate::core::{ConfigManager, Operation};
+use crate::db::task_helper::Task;
+use crate::db::task_manager::TaskManager;
+use crate::option_parser::parse_command_option;
+use crate::error::*;
+
+// Move the tasks completed more than `older_than_days` ago into the
+// archive, `archive_after_days` of the config is used when it's None.
+pub struct Archive {
+    pub older_than_days: Option<i64>,
+    pub database_manager: Option<TaskManager>,
+    result: Vec<Task>,
+}
+
+pub struct Unarchive {
+    pub task_ids: Vec<i64>,
+    pub database_manager: Option<TaskManager>,
+    result: Vec<Task>,
+}
+
+impl Archive {
+    pub fn new(older_than_days: Option<i64>) -> Archive {
+        Archive {
+            older_than_days,
+            database_manager: None,
+            result: vec![],
+        }
+    }
+}
+
+impl Unarchive {
+    pub fn new(input_str: &Vec<String>) -> Result<Unarchive, CoreError> {
+        let option = parse_command_option(
+            &input_str.iter().map(|s| &**s).collect(),
+            false,
+            false,
+            true,
+            &vec![],
+        )?;
+
+        Ok(Unarchive {
+            task_ids: option.task_ids,
+            database_manager: None,
+            result: vec![],
+        })
+    }
+}
+
+impl Operation for Archive {
+    fn init(&mut self) -> Result<(), CoreError> {
+        if self.database_manager.is_none() {
+            self.database_manager = Some(TaskManager::new(
+                &ConfigManager::init_and_get_database_path()?,
+            )?);
+        }
+        Ok(())
+    }
+
+    fn do_work(&mut self) -> Result<Vec<Task>, CoreError> {
+        return TaskManager::archive(
+            self.database_manager.as_mut().unwrap(),
+            &self.older_than_days,
+        );
+    }
+
+    fn set_result(&mut self, result: Vec<Task>) {
+        self.result = result;
+    }
+
+    fn get_result(&mut self) -> &Vec<Task> {
+        return &self.result;
+    }
+}
+
+impl Operation for Unarchive {
+    fn init(&mut self) -> Result<(), CoreError> {
+        if self.database_manager.is_none() {
+            self.database_manager = Some(TaskManager::new(
+                &ConfigManager::init_and_get_database_path()?,
+            )?);
+        }
+        Ok(())
+    }
+
+    fn do_work(&mut self) -> Result<Vec<Task>, CoreError> {
+        return TaskManager::unarchive(self.database_manager.as_mut().unwrap(), &self.task_ids);
+    }
+
+    fn set_result(&mut self, result: Vec<Task>) {
+        self.result = result;
+    }
+
+    fn get_result(&mut self) -> &Vec<Task> {
+        return &self.result;
+    }
+}
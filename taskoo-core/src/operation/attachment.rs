@@ -0,0 +1,132 @@
+use crate::core::{ConfigManager, Operation};
+use crate::db::attachment::Attachment;
+use crate::db::task_helper::Task;
+use crate::db::task_manager::TaskManager;
+use crate::error::*;
+
+// Like the note operations, the attachment operations return the task of
+// the attachment with all of its attachments
+
+pub struct AddAttachment {
+    pub task_id: i64,
+    // A URL or the path of a file
+    pub location: String,
+    // Copy the file into the database rather than keeping its path
+    pub copy: bool,
+    pub database_manager: Option<TaskManager>,
+    result: Vec<Task>,
+}
+
+pub struct DeleteAttachment {
+    pub attachment_id: i64,
+    pub database_manager: Option<TaskManager>,
+    result: Vec<Task>,
+}
+
+// An attachment with its copied data, for opening it. It isn't a list of
+// tasks, so it's executed by `execute_open_attachment` rather than
+// implementing `Operation`.
+pub struct OpenAttachment {
+    pub attachment_id: i64,
+    pub database_manager: Option<TaskManager>,
+    result: Option<(Attachment, Option<Vec<u8>>)>,
+}
+
+impl AddAttachment {
+    pub fn new(task_id: i64, location: String, copy: bool) -> AddAttachment {
+        AddAttachment {
+            task_id,
+            location,
+            copy,
+            database_manager: None,
+            result: vec![],
+        }
+    }
+}
+
+impl DeleteAttachment {
+    pub fn new(attachment_id: i64) -> DeleteAttachment {
+        DeleteAttachment {
+            attachment_id,
+            database_manager: None,
+            result: vec![],
+        }
+    }
+}
+
+fn init_database_manager(database_manager: &mut Option<TaskManager>) -> Result<(), CoreError> {
+    if database_manager.is_none() {
+        *database_manager = Some(TaskManager::new(
+            &ConfigManager::init_and_get_database_path()?,
+        )?);
+    }
+    Ok(())
+}
+
+impl Operation for AddAttachment {
+    fn init(&mut self) -> Result<(), CoreError> {
+        init_database_manager(&mut self.database_manager)
+    }
+
+    fn do_work(&mut self) -> Result<Vec<Task>, CoreError> {
+        TaskManager::add_attachment(
+            self.database_manager.as_mut().unwrap(),
+            self.task_id,
+            &self.location,
+            self.copy,
+        )
+    }
+
+    fn set_result(&mut self, result: Vec<Task>) {
+        self.result = result;
+    }
+
+    fn get_result(&mut self) -> &Vec<Task> {
+        return &self.result;
+    }
+}
+
+impl Operation for DeleteAttachment {
+    fn init(&mut self) -> Result<(), CoreError> {
+        init_database_manager(&mut self.database_manager)
+    }
+
+    fn do_work(&mut self) -> Result<Vec<Task>, CoreError> {
+        TaskManager::delete_attachment(self.database_manager.as_mut().unwrap(), self.attachment_id)
+    }
+
+    fn set_result(&mut self, result: Vec<Task>) {
+        self.result = result;
+    }
+
+    fn get_result(&mut self) -> &Vec<Task> {
+        return &self.result;
+    }
+}
+
+impl OpenAttachment {
+    pub fn new(attachment_id: i64) -> OpenAttachment {
+        OpenAttachment {
+            attachment_id,
+            database_manager: None,
+            result: None,
+        }
+    }
+
+    pub fn init(&mut self) -> Result<(), CoreError> {
+        init_database_manager(&mut self.database_manager)
+    }
+
+    pub fn do_work_for_open(&mut self) -> Result<(Attachment, Option<Vec<u8>>), CoreError> {
+        self.database_manager.as_mut().unwrap().get_attachment(self.attachment_id)
+    }
+
+    pub fn set_result(&mut self, result: (Attachment, Option<Vec<u8>>)) {
+        self.result = Some(result);
+    }
+
+    // None until the operation is executed
+    pub fn get_result(&self) -> &Option<(Attachment, Option<Vec<u8>>)> {
+        &self.result
+    }
+}
@@ -0,0 +1,60 @@
+use crate::core::ConfigManager;
+use crate::db::backup::Backup;
+use crate::db::task_manager::TaskManager;
+use crate::error::*;
+
+pub enum BackupAction {
+    // Take a snapshot now
+    Create,
+    List,
+    // Replace the database with the snapshot of this name
+    Restore(String),
+}
+
+// The snapshots aren't a list of tasks, so it's executed by
+// `execute_backup` rather than implementing `Operation`. The result is the
+// snapshot that was taken, the snapshots, or the snapshot of the database
+// before it was restored.
+pub struct Backups {
+    pub action: BackupAction,
+    pub database_manager: Option<TaskManager>,
+    result: Vec<Backup>,
+}
+
+impl Backups {
+    pub fn new(action: BackupAction) -> Backups {
+        Backups {
+            action,
+            database_manager: None,
+            result: vec![],
+        }
+    }
+
+    pub fn init(&mut self) -> Result<(), CoreError> {
+        if self.database_manager.is_none() {
+            self.database_manager = Some(TaskManager::new(
+                &ConfigManager::init_and_get_database_path()?,
+            )?);
+        }
+        Ok(())
+    }
+
+    pub fn do_work_for_backup(&mut self) -> Result<Vec<Backup>, CoreError> {
+        let database_manager = self.database_manager.as_mut().unwrap();
+        match &self.action {
+            BackupAction::Create => Ok(vec![database_manager.backup()?]),
+            BackupAction::List => database_manager.get_backups(),
+            BackupAction::Restore(name) => {
+                Ok(database_manager.restore_backup(name)?.into_iter().collect())
+            }
+        }
+    }
+
+    pub fn set_result(&mut self, result: Vec<Backup>) {
+        self.result = result;
+    }
+
+    pub fn get_result(&self) -> &Vec<Backup> {
+        &self.result
+    }
+}
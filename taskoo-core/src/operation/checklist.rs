@@ -0,0 +1,188 @@
+use crate::core::{ConfigManager, Operation};
+use crate::db::task_helper::Task;
+use crate::db::task_manager::TaskManager;
+use crate::error::*;
+
+// Like the note operations, the checklist operations return the task with
+// its whole checklist. Items are referred to by their position, from 1.
+
+pub struct AddChecklistItem {
+    pub task_id: i64,
+    pub body: String,
+    pub database_manager: Option<TaskManager>,
+    result: Vec<Task>,
+}
+
+pub struct CheckChecklistItem {
+    pub task_id: i64,
+    pub position: i64,
+    // False unchecks the item
+    pub done: bool,
+    pub database_manager: Option<TaskManager>,
+    result: Vec<Task>,
+}
+
+pub struct MoveChecklistItem {
+    pub task_id: i64,
+    pub from: i64,
+    pub to: i64,
+    pub database_manager: Option<TaskManager>,
+    result: Vec<Task>,
+}
+
+pub struct DeleteChecklistItem {
+    pub task_id: i64,
+    pub position: i64,
+    pub database_manager: Option<TaskManager>,
+    result: Vec<Task>,
+}
+
+impl AddChecklistItem {
+    pub fn new(task_id: i64, body: String) -> AddChecklistItem {
+        AddChecklistItem {
+            task_id,
+            body,
+            database_manager: None,
+            result: vec![],
+        }
+    }
+}
+
+impl CheckChecklistItem {
+    pub fn new(task_id: i64, position: i64, done: bool) -> CheckChecklistItem {
+        CheckChecklistItem {
+            task_id,
+            position,
+            done,
+            database_manager: None,
+            result: vec![],
+        }
+    }
+}
+
+impl MoveChecklistItem {
+    pub fn new(task_id: i64, from: i64, to: i64) -> MoveChecklistItem {
+        MoveChecklistItem {
+            task_id,
+            from,
+            to,
+            database_manager: None,
+            result: vec![],
+        }
+    }
+}
+
+impl DeleteChecklistItem {
+    pub fn new(task_id: i64, position: i64) -> DeleteChecklistItem {
+        DeleteChecklistItem {
+            task_id,
+            position,
+            database_manager: None,
+            result: vec![],
+        }
+    }
+}
+
+fn init_database_manager(database_manager: &mut Option<TaskManager>) -> Result<(), CoreError> {
+    if database_manager.is_none() {
+        *database_manager = Some(TaskManager::new(
+            &ConfigManager::init_and_get_database_path()?,
+        )?);
+    }
+    Ok(())
+}
+
+impl Operation for AddChecklistItem {
+    fn init(&mut self) -> Result<(), CoreError> {
+        init_database_manager(&mut self.database_manager)
+    }
+
+    fn do_work(&mut self) -> Result<Vec<Task>, CoreError> {
+        if self.body.trim().is_empty() {
+            return Err(CoreError::ArgumentError(String::from(
+                "The checklist item can't be empty",
+            )));
+        }
+        TaskManager::add_checklist_item(
+            self.database_manager.as_mut().unwrap(),
+            self.task_id,
+            self.body.trim(),
+        )
+    }
+
+    fn set_result(&mut self, result: Vec<Task>) {
+        self.result = result;
+    }
+
+    fn get_result(&mut self) -> &Vec<Task> {
+        return &self.result;
+    }
+}
+
+impl Operation for CheckChecklistItem {
+    fn init(&mut self) -> Result<(), CoreError> {
+        init_database_manager(&mut self.database_manager)
+    }
+
+    fn do_work(&mut self) -> Result<Vec<Task>, CoreError> {
+        TaskManager::check_checklist_item(
+            self.database_manager.as_mut().unwrap(),
+            self.task_id,
+            self.position,
+            self.done,
+        )
+    }
+
+    fn set_result(&mut self, result: Vec<Task>) {
+        self.result = result;
+    }
+
+    fn get_result(&mut self) -> &Vec<Task> {
+        return &self.result;
+    }
+}
+
+impl Operation for MoveChecklistItem {
+    fn init(&mut self) -> Result<(), CoreError> {
+        init_database_manager(&mut self.database_manager)
+    }
+
+    fn do_work(&mut self) -> Result<Vec<Task>, CoreError> {
+        TaskManager::move_checklist_item(
+            self.database_manager.as_mut().unwrap(),
+            self.task_id,
+            self.from,
+            self.to,
+        )
+    }
+
+    fn set_result(&mut self, result: Vec<Task>) {
+        self.result = result;
+    }
+
+    fn get_result(&mut self) -> &Vec<Task> {
+        return &self.result;
+    }
+}
+
+impl Operation for DeleteChecklistItem {
+    fn init(&mut self) -> Result<(), CoreError> {
+        init_database_manager(&mut self.database_manager)
+    }
+
+    fn do_work(&mut self) -> Result<Vec<Task>, CoreError> {
+        TaskManager::delete_checklist_item(
+            self.database_manager.as_mut().unwrap(),
+            self.task_id,
+            self.position,
+        )
+    }
+
+    fn set_result(&mut self, result: Vec<Task>) {
+        self.result = result;
+    }
+
+    fn get_result(&mut self) -> &Vec<Task> {
+        return &self.result;
+    }
+}
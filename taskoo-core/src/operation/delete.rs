@@ -1,12 +1,19 @@
 use crate::core::{ConfigManager, Operation};
 use crate::db::task_helper::Task;
 use crate::db::task_manager::TaskManager;
-use crate::option_parser::parse_command_option;
+use crate::option_parser::{
+    parse_command_option, parse_command_option_with_display_ids, CommandOption,
+};
 use crate::error::*;
+use crate::store::TaskStore;
 
-pub struct DeleteOperation {
+pub struct DeleteOperation<S: TaskStore = TaskManager> {
     pub task_ids: Vec<i64>,
-    pub database_manager: Option<TaskManager>,
+    pub task_uuids: Vec<String>, // Resolved to task ids by `do_work`
+    pub display_ids: Vec<i64>,   // Resolved to task ids by `do_work`
+    // Delete the subtasks too, otherwise they move up to the parent of the task
+    pub cascade: bool,
+    pub database_manager: Option<S>,
     pub result: Option<Vec<Task>>,
 }
 
@@ -17,28 +24,72 @@ impl DeleteOperation {
             false,
             false,
             true,
+            &vec![],
         )
         .unwrap();
 
-        Ok(DeleteOperation {
+        Ok(DeleteOperation::from_command_option(option, None))
+    }
+
+    // Numbers in `input_str` are display ids, see `parse_command_option_with_display_ids`
+    pub fn new_with_display_ids(input_str: &Vec<String>) -> Result<DeleteOperation, CoreError> {
+        let option = parse_command_option_with_display_ids(
+            &input_str.iter().map(|s| &**s).collect(),
+            false,
+            &vec![],
+        )?;
+        Ok(DeleteOperation::from_command_option(option, None))
+    }
+}
+
+impl<S: TaskStore> DeleteOperation<S> {
+    // Same as `new_with_display_ids`, the tasks are deleted from `store`
+    pub fn new_with_store(
+        input_str: &Vec<String>,
+        store: S,
+    ) -> Result<DeleteOperation<S>, CoreError> {
+        let option = parse_command_option_with_display_ids(
+            &input_str.iter().map(|s| &**s).collect(),
+            false,
+            &vec![],
+        )?;
+        Ok(DeleteOperation::from_command_option(option, Some(store)))
+    }
+
+    fn from_command_option(option: CommandOption, store: Option<S>) -> DeleteOperation<S> {
+        DeleteOperation {
             task_ids: option.task_ids,
-            database_manager: None,
+            task_uuids: option.task_uuids,
+            display_ids: option.display_ids,
+            cascade: false,
+            database_manager: store,
             result: None,
-        })
+        }
     }
 }
 
-impl Operation for DeleteOperation {
-    fn init(&mut self) -> Result<(), InitialError> {
+impl<S: TaskStore> Operation for DeleteOperation<S> {
+    fn init(&mut self) -> Result<(), CoreError> {
         if self.database_manager.is_none() {
-            self.database_manager = Some(TaskManager::new(
+            self.database_manager = Some(S::open(
                 &ConfigManager::init_and_get_database_path()?,
-            ));
+            )?);
         }
         Ok(())
     }
     fn do_work(&mut self) -> Result<Vec<Task>, CoreError> {
-        return TaskManager::delete(self.database_manager.as_mut().unwrap(), &self.task_ids);
+        let database_manager = self.database_manager.as_mut().unwrap();
+        let mut uuid_task_ids = database_manager.get_task_ids_by_uuid(&self.task_uuids)?;
+        self.task_ids.append(&mut uuid_task_ids);
+        self.task_uuids.clear();
+        let mut display_task_ids = database_manager.get_task_ids_by_display_id(&self.display_ids)?;
+        self.task_ids.append(&mut display_task_ids);
+        self.display_ids.clear();
+        if self.cascade {
+            let mut subtask_ids = database_manager.get_subtask_ids(&self.task_ids)?;
+            self.task_ids.append(&mut subtask_ids);
+        }
+        return database_manager.delete(&self.task_ids);
     }
     fn set_result(&mut self, result: Vec<Task>) {
         self.result = Some(result);
@@ -66,7 +117,7 @@ mod tests {
 
     #[test]
     fn test_delete_operation_returns_deleted_tasks() -> Result<(), CoreError> {
-        let mut database_manager = TaskManager::new(&get_setting());
+        let mut database_manager = TaskManager::new(&get_setting())?;
 
         let mut operation = Add::new_with_task_manager("Task One", &mut database_manager);
         execute(&mut operation)?;
@@ -76,6 +127,9 @@ mod tests {
         let delete_ids = vec![1, 2];
         let mut delete_operation = DeleteOperation {
             task_ids: delete_ids,
+            task_uuids: vec![],
+            display_ids: vec![],
+            cascade: false,
             database_manager: Some(database_manager),
             result: None,
         };
@@ -88,4 +142,28 @@ mod tests {
         assert_eq!(deleted[1].body, "Task Two");
         Ok(())
     }
+
+    #[test]
+    fn test_delete_operation_accepts_uuids() -> Result<(), CoreError> {
+        let mut database_manager = TaskManager::new(&get_setting())?;
+        for body in ["Task One", "Task Two", "Task Three"] {
+            let mut operation = Add::new_with_task_manager(body, &mut database_manager);
+            execute(&mut operation)?;
+        }
+        let tasks = database_manager.get(&None, &None, &vec![], &None, &None, &Some(3), &None)?;
+
+        let data = vec![String::from("1"), tasks[0].uuid.clone()];
+        let mut delete_operation = DeleteOperation::new(&data)?;
+        assert_eq!(delete_operation.task_uuids, vec![tasks[0].uuid.clone()]);
+        delete_operation.database_manager = Some(database_manager);
+        execute(&mut delete_operation)?;
+
+        let deleted: Vec<&str> = delete_operation
+            .get_result()
+            .iter()
+            .map(|task| task.body.as_str())
+            .collect();
+        assert_eq!(deleted, vec!["Task One", "Task Three"]);
+        Ok(())
+    }
 }
@@ -0,0 +1,43 @@
+use crate::core::ConfigManager;
+use crate::db::doctor::Problem;
+use crate::db::task_manager::TaskManager;
+use crate::error::*;
+
+// The problems aren't a list of tasks, so it's executed by
+// `execute_doctor` rather than implementing `Operation`.
+pub struct Doctor {
+    pub fix: bool, // Repair the problems as well
+    pub database_manager: Option<TaskManager>,
+    result: Vec<Problem>,
+}
+
+impl Doctor {
+    pub fn new(fix: bool) -> Doctor {
+        Doctor {
+            fix,
+            database_manager: None,
+            result: vec![],
+        }
+    }
+
+    pub fn init(&mut self) -> Result<(), CoreError> {
+        if self.database_manager.is_none() {
+            self.database_manager = Some(TaskManager::new(
+                &ConfigManager::init_and_get_database_path()?,
+            )?);
+        }
+        Ok(())
+    }
+
+    pub fn do_work_for_doctor(&mut self) -> Result<Vec<Problem>, CoreError> {
+        self.database_manager.as_mut().unwrap().doctor(self.fix)
+    }
+
+    pub fn set_result(&mut self, result: Vec<Problem>) {
+        self.result = result;
+    }
+
+    pub fn get_result(&self) -> &Vec<Problem> {
+        &self.result
+    }
+}
@@ -0,0 +1,56 @@
+use crate::core::ConfigManager;
+use crate::db::task_manager::TaskManager;
+use crate::error::*;
+
+pub enum EncryptionChange {
+    // Encrypt the database with the passphrase
+    Enable(String),
+    // Encrypt it again with the new passphrase
+    Rotate(String),
+    Disable,
+}
+
+// Changing the key doesn't return any task, so it's executed by
+// `execute_encryption` rather than implementing `Operation`.
+pub struct Encryption {
+    pub change: EncryptionChange,
+    pub database_manager: Option<TaskManager>,
+    // How many older snapshots were replaced by one with the new key
+    pub replaced_backups: usize,
+}
+
+impl Encryption {
+    pub fn new(change: EncryptionChange) -> Encryption {
+        Encryption {
+            change,
+            database_manager: None,
+            replaced_backups: 0,
+        }
+    }
+
+    pub fn init(&mut self) -> Result<(), CoreError> {
+        if self.database_manager.is_none() {
+            self.database_manager = Some(TaskManager::new(
+                &ConfigManager::init_and_get_database_path()?,
+            )?);
+        }
+        Ok(())
+    }
+
+    pub fn do_work_for_encryption(&mut self) -> Result<(), CoreError> {
+        let database_manager = self.database_manager.as_mut().unwrap();
+        self.replaced_backups = match &self.change {
+            EncryptionChange::Enable(passphrase) => {
+                database_manager.enable_encryption(passphrase)?
+            }
+            EncryptionChange::Rotate(passphrase) => {
+                database_manager.rotate_encryption_key(passphrase)?
+            }
+            EncryptionChange::Disable => {
+                database_manager.disable_encryption()?;
+                0
+            }
+        };
+        Ok(())
+    }
+}
@@ -1,20 +1,42 @@
 use crate::core::{ConfigManager, Operation};
-use crate::db::task_helper::Task;
+use crate::db::task_helper::{Effort, Task};
 use crate::db::task_manager::TaskManager;
-use crate::option_parser::{parse_command_option, CommandOption};
+use crate::db::time_tracking::parse_local_time;
+use crate::db::uda::resolve_udas;
+use crate::option_parser::{
+    parse_command_option, parse_command_option_with_display_ids, CommandOption,
+};
 use crate::error::*;
 use crate::command::ContextCommand;
 use crate::command::SimpleCommand;
+use crate::store::TaskStore;
+use std::cmp::Ordering;
 
-pub struct Get<'a> {
+pub struct Get<'a, S: TaskStore = TaskManager> {
     pub priority: Option<String>,
     pub context: Option<String>,
+    pub exact_context: bool, // Leave out the tasks of the subcontexts
     pub tags: Vec<String>,
     pub date_due: Option<&'a str>,
     pub date_scheduled: Option<&'a str>,
     pub task_id: Option<i64>,
+    pub task_uuid: Option<String>,     // UUID or unique UUID prefix, instead of task_id
+    pub display_id: Option<i64>,       // Short id of a pending task, instead of task_id
     pub not_tags: Option<Vec<String>>, // Tags that don't exist
-    database_manager: Option<TaskManager>,
+    pub archived: bool,                // Get the archived tasks instead
+    pub with_archived: bool,           // The archived tasks follow the others
+    pub search: Option<String>,        // Full-text search, the best match first
+    // Only the tasks modified, started or completed at this date or later
+    pub modified_after: Option<&'a str>,
+    pub started_after: Option<&'a str>,
+    pub completed_after: Option<&'a str>,
+    // Only the tasks with these UDA values, an empty value for the ones without the UDA
+    pub udas: Vec<(String, String)>,
+    // A UDA or a property of `Task`, the tasks without the UDA come last
+    pub sort_by: Option<String>,
+    pub parent_id: Option<i64>, // Only the subtasks of this task
+    pub project: Option<String>, // Only the tasks of this project
+    database_manager: Option<S>,
     result: Vec<Task>,
 }
 
@@ -26,9 +48,14 @@ impl<'a> Get<'a> {
             // because get operation is per context
             Get::new_operations(CommandOption::new(), None)
         } else {
-            let option =
-                parse_command_option(&data.iter().map(|s| &**s).collect(), false, false, false)
-                    .unwrap();
+            let option = parse_command_option(
+                &data.iter().map(|s| &**s).collect(),
+                false,
+                false,
+                false,
+                &ConfigManager::get_uda_names()?,
+            )
+            .unwrap();
             match option.context {
                 Some(ref context) => {
                     let context_name = context.clone();
@@ -43,54 +70,85 @@ impl<'a> Get<'a> {
         command_option: CommandOption,
         some_context_names: Option<Vec<String>>,
     ) -> Result<Vec<(String, Get)>, CoreError> {
-        let context_names = match some_context_names {
-            Some(context_names) => context_names,
-            None => {
-                // If no context names are passed, use all context
-                let mut command = ContextCommand::new()?;
-                let context_names = command.get_all()?;
-                context_names
-            }
-        };
+        // An operation for each context and subcontext, as a tree. If no
+        // context names are passed, use all context
+        let context_names = ContextCommand::new()?.get_tree(&some_context_names)?;
 
         let mut result = vec![];
         for context in context_names.iter() {
             let mut operation = Get::new();
             operation.context = Some(context.to_string());
+            operation.exact_context = true;
             operation.priority = command_option.priority.clone();
             operation.tags = command_option.tags.clone();
             operation.date_due = command_option.date_due;
             operation.date_scheduled = command_option.date_scheduled;
             operation.not_tags = command_option.not_tags.clone();
+            operation.udas = command_option.udas.clone();
+            operation.parent_id = command_option.parent_id.flatten();
+            operation.project = command_option.project.clone();
             result.push((context.to_string(), operation));
         }
         Ok(result)
     }
 
     pub fn new() -> Get<'a> {
+        Get::new_with_store(None)
+    }
+}
+
+impl<'a, S: TaskStore> Get<'a, S> {
+    // A new store is opened by `init` when `store` is None
+    pub fn new_with_store(store: Option<S>) -> Get<'a, S> {
         Get {
             priority: None,
             context: None,
+            exact_context: false,
             tags: vec![],
             date_due: None,
             date_scheduled: None,
             task_id: None,
+            task_uuid: None,
+            display_id: None,
             not_tags: None,
-            database_manager: None,
+            archived: false,
+            with_archived: false,
+            search: None,
+            modified_after: None,
+            started_after: None,
+            completed_after: None,
+            udas: vec![],
+            sort_by: None,
+            parent_id: None,
+            project: None,
+            database_manager: store,
             result: vec![],
         }
     }
-}
 
-impl<'a> Operation for Get<'a> {
-    fn init(&mut self) -> Result<(), InitialError> {
-        self.database_manager = Some(TaskManager::new(
-            &ConfigManager::init_and_get_database_path()?,
-        ));
+    // Get a single task by a display id, id:<id> or a UUID prefix
+    pub fn select_task(&mut self, reference: &str) -> Result<(), CoreError> {
+        let option = parse_command_option_with_display_ids(&vec![reference], false, &vec![])?;
+        let count = option.task_ids.len() + option.display_ids.len() + option.task_uuids.len();
+        if count != 1 {
+            return Err(CoreError::ArgumentError(format!(
+                "{} doesn't refer to a single task",
+                reference
+            )));
+        }
+        self.task_id = option.task_ids.first().copied();
+        self.display_id = option.display_ids.first().copied();
+        self.task_uuid = option.task_uuids.into_iter().next();
         Ok(())
     }
 
-    fn do_work(&mut self) -> Result<Vec<Task>, CoreError> {
+    // The remaining, estimated and tracked effort of the result
+    pub fn get_effort(&self) -> Effort {
+        Effort::of(&self.result)
+    }
+
+    // The tasks, or the archived tasks
+    fn get_tasks(&mut self, archived: bool) -> Result<Vec<Task>, CoreError> {
         // Treat all tag names as lowercase
         for tag in self.tags.iter_mut() {
             *tag = tag.to_lowercase();
@@ -107,8 +165,37 @@ impl<'a> Operation for Get<'a> {
             None => None,
         };
 
-        return TaskManager::get(
-            self.database_manager.as_mut().unwrap(),
+        if let Some(uuid) = self.task_uuid.take() {
+            let task_ids = self
+                .database_manager
+                .as_mut()
+                .unwrap()
+                .get_task_ids_by_uuid(&vec![uuid])?;
+            self.task_id = Some(task_ids[0]);
+        }
+
+        if let Some(display_id) = self.display_id.take() {
+            let task_ids = self
+                .database_manager
+                .as_mut()
+                .unwrap()
+                .get_task_ids_by_display_id(&vec![display_id])?;
+            self.task_id = Some(task_ids[0]);
+        }
+
+        if archived {
+            return self.database_manager.as_mut().unwrap().get_archived(
+                &self.priority,
+                &self.context,
+                &self.tags,
+                &self.date_due,
+                &self.date_scheduled,
+                &self.task_id,
+                &self.not_tags,
+            );
+        }
+
+        let tasks = self.database_manager.as_mut().unwrap().get(
             &self.priority,
             &self.context,
             &self.tags,
@@ -116,7 +203,91 @@ impl<'a> Operation for Get<'a> {
             &self.date_scheduled,
             &self.task_id,
             &self.not_tags,
-        );
+        )?;
+
+        let mut tasks = match &self.search {
+            Some(query) => {
+                let task_ids: Vec<i64> = tasks.iter().map(|task| task.id).collect();
+                let results = self.database_manager.as_mut().unwrap().search(query, "", "")?;
+                results
+                    .into_iter()
+                    .map(|result| result.task)
+                    .filter(|task| task_ids.contains(&task.id))
+                    .collect()
+            }
+            None => tasks,
+        };
+
+        let timestamps: [(Option<&str>, fn(&Task) -> &String); 3] = [
+            (self.modified_after, |task| &task.date_modified),
+            (self.started_after, |task| &task.date_started),
+            (self.completed_after, |task| &task.date_completed),
+        ];
+        for (after, timestamp) in timestamps {
+            if let Some(date) = after {
+                // The timestamps are in UTC, while the dates are given in local time
+                let date = parse_local_time(date)?;
+                tasks.retain(|task| !timestamp(task).is_empty() && *timestamp(task) >= date);
+            }
+        }
+
+        if let (true, Some(context)) = (self.exact_context, &self.context) {
+            tasks.retain(|task| &task.context == context);
+        }
+        if let Some(parent_id) = self.parent_id {
+            tasks.retain(|task| task.parent_id == Some(parent_id));
+        }
+        if let Some(project) = &self.project {
+            let project = project.to_lowercase();
+            tasks.retain(|task| task.project == project);
+        }
+
+        if self.udas.is_empty() && self.sort_by.is_none() {
+            return Ok(tasks);
+        }
+        let uda_types = self.database_manager.as_mut().unwrap().get_uda_types()?;
+        for (name, value) in resolve_udas(&uda_types, &self.udas)? {
+            tasks.retain(|task| task.udas.get(&name) == value.as_ref());
+        }
+        if let Some(attr) = &self.sort_by {
+            match uda_types.get(attr) {
+                Some(uda_type) => tasks.sort_by(|a, b| match (a.udas.get(attr), b.udas.get(attr)) {
+                    (Some(a), Some(b)) => uda_type.compare(a, b),
+                    (Some(_), None) => Ordering::Less,
+                    (None, Some(_)) => Ordering::Greater,
+                    (None, None) => Ordering::Equal,
+                }),
+                None => {
+                    if let Some(task) = tasks.first() {
+                        // Fails for the attributes that don't exist
+                        task.get_property_value(attr)?;
+                    }
+                    tasks.sort_by_cached_key(|task| {
+                        task.get_property_value(attr).unwrap_or_default()
+                    });
+                }
+            }
+        }
+        Ok(tasks)
+    }
+}
+
+impl<'a, S: TaskStore> Operation for Get<'a, S> {
+    fn init(&mut self) -> Result<(), CoreError> {
+        if self.database_manager.is_none() {
+            self.database_manager = Some(S::open(
+                &ConfigManager::init_and_get_database_path()?,
+            )?);
+        }
+        Ok(())
+    }
+
+    fn do_work(&mut self) -> Result<Vec<Task>, CoreError> {
+        let mut tasks = self.get_tasks(self.archived)?;
+        if self.with_archived && !self.archived {
+            tasks.extend(self.get_tasks(true)?);
+        }
+        Ok(tasks)
     }
 
     fn set_result(&mut self, result: Vec<Task>) {
@@ -127,3 +298,100 @@ impl<'a> Operation for Get<'a> {
         return &self.result;
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::operation::{Add, execute};
+    use std::collections::HashMap;
+
+    fn get_setting() -> HashMap<String, String> {
+        let mut setting = HashMap::new();
+        setting.insert("db_path".to_owned(), ":memory:".to_owned());
+        setting.insert("context".to_owned(), "Inbox, Work, Life".to_owned());
+        setting
+    }
+
+    #[test]
+    fn test_get_with_search() -> Result<(), CoreError> {
+        let mut database_manager = TaskManager::new(&get_setting())?;
+        for body in ["Call the vendor", "Email the vendor about the vendor invoice", "Buy milk"] {
+            let mut operation = Add::new_with_task_manager(body, &mut database_manager);
+            execute(&mut operation)?;
+        }
+        let mut operation = Add::new_with_task_manager("Vendor meeting", &mut database_manager);
+        operation.context = Some(String::from("Work"));
+        execute(&mut operation)?;
+
+        let mut operation = Get::new();
+        operation.database_manager = Some(database_manager);
+        operation.search = Some(String::from("vendor"));
+        execute(&mut operation)?;
+
+        // Only the inbox tasks, the best match first
+        let bodies: Vec<&str> = operation.get_result().iter().map(|t| t.body.as_str()).collect();
+        assert_eq!(
+            bodies,
+            vec!["Email the vendor about the vendor invoice", "Call the vendor"]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_get_by_timestamps() -> Result<(), CoreError> {
+        let mut database_manager = TaskManager::new(&get_setting())?;
+        for body in ["Started", "Completed", "Untouched"] {
+            let mut operation = Add::new_with_task_manager(body, &mut database_manager);
+            execute(&mut operation)?;
+        }
+        for (task_id, state) in [(1, "started"), (2, "completed")] {
+            database_manager.modify(
+                &vec![task_id], &None, &None, &None, &vec![], &None, &None, &None, &None,
+                &Some(state), &vec![],
+                &None,
+                &vec![],
+                &None,
+                &None,
+            )?;
+        }
+
+        let filters = [
+            (Some("today"), None, None, vec!["Started", "Completed", "Untouched"]),
+            (Some("2999-01-01"), None, None, vec![]),
+            (None, Some("today"), None, vec!["Started"]),
+            (None, None, Some("today"), vec!["Completed"]),
+        ];
+        for (modified_after, started_after, completed_after, expected) in filters {
+            let mut operation = Get::new_with_store(Some(database_manager));
+            operation.modified_after = modified_after;
+            operation.started_after = started_after;
+            operation.completed_after = completed_after;
+            execute(&mut operation)?;
+            let bodies: Vec<&str> =
+                operation.get_result().iter().map(|t| t.body.as_str()).collect();
+            assert_eq!(bodies, expected);
+            database_manager = operation.database_manager.take().unwrap();
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_get_with_uuid_prefix() -> Result<(), CoreError> {
+        let mut database_manager = TaskManager::new(&get_setting())?;
+        for body in ["Task One", "Task Two"] {
+            let mut operation = Add::new_with_task_manager(body, &mut database_manager);
+            execute(&mut operation)?;
+        }
+        let tasks = database_manager.get(&None, &None, &vec![], &None, &None, &Some(2), &None)?;
+        let uuid = tasks[0].uuid.clone();
+
+        let mut operation = Get::new();
+        operation.database_manager = Some(database_manager);
+        operation.task_uuid = Some(uuid[..8].to_uppercase());
+        execute(&mut operation)?;
+
+        assert_eq!(operation.get_result().len(), 1);
+        assert_eq!(operation.get_result()[0].uuid, uuid);
+        Ok(())
+    }
+}
@@ -0,0 +1,43 @@
+use crate::core::ConfigManager;
+use crate::db::history::TaskChange;
+use crate::db::task_manager::TaskManager;
+use crate::error::*;
+
+// The change history isn't a list of tasks, so it's executed
+// by `execute_history` rather than implementing `Operation`.
+pub struct History {
+    pub task_id: i64,
+    pub database_manager: Option<TaskManager>,
+    result: Vec<TaskChange>,
+}
+
+impl History {
+    pub fn new(task_id: i64) -> History {
+        History {
+            task_id,
+            database_manager: None,
+            result: vec![],
+        }
+    }
+
+    pub fn init(&mut self) -> Result<(), CoreError> {
+        if self.database_manager.is_none() {
+            self.database_manager = Some(TaskManager::new(
+                &ConfigManager::init_and_get_database_path()?,
+            )?);
+        }
+        Ok(())
+    }
+
+    pub fn do_work_for_history(&mut self) -> Result<Vec<TaskChange>, CoreError> {
+        self.database_manager.as_mut().unwrap().history(self.task_id)
+    }
+
+    pub fn set_result(&mut self, result: Vec<TaskChange>) {
+        self.result = result;
+    }
+
+    pub fn get_result(&self) -> &Vec<TaskChange> {
+        &self.result
+    }
+}
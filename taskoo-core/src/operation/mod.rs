@@ -1,20 +1,54 @@
 mod add;
+mod attachment;
+mod backup;
 mod agenda;
+mod archive;
+mod checklist;
 mod delete;
+mod doctor;
+mod encryption;
 mod get;
+mod history;
 mod modify;
+mod note;
+mod search;
+mod timesheet;
+mod trash;
+mod undo;
 mod view;
 
 pub use add::*;
+pub use attachment::*;
+pub use backup::*;
+pub use archive::*;
+pub use checklist::*;
 pub use delete::*;
+pub use doctor::*;
+pub use encryption::*;
 pub use get::*;
+pub use history::*;
 pub use modify::*;
+pub use note::*;
+pub use search::*;
+pub use timesheet::*;
+pub use trash::*;
+pub use undo::*;
 pub use view::*;
 pub use agenda::*;
 
 use crate::core::Operation;
 use crate::error::CoreError;
-pub use crate::db::task_helper::Task;
+use crate::store::TaskStore;
+pub use crate::db::task_helper::{format_estimate, Effort, Task};
+pub use crate::db::attachment::Attachment;
+pub use crate::db::backup::Backup;
+pub use crate::db::checklist::ChecklistItem;
+pub use crate::db::doctor::{Problem, ProblemKind};
+pub use crate::db::history::TaskChange;
+pub use crate::db::note::Note;
+pub use crate::db::project::Project;
+pub use crate::db::search::SearchResult;
+pub use crate::db::time_tracking::{TimeInterval, TimesheetEntry};
 
 pub fn execute(op: &mut impl Operation) -> Result<(), CoreError> {
     op.init()?;
@@ -35,10 +69,71 @@ pub fn execute2(op: &mut impl Operation) -> Result<&Task, CoreError> {
 }
 
 // TODO: This needs to be handled better
-pub fn execute_agenda(op: &mut Agenda) -> Result<(), CoreError> {
+pub fn execute_agenda<S: TaskStore>(op: &mut Agenda<S>) -> Result<(), CoreError> {
     op.init()?;
     op.do_work_for_agenda().map(|tasks| {
         op.set_result(tasks);
     })?;
     Ok(())
 }
+
+pub fn execute_history(op: &mut History) -> Result<(), CoreError> {
+    op.init()?;
+    op.do_work_for_history().map(|changes| {
+        op.set_result(changes);
+    })?;
+    Ok(())
+}
+
+pub fn execute_doctor(op: &mut Doctor) -> Result<(), CoreError> {
+    op.init()?;
+    op.do_work_for_doctor().map(|problems| {
+        op.set_result(problems);
+    })?;
+    Ok(())
+}
+
+pub fn execute_backup(op: &mut Backups) -> Result<(), CoreError> {
+    op.init()?;
+    op.do_work_for_backup().map(|backups| {
+        op.set_result(backups);
+    })?;
+    Ok(())
+}
+
+pub fn execute_encryption(op: &mut Encryption) -> Result<(), CoreError> {
+    op.init()?;
+    op.do_work_for_encryption()
+}
+
+pub fn execute_search(op: &mut Search) -> Result<(), CoreError> {
+    op.init()?;
+    op.do_work_for_search().map(|results| {
+        op.set_result(results);
+    })?;
+    Ok(())
+}
+
+pub fn execute_timesheet(op: &mut Timesheet) -> Result<(), CoreError> {
+    op.init()?;
+    op.do_work_for_timesheet().map(|entries| {
+        op.set_result(entries);
+    })?;
+    Ok(())
+}
+
+pub fn execute_open_attachment(op: &mut OpenAttachment) -> Result<(), CoreError> {
+    op.init()?;
+    op.do_work_for_open().map(|attachment| {
+        op.set_result(attachment);
+    })?;
+    Ok(())
+}
+
+pub fn execute_interval(op: &mut EditInterval) -> Result<(), CoreError> {
+    op.init()?;
+    op.do_work_for_interval().map(|intervals| {
+        op.set_result(intervals);
+    })?;
+    Ok(())
+}
@@ -2,11 +2,16 @@ use crate::core::{ConfigManager, Operation};
 use crate::db::task_helper::Task;
 use crate::db::task_manager::TaskManager;
 use crate::error::*;
-use crate::option_parser::{parse_command_option, CommandOption};
+use crate::option_parser::{
+    parse_command_option, parse_command_option_with_display_ids, CommandOption,
+};
+use crate::store::{ModifyParams, TaskStore};
 
 #[derive(Debug)]
-pub struct ModifyOperation<'a> {
+pub struct ModifyOperation<'a, S: TaskStore = TaskManager> {
     pub task_ids: Vec<i64>,
+    pub task_uuids: Vec<String>, // Resolved to task ids by `do_work`
+    pub display_ids: Vec<i64>,   // Resolved to task ids by `do_work`
     pub body: Option<&'a str>,
     pub priority: Option<String>,
     pub context_name: Option<String>,
@@ -17,7 +22,11 @@ pub struct ModifyOperation<'a> {
     pub scheduled_repeat: Option<&'a str>,
     state: Option<String>,
     pub tags_to_remove: Vec<String>,
-    database_manager: Option<TaskManager>,
+    pub estimate: Option<&'a str>,
+    pub udas: Vec<(String, String)>, // name and value, an empty value removes the UDA
+    pub parent_id: Option<Option<i64>>, // Some(None) makes them top level tasks
+    pub project: Option<String>,        // An empty name takes them out of their project
+    database_manager: Option<S>,
     result: Vec<Task>,
 }
 
@@ -29,15 +38,55 @@ impl<'a> ModifyOperation<'a> {
             )));
         }
 
-        let option = parse_command_option(&data.iter().map(|s| &**s).collect(), false, true, true)?;
-        Ok(Self::from_command_option(option))
+        let option = parse_command_option(
+            &data.iter().map(|s| &**s).collect(),
+            false,
+            true,
+            true,
+            &ConfigManager::get_uda_names()?,
+        )?;
+        Ok(Self::from_command_option(option, None))
+    }
+
+    // Numbers in `data` are display ids, see `parse_command_option_with_display_ids`
+    pub fn new_with_display_ids(data: &'a Vec<String>) -> Result<ModifyOperation<'a>, CoreError> {
+        if data.is_empty() {
+            return Err(CoreError::DateParseError(String::from(
+                "Empty data provided for modify",
+            )));
+        }
+
+        let option = parse_command_option_with_display_ids(
+            &data.iter().map(|s| &**s).collect(),
+            true,
+            &ConfigManager::get_uda_names()?,
+        )?;
+        Ok(Self::from_command_option(option, None))
+    }
+}
+
+impl<'a, S: TaskStore> ModifyOperation<'a, S> {
+    // Same as `new_with_display_ids`, the tasks are modified in `store`
+    pub fn new_with_store(
+        data: &'a Vec<String>,
+        mut store: S,
+    ) -> Result<ModifyOperation<'a, S>, CoreError> {
+        let uda_names: Vec<String> = store.get_uda_types()?.into_keys().collect();
+        let option = parse_command_option_with_display_ids(
+            &data.iter().map(|s| &**s).collect(),
+            true,
+            &uda_names,
+        )?;
+        Ok(Self::from_command_option(option, Some(store)))
     }
 
-    fn from_command_option(option: CommandOption<'a>) -> ModifyOperation<'a> {
+    fn from_command_option(option: CommandOption<'a>, store: Option<S>) -> ModifyOperation<'a, S> {
         ModifyOperation {
-            database_manager: None,
+            database_manager: store,
             result: vec![],
             task_ids: option.task_ids,
+            task_uuids: option.task_uuids,
+            display_ids: option.display_ids,
             body: None,
             priority: option.priority,
             context_name: option.context,
@@ -48,6 +97,10 @@ impl<'a> ModifyOperation<'a> {
             scheduled_repeat: option.repetition_scheduled,
             state: option.state,
             tags_to_remove: option.tags_to_remove,
+            estimate: option.estimate,
+            udas: option.udas,
+            parent_id: option.parent_id,
+            project: option.project,
         }
     }
 
@@ -69,12 +122,12 @@ impl<'a> ModifyOperation<'a> {
     }
 }
 
-impl<'a> Operation for ModifyOperation<'a> {
-    fn init(&mut self) -> Result<(), InitialError> {
+impl<'a, S: TaskStore> Operation for ModifyOperation<'a, S> {
+    fn init(&mut self) -> Result<(), CoreError> {
         if self.database_manager.is_none() {
-            self.database_manager = Some(TaskManager::new(
+            self.database_manager = Some(S::open(
                 &ConfigManager::init_and_get_database_path()?,
-            ));
+            )?);
         }
         Ok(())
     }
@@ -92,20 +145,31 @@ impl<'a> Operation for ModifyOperation<'a> {
             None => None,
         };
 
-        let tasks = TaskManager::modify(
-            self.database_manager.as_mut().unwrap(),
-            &self.task_ids,
-            &self.body,
-            &self.priority,
-            &self.context_name,
-            &self.tag_names,
-            &self.due_date,
-            &self.scheduled_at,
-            &self.due_repeat,
-            &self.scheduled_repeat,
-            &self.state.as_deref(),
-            &self.tags_to_remove,
-        )?;
+        let database_manager = self.database_manager.as_mut().unwrap();
+        let mut uuid_task_ids = database_manager.get_task_ids_by_uuid(&self.task_uuids)?;
+        self.task_ids.append(&mut uuid_task_ids);
+        self.task_uuids.clear();
+        let mut display_task_ids = database_manager.get_task_ids_by_display_id(&self.display_ids)?;
+        self.task_ids.append(&mut display_task_ids);
+        self.display_ids.clear();
+
+        let params = ModifyParams {
+            body: self.body,
+            priority: self.priority.clone(),
+            context: self.context_name.clone(),
+            tags: self.tag_names.clone(),
+            date_due: self.due_date,
+            date_scheduled: self.scheduled_at,
+            repetition_due: self.due_repeat,
+            repetition_scheduled: self.scheduled_repeat,
+            state: self.state.as_deref(),
+            tags_to_remove: self.tags_to_remove.clone(),
+            estimate: self.estimate,
+            udas: self.udas.clone(),
+            parent_id: self.parent_id,
+            project: self.project.clone(),
+        };
+        let tasks = database_manager.modify(&self.task_ids, &params)?;
 
         Ok(tasks)
     }
@@ -121,6 +185,8 @@ impl<'a> Operation for ModifyOperation<'a> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::operation::{Add, execute};
+    use std::collections::HashMap;
 
     #[test]
     fn test_new_parses_full_modify_command() {
@@ -134,6 +200,7 @@ mod tests {
             "pri:H".to_string(),
             "d:2026-07-10+weekly".to_string(),
             "s:2026-07-08+daily".to_string(),
+            "est:2h".to_string(),
         ];
 
         let op = ModifyOperation::new(&data).unwrap();
@@ -148,6 +215,7 @@ mod tests {
         assert_eq!(op.due_repeat, Some("weekly"));
         assert_eq!(op.scheduled_at, Some("2026-07-08"));
         assert_eq!(op.scheduled_repeat, Some("daily"));
+        assert_eq!(op.estimate, Some("2h"));
     }
 
     #[test]
@@ -160,4 +228,55 @@ mod tests {
         assert!(op.tag_names.is_empty());
         assert!(op.tags_to_remove.is_empty());
     }
+
+    #[test]
+    fn test_modify_with_uuid_prefix() -> Result<(), CoreError> {
+        let mut setting = HashMap::new();
+        setting.insert("db_path".to_owned(), ":memory:".to_owned());
+        setting.insert("context".to_owned(), "Inbox, Work, Life".to_owned());
+        let mut database_manager = TaskManager::new(&setting)?;
+        for body in ["Task One", "Task Two"] {
+            let mut operation = Add::new_with_task_manager(body, &mut database_manager);
+            execute(&mut operation)?;
+        }
+        let tasks = database_manager.get(&None, &None, &vec![], &None, &None, &Some(2), &None)?;
+
+        let data = vec![tasks[0].uuid[..13].to_string(), "+next".to_string()];
+        let mut operation = ModifyOperation::new(&data)?;
+        assert!(operation.task_ids.is_empty());
+        operation.database_manager = Some(database_manager);
+        execute(&mut operation)?;
+
+        assert_eq!(operation.get_result().len(), 1);
+        assert_eq!(operation.get_result()[0].id, 2);
+        assert_eq!(operation.get_result()[0].tags, vec!["next"]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_modify_with_display_ids() -> Result<(), CoreError> {
+        let mut setting = HashMap::new();
+        setting.insert("db_path".to_owned(), ":memory:".to_owned());
+        setting.insert("context".to_owned(), "Inbox, Work, Life".to_owned());
+        let mut database_manager = TaskManager::new(&setting)?;
+        for body in ["Task One", "Task Two", "Task Three"] {
+            let mut operation = Add::new_with_task_manager(body, &mut database_manager);
+            execute(&mut operation)?;
+        }
+        database_manager.delete(&vec![1])?;
+        database_manager.get(&None, &None, &vec![], &None, &None, &None, &None)?;
+
+        // 1 is the display id of task 2 now, id:1 is still the deleted task
+        let data = vec!["1".to_string(), "id:3".to_string(), "+next".to_string()];
+        let mut operation = ModifyOperation::new_with_display_ids(&data)?;
+        assert_eq!(operation.display_ids, vec![1]);
+        assert_eq!(operation.task_ids, vec![3]);
+        operation.database_manager = Some(database_manager);
+        execute(&mut operation)?;
+
+        let mut modified: Vec<i64> = operation.get_result().iter().map(|task| task.id).collect();
+        modified.sort();
+        assert_eq!(modified, vec![2, 3]);
+        Ok(())
+    }
 }
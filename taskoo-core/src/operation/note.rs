@@ -0,0 +1,140 @@
+use crate::core::{ConfigManager, Operation};
+use crate::db::task_helper::Task;
+use crate::db::task_manager::TaskManager;
+use crate::error::*;
+
+// The note operations return the task of the note with all of its notes
+
+pub struct AddNote {
+    pub task_id: i64,
+    pub body: String,
+    pub database_manager: Option<TaskManager>,
+    result: Vec<Task>,
+}
+
+pub struct EditNote {
+    pub note_id: i64,
+    pub body: String,
+    pub database_manager: Option<TaskManager>,
+    result: Vec<Task>,
+}
+
+pub struct DeleteNote {
+    pub note_id: i64,
+    pub database_manager: Option<TaskManager>,
+    result: Vec<Task>,
+}
+
+impl AddNote {
+    pub fn new(task_id: i64, body: String) -> AddNote {
+        AddNote {
+            task_id,
+            body,
+            database_manager: None,
+            result: vec![],
+        }
+    }
+}
+
+impl EditNote {
+    pub fn new(note_id: i64, body: String) -> EditNote {
+        EditNote {
+            note_id,
+            body,
+            database_manager: None,
+            result: vec![],
+        }
+    }
+}
+
+impl DeleteNote {
+    pub fn new(note_id: i64) -> DeleteNote {
+        DeleteNote {
+            note_id,
+            database_manager: None,
+            result: vec![],
+        }
+    }
+}
+
+fn init_database_manager(database_manager: &mut Option<TaskManager>) -> Result<(), CoreError> {
+    if database_manager.is_none() {
+        *database_manager = Some(TaskManager::new(
+            &ConfigManager::init_and_get_database_path()?,
+        )?);
+    }
+    Ok(())
+}
+
+fn validate_body(body: &str) -> Result<(), CoreError> {
+    if body.trim().is_empty() {
+        return Err(CoreError::ArgumentError(String::from(
+            "The note can't be empty",
+        )));
+    }
+    Ok(())
+}
+
+impl Operation for AddNote {
+    fn init(&mut self) -> Result<(), CoreError> {
+        init_database_manager(&mut self.database_manager)
+    }
+
+    fn do_work(&mut self) -> Result<Vec<Task>, CoreError> {
+        validate_body(&self.body)?;
+        TaskManager::add_note(
+            self.database_manager.as_mut().unwrap(),
+            self.task_id,
+            &self.body,
+        )
+    }
+
+    fn set_result(&mut self, result: Vec<Task>) {
+        self.result = result;
+    }
+
+    fn get_result(&mut self) -> &Vec<Task> {
+        return &self.result;
+    }
+}
+
+impl Operation for EditNote {
+    fn init(&mut self) -> Result<(), CoreError> {
+        init_database_manager(&mut self.database_manager)
+    }
+
+    fn do_work(&mut self) -> Result<Vec<Task>, CoreError> {
+        validate_body(&self.body)?;
+        TaskManager::edit_note(
+            self.database_manager.as_mut().unwrap(),
+            self.note_id,
+            &self.body,
+        )
+    }
+
+    fn set_result(&mut self, result: Vec<Task>) {
+        self.result = result;
+    }
+
+    fn get_result(&mut self) -> &Vec<Task> {
+        return &self.result;
+    }
+}
+
+impl Operation for DeleteNote {
+    fn init(&mut self) -> Result<(), CoreError> {
+        init_database_manager(&mut self.database_manager)
+    }
+
+    fn do_work(&mut self) -> Result<Vec<Task>, CoreError> {
+        TaskManager::delete_note(self.database_manager.as_mut().unwrap(), self.note_id)
+    }
+
+    fn set_result(&mut self, result: Vec<Task>) {
+        self.result = result;
+    }
+
+    fn get_result(&mut self) -> &Vec<Task> {
+        return &self.result;
+    }
+}
@@ -0,0 +1,51 @@
+use crate::core::ConfigManager;
+use crate::db::search::SearchResult;
+use crate::db::task_manager::TaskManager;
+use crate::error::*;
+
+// Like `Get` with `search`, but the results come with the snippets and
+// the ranks, so it's executed by `execute_search`.
+pub struct Search {
+    pub query: String,
+    pub highlight_start: String,
+    pub highlight_end: String,
+    pub database_manager: Option<TaskManager>,
+    result: Vec<SearchResult>,
+}
+
+impl Search {
+    pub fn new(query: String) -> Search {
+        Search {
+            query,
+            highlight_start: String::from("["),
+            highlight_end: String::from("]"),
+            database_manager: None,
+            result: vec![],
+        }
+    }
+
+    pub fn init(&mut self) -> Result<(), CoreError> {
+        if self.database_manager.is_none() {
+            self.database_manager = Some(TaskManager::new(
+                &ConfigManager::init_and_get_database_path()?,
+            )?);
+        }
+        Ok(())
+    }
+
+    pub fn do_work_for_search(&mut self) -> Result<Vec<SearchResult>, CoreError> {
+        self.database_manager.as_mut().unwrap().search(
+            &self.query,
+            &self.highlight_start,
+            &self.highlight_end,
+        )
+    }
+
+    pub fn set_result(&mut self, result: Vec<SearchResult>) {
+        self.result = result;
+    }
+
+    pub fn get_result(&self) -> &Vec<SearchResult> {
+        &self.result
+    }
+}
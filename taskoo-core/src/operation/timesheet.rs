@@ -0,0 +1,332 @@
+use crate::core::ConfigManager;
+use crate::db::task_manager::TaskManager;
+use crate::db::time_tracking::{
+    format_time, parse_time, summarize, to_utc, TimeInterval, TimesheetEntry,
+};
+use crate::error::*;
+use chrono::{Duration, NaiveDate, NaiveDateTime, Utc};
+use serde::Serialize;
+
+// The time spent on the tasks per day, from `start_day` to `end_day`
+// included. The report isn't a list of tasks, so it's executed by
+// `execute_timesheet` rather than implementing `Operation`.
+pub struct Timesheet {
+    pub start_day: String,
+    pub end_day: Option<String>,
+    pub context_name: Option<String>,
+    pub tags: Vec<String>, // Only the tasks with all of these tags
+    pub database_manager: Option<TaskManager>,
+    // The intervals that the report is made of
+    intervals: Vec<TimeInterval>,
+    result: Vec<TimesheetEntry>,
+}
+
+// Adds an interval to a task, or changes or deletes an interval. It's
+// executed by `execute_interval`, the intervals of the task are the result.
+pub struct EditInterval {
+    task_id: Option<i64>,
+    interval_id: Option<i64>,
+    pub started_at: Option<String>,
+    pub stopped_at: Option<String>,
+    delete: bool,
+    pub database_manager: Option<TaskManager>,
+    result: Vec<TimeInterval>,
+}
+
+fn init_database_manager(database_manager: &mut Option<TaskManager>) -> Result<(), CoreError> {
+    if database_manager.is_none() {
+        *database_manager = Some(TaskManager::new(
+            &ConfigManager::init_and_get_database_path()?,
+        )?);
+    }
+    Ok(())
+}
+
+fn parse_day(day: &str) -> Result<NaiveDate, CoreError> {
+    Ok(parse_time(&TaskManager::parse_date_string(day)?)?.date())
+}
+
+impl Timesheet {
+    // [start day] [end day] [c:<context>] [+<tag>]..., the report is for
+    // today when no day is provided
+    pub fn new2(data: &Vec<String>) -> Result<Timesheet, CoreError> {
+        let mut days = vec![];
+        let mut context_name = None;
+        let mut tags = vec![];
+        for argument in data.iter() {
+            if let Some(context) = argument.strip_prefix("c:") {
+                context_name = Some(context.to_lowercase());
+            } else if let Some(tag) = argument.strip_prefix('+') {
+                tags.push(tag.to_lowercase());
+            } else if days.len() < 2 {
+                days.push(argument.clone());
+            } else {
+                return Err(CoreError::ArgumentError(format!(
+                    "Unexpected argument {} for timesheet",
+                    argument
+                )));
+            }
+        }
+
+        let mut days = days.into_iter();
+        let mut timesheet =
+            Timesheet::new(days.next().unwrap_or(String::from("today")), days.next());
+        timesheet.context_name = context_name;
+        timesheet.tags = tags;
+        Ok(timesheet)
+    }
+
+    pub fn new(start_day: String, end_day: Option<String>) -> Timesheet {
+        Timesheet {
+            start_day,
+            end_day,
+            context_name: None,
+            tags: vec![],
+            database_manager: None,
+            intervals: vec![],
+            result: vec![],
+        }
+    }
+
+    pub fn init(&mut self) -> Result<(), CoreError> {
+        init_database_manager(&mut self.database_manager)
+    }
+
+    pub fn do_work_for_timesheet(&mut self) -> Result<Vec<TimesheetEntry>, CoreError> {
+        let start_day = parse_day(&self.start_day)?;
+        let end_day = match &self.end_day {
+            Some(day) => parse_day(day)?,
+            None => start_day,
+        };
+        if end_day < start_day {
+            return Err(CoreError::ArgumentError(format!(
+                "The end day {} is before the start day {}",
+                end_day, start_day
+            )));
+        }
+        // The days are in local time, the intervals in UTC
+        let start = start_day.and_hms_opt(0, 0, 0).unwrap();
+        let end = (end_day + Duration::days(1)).and_hms_opt(0, 0, 0).unwrap();
+
+        let intervals = self.database_manager.as_mut().unwrap().get_intervals(
+            &Some(format_time(&to_utc(&start))),
+            &Some(format_time(&to_utc(&end))),
+            &None,
+        )?;
+        self.intervals = intervals
+            .into_iter()
+            .filter(|interval| {
+                self.context_name
+                    .as_ref()
+                    .map_or(true, |context| &interval.context == context)
+                    && self.tags.iter().all(|tag| interval.tags.contains(tag))
+            })
+            .collect();
+        summarize(&self.intervals, &start, &end, &Utc::now().naive_utc())
+    }
+
+    pub fn set_result(&mut self, result: Vec<TimesheetEntry>) {
+        self.result = result;
+    }
+
+    pub fn get_result(&self) -> &Vec<TimesheetEntry> {
+        &self.result
+    }
+
+    pub fn get_intervals(&self) -> &Vec<TimeInterval> {
+        &self.intervals
+    }
+}
+
+impl EditInterval {
+    pub fn add(task_id: i64, started_at: String, stopped_at: String) -> EditInterval {
+        EditInterval {
+            task_id: Some(task_id),
+            interval_id: None,
+            started_at: Some(started_at),
+            stopped_at: Some(stopped_at),
+            delete: false,
+            database_manager: None,
+            result: vec![],
+        }
+    }
+
+    // Only the provided times are changed
+    pub fn edit(
+        interval_id: i64,
+        started_at: Option<String>,
+        stopped_at: Option<String>,
+    ) -> EditInterval {
+        EditInterval {
+            task_id: None,
+            interval_id: Some(interval_id),
+            started_at,
+            stopped_at,
+            delete: false,
+            database_manager: None,
+            result: vec![],
+        }
+    }
+
+    pub fn delete(interval_id: i64) -> EditInterval {
+        EditInterval {
+            task_id: None,
+            interval_id: Some(interval_id),
+            started_at: None,
+            stopped_at: None,
+            delete: true,
+            database_manager: None,
+            result: vec![],
+        }
+    }
+
+    pub fn init(&mut self) -> Result<(), CoreError> {
+        init_database_manager(&mut self.database_manager)
+    }
+
+    pub fn do_work_for_interval(&mut self) -> Result<Vec<TimeInterval>, CoreError> {
+        let database_manager = self.database_manager.as_mut().unwrap();
+        match (self.task_id, self.interval_id) {
+            (_, Some(interval_id)) if self.delete => database_manager.delete_interval(interval_id),
+            (_, Some(interval_id)) => database_manager.edit_interval(
+                interval_id,
+                &self.started_at.as_deref(),
+                &self.stopped_at.as_deref(),
+            ),
+            (Some(task_id), None) => database_manager.add_interval(
+                task_id,
+                self.started_at.as_deref().unwrap_or_default(),
+                self.stopped_at.as_deref().unwrap_or_default(),
+            ),
+            (None, None) => unreachable!("EditInterval always has a task or an interval"),
+        }
+    }
+
+    pub fn set_result(&mut self, result: Vec<TimeInterval>) {
+        self.result = result;
+    }
+
+    pub fn get_result(&self) -> &Vec<TimeInterval> {
+        &self.result
+    }
+}
+
+#[derive(Serialize)]
+struct ExportedInterval<'a> {
+    #[serde(flatten)]
+    interval: &'a TimeInterval,
+    seconds: i64,
+}
+
+const CSV_HEADER: &str = "id,task_id,body,context,tags,started_at,stopped_at,seconds";
+
+fn escape_csv(field: &str) -> String {
+    if field.contains(&[',', '"', '\n', '\r'][..]) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+// Export the intervals as `csv` or `json`. The times stay in UTC, the
+// running interval lasts until now.
+pub fn export_intervals(intervals: &Vec<TimeInterval>, format: &str) -> Result<String, CoreError> {
+    let now: NaiveDateTime = Utc::now().naive_utc();
+    match format {
+        "csv" => {
+            let mut output = format!("{}\n", CSV_HEADER);
+            for interval in intervals.iter() {
+                let fields = [
+                    interval.id.to_string(),
+                    interval.task_id.to_string(),
+                    escape_csv(&interval.body),
+                    escape_csv(&interval.context),
+                    escape_csv(&interval.tags.join(" ")),
+                    interval.started_at.clone(),
+                    interval.stopped_at.clone(),
+                    interval.seconds(&now)?.to_string(),
+                ];
+                output.push_str(&fields.join(","));
+                output.push('\n');
+            }
+            Ok(output)
+        }
+        "json" => {
+            let mut exported = vec![];
+            for interval in intervals.iter() {
+                exported.push(ExportedInterval {
+                    interval,
+                    seconds: interval.seconds(&now)?,
+                });
+            }
+            serde_json::to_string_pretty(&exported)
+                .map_err(|error| CoreError::ArgumentError(error.to_string()))
+        }
+        _ => Err(CoreError::ArgumentError(format!(
+            "Unknown export format {}, use csv or json",
+            format
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn interval(body: &str, stopped_at: &str) -> TimeInterval {
+        TimeInterval {
+            id: 1,
+            task_id: 2,
+            body: body.to_string(),
+            context: String::from("work"),
+            tags: vec![String::from("client"), String::from("billable")],
+            started_at: String::from("2026-07-01 09:00:00"),
+            stopped_at: stopped_at.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_new2_parses_range_and_filters() -> Result<(), CoreError> {
+        let data = vec![
+            String::from("2026-07-01"),
+            String::from("+Client"),
+            String::from("2026-07-31"),
+            String::from("c:Work"),
+        ];
+        let timesheet = Timesheet::new2(&data)?;
+        assert_eq!(timesheet.start_day, "2026-07-01");
+        assert_eq!(timesheet.end_day, Some(String::from("2026-07-31")));
+        assert_eq!(timesheet.context_name, Some(String::from("work")));
+        assert_eq!(timesheet.tags, vec!["client"]);
+
+        let timesheet = Timesheet::new2(&vec![])?;
+        assert_eq!(timesheet.start_day, "today");
+        assert_eq!(timesheet.end_day, None);
+
+        let data = vec![String::from("today"), String::from("today"), String::from("today")];
+        assert!(Timesheet::new2(&data).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_export_intervals() -> Result<(), CoreError> {
+        let intervals = vec![interval("Call \"the\" vendor, again", "2026-07-01 10:30:00")];
+        assert_eq!(
+            export_intervals(&intervals, "csv")?,
+            format!(
+                "{}\n1,2,\"Call \"\"the\"\" vendor, again\",work,client billable,\
+                2026-07-01 09:00:00,2026-07-01 10:30:00,5400\n",
+                CSV_HEADER
+            )
+        );
+
+        let json: serde_json::Value = serde_json::from_str(&export_intervals(&intervals, "json")?)
+            .expect("the export is valid JSON");
+        assert_eq!(json[0]["body"], "Call \"the\" vendor, again");
+        assert_eq!(json[0]["tags"][1], "billable");
+        assert_eq!(json[0]["seconds"], 5400);
+
+        assert!(export_intervals(&intervals, "xml").is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,132 @@
+use crate::core::{ConfigManager, Operation};
+use crate::db::task_helper::Task;
+use crate::db::task_manager::TaskManager;
+use crate::option_parser::parse_command_option;
+use crate::error::*;
+
+// List the tasks in the trash
+pub struct Trash {
+    pub database_manager: Option<TaskManager>,
+    result: Vec<Task>,
+}
+
+pub struct Restore {
+    pub task_ids: Vec<i64>,
+    pub database_manager: Option<TaskManager>,
+    result: Vec<Task>,
+}
+
+pub struct Purge {
+    pub older_than: Option<String>,
+    pub database_manager: Option<TaskManager>,
+    result: Vec<Task>,
+}
+
+impl Trash {
+    pub fn new() -> Trash {
+        Trash {
+            database_manager: None,
+            result: vec![],
+        }
+    }
+}
+
+impl Restore {
+    pub fn new(input_str: &Vec<String>) -> Result<Restore, CoreError> {
+        let option = parse_command_option(
+            &input_str.iter().map(|s| &**s).collect(),
+            false,
+            false,
+            true,
+            &vec![],
+        )?;
+
+        Ok(Restore {
+            task_ids: option.task_ids,
+            database_manager: None,
+            result: vec![],
+        })
+    }
+}
+
+impl Purge {
+    pub fn new(older_than: Option<String>) -> Purge {
+        Purge {
+            older_than,
+            database_manager: None,
+            result: vec![],
+        }
+    }
+}
+
+impl Operation for Trash {
+    fn init(&mut self) -> Result<(), CoreError> {
+        if self.database_manager.is_none() {
+            self.database_manager = Some(TaskManager::new(
+                &ConfigManager::init_and_get_database_path()?,
+            )?);
+        }
+        Ok(())
+    }
+
+    fn do_work(&mut self) -> Result<Vec<Task>, CoreError> {
+        return TaskManager::trash(self.database_manager.as_mut().unwrap());
+    }
+
+    fn set_result(&mut self, result: Vec<Task>) {
+        self.result = result;
+    }
+
+    fn get_result(&mut self) -> &Vec<Task> {
+        return &self.result;
+    }
+}
+
+impl Operation for Restore {
+    fn init(&mut self) -> Result<(), CoreError> {
+        if self.database_manager.is_none() {
+            self.database_manager = Some(TaskManager::new(
+                &ConfigManager::init_and_get_database_path()?,
+            )?);
+        }
+        Ok(())
+    }
+
+    fn do_work(&mut self) -> Result<Vec<Task>, CoreError> {
+        return TaskManager::restore(self.database_manager.as_mut().unwrap(), &self.task_ids);
+    }
+
+    fn set_result(&mut self, result: Vec<Task>) {
+        self.result = result;
+    }
+
+    fn get_result(&mut self) -> &Vec<Task> {
+        return &self.result;
+    }
+}
+
+impl Operation for Purge {
+    fn init(&mut self) -> Result<(), CoreError> {
+        if self.database_manager.is_none() {
+            self.database_manager = Some(TaskManager::new(
+                &ConfigManager::init_and_get_database_path()?,
+            )?);
+        }
+        Ok(())
+    }
+
+    fn do_work(&mut self) -> Result<Vec<Task>, CoreError> {
+        return TaskManager::purge(
+            self.database_manager.as_mut().unwrap(),
+            &self.older_than.as_deref(),
+        );
+    }
+
+    fn set_result(&mut self, result: Vec<Task>) {
+        self.result = result;
+    }
+
+    fn get_result(&mut self) -> &Vec<Task> {
+        return &self.result;
+    }
+}
@@ -0,0 +1,78 @@
+use crate::core::{ConfigManager, Operation};
+use crate::db::task_helper::Task;
+use crate::db::task_manager::TaskManager;
+use crate::error::*;
+
+pub struct Undo {
+    pub database_manager: Option<TaskManager>,
+    result: Vec<Task>,
+}
+
+pub struct Redo {
+    pub database_manager: Option<TaskManager>,
+    result: Vec<Task>,
+}
+
+impl Undo {
+    pub fn new() -> Undo {
+        Undo {
+            database_manager: None,
+            result: vec![],
+        }
+    }
+}
+
+impl Redo {
+    pub fn new() -> Redo {
+        Redo {
+            database_manager: None,
+            result: vec![],
+        }
+    }
+}
+
+impl Operation for Undo {
+    fn init(&mut self) -> Result<(), CoreError> {
+        if self.database_manager.is_none() {
+            self.database_manager = Some(TaskManager::new(
+                &ConfigManager::init_and_get_database_path()?,
+            )?);
+        }
+        Ok(())
+    }
+
+    fn do_work(&mut self) -> Result<Vec<Task>, CoreError> {
+        return TaskManager::undo(self.database_manager.as_mut().unwrap());
+    }
+
+    fn set_result(&mut self, result: Vec<Task>) {
+        self.result = result;
+    }
+
+    fn get_result(&mut self) -> &Vec<Task> {
+        return &self.result;
+    }
+}
+
+impl Operation for Redo {
+    fn init(&mut self) -> Result<(), CoreError> {
+        if self.database_manager.is_none() {
+            self.database_manager = Some(TaskManager::new(
+                &ConfigManager::init_and_get_database_path()?,
+            )?);
+        }
+        Ok(())
+    }
+
+    fn do_work(&mut self) -> Result<Vec<Task>, CoreError> {
+        return TaskManager::redo(self.database_manager.as_mut().unwrap());
+    }
+
+    fn set_result(&mut self, result: Vec<Task>) {
+        self.result = result;
+    }
+
+    fn get_result(&mut self) -> &Vec<Task> {
+        return &self.result;
+    }
+}
@@ -27,11 +27,11 @@ impl View {
     }
 }
 impl Operation for View {
-    fn init(&mut self) -> Result<(), InitialError> {
+    fn init(&mut self) -> Result<(), CoreError> {
         if self.database_manager.is_none() {
             self.database_manager = Some(TaskManager::new(
                 &ConfigManager::init_and_get_database_path()?,
-            ));
+            )?);
         }
         Ok(())
     }
@@ -13,6 +13,12 @@ pub enum CommandError {
     InvalidTagName(String),
     #[error("Invalid tag name {0}")]
     InvalidTaskId(String),
+    #[error("Invalid estimate {0}")]
+    InvalidEstimate(String),
+    #[error("Invalid UDA {0}")]
+    InvalidUda(String),
+    #[error("Invalid project name {0}")]
+    InvalidProjectName(String),
     #[error("Invalid body {0}")]
     InvalidBodyError(String),
     #[error(transparent)]
@@ -29,11 +35,17 @@ pub struct CommandOption<'a> {
     pub tags_to_remove: Vec<String>,
     pub not_tags: Option<Vec<String>>,
     pub task_ids: Vec<i64>,
+    pub display_ids: Vec<i64>, // Short ids of the pending tasks
+    pub task_uuids: Vec<String>, // UUIDs or unique UUID prefixes
     pub context: Option<String>,
     pub state: Option<String>,
     pub body: Option<String>,
     pub priority: Option<String>,
     pub parent_task_ids: Option<Vec<i64>>,
+    pub estimate: Option<&'a str>, // Empty to remove the estimate
+    pub udas: Vec<(String, String)>, // name:value, an empty value removes the UDA
+    pub parent_id: Option<Option<i64>>, // Some(None) makes it a top level task
+    pub project: Option<String>,        // Empty to take it out of its project
 }
 
 impl<'a> CommandOption<'a> {
@@ -45,6 +57,8 @@ impl<'a> CommandOption<'a> {
             repetition_due: None,
             tags: vec![],
             task_ids: vec![],
+            display_ids: vec![],
+            task_uuids: vec![],
             context: None,
             state: None,
             body: None,
@@ -52,15 +66,87 @@ impl<'a> CommandOption<'a> {
             tags_to_remove: vec![],
             parent_task_ids: None,
             not_tags: None,
+            estimate: None,
+            udas: vec![],
+            parent_id: None,
+            project: None,
         };
     }
 }
 
+// The shortest UUID prefix that's accepted, shorter ones are too likely
+// to be mistaken for words.
+pub const MIN_UUID_PREFIX_LEN: usize = 8;
+
+// A UUID or a prefix of it, numbers are always task ids
+pub fn is_uuid_prefix(input: &str) -> bool {
+    input.len() >= MIN_UUID_PREFIX_LEN
+        && input.chars().all(|c| c.is_ascii_hexdigit() || c == '-')
+        && !input.chars().all(|c| c.is_ascii_digit())
+}
+
+// Prefix of the permanent task ids where display ids are expected
+pub const TASK_ID_PREFIX: &str = "id:";
+
+// A user-defined attribute like client:acme. Only the UDAs declared in
+// the [uda] section of the config are, other words like re:invoice are
+// part of the body.
+fn parse_uda(input: &str, uda_names: &Vec<String>) -> Option<(String, String)> {
+    let (name, value) = input.split_once(':')?;
+    if !uda_names.iter().any(|uda_name| uda_name == name) {
+        return None;
+    }
+    Some((name.to_string(), value.to_string()))
+}
+
+// A number or an inclusive range like 1..3
+fn parse_ids(input: &str) -> Result<Vec<i64>, CommandError> {
+    if input.contains("..") {
+        let ranged_selection = input.split("..").collect::<Vec<&str>>();
+        if ranged_selection.len() != 2 {
+            return Err(CommandError::InvalidTaskId(input.to_string()));
+        }
+        let start = ranged_selection[0]
+            .parse::<i64>()
+            .map_err(|_| CommandError::InvalidTaskId(input.to_string()))?;
+        let end = ranged_selection[1]
+            .parse::<i64>()
+            .map_err(|_| CommandError::InvalidTaskId(input.to_string()))?;
+        Ok((start..=end).collect())
+    } else {
+        Ok(vec![input.parse()?])
+    }
+}
+
+// `uda_names` are the names of the declared UDAs, see `parse_uda`
 pub fn parse_command_option<'a>(
     options: &Vec<&'a str>,
     parse_body: bool,
     parse_tags_to_remove: bool,
     parse_task_ids: bool,
+    uda_names: &Vec<String>,
+) -> Result<CommandOption<'a>, CommandError> {
+    parse(options, parse_body, parse_tags_to_remove, parse_task_ids, false, uda_names)
+}
+
+// Same as `parse_command_option` with task ids, except the numbers and
+// ranges are the display ids of the pending tasks. Permanent ids are
+// provided as id:<id> or id:<start>..<end>.
+pub fn parse_command_option_with_display_ids<'a>(
+    options: &Vec<&'a str>,
+    parse_tags_to_remove: bool,
+    uda_names: &Vec<String>,
+) -> Result<CommandOption<'a>, CommandError> {
+    parse(options, false, parse_tags_to_remove, true, true, uda_names)
+}
+
+fn parse<'a>(
+    options: &Vec<&'a str>,
+    parse_body: bool,
+    parse_tags_to_remove: bool,
+    parse_task_ids: bool,
+    parse_display_ids: bool,
+    uda_names: &Vec<String>,
 ) -> Result<CommandOption<'a>, CommandError> {
     let mut command_option = CommandOption::new();
     let mut start_parse_options = false;
@@ -107,6 +193,34 @@ pub fn parse_command_option<'a>(
             } else {
                 return Err(CommandError::InvalidContextName(option.to_string()));
             };
+        } else if option.starts_with("est:") {
+            start_parse_options = true;
+            if command_option.estimate.is_none() {
+                command_option.estimate = Some(&option[4..]);
+            } else {
+                return Err(CommandError::InvalidEstimate(option.to_string()));
+            };
+        } else if let Some(parent_id) = option.strip_prefix("parent:") {
+            start_parse_options = true;
+            if command_option.parent_id.is_none() {
+                command_option.parent_id = match parent_id {
+                    "" => Some(None),
+                    parent_id => Some(Some(
+                        parent_id
+                            .parse()
+                            .map_err(|_| CommandError::InvalidTaskId(option.to_string()))?,
+                    )),
+                };
+            } else {
+                return Err(CommandError::InvalidTaskId(option.to_string()));
+            };
+        } else if let Some(project) = option.strip_prefix("proj:") {
+            start_parse_options = true;
+            if command_option.project.is_none() {
+                command_option.project = Some(project.to_string());
+            } else {
+                return Err(CommandError::InvalidProjectName(option.to_string()));
+            };
         } else if option.starts_with("dep:") {
             start_parse_options = true;
             if command_option.parent_task_ids.is_none() {
@@ -141,6 +255,12 @@ pub fn parse_command_option<'a>(
             } else {
                 return Err(CommandError::InvalidContextName(option.to_string()));
             }
+        } else if let Some((name, value)) = parse_uda(option, uda_names) {
+            start_parse_options = true;
+            if command_option.udas.iter().any(|(uda_name, _)| uda_name == &name) {
+                return Err(CommandError::InvalidUda(option.to_string()));
+            }
+            command_option.udas.push((name, value));
         } else {
             if !start_parse_options {
                 if parse_body {
@@ -150,22 +270,14 @@ pub fn parse_command_option<'a>(
                     }
                     body.push_str(option);
                 } else if parse_task_ids {
-                    if option.contains("..") {
-                        let ranged_selection = option.split("..").collect::<Vec<&str>>();
-                        if ranged_selection.len() != 2 {
-                            return Err(CommandError::InvalidTaskId(option.to_string()));
-                        }
-                        let start = ranged_selection[0]
-                            .parse::<i64>()
-                            .expect("Can't find valid start from provided range");
-                        let end = ranged_selection[1]
-                            .parse::<i64>()
-                            .expect("Can't find valid end from provided range");
-                        command_option
-                            .task_ids
-                            .append(&mut (start..=end).collect::<Vec<i64>>());
+                    if let Some(ids) = option.strip_prefix(TASK_ID_PREFIX) {
+                        command_option.task_ids.append(&mut parse_ids(ids)?);
+                    } else if is_uuid_prefix(option) {
+                        command_option.task_uuids.push(option.to_lowercase());
+                    } else if parse_display_ids {
+                        command_option.display_ids.append(&mut parse_ids(option)?);
                     } else {
-                        command_option.task_ids.push(option.parse()?);
+                        command_option.task_ids.append(&mut parse_ids(option)?);
                     }
                 }
             }
@@ -190,7 +302,7 @@ mod tests {
     #[test]
     fn test_parse_schedule_at_ok() {
         let option = vec!["s:2020-11-11"];
-        let parsed_option = parse_command_option(&option, false, false, false).unwrap();
+        let parsed_option = parse_command_option(&option, false, false, false, &vec![]).unwrap();
         assert_eq!(parsed_option.date_scheduled, Some("2020-11-11"));
     }
 
@@ -198,13 +310,13 @@ mod tests {
     #[should_panic]
     fn test_parse_schedule_at_error() {
         let option = vec!["s:2020-11-11", "s:2020-11-11"];
-        let parsed_option = parse_command_option(&option, false, false, false).unwrap();
+        let parsed_option = parse_command_option(&option, false, false, false, &vec![]).unwrap();
     }
 
     #[test]
     fn test_parse_due_date_ok() {
         let option = vec!["d:2020-11-11"];
-        let parsed_option = parse_command_option(&option, false, false, false).unwrap();
+        let parsed_option = parse_command_option(&option, false, false, false, &vec![]).unwrap();
         assert_eq!(parsed_option.date_due, Some("2020-11-11"));
     }
 
@@ -212,55 +324,139 @@ mod tests {
     #[should_panic]
     fn test_parse_due_date_error() {
         let option = vec!["d:2020-11-11", "d:2020-11-11"];
-        let _ = parse_command_option(&option, false, false, false).unwrap();
+        let _ = parse_command_option(&option, false, false, false, &vec![]).unwrap();
     }
 
     #[test]
     fn test_parse_tags_ok() {
         let option = vec!["+hello", "+world"];
-        let parsed_option = parse_command_option(&option, false, false, false).unwrap();
+        let parsed_option = parse_command_option(&option, false, false, false, &vec![]).unwrap();
         assert_eq!(parsed_option.tags, vec!["hello", "world"]);
     }
 
     #[test]
     fn test_parse_context_ok() {
         let option = vec!["c:inbox"];
-        let parsed_option = parse_command_option(&option, false, false, false).unwrap();
+        let parsed_option = parse_command_option(&option, false, false, false, &vec![]).unwrap();
         assert_eq!(parsed_option.context, Some("inbox".to_string()));
     }
 
     #[test]
     fn test_parse_priority_ok() {
         let option = vec!["pri:h"];
-        let parsed_option = parse_command_option(&option, false, false, false).unwrap();
+        let parsed_option = parse_command_option(&option, false, false, false, &vec![]).unwrap();
         assert_eq!(parsed_option.priority, Some("h".to_string()));
     }
 
+    #[test]
+    fn test_parse_estimate() {
+        let option = vec!["Write", "the", "report", "est:1h30m"];
+        let parsed_option = parse_command_option(&option, true, false, false, &vec![]).unwrap();
+        assert_eq!(parsed_option.body, Some("Write the report".to_string()));
+        assert_eq!(parsed_option.estimate, Some("1h30m"));
+
+        let option = vec!["est:45m", "est:2h"];
+        assert!(parse_command_option(&option, false, false, false, &vec![]).is_err());
+    }
+
+    #[test]
+    fn test_parse_udas() {
+        let uda_names = vec!["client".to_string(), "cost".to_string(), "ticket".to_string()];
+        let option = vec!["Renew", "the", "domain", "client:acme", "cost:40", "ticket:"];
+        let parsed_option = parse_command_option(&option, true, false, false, &uda_names).unwrap();
+        assert_eq!(parsed_option.body, Some("Renew the domain".to_string()));
+        assert_eq!(
+            parsed_option.udas,
+            vec![
+                ("client".to_string(), "acme".to_string()),
+                ("cost".to_string(), "40".to_string()),
+                ("ticket".to_string(), "".to_string()),
+            ]
+        );
+
+        // Ids, URLs and capitalized words aren't UDAs
+        let option = vec!["Read", "https://example.com", "Re:", "id:3"];
+        let parsed_option = parse_command_option(&option, true, false, false, &uda_names).unwrap();
+        assert_eq!(parsed_option.body, Some("Read https://example.com Re: id:3".to_string()));
+        assert!(parsed_option.udas.is_empty());
+
+        let option = vec!["client:acme", "client:globex"];
+        assert!(parse_command_option(&option, false, false, false, &uda_names).is_err());
+    }
+
+    #[test]
+    fn test_parse_undeclared_uda_is_body() {
+        let uda_names = vec!["client".to_string()];
+        let option = vec!["Email", "vendor", "re:invoice", "about", "payment", "client:acme"];
+        let parsed_option = parse_command_option(&option, true, false, false, &uda_names).unwrap();
+        assert_eq!(
+            parsed_option.body,
+            Some("Email vendor re:invoice about payment".to_string())
+        );
+        assert_eq!(
+            parsed_option.udas,
+            vec![("client".to_string(), "acme".to_string())]
+        );
+
+        // Nothing is a UDA when none are declared
+        let option = vec!["Write", "todo:", "note:x", "later"];
+        let parsed_option = parse_command_option(&option, true, false, false, &vec![]).unwrap();
+        assert_eq!(parsed_option.body, Some("Write todo: note:x later".to_string()));
+        assert!(parsed_option.udas.is_empty());
+    }
+
+    #[test]
+    fn test_parse_parent() {
+        let option = vec!["Book", "the", "venue", "parent:12"];
+        let parsed_option = parse_command_option(&option, true, false, false, &vec![]).unwrap();
+        assert_eq!(parsed_option.body, Some("Book the venue".to_string()));
+        assert_eq!(parsed_option.parent_id, Some(Some(12)));
+
+        let parsed_option = parse_command_option(&vec!["parent:"], false, false, false, &vec![]).unwrap();
+        assert_eq!(parsed_option.parent_id, Some(None));
+
+        assert!(parse_command_option(&vec!["parent:x"], false, false, false, &vec![]).is_err());
+        assert!(parse_command_option(&vec!["parent:1", "parent:2"], false, false, false, &vec![]).is_err());
+    }
+
+    #[test]
+    fn test_parse_project() {
+        let option = vec!["Book", "the", "venue", "proj:Wedding"];
+        let parsed_option = parse_command_option(&option, true, false, false, &vec![]).unwrap();
+        assert_eq!(parsed_option.body, Some("Book the venue".to_string()));
+        assert_eq!(parsed_option.project, Some("Wedding".to_string()));
+        assert!(parsed_option.udas.is_empty());
+
+        let parsed_option = parse_command_option(&vec!["proj:"], false, false, false, &vec![]).unwrap();
+        assert_eq!(parsed_option.project, Some(String::new()));
+        assert!(parse_command_option(&vec!["proj:a", "proj:b"], false, false, false, &vec![]).is_err());
+    }
+
     #[test]
     #[should_panic]
     fn test_parse_context_error() {
         let option = vec!["c:inbox", "c:work"];
-        let parsed_option = parse_command_option(&option, false, false, false).unwrap();
+        let parsed_option = parse_command_option(&option, false, false, false, &vec![]).unwrap();
     }
 
     #[test]
     fn test_parse_body() {
         let option = vec!["THIS", "IS", "A", "BODY", "c:inbox"];
-        let parsed_option = parse_command_option(&option, true, false, false).unwrap();
+        let parsed_option = parse_command_option(&option, true, false, false, &vec![]).unwrap();
         assert_eq!(parsed_option.body, Some("THIS IS A BODY".to_string()));
     }
 
     #[test]
     fn test_parse_state_name() {
         let option = vec!["@ready"];
-        let parsed_option = parse_command_option(&option, true, false, false).unwrap();
+        let parsed_option = parse_command_option(&option, true, false, false, &vec![]).unwrap();
         assert_eq!(parsed_option.state, Some("ready".to_string()));
     }
 
     #[test]
     fn test_parse_tags_to_remove() {
         let option = vec!["~Tag1", "~Tag2"];
-        let parsed_option = parse_command_option(&option, true, true, false).unwrap();
+        let parsed_option = parse_command_option(&option, true, true, false, &vec![]).unwrap();
         assert_eq!(parsed_option.tags_to_remove, vec!["Tag1", "Tag2"]);
     }
 
@@ -268,35 +464,35 @@ mod tests {
     #[should_panic]
     fn test_parse_tags_to_remove_when_no_need() {
         let option = vec!["+-Tag1", "+-Tag2"];
-        let parsed_option = parse_command_option(&option, true, false, false).unwrap();
+        let parsed_option = parse_command_option(&option, true, false, false, &vec![]).unwrap();
         assert_eq!(parsed_option.tags_to_remove, vec!["Tag1", "Tag2"]);
     }
 
     #[test]
     fn test_parse_task_ids() {
         let option = vec!["1", "2"];
-        let parsed_option = parse_command_option(&option, false, false, true).unwrap();
+        let parsed_option = parse_command_option(&option, false, false, true, &vec![]).unwrap();
         assert_eq!(parsed_option.task_ids, vec![1, 2]);
     }
 
     #[test]
     fn test_parse_range_task_ids() {
         let option = vec!["1..3"];
-        let parsed_option = parse_command_option(&option, false, false, true).unwrap();
+        let parsed_option = parse_command_option(&option, false, false, true, &vec![]).unwrap();
         assert_eq!(parsed_option.task_ids, vec![1, 2, 3]);
     }
 
     #[test]
     fn test_parse_parent_task_ids() {
         let option = vec!["dep:1,2,3"];
-        let parsed_option = parse_command_option(&option, false, false, true).unwrap();
+        let parsed_option = parse_command_option(&option, false, false, true, &vec![]).unwrap();
         assert_eq!(parsed_option.parent_task_ids, Some(vec![1, 2, 3]));
     }
 
     #[test]
     fn test_parse_not_tag_ids() {
         let option = vec!["^tag1"];
-        let parsed_option = parse_command_option(&option, false, false, true).unwrap();
+        let parsed_option = parse_command_option(&option, false, false, true, &vec![]).unwrap();
         assert_eq!(parsed_option.not_tags, Some(vec!["tag1".to_string()]));
     }
 
@@ -314,7 +510,7 @@ mod tests {
             "d:2026-07-10+weekly",
             "s:2026-07-08+daily",
         ];
-        let parsed_option = parse_command_option(&option, false, true, true).unwrap();
+        let parsed_option = parse_command_option(&option, false, true, true, &vec![]).unwrap();
 
         assert_eq!(parsed_option.task_ids, vec![12, 15, 19]);
         assert_eq!(parsed_option.tags, vec!["next"]);
@@ -331,9 +527,47 @@ mod tests {
     #[test]
     fn test_parse_web_bulk_modify_range_task_ids() {
         let option = vec!["1..3", "@completed"];
-        let parsed_option = parse_command_option(&option, false, true, true).unwrap();
+        let parsed_option = parse_command_option(&option, false, true, true, &vec![]).unwrap();
 
         assert_eq!(parsed_option.task_ids, vec![1, 2, 3]);
         assert_eq!(parsed_option.state, Some("completed".to_string()));
     }
+
+    #[test]
+    fn test_parse_task_uuids() {
+        let option = vec!["3", "0F8FAD5B", "0f8fad5b-d9cb-469f-a165-70867728950e", "12345678", "+next"];
+        let parsed_option = parse_command_option(&option, false, true, true, &vec![]).unwrap();
+
+        assert_eq!(parsed_option.task_ids, vec![3, 12345678]);
+        assert_eq!(
+            parsed_option.task_uuids,
+            vec!["0f8fad5b", "0f8fad5b-d9cb-469f-a165-70867728950e"]
+        );
+        assert!(!is_uuid_prefix("0f8fad"));
+        assert!(!is_uuid_prefix("deadbeefs"));
+    }
+
+    #[test]
+    fn test_parse_display_ids() {
+        let option = vec!["3", "5..7", "id:12", "id:20..21", "0f8fad5b", "@completed"];
+        let parsed_option = parse_command_option_with_display_ids(&option, false, &vec![]).unwrap();
+
+        assert_eq!(parsed_option.display_ids, vec![3, 5, 6, 7]);
+        assert_eq!(parsed_option.task_ids, vec![12, 20, 21]);
+        assert_eq!(parsed_option.task_uuids, vec!["0f8fad5b"]);
+        assert_eq!(parsed_option.state, Some("completed".to_string()));
+
+        // Without display ids, id: is the same as a plain number
+        let parsed_option = parse_command_option(&option, false, false, true, &vec![]).unwrap();
+        assert_eq!(parsed_option.task_ids, vec![3, 5, 6, 7, 12, 20, 21]);
+        assert!(parsed_option.display_ids.is_empty());
+    }
+
+    #[test]
+    fn test_parse_invalid_range() {
+        let option = vec!["1..x"];
+        assert!(parse_command_option_with_display_ids(&option, false, &vec![]).is_err());
+        let option = vec!["id:1..2..3"];
+        assert!(parse_command_option(&option, false, false, true, &vec![]).is_err());
+    }
 }
@@ -0,0 +1,884 @@
+use super::{AddParams, ModifyParams, TaskStore};
+use crate::db::project::is_valid_project_name;
+use crate::db::search::SearchResult;
+use crate::db::task_helper::{
+    get_label_ancestors, is_valid_label_name, is_within_label, parse_estimate, Task,
+    DEFAULT_CONTEXT, PRIORITIES, TASK_STATES,
+};
+use crate::db::subtask::is_auto_complete_parent;
+use crate::db::task_manager::TaskManager;
+use crate::db::uda::{get_uda_types, resolve_udas, UdaType};
+use crate::error::{ArgumentError, CoreError};
+use chrono::{Duration, NaiveDate, NaiveDateTime, Utc};
+use std::cell::RefCell;
+use std::collections::hash_map::RandomState;
+use std::collections::{BTreeMap, HashMap};
+use std::hash::{BuildHasher, Hasher};
+use std::rc::Rc;
+
+struct MemoryTask {
+    task: Task,
+    parent_task_ids: Vec<i64>,
+    deleted: bool,
+}
+
+impl MemoryTask {
+    fn is_pending(&self) -> bool {
+        !self.deleted && !self.task.is_completed()
+    }
+}
+
+#[derive(Default)]
+struct MemoryData {
+    tasks: BTreeMap<i64, MemoryTask>,
+    contexts: Vec<String>,
+    tags: Vec<String>,
+    states: Vec<String>,
+    // display id -> task id, see `db::working_set`
+    working_set: BTreeMap<i64, i64>,
+    // The setting the store was opened with, for the UDAs
+    setting: HashMap<String, String>,
+}
+
+// Keeps the tasks in memory, nothing is written to disk. Clones share the
+// same tasks, so one store can be handed to several operations.
+//
+// It behaves like the SQLite backend for the `TaskStore` methods. The
+// journal, the history and the archive aren't kept.
+#[derive(Clone)]
+pub struct MemoryStore {
+    data: Rc<RefCell<MemoryData>>,
+}
+
+impl Default for MemoryStore {
+    fn default() -> Self {
+        MemoryStore::new()
+    }
+}
+
+impl MemoryStore {
+    pub fn new() -> MemoryStore {
+        let data = MemoryData {
+            contexts: DEFAULT_CONTEXT.iter().map(|name| name.to_string()).collect(),
+            states: TASK_STATES.iter().map(|name| name.to_string()).collect(),
+            ..Default::default()
+        };
+        MemoryStore {
+            data: Rc::new(RefCell::new(data)),
+        }
+    }
+}
+
+// Same layout as the UUIDs generated by SQLite
+fn new_uuid() -> String {
+    let random = || RandomState::new().build_hasher().finish();
+    let (high, low) = (random(), random());
+    format!(
+        "{:08x}-{:04x}-4{:03x}-{:x}{:03x}-{:012x}",
+        high >> 32,
+        (high >> 16) & 0xffff,
+        high & 0xfff,
+        8 + (low >> 62),
+        (low >> 48) & 0xfff,
+        low & 0xffff_ffff_ffff
+    )
+}
+
+// In UTC like `CURRENT_TIMESTAMP`
+fn now() -> String {
+    Utc::now().format("%Y-%m-%d %H:%M:%S").to_string()
+}
+
+// Same as `db::modify::update_state_timestamps`
+fn update_state_timestamps(task: &mut Task) {
+    match task.state.as_str() {
+        "started" if task.date_started.is_empty() => task.date_started = now(),
+        "started" => {}
+        "completed" => {}
+        _ => task.date_started = String::new(),
+    }
+    if task.state != TASK_STATES[1] {
+        task.date_completed = String::new();
+    } else if task.date_completed.is_empty() {
+        task.date_completed = now();
+    }
+}
+
+fn parse_day(day: &str) -> Result<NaiveDate, CoreError> {
+    let date = TaskManager::parse_date_string(day)?;
+    Ok(NaiveDateTime::parse_from_str(&date, "%Y-%m-%d %H:%M:%S")
+        .expect("parse_date_string returns valid dates")
+        .date())
+}
+
+// Dates are parsed, an empty string clears the date
+fn parse_optional_date(date: &Option<&str>) -> Result<Option<String>, CoreError> {
+    match date {
+        Some(date) if date.is_empty() => Ok(Some(String::new())),
+        Some(date) => Ok(Some(TaskManager::parse_date_string(date)?)),
+        None => Ok(None),
+    }
+}
+
+// Project names are lowercased, an empty one takes the task out of its project
+fn parse_project(project: &Option<String>) -> Result<Option<String>, CoreError> {
+    match project.as_deref() {
+        Some("") => Ok(Some(String::new())),
+        Some(name) if is_valid_project_name(name) => Ok(Some(name.to_lowercase())),
+        Some(name) => Err(CoreError::ArgumentError(format!("Invalid project name {}", name))),
+        None => Ok(None),
+    }
+}
+
+fn find_priority(priority: &Option<String>) -> Result<Option<String>, CoreError> {
+    match priority {
+        Some(name) => {
+            let name = name.to_lowercase();
+            if PRIORITIES.iter().any(|priority| priority.to_lowercase() == name) {
+                Ok(Some(name))
+            } else {
+                Err(ArgumentError::InvalidOption(format!(
+                    "Invalid priority {} is provided",
+                    name
+                )))?
+            }
+        }
+        None => Ok(None),
+    }
+}
+
+fn add_name(names: &mut Vec<String>, name: &str) -> i64 {
+    let name = name.trim();
+    match names.iter().position(|existing| existing == name) {
+        Some(index) => index as i64 + 1,
+        None => {
+            names.push(name.to_string());
+            names.len() as i64
+        }
+    }
+}
+
+// A context or a tag, its parents are added with it like in the database
+fn add_label(
+    names: &mut Vec<String>,
+    name: &str,
+    invalid: fn(String) -> ArgumentError,
+) -> Result<(), CoreError> {
+    let name = name.trim();
+    if !is_valid_label_name(name) {
+        return Err(invalid(name.to_string()))?;
+    }
+    for name in get_label_ancestors(name).into_iter().chain([name]) {
+        add_name(names, name);
+    }
+    Ok(())
+}
+
+impl MemoryData {
+    fn context_exists(&self, name: &str) -> bool {
+        self.contexts.iter().any(|context| context == name)
+    }
+
+    fn set_tags(task: &mut Task, tags: &Vec<String>, tag_names: Vec<String>) {
+        task.tag_ids = tag_names
+            .iter()
+            .map(|name| tags.iter().position(|tag| tag == name).unwrap() as i64 + 1)
+            .collect();
+        task.tags = tag_names;
+    }
+
+    fn existing_task(&self, task_id: &i64) -> Result<&MemoryTask, CoreError> {
+        match self.tasks.get(task_id) {
+            Some(task) if !task.deleted => Ok(task),
+            _ => Err(CoreError::ArgumentError(format!(
+                "Task {} doesn't exist",
+                task_id
+            ))),
+        }
+    }
+
+    fn sync_working_set(&mut self, renumber: bool) {
+        let tasks = &self.tasks;
+        self.working_set
+            .retain(|_, task_id| tasks.get(task_id).map_or(false, |task| task.is_pending()));
+        if renumber {
+            self.working_set = self
+                .working_set
+                .values()
+                .enumerate()
+                .map(|(index, task_id)| (index as i64 + 1, *task_id))
+                .collect();
+        }
+
+        let mut next = self.working_set.keys().last().map_or(1, |id| id + 1);
+        for (task_id, task) in self.tasks.iter() {
+            if task.is_pending() && !self.working_set.values().any(|id| id == task_id) {
+                self.working_set.insert(next, *task_id);
+                next += 1;
+            }
+        }
+    }
+
+    // The task as the SQLite backend returns it, with the current display id
+    // and the progress of its subtasks
+    fn snapshot(&self, task_id: &i64) -> Task {
+        let mut task = self.tasks[task_id].task.clone();
+        task.display_id = self
+            .working_set
+            .iter()
+            .find(|(_, id)| *id == task_id)
+            .map(|(display_id, _)| *display_id);
+        let subtasks: Vec<&Task> = self.children(task_id).map(|child| &child.task).collect();
+        task.subtasks_total = subtasks.len() as i64;
+        task.subtasks_completed = subtasks.iter().filter(|task| task.is_completed()).count() as i64;
+        task
+    }
+
+    // The direct subtasks that aren't deleted
+    fn children<'a>(&'a self, task_id: &'a i64) -> impl Iterator<Item = &'a MemoryTask> + 'a {
+        self.tasks
+            .values()
+            .filter(move |task| !task.deleted && task.task.parent_id == Some(*task_id))
+    }
+
+    // Same as `db::subtask::get_subtask_ids`
+    fn subtask_ids(&self, task_ids: &Vec<i64>) -> Vec<i64> {
+        let mut subtask_ids: Vec<i64> = vec![];
+        let mut parent_ids = task_ids.clone();
+        while let Some(parent_id) = parent_ids.pop() {
+            for child in self.children(&parent_id) {
+                if !subtask_ids.contains(&child.task.id) {
+                    subtask_ids.push(child.task.id);
+                    parent_ids.push(child.task.id);
+                }
+            }
+        }
+        subtask_ids.sort();
+        subtask_ids
+    }
+
+    // Same as `db::subtask::set_parent`
+    fn check_parent(&self, task_ids: &Vec<i64>, parent_id: &i64) -> Result<(), CoreError> {
+        self.existing_task(parent_id).map_err(|_| {
+            CoreError::ArgumentError(format!("Parent task {} doesn't exist", parent_id))
+        })?;
+        if task_ids.contains(parent_id) || self.subtask_ids(task_ids).contains(parent_id) {
+            return Err(CoreError::ArgumentError(format!(
+                "Task {} is one of the tasks or their subtasks, it can't be their parent",
+                parent_id
+            )));
+        }
+        Ok(())
+    }
+
+    // The parents that aren't completed while all of their subtasks are
+    fn completable_parent_ids(&self, task_ids: &Vec<i64>) -> Vec<i64> {
+        let mut parent_ids: Vec<i64> = task_ids
+            .iter()
+            .filter_map(|task_id| self.tasks[task_id].task.parent_id)
+            .filter(|parent_id| {
+                let parent = &self.tasks[parent_id];
+                !parent.deleted
+                    && !parent.task.is_completed()
+                    && self.children(parent_id).all(|child| child.task.is_completed())
+            })
+            .collect();
+        parent_ids.sort();
+        parent_ids.dedup();
+        parent_ids
+    }
+
+    fn snapshots(&mut self, task_ids: &Vec<i64>) -> Vec<Task> {
+        self.sync_working_set(false);
+        task_ids.iter().map(|task_id| self.snapshot(task_id)).collect()
+    }
+
+    // Completing or deleting a task makes the tasks blocked by it ready,
+    // once all of their parents are completed or deleted
+    fn update_dependency(&mut self, task_id: &i64) {
+        let children: Vec<i64> = self
+            .tasks
+            .iter()
+            .filter(|(_, task)| task.parent_task_ids.contains(task_id))
+            .filter(|(_, task)| !task.deleted && task.task.is_blocked())
+            .map(|(id, _)| *id)
+            .collect();
+        for child_id in children {
+            let are_all_parents_completed =
+                self.tasks[&child_id].parent_task_ids.iter().all(|parent_id| {
+                    self.tasks
+                        .get(parent_id)
+                        .map_or(false, |parent| parent.deleted || parent.task.is_completed())
+                });
+            if are_all_parents_completed {
+                let child = &mut self.tasks.get_mut(&child_id).unwrap().task;
+                child.state = String::from(TASK_STATES[0]);
+                update_state_timestamps(child);
+                child.date_modified = now();
+            }
+        }
+    }
+
+    // Completed tasks that repeat get their next date and become ready again
+    fn update_repetition(&mut self, task_id: &i64) -> Result<(), CoreError> {
+        let task = &mut self.tasks.get_mut(task_id).unwrap().task;
+        if !task.repetition_due.is_empty() {
+            task.date_due = TaskManager::parse_date_string(&task.repetition_due)?;
+            task.state = String::from(TASK_STATES[0]);
+        }
+        if !task.repetition_scheduled.is_empty() {
+            task.date_scheduled = TaskManager::parse_date_string(&task.repetition_scheduled)?;
+            task.state = String::from(TASK_STATES[0]);
+        }
+        update_state_timestamps(task);
+        Ok(())
+    }
+}
+
+impl TaskStore for MemoryStore {
+    fn open(setting: &HashMap<String, String>) -> Result<MemoryStore, CoreError> {
+        let store = MemoryStore::new();
+        store.data.borrow_mut().setting = setting.clone();
+        Ok(store)
+    }
+
+    fn add(&mut self, params: &AddParams) -> Result<Vec<Task>, CoreError> {
+        let AddParams {
+            body,
+            priority,
+            context,
+            tags,
+            date_due,
+            date_scheduled,
+            repetition_due,
+            repetition_scheduled,
+            annotation,
+            state_name,
+            parent_task_ids,
+            estimate,
+            udas,
+            parent_id,
+            project,
+        } = params;
+        let udas = resolve_udas(&self.get_uda_types()?, udas)?;
+        let project = parse_project(project)?.unwrap_or_default();
+        let mut data = self.data.borrow_mut();
+        if let Some(parent_id) = parent_id {
+            data.check_parent(&vec![], parent_id)?;
+        }
+
+        // Tasks depending on unfinished tasks are blocked, unless the
+        // client provides a state
+        let parent_task_ids = parent_task_ids.clone().unwrap_or_default();
+        let mut state = String::from(TASK_STATES[0]);
+        for parent_task_id in parent_task_ids.iter() {
+            let parent = data.existing_task(parent_task_id).map_err(|_| {
+                CoreError::ArgumentError(String::from("Invalid parent task is provided"))
+            })?;
+            if !parent.task.is_completed() {
+                state = String::from(TASK_STATES[2]);
+            }
+        }
+        if let Some(name) = state_name {
+            state = name.trim().to_lowercase();
+        }
+
+        let date_due = parse_optional_date(date_due)?.unwrap_or_default();
+        let date_scheduled = parse_optional_date(date_scheduled)?.unwrap_or_default();
+        for repetition in [repetition_due, repetition_scheduled].iter() {
+            if let Some(period) = repetition {
+                TaskManager::parse_date_string(period)?;
+            }
+        }
+        let priority = find_priority(priority)?.unwrap_or_default();
+        let estimate = estimate.map(parse_estimate).transpose()?;
+
+        let context = context
+            .as_deref()
+            .unwrap_or(DEFAULT_CONTEXT[0])
+            .trim()
+            .to_string();
+        add_label(&mut data.contexts, &context, ArgumentError::InvalidContext)?;
+        add_name(&mut data.states, &state);
+        for tag in tags.iter() {
+            add_label(&mut data.tags, tag, ArgumentError::InvalidTag)?;
+        }
+
+        let task_id = data.tasks.keys().last().map_or(1, |id| id + 1);
+        let mut task = Task {
+            id: task_id,
+            uuid: new_uuid(),
+            display_id: None,
+            body: body.to_string(),
+            priority,
+            context,
+            project,
+            tags: vec![],
+            tag_ids: vec![],
+            date_created: now(),
+            date_modified: now(),
+            date_started: String::new(),
+            date_completed: String::new(),
+            estimate,
+            time_tracked: 0,
+            date_due,
+            date_scheduled,
+            repetition_due: repetition_due.unwrap_or("").to_string(),
+            repetition_scheduled: repetition_scheduled.unwrap_or("").to_string(),
+            state,
+            annotation: annotation.unwrap_or("").to_string(),
+            parent_task_ids: parent_task_ids.iter().map(|id| id.to_string()).collect(),
+            notes: vec![],
+            attachments: vec![],
+            checklist: vec![],
+            udas: udas
+                .into_iter()
+                .filter_map(|(name, value)| Some((name, value?)))
+                .collect(),
+            parent_id: *parent_id,
+            subtasks_total: 0,
+            subtasks_completed: 0,
+        };
+        update_state_timestamps(&mut task);
+        let mut tag_names: Vec<String> = tags.iter().map(|tag| tag.trim().to_string()).collect();
+        tag_names.dedup();
+        MemoryData::set_tags(&mut task, &data.tags, tag_names);
+        data.tasks.insert(
+            task_id,
+            MemoryTask {
+                task,
+                parent_task_ids,
+                deleted: false,
+            },
+        );
+        Ok(data.snapshots(&vec![task_id]))
+    }
+
+    fn get(
+        &mut self,
+        priority: &Option<String>,
+        context: &Option<String>,
+        tags: &Vec<String>,
+        date_due: &Option<&str>,
+        date_scheduled: &Option<&str>,
+        task_id: &Option<i64>,
+        not_tags: &Option<Vec<String>>,
+    ) -> Result<Vec<Task>, CoreError> {
+        let mut data = self.data.borrow_mut();
+        let context = context.as_deref().unwrap_or(DEFAULT_CONTEXT[0]);
+        if !data.context_exists(context) {
+            return Err(ArgumentError::InvalidContext(context.to_string()))?;
+        }
+        let priority = find_priority(priority)?;
+
+        // Listing the tasks renumbers the display ids
+        data.sync_working_set(task_id.is_none());
+
+        // A task id replaces the context and the date filters
+        let is_selected = |task: &Task| match task_id {
+            Some(id) => task.id == *id,
+            None => {
+                is_within_label(&task.context, context)
+                    && date_due.map_or(true, |date| task.date_due == date)
+                    && date_scheduled.map_or(true, |date| task.date_scheduled == date)
+            }
+        };
+        // Filtering on a label includes its descendants
+        let has_tag = |task: &Task, tag: &String| {
+            task.tags.iter().any(|name| is_within_label(name, tag))
+        };
+        let matches = |task: &Task| {
+            is_selected(task)
+            && priority.as_ref().map_or(true, |name| &task.priority == name)
+            && tags.iter().all(|tag| has_tag(task, tag))
+            && not_tags
+                .as_ref()
+                .map_or(true, |not_tags| !not_tags.iter().any(|tag| has_tag(task, tag)))
+        };
+        let task_ids: Vec<i64> = data
+            .tasks
+            .values()
+            .filter(|task| !task.deleted && matches(&task.task))
+            .map(|task| task.task.id)
+            .collect();
+        Ok(task_ids.iter().map(|task_id| data.snapshot(task_id)).collect())
+    }
+
+    // Nothing is archived in memory
+    fn get_archived(
+        &mut self,
+        _priority: &Option<String>,
+        _context: &Option<String>,
+        _tags: &Vec<String>,
+        _date_due: &Option<&str>,
+        _date_scheduled: &Option<&str>,
+        _task_id: &Option<i64>,
+        _not_tags: &Option<Vec<String>>,
+    ) -> Result<Vec<Task>, CoreError> {
+        Ok(vec![])
+    }
+
+    fn modify(
+        &mut self,
+        task_ids: &Vec<i64>,
+        params: &ModifyParams,
+    ) -> Result<Vec<Task>, CoreError> {
+        let ModifyParams {
+            body,
+            priority,
+            context,
+            tags,
+            date_due,
+            date_scheduled,
+            repetition_due,
+            repetition_scheduled,
+            state,
+            tags_to_remove,
+            estimate,
+            udas,
+            parent_id,
+            project,
+        } = params;
+        if task_ids.is_empty() {
+            Err(ArgumentError::InvalidOption(
+                "Task Ids can't be empty".to_string(),
+            ))?
+        }
+        let udas = resolve_udas(&self.get_uda_types()?, udas)?;
+        let auto_complete_parent = state.map(|name| name.trim().to_lowercase())
+            == Some(String::from(TASK_STATES[1]))
+            && is_auto_complete_parent(&self.data.borrow().setting)?;
+        let mut data = self.data.borrow_mut();
+        let priority = find_priority(priority)?;
+        let date_due = parse_optional_date(date_due)?;
+        let date_scheduled = parse_optional_date(date_scheduled)?;
+        let state = state.map(|name| name.trim().to_lowercase());
+        let project = parse_project(project)?;
+        let estimate = match estimate {
+            Some("") => Some(None),
+            Some(estimate) => Some(Some(parse_estimate(estimate)?)),
+            None => None,
+        };
+
+        if body.is_none()
+            && date_due.is_none()
+            && date_scheduled.is_none()
+            && repetition_due.is_none()
+            && repetition_scheduled.is_none()
+            && tags.is_empty()
+            && tags_to_remove.is_empty()
+            && state.is_none()
+            && context.is_none()
+            && priority.is_none()
+            && estimate.is_none()
+            && udas.is_empty()
+            && parent_id.is_none()
+            && project.is_none()
+        {
+            return Ok(vec![]);
+        }
+        for task_id in task_ids.iter() {
+            data.existing_task(task_id)?;
+        }
+        if let Some(Some(parent_id)) = parent_id {
+            data.check_parent(task_ids, parent_id)?;
+        }
+
+        if let Some(context) = context {
+            add_label(&mut data.contexts, context, ArgumentError::InvalidContext)?;
+        }
+        if let Some(state) = &state {
+            add_name(&mut data.states, state);
+        }
+        for tag in tags.iter() {
+            add_label(&mut data.tags, tag, ArgumentError::InvalidTag)?;
+        }
+
+        let MemoryData {
+            tasks,
+            tags: tag_table,
+            ..
+        } = &mut *data;
+        for task_id in task_ids.iter() {
+            let task = &mut tasks.get_mut(task_id).unwrap().task;
+            let updates = [
+                (&mut task.body, body.map(|body| body.to_string())),
+                (&mut task.date_due, date_due.clone()),
+                (&mut task.date_scheduled, date_scheduled.clone()),
+                (&mut task.repetition_due, repetition_due.map(|repeat| repeat.to_string())),
+                (
+                    &mut task.repetition_scheduled,
+                    repetition_scheduled.map(|repeat| repeat.to_string()),
+                ),
+                (&mut task.context, context.as_ref().map(|name| name.trim().to_string())),
+                (&mut task.state, state.clone()),
+                (&mut task.priority, priority.clone()),
+                (&mut task.project, project.clone()),
+            ];
+            for (field, value) in updates {
+                if let Some(value) = value {
+                    *field = value;
+                }
+            }
+            if let Some(estimate) = estimate {
+                task.estimate = estimate;
+            }
+            for (name, value) in udas.iter() {
+                match value {
+                    Some(value) => task.udas.insert(name.clone(), value.clone()),
+                    None => task.udas.remove(name),
+                };
+            }
+            if let Some(parent_id) = parent_id {
+                task.parent_id = *parent_id;
+            }
+
+            let mut tag_names = task.tags.clone();
+            for tag in tags.iter() {
+                if !tag_names.contains(tag) {
+                    tag_names.push(tag.trim().to_string());
+                }
+            }
+            tag_names.retain(|tag| !tags_to_remove.contains(tag));
+            MemoryData::set_tags(task, tag_table, tag_names);
+            update_state_timestamps(task);
+            task.date_modified = now();
+        }
+
+        let mut completed_task_ids = task_ids.clone();
+        let mut modified_task_ids = task_ids.clone();
+        if state.as_deref() == Some(TASK_STATES[1]) {
+            loop {
+                for task_id in completed_task_ids.iter() {
+                    data.update_repetition(task_id)?;
+                    data.update_dependency(task_id);
+                }
+                if !auto_complete_parent {
+                    break;
+                }
+                // The parents are completed with their last subtask
+                completed_task_ids = data.completable_parent_ids(&completed_task_ids);
+                if completed_task_ids.is_empty() {
+                    break;
+                }
+                for parent_id in completed_task_ids.iter() {
+                    let parent = &mut data.tasks.get_mut(parent_id).unwrap().task;
+                    parent.state = String::from(TASK_STATES[1]);
+                    update_state_timestamps(parent);
+                    parent.date_modified = now();
+                }
+                modified_task_ids.extend(completed_task_ids.iter());
+            }
+        }
+        Ok(data.snapshots(&modified_task_ids))
+    }
+
+    fn delete(&mut self, task_ids: &Vec<i64>) -> Result<Vec<Task>, CoreError> {
+        let mut data = self.data.borrow_mut();
+        let mut deleted_ids: Vec<i64> = task_ids
+            .iter()
+            .filter(|task_id| data.existing_task(task_id).is_ok())
+            .copied()
+            .collect();
+        deleted_ids.sort();
+        deleted_ids.dedup();
+
+        let mut tasks: Vec<Task> =
+            deleted_ids.iter().map(|task_id| data.snapshot(task_id)).collect();
+        for task_id in deleted_ids.iter() {
+            data.tasks.get_mut(task_id).unwrap().deleted = true;
+        }
+        for task_id in deleted_ids.iter() {
+            data.update_dependency(task_id);
+        }
+        // The subtasks move up to the parent of the deleted task
+        let parents: HashMap<i64, Option<i64>> = data
+            .tasks
+            .iter()
+            .map(|(task_id, task)| (*task_id, task.task.parent_id))
+            .collect();
+        for task in data.tasks.values_mut() {
+            if deleted_ids.contains(&task.task.id) {
+                continue;
+            }
+            while let Some(parent_id) = task.task.parent_id {
+                if !deleted_ids.contains(&parent_id) {
+                    break;
+                }
+                task.task.parent_id = parents[&parent_id];
+            }
+        }
+        data.sync_working_set(false);
+        for task in tasks.iter_mut() {
+            task.display_id = None;
+        }
+        Ok(tasks)
+    }
+
+    fn get_subtask_ids(&mut self, task_ids: &Vec<i64>) -> Result<Vec<i64>, CoreError> {
+        Ok(self.data.borrow().subtask_ids(task_ids))
+    }
+
+    fn view_agenda(
+        &mut self,
+        start_day: String,
+        end_day: Option<String>,
+        context_name: Option<String>,
+    ) -> Result<Vec<(NaiveDate, Vec<Task>)>, CoreError> {
+        let start_day = parse_day(&start_day)?;
+        let end_day = match end_day {
+            Some(day) => parse_day(&day)?,
+            None => start_day,
+        };
+
+        let data = self.data.borrow();
+        let mut result = vec![];
+        let mut day = start_day;
+        loop {
+            // Due or scheduled on the day or before it
+            let next_day = (day + Duration::days(1)).format("%Y-%m-%d").to_string();
+            let is_before = |date: &String| !date.is_empty() && date < &next_day;
+            let tasks = data
+                .tasks
+                .values()
+                .filter(|stored| {
+                    let task = &stored.task;
+                    !stored.deleted
+                        && (is_before(&task.date_due) || is_before(&task.date_scheduled))
+                        && context_name
+                            .as_ref()
+                            .map_or(true, |name| is_within_label(&task.context, name))
+                })
+                .map(|stored| data.snapshot(&stored.task.id))
+                .collect();
+            result.push((day, tasks));
+            if day >= end_day {
+                break;
+            }
+            day += Duration::days(1);
+        }
+        Ok(result)
+    }
+
+    // Every word has to match the start of a word in the body or the
+    // annotation, the tasks with the most matches come first
+    fn search(
+        &mut self,
+        query: &str,
+        highlight_start: &str,
+        highlight_end: &str,
+    ) -> Result<Vec<SearchResult>, CoreError> {
+        let query: Vec<String> =
+            query.split_whitespace().map(|word| word.to_lowercase()).collect();
+        if query.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let data = self.data.borrow();
+        let mut results = vec![];
+        for (task_id, stored) in data.tasks.iter() {
+            if stored.deleted {
+                continue;
+            }
+            let text = format!("{} {}", stored.task.body, stored.task.annotation);
+            let is_match = |word: &str| {
+                let word = word.to_lowercase();
+                query.iter().any(|prefix| word.starts_with(prefix.as_str()))
+            };
+            let words: Vec<&str> = text.split_whitespace().collect();
+            let matches = words.iter().filter(|word| is_match(word)).count();
+            let has_all_words = query.iter().all(|prefix| {
+                words
+                    .iter()
+                    .any(|word| word.to_lowercase().starts_with(prefix.as_str()))
+            });
+            if !has_all_words {
+                continue;
+            }
+
+            let snippet: Vec<String> = stored
+                .task
+                .body
+                .split_whitespace()
+                .map(|word| {
+                    if is_match(word) {
+                        format!("{}{}{}", highlight_start, word, highlight_end)
+                    } else {
+                        word.to_string()
+                    }
+                })
+                .collect();
+            results.push(SearchResult {
+                task: data.snapshot(task_id),
+                snippet: snippet.join(" "),
+                rank: -(matches as f64),
+            });
+        }
+        results.sort_by(|a, b| a.rank.partial_cmp(&b.rank).unwrap());
+        Ok(results)
+    }
+
+    fn get_task_ids_by_uuid(&mut self, uuids: &Vec<String>) -> Result<Vec<i64>, CoreError> {
+        let data = self.data.borrow();
+        let mut task_ids = vec![];
+        for prefix in uuids.iter() {
+            let lower_prefix = prefix.to_lowercase();
+            let matched: Vec<i64> = data
+                .tasks
+                .values()
+                .filter(|task| task.task.uuid.starts_with(&lower_prefix))
+                .map(|task| task.task.id)
+                .collect();
+            match matched.len() {
+                0 => {
+                    return Err(CoreError::ArgumentError(format!(
+                        "No task has the UUID {}",
+                        prefix
+                    )))
+                }
+                1 => task_ids.push(matched[0]),
+                _ => {
+                    return Err(CoreError::ArgumentError(format!(
+                        "The UUID prefix {} matches more than one task",
+                        prefix
+                    )))
+                }
+            }
+        }
+        Ok(task_ids)
+    }
+
+    fn get_task_ids_by_display_id(
+        &mut self,
+        display_ids: &Vec<i64>,
+    ) -> Result<Vec<i64>, CoreError> {
+        let data = self.data.borrow();
+        display_ids
+            .iter()
+            .map(|display_id| {
+                data.working_set.get(display_id).copied().ok_or_else(|| {
+                    CoreError::ArgumentError(format!(
+                        "No pending task has the id {}, use id:<id> for the permanent id",
+                        display_id
+                    ))
+                })
+            })
+            .collect()
+    }
+
+    fn get_context_names(&mut self) -> Result<Vec<String>, CoreError> {
+        Ok(self.data.borrow().contexts.clone())
+    }
+
+    fn get_tag_names(&mut self) -> Result<Vec<String>, CoreError> {
+        Ok(self.data.borrow().tags.clone())
+    }
+
+    fn get_state_names(&mut self) -> Result<Vec<String>, CoreError> {
+        Ok(self.data.borrow().states.clone())
+    }
+
+    fn get_uda_types(&mut self) -> Result<BTreeMap<String, UdaType>, CoreError> {
+        get_uda_types(&self.data.borrow().setting)
+    }
+}
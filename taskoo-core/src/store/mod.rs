@@ -0,0 +1,128 @@
+mod memory;
+mod sqlite;
+
+#[cfg(test)]
+mod tests;
+
+pub use memory::MemoryStore;
+
+use crate::db::search::SearchResult;
+use crate::db::task_helper::Task;
+use crate::db::uda::UdaType;
+use crate::error::CoreError;
+use chrono::NaiveDate;
+use std::collections::{BTreeMap, HashMap};
+
+// The fields of a new task, `..Default::default()` leaves out the ones
+// that aren't provided
+#[derive(Debug, Default, Clone)]
+pub struct AddParams<'a> {
+    pub body: &'a str,
+    pub priority: Option<String>,
+    pub context: Option<String>,
+    pub tags: Vec<String>,
+    pub date_due: Option<&'a str>,
+    pub date_scheduled: Option<&'a str>,
+    pub repetition_due: Option<&'a str>,
+    pub repetition_scheduled: Option<&'a str>,
+    pub annotation: Option<&'a str>,
+    pub state_name: Option<String>,
+    pub parent_task_ids: Option<Vec<i64>>,
+    pub estimate: Option<&'a str>,
+    pub udas: Vec<(String, String)>, // name and value, an empty value removes the UDA
+    pub parent_id: Option<i64>,
+    pub project: Option<String>,
+}
+
+// The changes to the tasks, the default changes nothing
+#[derive(Debug, Default, Clone)]
+pub struct ModifyParams<'a> {
+    pub body: Option<&'a str>,
+    pub priority: Option<String>,
+    pub context: Option<String>,
+    pub tags: Vec<String>,
+    pub date_due: Option<&'a str>,
+    pub date_scheduled: Option<&'a str>,
+    pub repetition_due: Option<&'a str>,
+    pub repetition_scheduled: Option<&'a str>,
+    pub state: Option<&'a str>,
+    pub tags_to_remove: Vec<String>,
+    pub estimate: Option<&'a str>, // An empty estimate removes it
+    pub udas: Vec<(String, String)>,
+    pub parent_id: Option<Option<i64>>, // Some(None) makes them top level tasks
+    pub project: Option<String>,        // An empty name takes the tasks out of their project
+}
+
+// Where the operations keep the tasks. `TaskManager` is the SQLite
+// backend, `MemoryStore` keeps everything in memory for tests and
+// embedders that don't want a database file.
+//
+// The arguments, and the fields of `AddParams` and `ModifyParams`, follow
+// `TaskManager`, see its methods for the details.
+pub trait TaskStore {
+    // Used by the operations that weren't given a store
+    fn open(setting: &HashMap<String, String>) -> Result<Self, CoreError>
+    where
+        Self: Sized;
+
+    fn add(&mut self, params: &AddParams) -> Result<Vec<Task>, CoreError>;
+
+    fn get(
+        &mut self,
+        priority: &Option<String>,
+        context: &Option<String>,
+        tags: &Vec<String>,
+        date_due: &Option<&str>,
+        date_scheduled: &Option<&str>,
+        task_id: &Option<i64>,
+        not_tags: &Option<Vec<String>>,
+    ) -> Result<Vec<Task>, CoreError>;
+
+    fn get_archived(
+        &mut self,
+        priority: &Option<String>,
+        context: &Option<String>,
+        tags: &Vec<String>,
+        date_due: &Option<&str>,
+        date_scheduled: &Option<&str>,
+        task_id: &Option<i64>,
+        not_tags: &Option<Vec<String>>,
+    ) -> Result<Vec<Task>, CoreError>;
+
+    fn modify(
+        &mut self,
+        task_ids: &Vec<i64>,
+        params: &ModifyParams,
+    ) -> Result<Vec<Task>, CoreError>;
+
+    fn delete(&mut self, task_ids: &Vec<i64>) -> Result<Vec<Task>, CoreError>;
+
+    fn get_subtask_ids(&mut self, task_ids: &Vec<i64>) -> Result<Vec<i64>, CoreError>;
+
+    fn view_agenda(
+        &mut self,
+        start_day: String,
+        end_day: Option<String>,
+        context_name: Option<String>,
+    ) -> Result<Vec<(NaiveDate, Vec<Task>)>, CoreError>;
+
+    fn search(
+        &mut self,
+        query: &str,
+        highlight_start: &str,
+        highlight_end: &str,
+    ) -> Result<Vec<SearchResult>, CoreError>;
+
+    fn get_task_ids_by_uuid(&mut self, uuids: &Vec<String>) -> Result<Vec<i64>, CoreError>;
+
+    fn get_task_ids_by_display_id(&mut self, display_ids: &Vec<i64>)
+        -> Result<Vec<i64>, CoreError>;
+
+    // The names of the contexts, tags and states, including the unused ones
+    fn get_context_names(&mut self) -> Result<Vec<String>, CoreError>;
+    fn get_tag_names(&mut self) -> Result<Vec<String>, CoreError>;
+    fn get_state_names(&mut self) -> Result<Vec<String>, CoreError>;
+
+    // The UDAs declared in the setting the store was opened with
+    fn get_uda_types(&mut self) -> Result<BTreeMap<String, UdaType>, CoreError>;
+}
@@ -0,0 +1,171 @@
+use super::{AddParams, ModifyParams, TaskStore};
+use crate::db::search::SearchResult;
+use crate::db::task_helper::Task;
+use crate::db::task_manager::TaskManager;
+use crate::error::CoreError;
+use chrono::NaiveDate;
+use crate::db::uda::UdaType;
+use std::collections::{BTreeMap, HashMap};
+
+impl TaskManager {
+    fn get_names(&mut self, table: &str) -> Result<Vec<String>, CoreError> {
+        let mut statement = self
+            .conn
+            .prepare(&format!("SELECT name FROM {} ORDER BY id", table))?;
+        let mut rows = statement.query([])?;
+        let mut names = vec![];
+        while let Some(row) = rows.next()? {
+            names.push(row.get(0)?);
+        }
+        Ok(names)
+    }
+}
+
+impl TaskStore for TaskManager {
+    fn open(setting: &HashMap<String, String>) -> Result<TaskManager, CoreError> {
+        TaskManager::new(setting)
+    }
+
+    fn add(&mut self, params: &AddParams) -> Result<Vec<Task>, CoreError> {
+        TaskManager::add(
+            self,
+            params.body,
+            &params.priority,
+            &params.context,
+            &params.tags,
+            &params.date_due,
+            &params.date_scheduled,
+            &params.repetition_due,
+            &params.repetition_scheduled,
+            &params.annotation,
+            &params.state_name,
+            &params.parent_task_ids,
+            &params.estimate,
+            &params.udas,
+            &params.parent_id,
+            &params.project,
+        )
+    }
+
+    fn get(
+        &mut self,
+        priority: &Option<String>,
+        context: &Option<String>,
+        tags: &Vec<String>,
+        date_due: &Option<&str>,
+        date_scheduled: &Option<&str>,
+        task_id: &Option<i64>,
+        not_tags: &Option<Vec<String>>,
+    ) -> Result<Vec<Task>, CoreError> {
+        TaskManager::get(
+            self,
+            priority,
+            context,
+            tags,
+            date_due,
+            date_scheduled,
+            task_id,
+            not_tags,
+        )
+    }
+
+    fn get_archived(
+        &mut self,
+        priority: &Option<String>,
+        context: &Option<String>,
+        tags: &Vec<String>,
+        date_due: &Option<&str>,
+        date_scheduled: &Option<&str>,
+        task_id: &Option<i64>,
+        not_tags: &Option<Vec<String>>,
+    ) -> Result<Vec<Task>, CoreError> {
+        TaskManager::get_archived(
+            self,
+            priority,
+            context,
+            tags,
+            date_due,
+            date_scheduled,
+            task_id,
+            not_tags,
+        )
+    }
+
+    fn modify(
+        &mut self,
+        task_ids: &Vec<i64>,
+        params: &ModifyParams,
+    ) -> Result<Vec<Task>, CoreError> {
+        TaskManager::modify(
+            self,
+            task_ids,
+            &params.body,
+            &params.priority,
+            &params.context,
+            &params.tags,
+            &params.date_due,
+            &params.date_scheduled,
+            &params.repetition_due,
+            &params.repetition_scheduled,
+            &params.state,
+            &params.tags_to_remove,
+            &params.estimate,
+            &params.udas,
+            &params.parent_id,
+            &params.project,
+        )
+    }
+
+    fn delete(&mut self, task_ids: &Vec<i64>) -> Result<Vec<Task>, CoreError> {
+        TaskManager::delete(self, task_ids)
+    }
+
+    fn get_subtask_ids(&mut self, task_ids: &Vec<i64>) -> Result<Vec<i64>, CoreError> {
+        TaskManager::get_subtask_ids(self, task_ids)
+    }
+
+    fn view_agenda(
+        &mut self,
+        start_day: String,
+        end_day: Option<String>,
+        context_name: Option<String>,
+    ) -> Result<Vec<(NaiveDate, Vec<Task>)>, CoreError> {
+        TaskManager::view_agenda(self, start_day, end_day, context_name)
+    }
+
+    fn search(
+        &mut self,
+        query: &str,
+        highlight_start: &str,
+        highlight_end: &str,
+    ) -> Result<Vec<SearchResult>, CoreError> {
+        TaskManager::search(self, query, highlight_start, highlight_end)
+    }
+
+    fn get_task_ids_by_uuid(&mut self, uuids: &Vec<String>) -> Result<Vec<i64>, CoreError> {
+        TaskManager::get_task_ids_by_uuid(self, uuids)
+    }
+
+    fn get_task_ids_by_display_id(
+        &mut self,
+        display_ids: &Vec<i64>,
+    ) -> Result<Vec<i64>, CoreError> {
+        TaskManager::get_task_ids_by_display_id(self, display_ids)
+    }
+
+    fn get_context_names(&mut self) -> Result<Vec<String>, CoreError> {
+        self.get_names("context")
+    }
+
+    fn get_tag_names(&mut self) -> Result<Vec<String>, CoreError> {
+        self.get_names("tag")
+    }
+
+    fn get_state_names(&mut self) -> Result<Vec<String>, CoreError> {
+        self.get_names("state")
+    }
+
+    fn get_uda_types(&mut self) -> Result<BTreeMap<String, UdaType>, CoreError> {
+        TaskManager::get_uda_types(self)
+    }
+}
@@ -0,0 +1,393 @@
+use std::collections::HashMap;
+
+use super::{AddParams, MemoryStore, ModifyParams, TaskStore};
+use crate::core::Operation;
+use crate::db::task_helper::Task;
+use crate::db::task_manager::TaskManager;
+use crate::error::CoreError;
+use crate::operation::{execute, execute_agenda, Add, Agenda, DeleteOperation, Get, ModifyOperation};
+
+fn get_setting() -> HashMap<String, String> {
+    let mut setting = HashMap::new();
+    setting.insert("db_path".to_owned(), ":memory:".to_owned());
+    setting.insert("context".to_owned(), "inbox, Work, Life".to_owned());
+    setting.insert("uda.client".to_owned(), "string".to_owned());
+    setting.insert("uda.cost".to_owned(), "number".to_owned());
+    setting
+}
+
+fn add<S: TaskStore>(
+    store: &mut S,
+    body: &str,
+    context: Option<&str>,
+    tags: Vec<&str>,
+    parent_task_ids: Option<Vec<i64>>,
+) -> Result<Task, CoreError> {
+    let tags = tags.iter().map(|tag| tag.to_string()).collect();
+    let context = context.map(|name| name.to_string());
+    let mut tasks = store.add(&AddParams {
+        body,
+        context,
+        tags,
+        parent_task_ids,
+        ..Default::default()
+    })?;
+    Ok(tasks.remove(0))
+}
+
+fn list<S: TaskStore>(store: &mut S, context: Option<&str>) -> Result<Vec<Task>, CoreError> {
+    store.get(&None, &context.map(|name| name.to_string()), &vec![], &None, &None, &None, &None)
+}
+
+fn set_state<S: TaskStore>(
+    store: &mut S,
+    task_ids: Vec<i64>,
+    state: &str,
+) -> Result<Vec<Task>, CoreError> {
+    store.modify(
+        &task_ids,
+        &ModifyParams {
+            state: Some(state),
+            ..Default::default()
+        },
+    )
+}
+
+fn bodies(tasks: &Vec<Task>) -> Vec<&str> {
+    tasks.iter().map(|task| task.body.as_str()).collect()
+}
+
+fn check_add_and_get<S: TaskStore>(store: &mut S) -> Result<(), CoreError> {
+    add(store, "One", Some("work"), vec!["b", "a"], None)?;
+    add(store, "Two", None, vec![], None)?;
+    let child = add(store, "Child", None, vec![], Some(vec![1]))?;
+    assert_eq!(child.state, "blocked");
+    assert_eq!(child.parent_task_ids, vec!["1"]);
+    assert_eq!(child.uuid.len(), 36);
+
+    assert_eq!(bodies(&list(store, None)?), vec!["Two", "Child"]);
+    let mut work = list(store, Some("work"))?;
+    assert_eq!(bodies(&work), vec!["One"]);
+    work[0].tags.sort();
+    assert_eq!(work[0].tags, vec!["a", "b"]);
+    assert_eq!(work[0].state, "ready");
+
+    let context = Some(String::from("work"));
+    let tagged = store.get(&None, &context, &vec![String::from("a")], &None, &None, &None, &None)?;
+    assert_eq!(bodies(&tagged), vec!["One"]);
+    let not_tagged =
+        store.get(&None, &context, &vec![], &None, &None, &None, &Some(vec![String::from("a")]))?;
+    assert!(not_tagged.is_empty());
+    let by_id = store.get(&None, &None, &vec![], &None, &None, &Some(1), &None)?;
+    assert_eq!(bodies(&by_id), vec!["One"]);
+
+    assert!(list(store, Some("nowhere")).is_err());
+    assert!(add(store, "Orphan", None, vec![], Some(vec![9])).is_err());
+    Ok(())
+}
+
+fn check_modify<S: TaskStore>(store: &mut S) -> Result<(), CoreError> {
+    add(store, "Parent", None, vec!["old"], None)?;
+    add(store, "Child", None, vec![], Some(vec![1]))?;
+
+    let tasks = store.modify(
+        &vec![1],
+        &ModifyParams {
+            body: Some("Renamed"),
+            priority: Some(String::from("H")),
+            context: Some(String::from("work")),
+            tags: vec![String::from("new")],
+            date_due: Some("2026-07-08"),
+            repetition_due: Some("1days"),
+            tags_to_remove: vec![String::from("old")],
+            estimate: Some("1h30m"),
+            ..Default::default()
+        },
+    )?;
+    assert_eq!(tasks[0].body, "Renamed");
+    assert_eq!(tasks[0].priority, "h");
+    assert_eq!(tasks[0].context, "work");
+    assert_eq!(tasks[0].tags, vec!["new"]);
+    assert_eq!(tasks[0].date_due, "2026-07-08 00:00:00");
+    assert_eq!(tasks[0].estimate, Some(90));
+
+    // Completing a repeating task moves the due date, it's ready again so
+    // the child stays blocked
+    let tasks = set_state(store, vec![1], "completed")?;
+    assert_eq!(tasks[0].state, "ready");
+    assert_ne!(tasks[0].date_due, "2026-07-08 00:00:00");
+    let child = store.get(&None, &None, &vec![], &None, &None, &Some(2), &None)?;
+    assert_eq!(child[0].state, "blocked");
+
+    assert_eq!(tasks[0].date_completed, "");
+    assert_eq!(set_state(store, vec![2], "started")?[0].date_completed, "");
+
+    add(store, "Other child", None, vec![], Some(vec![2]))?;
+    let tasks = set_state(store, vec![2], "completed")?;
+    assert_ne!(tasks[0].date_started, "");
+    assert_ne!(tasks[0].date_completed, "");
+    let child = store.get(&None, &None, &vec![], &None, &None, &Some(3), &None)?;
+    assert_eq!(child[0].state, "ready");
+    assert_ne!(child[0].date_modified, "");
+    let tasks = set_state(store, vec![2], "ready")?;
+    assert_eq!((tasks[0].date_started.as_str(), tasks[0].date_completed.as_str()), ("", ""));
+
+    let nothing = store.modify(&vec![1], &ModifyParams::default())?;
+    assert!(nothing.is_empty());
+    assert!(set_state(store, vec![], "ready").is_err());
+    let invalid_priority = store.modify(
+        &vec![1],
+        &ModifyParams {
+            priority: Some(String::from("X")),
+            ..Default::default()
+        },
+    );
+    assert!(invalid_priority.is_err());
+    Ok(())
+}
+
+fn check_delete_and_ids<S: TaskStore>(store: &mut S) -> Result<(), CoreError> {
+    for body in ["One", "Two", "Three"] {
+        add(store, body, None, vec![], None)?;
+    }
+    let tasks = list(store, None)?;
+    let display_ids: Vec<Option<i64>> = tasks.iter().map(|task| task.display_id).collect();
+    assert_eq!(display_ids, vec![Some(1), Some(2), Some(3)]);
+
+    let deleted = store.delete(&vec![1, 7])?;
+    assert_eq!(bodies(&deleted), vec!["One"]);
+    assert_eq!(deleted[0].display_id, None);
+
+    // The ids shown last keep working until the next listing
+    assert_eq!(store.get_task_ids_by_display_id(&vec![3])?, vec![3]);
+    assert!(store.get_task_ids_by_display_id(&vec![1]).is_err());
+    let tasks = list(store, None)?;
+    let display_ids: Vec<(Option<i64>, i64)> =
+        tasks.iter().map(|task| (task.display_id, task.id)).collect();
+    assert_eq!(display_ids, vec![(Some(1), 2), (Some(2), 3)]);
+
+    let uuid = tasks[1].uuid[..8].to_uppercase();
+    assert_eq!(store.get_task_ids_by_uuid(&vec![uuid])?, vec![3]);
+    assert!(store.get_task_ids_by_uuid(&vec![String::from("zz")]).is_err());
+
+    // Deleted tasks keep their ids
+    assert_eq!(add(store, "Four", None, vec![], None)?.id, 4);
+    Ok(())
+}
+
+fn check_agenda<S: TaskStore>(store: &mut S) -> Result<(), CoreError> {
+    store.add(&AddParams {
+        body: "Due",
+        date_due: Some("2026-07-08"),
+        ..Default::default()
+    })?;
+    store.add(&AddParams {
+        body: "Scheduled",
+        context: Some(String::from("work")),
+        date_scheduled: Some("2026-07-09"),
+        ..Default::default()
+    })?;
+
+    let agenda = store.view_agenda(
+        String::from("2026-07-07"),
+        Some(String::from("2026-07-09")),
+        None,
+    )?;
+    let days: Vec<(String, Vec<&str>)> = agenda
+        .iter()
+        .map(|(day, tasks)| (day.to_string(), bodies(tasks)))
+        .collect();
+    assert_eq!(
+        days,
+        vec![
+            (String::from("2026-07-07"), vec![]),
+            (String::from("2026-07-08"), vec!["Due"]),
+            (String::from("2026-07-09"), vec!["Due", "Scheduled"]),
+        ]
+    );
+
+    let agenda = store.view_agenda(String::from("2026-07-09"), None, Some(String::from("work")))?;
+    assert_eq!(bodies(&agenda[0].1), vec!["Scheduled"]);
+    Ok(())
+}
+
+fn check_metadata<S: TaskStore>(store: &mut S) -> Result<(), CoreError> {
+    add(store, "One", Some("work"), vec!["tag1"], None)?;
+    set_state(store, vec![1], "waiting")?;
+
+    assert_eq!(store.get_context_names()?, vec!["inbox", "work"]);
+    assert_eq!(store.get_tag_names()?, vec!["tag1"]);
+    assert_eq!(
+        store.get_state_names()?,
+        vec!["ready", "completed", "blocked", "started", "waiting"]
+    );
+    Ok(())
+}
+
+fn check_udas<S: TaskStore>(store: &mut S) -> Result<(), CoreError> {
+    let udas = vec![(String::from("client"), String::from("acme"))];
+    let task = store.add(&AddParams {
+        body: "One",
+        udas,
+        ..Default::default()
+    })?;
+    assert_eq!(task[0].get_property_value("client")?, "acme");
+
+    let udas = vec![
+        (String::from("client"), String::new()),
+        (String::from("cost"), String::from("40")),
+    ];
+    let tasks = store.modify(
+        &vec![1],
+        &ModifyParams {
+            udas,
+            ..Default::default()
+        },
+    )?;
+    assert_eq!(tasks[0].format_udas(), "cost:40");
+
+    for (name, value) in [("cost", "a lot"), ("ticket", "OPS-123")] {
+        let udas = vec![(name.to_string(), value.to_string())];
+        let invalid = store.modify(
+            &vec![1],
+            &ModifyParams {
+                udas,
+                ..Default::default()
+            },
+        );
+        assert!(invalid.is_err());
+    }
+    assert_eq!(store.get_uda_types()?.len(), 2);
+    Ok(())
+}
+
+fn check_subtasks<S: TaskStore>(store: &mut S) -> Result<(), CoreError> {
+    add(store, "Parent", None, vec![], None)?;
+    for body in ["One", "Two"] {
+        store.add(&AddParams {
+            body,
+            parent_id: Some(1),
+            ..Default::default()
+        })?;
+    }
+    let parent = store.get(&None, &None, &vec![], &None, &None, &Some(1), &None)?;
+    assert_eq!(parent[0].format_subtask_progress(), "0/2");
+    assert_eq!(store.get_subtask_ids(&vec![1])?, vec![2, 3]);
+
+    set_state(store, vec![2], "completed")?;
+    let parent = store.get(&None, &None, &vec![], &None, &None, &Some(1), &None)?;
+    assert_eq!(parent[0].format_subtask_progress(), "1/2");
+
+    let cycle = store.modify(
+        &vec![1],
+        &ModifyParams {
+            parent_id: Some(Some(3)),
+            ..Default::default()
+        },
+    );
+    assert!(cycle.is_err());
+
+    // The subtasks of a deleted task become top level tasks
+    store.delete(&vec![1])?;
+    let child = store.get(&None, &None, &vec![], &None, &None, &Some(3), &None)?;
+    assert_eq!(child[0].parent_id, None);
+    Ok(())
+}
+
+fn check_projects<S: TaskStore>(store: &mut S) -> Result<(), CoreError> {
+    let task = store.add(&AddParams {
+        body: "Book the venue",
+        project: Some(String::from("Wedding")),
+        ..Default::default()
+    })?;
+    assert_eq!(task[0].project, "wedding");
+    let invalid = store.add(&AddParams {
+        body: "Invite",
+        project: Some(String::from("a,b")),
+        ..Default::default()
+    });
+    assert!(invalid.is_err());
+
+    // An empty project takes the task out of its project
+    let tasks = store.modify(
+        &vec![1],
+        &ModifyParams {
+            project: Some(String::new()),
+            ..Default::default()
+        },
+    )?;
+    assert_eq!(tasks[0].project, "");
+    Ok(())
+}
+
+fn check_label_hierarchy<S: TaskStore>(store: &mut S) -> Result<(), CoreError> {
+    add(store, "Call the client", Some("work.clienta"), vec!["errand.phone"], None)?;
+    add(store, "Buy screws", Some("work"), vec!["errand"], None)?;
+    assert!(add(store, "Invalid", Some("work."), vec![], None).is_err());
+    assert_eq!(bodies(&list(store, Some("work"))?), vec!["Call the client", "Buy screws"]);
+    assert_eq!(bodies(&list(store, Some("work.clienta"))?), vec!["Call the client"]);
+
+    let work = Some(String::from("work"));
+    let tags = vec![String::from("errand")];
+    assert_eq!(store.get(&None, &work, &tags, &None, &None, &None, &None)?.len(), 2);
+    assert!(store.get(&None, &work, &vec![], &None, &None, &None, &Some(tags))?.is_empty());
+    Ok(())
+}
+
+// Both backends have to pass the same checks
+fn check_all<S: TaskStore>(new_store: impl Fn() -> S) -> Result<(), CoreError> {
+    check_add_and_get(&mut new_store())?;
+    check_modify(&mut new_store())?;
+    check_delete_and_ids(&mut new_store())?;
+    check_agenda(&mut new_store())?;
+    check_metadata(&mut new_store())?;
+    check_udas(&mut new_store())?;
+    check_subtasks(&mut new_store())?;
+    check_projects(&mut new_store())?;
+    check_label_hierarchy(&mut new_store())?;
+    Ok(())
+}
+
+#[test]
+fn test_sqlite_store() -> Result<(), CoreError> {
+    check_all(|| TaskManager::new(&get_setting()).unwrap())
+}
+
+#[test]
+fn test_memory_store() -> Result<(), CoreError> {
+    check_all(|| MemoryStore::open(&get_setting()).unwrap())
+}
+
+#[test]
+fn test_operations_with_memory_store() -> Result<(), CoreError> {
+    let mut store = MemoryStore::new();
+    for body in ["Call the vendor", "Buy milk", "Email the vendor"] {
+        let mut operation = Add::new_with_task_manager(body, &mut store);
+        execute(&mut operation)?;
+    }
+
+    let data = vec![String::from("2"), String::from("@completed")];
+    let mut operation = ModifyOperation::new_with_store(&data, store.clone())?;
+    execute(&mut operation)?;
+
+    let data = vec![String::from("id:3")];
+    let mut operation = DeleteOperation::new_with_store(&data, store.clone())?;
+    execute(&mut operation)?;
+
+    // Clones share the tasks
+    let mut operation = Get::new_with_store(Some(store.clone()));
+    operation.search = Some(String::from("vendor"));
+    execute(&mut operation)?;
+    assert_eq!(bodies(operation.get_result()), vec!["Call the vendor"]);
+
+    let mut operation = Get::new_with_store(Some(store.clone()));
+    execute(&mut operation)?;
+    let states: Vec<&str> = operation.get_result().iter().map(|task| task.state.as_str()).collect();
+    assert_eq!(states, vec!["ready", "completed"]);
+
+    let mut operation =
+        Agenda::new_with_store(String::from("today"), None, None, Some(store.clone()));
+    execute_agenda(&mut operation)?;
+    assert!(operation.get_result()[0].1.is_empty());
+    Ok(())
+}
@@ -6,7 +6,7 @@ use std::os::raw::c_char;
 use crate::command::{ContextCommand, SimpleCommand, StateCommand, TagCommand};
 use crate::operation;
 use crate::core::Operation;
-use crate::wasm::helpers::read_data_from_js;
+use crate::wasm::helpers::{escape_html, read_data_from_js};
 
 pub use crate::db::task_helper::Task;
 
@@ -18,6 +18,49 @@ struct AnnotationInput {
     annotation: String,
 }
 
+#[derive(serde::Deserialize)]
+struct AddNoteInput {
+    task_id: i64,
+    body: String,
+}
+
+#[derive(serde::Deserialize)]
+struct EditNoteInput {
+    note_id: i64,
+    body: String,
+}
+
+#[derive(serde::Deserialize)]
+struct DeleteNoteInput {
+    note_id: i64,
+}
+
+#[derive(serde::Deserialize)]
+struct AddChecklistItemInput {
+    task_id: i64,
+    body: String,
+}
+
+#[derive(serde::Deserialize)]
+struct CheckChecklistItemInput {
+    task_id: i64,
+    position: i64,
+    done: bool,
+}
+
+#[derive(serde::Deserialize)]
+struct MoveChecklistItemInput {
+    task_id: i64,
+    from: i64,
+    to: i64,
+}
+
+#[derive(serde::Deserialize)]
+struct DeleteChecklistItemInput {
+    task_id: i64,
+    position: i64,
+}
+
 #[derive(serde::Deserialize)]
 struct BodyInput {
     task_id: i64,
@@ -92,15 +135,18 @@ unsafe fn read_raw_string_from_js(ptr: *mut u8, len: usize) -> String {
     String::from_utf8(data).unwrap()
 }
 
-// List Operation
+// List Operation, the archived tasks follow the others with --with-archived
 #[no_mangle]
 pub unsafe fn list(ptr: *mut u8, len: usize) -> *mut c_char {
-    let data = read_data_from_js(ptr, len);
+    let mut data = read_data_from_js(ptr, len);
+    let with_archived = data.iter().any(|word| word == "--with-archived");
+    data.retain(|word| word != "--with-archived");
     let mut operations = operation::Get::new2(&data).expect("Failed to create the get operation");
 
     let mut ret: Vec<(String, &Vec<Task>)> = vec![];
 
     for operation_tuple in operations.iter_mut() {
+        operation_tuple.1.with_archived = with_archived;
         let serded_string: String = match operation::execute(&mut operation_tuple.1) {
             Ok(_) => {
                 println!("got ok here");
@@ -122,12 +168,48 @@ pub unsafe fn list(ptr: *mut u8, len: usize) -> *mut c_char {
     return s.into_raw();
 }
 
+// List the archived tasks, takes the same filters as list
+#[no_mangle]
+pub unsafe fn archived(ptr: *mut u8, len: usize) -> *mut c_char {
+    let data = read_data_from_js(ptr, len);
+    let mut operations = match operation::Get::new2(&data) {
+        Ok(operations) => operations,
+        Err(e) => {
+            let message = serde_json::json!({"error": e.to_string()}).to_string();
+            LENGTH = message.len();
+            return CString::new(message).unwrap().into_raw();
+        }
+    };
+
+    for operation_tuple in operations.iter_mut() {
+        operation_tuple.1.archived = true;
+        if let Err(e) = operation::execute(&mut operation_tuple.1) {
+            let message = serde_json::json!({"error": e.to_string()}).to_string();
+            LENGTH = message.len();
+            return CString::new(message).unwrap().into_raw();
+        }
+    }
+
+    let mut ret: Vec<(String, &Vec<Task>)> = vec![];
+    for operation_tuple in operations.iter_mut() {
+        ret.push((operation_tuple.0.to_owned(), operation_tuple.1.get_result()));
+    }
+    let serded_ret: String = serde_json::to_string(&ret).unwrap();
+    LENGTH = serded_ret.len();
+    let s = CString::new(serded_ret).unwrap();
+    return s.into_raw();
+}
+
 // Add Operation
 #[no_mangle]
 pub unsafe fn add(ptr: *mut u8, len: usize) {
     let data = read_data_from_js(ptr, len);
     let mut operation = operation::Add::new(&data).expect("Failed to create the add operation");
-    operation::execute(&mut operation).expect("Failed to execute the operation");
+    // Opening the database fails when it can't be migrated
+    if let Err(e) = operation::execute(&mut operation) {
+        eprintln!("Failed to add the task: {}", e);
+        return;
+    }
     let added_tasks = &operation.get_result();
     println!("Added a task with id {}", added_tasks[0].id);
 }
@@ -198,6 +280,110 @@ pub unsafe fn annotation(ptr: *mut u8, len: usize) -> *mut c_char {
     return s.into_raw();
 }
 
+// Runs a note or a checklist operation, the task is returned with its notes
+// and its checklist
+fn run_task_operation(operation: &mut impl Operation, what: &str) -> String {
+    let serded_string: String = match operation.init() {
+        Ok(_) => match operation.do_work() {
+            Ok(tasks) => {
+                if tasks.is_empty() {
+                    serde_json::json!({"error": format!("Unable to update the {}", what)})
+                        .to_string()
+                } else {
+                    serde_json::to_string(&tasks[0]).unwrap()
+                }
+            }
+            Err(e) => serde_json::json!({"error": e.to_string()}).to_string(),
+        },
+        Err(e) => serde_json::json!({"error": e.to_string()}).to_string(),
+    };
+
+    unsafe { LENGTH = serded_string.len() }
+    serded_string
+}
+
+fn parse_input<T: serde::de::DeserializeOwned>(input: &str) -> Result<T, *mut c_char> {
+    serde_json::from_str(input).map_err(|e| {
+        let message = serde_json::json!({"error": e.to_string()}).to_string();
+        unsafe { LENGTH = message.len() }
+        CString::new(message).unwrap().into_raw()
+    })
+}
+
+#[no_mangle]
+pub unsafe fn add_note(ptr: *mut u8, len: usize) -> *mut c_char {
+    let payload: AddNoteInput = match parse_input(&read_raw_string_from_js(ptr, len)) {
+        Ok(payload) => payload,
+        Err(message) => return message,
+    };
+    let mut operation = operation::AddNote::new(payload.task_id, payload.body);
+    CString::new(run_task_operation(&mut operation, "note")).unwrap().into_raw()
+}
+
+#[no_mangle]
+pub unsafe fn edit_note(ptr: *mut u8, len: usize) -> *mut c_char {
+    let payload: EditNoteInput = match parse_input(&read_raw_string_from_js(ptr, len)) {
+        Ok(payload) => payload,
+        Err(message) => return message,
+    };
+    let mut operation = operation::EditNote::new(payload.note_id, payload.body);
+    CString::new(run_task_operation(&mut operation, "note")).unwrap().into_raw()
+}
+
+#[no_mangle]
+pub unsafe fn delete_note(ptr: *mut u8, len: usize) -> *mut c_char {
+    let payload: DeleteNoteInput = match parse_input(&read_raw_string_from_js(ptr, len)) {
+        Ok(payload) => payload,
+        Err(message) => return message,
+    };
+    let mut operation = operation::DeleteNote::new(payload.note_id);
+    CString::new(run_task_operation(&mut operation, "note")).unwrap().into_raw()
+}
+
+#[no_mangle]
+pub unsafe fn add_checklist_item(ptr: *mut u8, len: usize) -> *mut c_char {
+    let payload: AddChecklistItemInput = match parse_input(&read_raw_string_from_js(ptr, len)) {
+        Ok(payload) => payload,
+        Err(message) => return message,
+    };
+    let mut operation = operation::AddChecklistItem::new(payload.task_id, payload.body);
+    CString::new(run_task_operation(&mut operation, "checklist")).unwrap().into_raw()
+}
+
+#[no_mangle]
+pub unsafe fn check_checklist_item(ptr: *mut u8, len: usize) -> *mut c_char {
+    let payload: CheckChecklistItemInput = match parse_input(&read_raw_string_from_js(ptr, len))
+    {
+        Ok(payload) => payload,
+        Err(message) => return message,
+    };
+    let mut operation =
+        operation::CheckChecklistItem::new(payload.task_id, payload.position, payload.done);
+    CString::new(run_task_operation(&mut operation, "checklist")).unwrap().into_raw()
+}
+
+#[no_mangle]
+pub unsafe fn move_checklist_item(ptr: *mut u8, len: usize) -> *mut c_char {
+    let payload: MoveChecklistItemInput = match parse_input(&read_raw_string_from_js(ptr, len)) {
+        Ok(payload) => payload,
+        Err(message) => return message,
+    };
+    let mut operation =
+        operation::MoveChecklistItem::new(payload.task_id, payload.from, payload.to);
+    CString::new(run_task_operation(&mut operation, "checklist")).unwrap().into_raw()
+}
+
+#[no_mangle]
+pub unsafe fn delete_checklist_item(ptr: *mut u8, len: usize) -> *mut c_char {
+    let payload: DeleteChecklistItemInput =
+        match parse_input(&read_raw_string_from_js(ptr, len)) {
+            Ok(payload) => payload,
+            Err(message) => return message,
+        };
+    let mut operation = operation::DeleteChecklistItem::new(payload.task_id, payload.position);
+    CString::new(run_task_operation(&mut operation, "checklist")).unwrap().into_raw()
+}
+
 // Tag delete operation
 #[no_mangle]
 pub unsafe fn tag_delete(ptr: *mut u8, len: usize) -> *mut c_char {
@@ -289,6 +475,34 @@ pub unsafe fn modify(ptr: *mut u8, len: usize) -> *mut c_char {
     return s.into_raw();
 }
 
+// Undo Operation
+#[no_mangle]
+pub unsafe fn undo() -> *mut c_char {
+    let mut operation = operation::Undo::new();
+    let serded_string: String = match operation::execute(&mut operation) {
+        Ok(_) => serde_json::to_string(&operation.get_result()).unwrap(),
+        Err(e) => serde_json::json!({"error": e.to_string()}).to_string(),
+    };
+
+    LENGTH = serded_string.len();
+    let s = CString::new(serded_string).unwrap();
+    return s.into_raw();
+}
+
+// Redo Operation
+#[no_mangle]
+pub unsafe fn redo() -> *mut c_char {
+    let mut operation = operation::Redo::new();
+    let serded_string: String = match operation::execute(&mut operation) {
+        Ok(_) => serde_json::to_string(&operation.get_result()).unwrap(),
+        Err(e) => serde_json::json!({"error": e.to_string()}).to_string(),
+    };
+
+    LENGTH = serded_string.len();
+    let s = CString::new(serded_string).unwrap();
+    return s.into_raw();
+}
+
 // Metadata for UI filters and editors
 #[no_mangle]
 pub unsafe fn metadata() -> *mut c_char {
@@ -318,7 +532,8 @@ pub unsafe fn metadata() -> *mut c_char {
     return s.into_raw();
 }
 
-// Task info operation
+// Task info operation, the task comes with its notes, attachments and
+// checklist
 #[no_mangle]
 pub unsafe fn info(ptr: *mut u8, len: usize) -> *mut c_char {
     let data = read_data_from_js(ptr, len);
@@ -353,6 +568,61 @@ pub unsafe fn info(ptr: *mut u8, len: usize) -> *mut c_char {
     return s.into_raw();
 }
 
+#[no_mangle]
+pub unsafe fn history(ptr: *mut u8, len: usize) -> *mut c_char {
+    let data = read_data_from_js(ptr, len);
+    let input = data.join(" ");
+    let task_id = match input.trim().parse::<i64>() {
+        Ok(task_id) => task_id,
+        Err(_) => {
+            let message = serde_json::json!({"error": "Task id must be an integer"}).to_string();
+            LENGTH = message.len();
+            return CString::new(message).unwrap().into_raw();
+        }
+    };
+
+    let mut operation = operation::History::new(task_id);
+    let serded_string: String = match operation::execute_history(&mut operation) {
+        Ok(_) => serde_json::to_string(operation.get_result()).unwrap(),
+        Err(e) => serde_json::json!({"error": e.to_string()}).to_string(),
+    };
+
+    LENGTH = serded_string.len();
+    let s = CString::new(serded_string).unwrap();
+    return s.into_raw();
+}
+
+// The input is the search query. The snippets are HTML, the text is
+// escaped and the matched words are wrapped by <mark>.
+#[no_mangle]
+pub unsafe fn search(ptr: *mut u8, len: usize) -> *mut c_char {
+    let data = read_data_from_js(ptr, len);
+    let mut operation = operation::Search::new(data.join(" "));
+    // Characters of the private use area mark the matches until the text
+    // is escaped
+    operation.highlight_start = String::from("\u{E000}");
+    operation.highlight_end = String::from("\u{E001}");
+
+    let serded_string: String = match operation.init() {
+        Ok(_) => match operation.do_work_for_search() {
+            Ok(mut results) => {
+                for result in results.iter_mut() {
+                    result.snippet = escape_html(&result.snippet)
+                        .replace(&operation.highlight_start, "<mark>")
+                        .replace(&operation.highlight_end, "</mark>");
+                }
+                serde_json::to_string(&results).unwrap()
+            }
+            Err(e) => serde_json::json!({"error": e.to_string()}).to_string(),
+        },
+        Err(e) => serde_json::json!({"error": e.to_string()}).to_string(),
+    };
+
+    LENGTH = serded_string.len();
+    let s = CString::new(serded_string).unwrap();
+    return s.into_raw();
+}
+
 // Delete Operation
 #[no_mangle]
 pub unsafe fn delete(ptr: *mut u8, len: usize) {
@@ -11,3 +11,19 @@ pub unsafe fn read_data_from_js(ptr: *mut u8, len: usize) -> Vec<String> {
     println!("read {:?}", ret);
     ret
 }
+
+// Escape the text so it's shown as it is when it's inserted as HTML
+pub fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
@@ -25,21 +25,32 @@ function expandHome(value) {
   return value;
 }
 
+// Same profile selection as ConfigManager of taskoo-core: TASKOO_PROFILE,
+// then the profile set by `taskoo profile use`, then the top-level db_path
 function readConfiguredDbDir() {
   if (!fs.existsSync(CONFIG_PATH)) {
     return TASKOO_CONFIG_DIR;
   }
 
-  const config = fs.readFileSync(CONFIG_PATH, "utf8");
-  const dbPathLine = config
-    .split(/\r?\n/)
-    .find((line) => line.trim().startsWith("db_path="));
-  if (!dbPathLine) {
-    return TASKOO_CONFIG_DIR;
+  const sections = { "": {} };
+  let section = "";
+  for (const rawLine of fs.readFileSync(CONFIG_PATH, "utf8").split(/\r?\n/)) {
+    const line = rawLine.trim();
+    if (line.startsWith("[") && line.endsWith("]")) {
+      section = line.slice(1, -1).trim();
+      sections[section] = sections[section] || {};
+    } else if (line.includes("=") && !line.startsWith(";") && !line.startsWith("#")) {
+      const [key, ...value] = line.split("=");
+      sections[section][key.trim()] = value.join("=").trim();
+    }
   }
 
-  const dbPath = expandHome(dbPathLine.split("=").slice(1).join("=").trim());
-  return path.dirname(dbPath);
+  const profile = process.env.TASKOO_PROFILE || sections[""].profile || "default";
+  const settings = profile === "default" ? sections[""] : sections[`profile.${profile}`];
+  if (!settings || !settings.db_path) {
+    return TASKOO_CONFIG_DIR;
+  }
+  return path.dirname(expandHome(settings.db_path));
 }
 
 fs.mkdirSync(TASKOO_CONFIG_DIR, { recursive: true });
@@ -61,7 +72,8 @@ const wasi = new WASI({
   env: {
     RUST_LOG: "debug", // Enable the debug logging for Taskoo
     HOME: TASKOO_HOME,
-    RUST_BACKTRACE: "full"
+    RUST_BACKTRACE: "full",
+    ...(process.env.TASKOO_PROFILE && { TASKOO_PROFILE: process.env.TASKOO_PROFILE }),
   }
 });
 
@@ -136,6 +148,12 @@ export class Endpoints {
     return readSharedString(offset);
   }
 
+  static Archived(input) {
+    const allocated = allocateInput(input);
+    const offset = instance.exports.archived(allocated.ptr, allocated.bytes.length);
+    return readSharedString(offset);
+  }
+
   static Agenda(input) {
     const allocated = allocateInput(input);
     const offset = instance.exports.agenda(allocated.ptr, allocated.bytes.length);
@@ -160,6 +178,24 @@ export class Endpoints {
     return readSharedString(result);
   }
 
+  static AddNote(input) {
+    const allocated = allocateInput(input);
+    const result = instance.exports.add_note(allocated.ptr, allocated.bytes.length);
+    return readSharedString(result);
+  }
+
+  static EditNote(input) {
+    const allocated = allocateInput(input);
+    const result = instance.exports.edit_note(allocated.ptr, allocated.bytes.length);
+    return readSharedString(result);
+  }
+
+  static DeleteNote(input) {
+    const allocated = allocateInput(input);
+    const result = instance.exports.delete_note(allocated.ptr, allocated.bytes.length);
+    return readSharedString(result);
+  }
+
   static Delete(input) {
     const allocated = allocateInput(input);
     instance.exports.delete(allocated.ptr, allocated.bytes.length);
@@ -191,6 +227,28 @@ export class Endpoints {
     return readSharedString(result);
   }
 
+  static Search(input) {
+    const allocated = allocateInput(input);
+    const result = instance.exports.search(allocated.ptr, allocated.bytes.length);
+    return readSharedString(result);
+  }
+
+  static History(input) {
+    const allocated = allocateInput(input);
+    const result = instance.exports.history(allocated.ptr, allocated.bytes.length);
+    return readSharedString(result);
+  }
+
+  static Undo() {
+    const result = instance.exports.undo();
+    return readSharedString(result);
+  }
+
+  static Redo() {
+    const result = instance.exports.redo();
+    return readSharedString(result);
+  }
+
   static Metadata() {
     const result = instance.exports.metadata();
     return readSharedString(result);
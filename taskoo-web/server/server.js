@@ -64,6 +64,24 @@ app.post('/info', (req, res) => {
   res.status(ret.status).send(ret.body);
 });
 
+app.post('/archived', (req, res) => {
+  console.log("archived endpoint");
+  const ret = parseEndpointData(Endpoints.Archived(req.body.data));
+  res.status(ret.status).send(ret.body);
+});
+
+app.post('/search', (req, res) => {
+  console.log("search endpoint");
+  const ret = parseEndpointData(Endpoints.Search(req.body.data));
+  res.status(ret.status).send(ret.body);
+});
+
+app.post('/history', (req, res) => {
+  console.log("history endpoint");
+  const ret = parseEndpointData(Endpoints.History(req.body.data));
+  res.status(ret.status).send(ret.body);
+});
+
 const createPost = (req, res, next) => {
     console.log('createPost', req.body)
     next()
@@ -92,6 +110,24 @@ app.post('/annotation', createPost, (req, res) => {
   res.status(ret.status).send(ret.body);
 });
 
+app.post('/add_note', createPost, (req, res) => {
+  console.log("add_note endpoint");
+  const ret = parseEndpointData(Endpoints.AddNote(req.body.data));
+  res.status(ret.status).send(ret.body);
+});
+
+app.post('/edit_note', createPost, (req, res) => {
+  console.log("edit_note endpoint");
+  const ret = parseEndpointData(Endpoints.EditNote(req.body.data));
+  res.status(ret.status).send(ret.body);
+});
+
+app.post('/delete_note', createPost, (req, res) => {
+  console.log("delete_note endpoint");
+  const ret = parseEndpointData(Endpoints.DeleteNote(req.body.data));
+  res.status(ret.status).send(ret.body);
+});
+
 app.post('/state_change', createPost, (req, res) => {
   console.log("state_change endpoint");
   const ret = parseEndpointData(Endpoints.StateChange(req.body.data));
@@ -110,6 +146,18 @@ app.post('/delete', createPost, (req, res) => {
   res.status(ret.status).send(ret.body);
 });
 
+app.post('/undo', (req, res) => {
+  console.log("undo endpoint");
+  const ret = parseEndpointData(Endpoints.Undo());
+  res.status(ret.status).send(ret.body);
+});
+
+app.post('/redo', (req, res) => {
+  console.log("redo endpoint");
+  const ret = parseEndpointData(Endpoints.Redo());
+  res.status(ret.status).send(ret.body);
+});
+
 app.post('/tag_delete', createPost, (req, res) => {
   console.log("tag_delete endpoint");
   const ret = parseEndpointData(Endpoints.TagDelete(req.body.data));